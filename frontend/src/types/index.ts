@@ -22,13 +22,89 @@ export interface YarnSpec {
   recommended_hook_size_mm: number;
 }
 
+export type YarnWeight =
+  | 'Lace'
+  | 'SuperFine'
+  | 'Fine'
+  | 'Light'
+  | 'Medium'
+  | 'Bulky'
+  | 'SuperBulky'
+  | 'Jumbo';
+
+export type Handedness = 'Right' | 'Left';
+
+export type RoundStart = 'Front' | 'Left' | 'Back' | 'Right' | { Angle: number };
+
 export interface AmigurumiConfig {
   total_height_cm: number;
   yarn: YarnSpec;
+  max_stitch_delta_fraction?: number;
+  max_stitch_delta?: number | null;
+  handedness?: Handedness;
+  round_start?: RoundStart;
+  limits?: InputLimits;
+  quality?: Quality;
+  gauge_estimated?: boolean;
+  balance_decreases?: boolean;
+  min_round_stitches?: number;
+  optimization?: OptimizationConfig;
+  stitches?: StitchRepertoire;
+  marker_every?: number | null;
+  yarn_estimate?: YarnEstimateConfig;
+  crocheter?: CrocheterProfile;
+}
+
+export interface CrocheterProfile {
+  sc_per_minute?: number;
+  inc_penalty?: number;
+  dec_penalty?: number;
+  color_change_seconds?: number;
+  row_start_seconds?: number;
+}
+
+export interface YarnEstimateConfig {
+  weight?: YarnWeight | null;
+  skein_grams?: number;
+  calibration?: number;
+}
+
+/** Stitches the generator may use; the invisible decrease is preferred */
+export interface StitchRepertoire {
+  round?: StitchType[];
+  flat?: StitchType[];
+}
+
+export interface OptimizationConfig {
+  seed?: number | null;
+  iterations?: number | null;
+  cooling_rate?: number | null;
+  weights?: OptimizationWeights;
+  method?: PlacementMethod;
+  ridge_window?: number | null;
+}
+
+export type PlacementMethod = 'Even' | 'Annealing';
+
+export interface OptimizationWeights {
+  spacing?: number;
+  stagger?: number;
+  drift?: number;
+}
+
+export type Quality = 'Fast' | 'Balanced' | 'Best';
+
+export interface InputLimits {
+  max_segments: number;
+  max_rows: number;
+  max_stitches_per_row: number;
+  max_input_bytes: number;
 }
 
 export type StitchType = 'SC' | 'INC' | 'DEC' | 'INVDEC';
 
+export type Terminology = 'US' | 'UK';
+
 export interface StitchInstruction {
   stitch_type: StitchType;
   angular_position: number;
@@ -39,18 +115,404 @@ export interface Row {
   row_number: number;
   total_stitches: number;
   pattern: StitchInstruction[];
+  markers?: MarkerHint[];
+}
+
+export type MarkerHint =
+  | 'RoundStart'
+  | { Checkpoint: { worked: number; stitch: number; total: number } };
+
+export interface ShapeFidelity {
+  row_errors_cm: number[];
+  max_deviation_cm: number;
+  rms_deviation_cm: number;
+}
+
+export interface Dimensions {
+  height_cm: number;
+  width_cm: number;
 }
 
 export interface PatternMetadata {
   total_rows: number;
   total_stitches: number;
   estimated_time_minutes: number;
+  row_time_minutes?: number[];
   yarn_length_meters: number;
+  shape_fidelity: ShapeFidelity;
+  warnings: string[];
+  design_size: Dimensions;
+  finished_size: Dimensions;
+  gauge_estimated: boolean;
+  seed?: number | null;
+  iterations?: number | null;
+  cooling_rate?: number | null;
+  method?: PlacementMethod | null;
+  gauge_table: GaugeVariation[];
+  ridge_energy?: number | null;
+  yarn_by_color?: ColorYarnUsage[];
+  difficulty?: DifficultyRating | null;
+}
+
+export type Difficulty = 'Beginner' | 'Intermediate' | 'Advanced';
+
+export interface DifficultyRating {
+  score: number;
+  level: Difficulty;
+  skills: string[];
+}
+
+export interface ColorYarnUsage {
+  color: string;
+  meters: number;
+  grams: number;
+  skeins: number;
+}
+
+export interface GaugeVariation {
+  deviation_percent: number;
+  stitches_per_cm: number;
+  rows_per_cm: number;
+  finished_size: Dimensions;
+}
+
+export type Construction = 'Round' | 'Flat';
+
+export interface RowGroup {
+  first_row: number;
+  last_row: number;
+  total_stitches: number;
 }
 
 export interface CrochetPattern {
   rows: Row[];
   metadata: PatternMetadata;
+  row_groups: RowGroup[];
+  construction: Construction;
+  foundation: Foundation;
+  notes: string[];
+  anchors: Anchor[];
+  sections: Section[];
+  color_changes: ColorChange[];
+  connections: RowConnections[];
+}
+
+export interface RowConnections {
+  row_number: number;
+  worked_into: number[][];
+}
+
+export type Foundation = 'Chain' | 'SingleCrochet' | 'ChainRing';
+
+export interface RingJoinCheck {
+  chain_count: number;
+  marker_every: number;
+  marker_count: number;
+}
+
+export interface StandingPieces {
+  base: CrochetPattern;
+  body: CrochetPattern;
+}
+
+export interface Point3D {
+  x: number;
+  y: number;
+  z: number;
+}
+
+export interface StitchGlyph {
+  row_number: number;
+  stitch_index: number;
+  stitch_type: StitchType;
+  position: Point3D;
+  direction: Point3D;
+  normal: Point3D;
+  width_cm: number;
+  height_cm: number;
+}
+
+export interface StitchScene {
+  construction: Construction;
+  glyphs: StitchGlyph[];
+}
+
+export interface YarnMesh {
+  vertices: Point3D[];
+  normals: Point3D[];
+  indices: number[];
+}
+
+export interface ChartRowIndex {
+  row_number: number;
+  group_id: string;
+  stitch_ids: string[];
+}
+
+export interface PatternChange {
+  field: string;
+  before: string;
+  after: string;
+}
+
+export interface StitchChange {
+  stitch_index: number;
+  before: StitchType | null;
+  after: StitchType | null;
+}
+
+export interface RowDiff {
+  row_number: number;
+  before_stitches: number | null;
+  after_stitches: number | null;
+  stitch_changes: StitchChange[];
+}
+
+export interface Section {
+  name: string;
+  first_row: number;
+  last_row: number;
+}
+
+export type JogMethod = 'SlipStitch' | 'StandingStitch';
+
+export interface StripeConfig {
+  colors: string[];
+  rows_per_stripe: number;
+  jogless?: JogMethod | null;
+}
+
+export interface ColorChange {
+  row_number: number;
+  color: string;
+  jog: JogMethod | null;
+  start_shift: number;
+}
+
+export interface ColorRun {
+  color: string;
+  stitches: number;
+}
+
+export interface TapestryRow {
+  row_number: number;
+  runs: ColorRun[];
+}
+
+export interface TapestryChart {
+  construction: Construction;
+  rows: TapestryRow[];
+}
+
+export type FloatFix = 'Bobbin' | { Catch: { row_number: number; stitch: number } };
+
+export interface LongFloat {
+  row_number: number;
+  color: string;
+  start_stitch: number;
+  length: number;
+  fix: FloatFix;
+}
+
+export type ProblemKind =
+  | 'Numbering'
+  | 'StitchCount'
+  | 'TooFewStitches'
+  | 'DeltaCap'
+  | 'Limits'
+  | 'DisallowedStitch';
+
+export interface PatternProblem {
+  row_number: number | null;
+  kind: ProblemKind;
+  message: string;
+}
+
+export interface Anchor {
+  name: string;
+  row_number: number;
+  stitch: number;
+}
+
+export interface ColumnFlow {
+  rows: ColumnFlowRow[];
+}
+
+export interface ColumnFlowRow {
+  row_number: number;
+  stitches: FlowStitch[];
+}
+
+export interface FlowStitch {
+  stitch_type: StitchType;
+  worked_into: number[];
+}
+
+export type TextureStitch = 'Short' | 'Long';
+
+export type TextureColor = 'A' | 'B';
+
+export interface TextureConfig {
+  construction: Construction;
+  width_cm: number;
+  height_cm: number;
+  yarn: YarnSpec;
+  color_a: string;
+  color_b: string;
+  palette?: string | null;
+}
+
+export interface TextureGauge {
+  stitches_per_cm: number;
+  rows_per_cm: number;
+  long_yarn_scale: number;
+}
+
+export interface TextureRow {
+  row_number: number;
+  color: TextureColor;
+  stitch: TextureStitch;
+  stitches: number;
+}
+
+export interface TexturePattern {
+  construction: Construction;
+  color_a: string;
+  color_b: string;
+  rows: TextureRow[];
+  gauge: TextureGauge;
+  yarn_meters: [number, number];
+  finished_size: Dimensions;
+  notes: string[];
+  palette_colors: [PaletteColor | null, PaletteColor | null];
+}
+
+export interface YarnShade {
+  line: string;
+  shade: string;
+}
+
+export interface PaletteColor {
+  name: string;
+  hex: string;
+  part: string;
+  yarns: YarnShade[];
+}
+
+export interface ColorPalette {
+  name: string;
+  description: string;
+  colors: PaletteColor[];
+}
+
+export interface YarnSubstitution {
+  weight: YarnWeight;
+  config: AmigurumiConfig;
+  stitch_scale: number;
+  row_scale: number;
+  total_rows: number;
+}
+
+export type ExportFormat = 'text' | 'pdf' | 'markdown' | 'html' | 'crochetout' | 'ssml';
+
+export interface NoteSection {
+  title: string;
+  body: string;
+}
+
+export interface Branding {
+  header?: string | null;
+  footer?: string | null;
+  variables?: Record<string, string>;
+  sections?: NoteSection[];
+}
+
+export interface EpubMetadata {
+  title?: string;
+  author?: string | null;
+  language?: string;
+  /** Last-modified time, "YYYY-MM-DDThh:mm:ssZ" */
+  modified: string;
+}
+
+export interface ExportOptions {
+  format?: ExportFormat;
+  terminology?: Terminology;
+  branding?: Branding;
+  worked_into?: boolean;
+  palette?: DiagramPalette;
+  legend_thumbnails?: boolean;
+}
+
+export type DiagramPalette = 'Standard' | 'ColorblindSafe' | 'Grayscale' | 'HighContrast';
+
+export interface ProjectPiece {
+  name: string;
+  color: string;
+  pattern: CrochetPattern;
+  yarn: YarnSpec;
+  quantity?: number;
+  stuffed?: boolean;
+  safety_eyes?: number;
+}
+
+export interface YarnRequirement {
+  color: string;
+  meters: number;
+  skeins: number;
+}
+
+export interface ShoppingList {
+  yarn: YarnRequirement[];
+  hook_sizes_mm: number[];
+  stuffing_grams: number;
+  safety_eyes: number;
+  stitch_markers: number;
+}
+
+export interface StashYarn {
+  color: string;
+  weight: YarnWeight;
+  meters: number;
+}
+
+export interface StashMatch {
+  piece: string;
+  color: string;
+  weight: YarnWeight;
+  meters: number;
+  feasible: boolean;
+  use_color?: string | null;
+  use_weight?: YarnWeight | null;
+  shortfall_meters?: number;
+}
+
+export interface StashReport {
+  matches: StashMatch[];
+  feasible: boolean;
+}
+
+export type ErrorCode =
+  | 'InvalidJson'
+  | 'InvalidBinary'
+  | 'InputTooLarge'
+  | 'UnknownOption'
+  | 'InvalidProfileCurve'
+  | 'InvalidConfiguration'
+  | 'OptimizationFailure'
+  | 'UnreadableInstructions'
+  | 'Cancelled'
+  | 'SerializationFailure'
+  | 'InternalError';
+
+export type ErrorStage = 'Input' | 'Validation' | 'Generation' | 'Export';
+
+/** Error thrown (as a JSON string) by every WASM binding */
+export interface CrochetError {
+  code: ErrorCode;
+  stage: ErrorStage;
+  message: string;
+  details?: string | null;
 }
 
 export interface ValidationError {
@@ -69,3 +531,11 @@ export interface AppState {
   error: string | null;
   drawingTool: DrawingTool;
 }
+
+export interface CorpusComparison {
+  name: string;
+  expected_counts: number[];
+  generated_counts: number[];
+  max_row_difference: number;
+  total_stitch_difference: number;
+}
@@ -5,6 +5,107 @@ declare module '*wasm/crochet_wasm.js' {
     profile_json: string,
     config_json: string
   ): string;
+  export function generate_pattern_async(
+    profile_json: string,
+    config_json: string
+  ): Promise<string>;
+  export function generate_pattern_with_progress(
+    profile_json: string,
+    config_json: string,
+    on_progress: (stage: string, percent: number) => boolean | void
+  ): Promise<string>;
+  export function generate_pattern_streaming(
+    profile_json: string,
+    config_json: string,
+    on_rows: (rows_json: string) => boolean | void
+  ): Promise<string>;
+  export function generate_pattern_binary(profile_json: string, config_json: string): Uint8Array;
+  export function pattern_from_binary(pattern_cbor: Uint8Array): string;
+  export function pattern_to_binary(pattern_json: string): Uint8Array;
+  export function generate_gauge_swatch_from_json(yarn_json: string, foundation: string): string;
+  export function generate_shopping_list(pieces_json: string, format: string): string;
+  export function export_project_epub(
+    pieces_json: string,
+    metadata_json: string,
+    options_json: string
+  ): Uint8Array;
+  export function match_yarn_stash(pieces_json: string, stash_json: string): string;
+  export function substitute_yarn_weight(config_json: string, weight: string): string;
+  export function export_pattern(
+    pattern_json: string,
+    config_json: string,
+    options_json: string
+  ): string;
+  export function export_pattern_binary(
+    pattern_cbor: Uint8Array,
+    config_json: string,
+    options_json: string
+  ): Uint8Array;
+  export function import_written_pattern(
+    text: string,
+    config_json: string,
+    options_json: string
+  ): string;
+  export function place_safety_eyes_on_pattern(
+    pattern_json: string,
+    config_json: string,
+    row_number: number,
+    eye_distance_cm: number
+  ): string;
+  export function anchor_at_angle_on_pattern(
+    pattern_json: string,
+    name: string,
+    row_number: number,
+    angle_degrees: number
+  ): string;
+  export function separate_flat_base_from_pattern(
+    pattern_json: string,
+    config_json: string,
+    base_rows: number
+  ): string;
+  export function get_ring_join_check(pattern_json: string): string;
+  export function compare_pattern_json(before_json: string, after_json: string): string;
+  export function diff_pattern_json(a_json: string, b_json: string): string;
+  export function add_pattern_section(
+    pattern_json: string,
+    name: string,
+    first_row: number,
+    last_row: number
+  ): string;
+  export function rate_pattern_difficulty(pattern_json: string, pieces: number): string;
+  export function verify_pattern(pattern_json: string, config_json?: string | null): string;
+  export function add_pattern_stripes(pattern_json: string, stripes_json: string): string;
+  export function find_tapestry_floats(chart_json: string, max_float: number): string;
+  export function apply_stitch_repertoire(pattern_json: string, repertoire_json: string): string;
+  export function add_pattern_markers(pattern_json: string, every: number): string;
+  export function renumber_pattern_rows(pattern_json: string): string;
+  export function generate_symbol_chart(pattern_json: string, options_json: string): string;
+  export function symbol_chart_index(pattern_json: string): string;
+  export function generate_row_diagrams(pattern_json: string, options_json: string): string;
+  export function generate_stitch_ticker(pattern_json: string, options_json: string): string;
+  export function get_column_flow(pattern_json: string): string;
+  export function get_row_connections(pattern_json: string): string;
+  export function generate_column_flow_svg(pattern_json: string, options_json: string): string;
+  export function list_palettes(): string;
+  export function generate_texture_pattern_from_json(config_json: string): string;
+  export function texture_pattern_to_text(pattern_json: string, options_json: string): string;
+  export function generate_texture_chart(pattern_json: string, options_json: string): string;
+  export function estimate_gauge_from_yarn(
+    hook_size_mm: number,
+    weight: string,
+    fiber: string
+  ): string;
+  export function estimate_pattern_yarn(pattern_json: string, config_json: string): string;
+  export function estimate_pattern_time(pattern_json: string, config_json: string): string;
+  export function export_stitch_scene(pattern_json: string, config_json: string): string;
+  export function generate_yarn_preview(
+    pattern_json: string,
+    config_json: string,
+    format: string
+  ): string;
   export function validate_profile(profile_json: string): string;
   export function validate_config(config_json: string): string;
+  export function list_corpus_shapes(): string;
+  export function load_corpus_pattern_json(name: string, config_json: string): string;
+  export function compare_generator_with_corpus(config_json: string): string;
 }
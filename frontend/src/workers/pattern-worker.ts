@@ -1,5 +1,5 @@
 /// <reference path="../types/wasm.d.ts" />
-import type { ProfileCurve, AmigurumiConfig, CrochetPattern } from '../types';
+import type { ProfileCurve, AmigurumiConfig, CrochetPattern, CrochetError } from '../types';
 
 // WASM function references
 let generate_pattern_from_json: any = null;
@@ -28,6 +28,15 @@ async function initWasm(): Promise<void> {
   }
 }
 
+function describeWasmError(wasmError: unknown): string {
+  try {
+    const error: CrochetError = JSON.parse(String(wasmError));
+    return error.details ? `${error.message} (${error.details})` : error.message;
+  } catch {
+    return String(wasmError);
+  }
+}
+
 // Define the shape of incoming messages for internal type safety
 interface WorkerRequest {
   id: string;
@@ -59,8 +68,8 @@ self.onmessage = async (e: MessageEvent<WorkerRequest>) => {
           
           self.postMessage({ id, type: 'SUCCESS', payload: pattern });
         } catch (wasmError) {
-          // WASM threw an error
-          throw new Error(`Pattern generation failed: ${wasmError}`);
+          // WASM throws a CrochetError as JSON
+          throw new Error(`Pattern generation failed: ${describeWasmError(wasmError)}`);
         }
         break;
       }
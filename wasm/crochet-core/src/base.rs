@@ -0,0 +1,231 @@
+use crochet_types::*;
+use std::f64::consts::PI;
+
+use crate::generator::calculate_metadata;
+use crate::grouping::group_rows;
+use crate::lineage::row_connections;
+use crate::sections::renumber_rows;
+
+/// Share of flat-circle growth a round needs to count as part of the base
+const FLAT_GROWTH_SHARE: f64 = 0.9;
+
+/// Chains between stitch markers on a chain ring
+const RING_MARKER_EVERY: usize = 10;
+
+/// Split the bottom of a piece worked in the round into a separate flat base
+///
+/// The base is the first `base_rows` rounds, or when `None`, the support
+/// surface found by `detect_flat_base`. It is worked on its own and sewn
+/// into the body, whose first round becomes a chain ring of the base's
+/// final stitch count. A base sewn on this way gives a firm, flat bottom
+/// that a curved cap worked in one piece does not.
+pub fn separate_flat_base(
+    pattern: &CrochetPattern,
+    config: &AmigurumiConfig,
+    base_rows: Option<usize>,
+) -> Result<StandingPieces> {
+    if pattern.construction != Construction::Round {
+        return Err(PatternError::InvalidConfiguration(
+            "Only pieces worked in the round have a base to separate".to_string(),
+        ));
+    }
+    let base_rows = match base_rows {
+        Some(rows) => rows,
+        None => detect_flat_base(pattern, config).ok_or_else(|| {
+            PatternError::InvalidConfiguration("Pattern has no flat bottom to separate".to_string())
+        })?,
+    };
+    if base_rows < 2 || base_rows >= pattern.rows.len() {
+        return Err(PatternError::InvalidConfiguration(format!(
+            "Base must be between 2 and {} rounds",
+            pattern.rows.len().saturating_sub(1)
+        )));
+    }
+
+    let edge = &pattern.rows[base_rows - 1];
+    let edge_stitches = edge.total_stitches;
+
+    let base_rows_list = pattern.rows[..base_rows].to_vec();
+    let base = CrochetPattern {
+        metadata: calculate_metadata(&base_rows_list, config, Construction::Round),
+        row_groups: group_rows(&base_rows_list),
+        connections: row_connections(&base_rows_list, Construction::Round),
+        rows: base_rows_list,
+        construction: Construction::Round,
+        foundation: Foundation::Chain,
+        notes: vec![format!(
+            "Flat base: fasten off leaving a long tail, and sew the {} stitches of the last round to round 1 of the body.",
+            edge_stitches
+        )],
+        anchors: vec![],
+        sections: vec![],
+        color_changes: vec![],
+    };
+
+    // The body starts from a ring as wide as the base, in place of the base rounds
+    let ring = Row {
+        row_number: edge.row_number,
+        total_stitches: edge_stitches,
+        pattern: (0..edge_stitches)
+            .map(|i| StitchInstruction {
+                stitch_type: StitchType::SC,
+                angular_position: 2.0 * PI * i as f64 / edge_stitches as f64,
+                stitch_index: i,
+            })
+            .collect(),
+        markers: Vec::new(),
+    };
+    let mut body = CrochetPattern {
+        rows: std::iter::once(ring)
+            .chain(pattern.rows[base_rows..].iter().cloned())
+            .collect(),
+        foundation: Foundation::ChainRing,
+        ..pattern.clone()
+    };
+    renumber_rows(&mut body);
+    body.metadata = PatternMetadata {
+        warnings: pattern.metadata.warnings.clone(),
+        design_size: pattern.metadata.design_size,
+        seed: pattern.metadata.seed,
+        iterations: pattern.metadata.iterations,
+        cooling_rate: pattern.metadata.cooling_rate,
+        method: pattern.metadata.method,
+        ..calculate_metadata(&body.rows, config, Construction::Round)
+    };
+    if let Some(check) = chain_ring_check(&body) {
+        body.notes.extend(ring_join_notes(&check));
+    }
+    body.notes.push(format!(
+        "Sew the flat base into the ring of round 1 before stuffing ({} stitches each).",
+        edge_stitches
+    ));
+
+    Ok(StandingPieces { base, body })
+}
+
+/// Number of opening rounds that already lie flat
+///
+/// A round lies flat when it grows by about as many stitches as a flat
+/// circle would (2 pi x row height x stitches per cm). Returns `None` when
+/// fewer than two rounds do, or when every round does (nothing is left to
+/// be the body).
+pub fn detect_flat_base(pattern: &CrochetPattern, config: &AmigurumiConfig) -> Option<usize> {
+    let flat_growth = 2.0 * PI * config.yarn.gauge_stitches_per_cm / config.yarn.gauge_rows_per_cm;
+    let flat_rounds = 1 + pattern
+        .rows
+        .windows(2)
+        .take_while(|pair| {
+            let growth = pair[1].total_stitches as f64 - pair[0].total_stitches as f64;
+            growth >= FLAT_GROWTH_SHARE * flat_growth
+        })
+        .count();
+
+    (flat_rounds >= 2 && flat_rounds < pattern.rows.len()).then_some(flat_rounds)
+}
+
+/// Marker and count check for a pattern started on a chain ring
+///
+/// Returns `None` for any other foundation.
+pub fn chain_ring_check(pattern: &CrochetPattern) -> Option<RingJoinCheck> {
+    if pattern.foundation != Foundation::ChainRing {
+        return None;
+    }
+    let chain_count = pattern.rows.first()?.total_stitches;
+    let marker_every = RING_MARKER_EVERY.min(chain_count).max(1);
+    Some(RingJoinCheck {
+        chain_count,
+        marker_every,
+        marker_count: chain_count / marker_every,
+    })
+}
+
+/// Written steps for chaining, checking and joining the ring untwisted
+pub fn ring_join_notes(check: &RingJoinCheck) -> Vec<String> {
+    vec![
+        format!(
+            "Ring: as you chain, place a stitch marker in every {} chain ({} markers).",
+            ordinal(check.marker_every),
+            check.marker_count
+        ),
+        format!(
+            "Before joining, count the chains: {} in all, {} after the last marker.",
+            check.chain_count,
+            check.chain_count - check.marker_count * check.marker_every
+        ),
+        "Lay the chain flat with every marker on the same side; if one has flipped over, the chain is twisted - untwist it, then join with sl st to the first ch.".to_string(),
+    ]
+}
+
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::{corpus_shape, load_corpus_pattern};
+    use crate::formatter::PatternFormatter;
+
+    #[test]
+    fn test_sphere_base_is_detected_and_split() {
+        let config = AmigurumiConfig::default();
+        let sphere = load_corpus_pattern(&corpus_shape("sphere").unwrap(), &config).unwrap();
+
+        assert_eq!(detect_flat_base(&sphere, &config), Some(10));
+        let pieces = separate_flat_base(&sphere, &config, Some(6)).unwrap();
+
+        assert_eq!(pieces.base.rows.len(), 6);
+        assert_eq!(pieces.base.rows[5].total_stitches, 36);
+        assert_eq!(pieces.body.foundation, Foundation::ChainRing);
+        assert_eq!(pieces.body.rows.len(), sphere.rows.len() - 5);
+        assert_eq!(pieces.body.rows[0].total_stitches, 36);
+        assert_eq!(pieces.body.rows[1].total_stitches, 42);
+        let lines = PatternFormatter::default().instruction_lines(&pieces.body);
+        assert_eq!(
+            lines[0].1,
+            "Ch 36, join with sl st to form a ring, SC in each ch around (36)"
+        );
+        assert_eq!(
+            chain_ring_check(&pieces.body),
+            Some(RingJoinCheck {
+                chain_count: 36,
+                marker_every: 10,
+                marker_count: 3,
+            })
+        );
+        assert!(pieces.body.notes.iter().any(|n| n
+            == "Ring: as you chain, place a stitch marker in every 10th chain (3 markers)."));
+        assert!(pieces
+            .body
+            .notes
+            .iter()
+            .any(|n| n == "Before joining, count the chains: 36 in all, 6 after the last marker."));
+        assert_eq!(chain_ring_check(&pieces.base), None);
+        assert_eq!(
+            pieces.body.metadata.total_stitches,
+            pieces
+                .body
+                .rows
+                .iter()
+                .map(|r| r.total_stitches)
+                .sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn test_pointed_bottom_has_no_base() {
+        let config = AmigurumiConfig::default();
+        let cone = load_corpus_pattern(&corpus_shape("cone").unwrap(), &config).unwrap();
+
+        assert_eq!(detect_flat_base(&cone, &config), None);
+        assert!(separate_flat_base(&cone, &config, None).is_err());
+        assert!(separate_flat_base(&cone, &config, Some(1)).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+use crochet_types::*;
+use std::collections::BTreeSet;
+
+/// Relative difference below which two measurements count as equal
+const FLOAT_TOLERANCE: f64 = 1e-6;
+
+/// Semantic differences between two versions of a pattern
+///
+/// Rows are compared by their stitch counts and written stitch sequences,
+/// so angular positions and other float noise never show up; notes,
+/// warnings, anchors, sections and color changes are compared as sets.
+/// Row groups and fit measurements derived from the rows are not
+/// compared. An empty result means a crocheter would make the same piece
+/// from both.
+pub fn compare_patterns(before: &CrochetPattern, after: &CrochetPattern) -> Vec<PatternChange> {
+    let mut changes = Vec::new();
+    let mut push = |field: &str, old: String, new: String| {
+        if old != new {
+            changes.push(PatternChange {
+                field: field.to_string(),
+                before: old,
+                after: new,
+            });
+        }
+    };
+
+    push(
+        "construction",
+        format!("{:?}", before.construction),
+        format!("{:?}", after.construction),
+    );
+    push(
+        "foundation",
+        format!("{:?}", before.foundation),
+        format!("{:?}", after.foundation),
+    );
+    push(
+        "rows",
+        before.rows.len().to_string(),
+        after.rows.len().to_string(),
+    );
+
+    let row_count = before.rows.len().max(after.rows.len());
+    for idx in 0..row_count {
+        let field = format!("row {}", idx + 1);
+        push(
+            &field,
+            before.rows.get(idx).map(row_summary).unwrap_or_default(),
+            after.rows.get(idx).map(row_summary).unwrap_or_default(),
+        );
+    }
+
+    let metadata = [
+        (
+            "metadata.total_stitches",
+            before.metadata.total_stitches as f64,
+            after.metadata.total_stitches as f64,
+        ),
+        (
+            "metadata.yarn_length_meters",
+            before.metadata.yarn_length_meters,
+            after.metadata.yarn_length_meters,
+        ),
+        (
+            "metadata.estimated_time_minutes",
+            before.metadata.estimated_time_minutes,
+            after.metadata.estimated_time_minutes,
+        ),
+        (
+            "metadata.finished_size.height_cm",
+            before.metadata.finished_size.height_cm,
+            after.metadata.finished_size.height_cm,
+        ),
+        (
+            "metadata.finished_size.width_cm",
+            before.metadata.finished_size.width_cm,
+            after.metadata.finished_size.width_cm,
+        ),
+    ];
+    for (field, a, b) in metadata {
+        if !nearly_equal(a, b) {
+            push(field, a.to_string(), b.to_string());
+        }
+    }
+
+    let set_fields = [
+        ("notes", set_of(&before.notes), set_of(&after.notes)),
+        (
+            "metadata.warnings",
+            set_of(&before.metadata.warnings),
+            set_of(&after.metadata.warnings),
+        ),
+        (
+            "anchors",
+            before.anchors.iter().map(anchor_summary).collect(),
+            after.anchors.iter().map(anchor_summary).collect(),
+        ),
+        (
+            "sections",
+            before.sections.iter().map(section_summary).collect(),
+            after.sections.iter().map(section_summary).collect(),
+        ),
+        (
+            "color_changes",
+            before.color_changes.iter().map(color_summary).collect(),
+            after.color_changes.iter().map(color_summary).collect(),
+        ),
+    ];
+    for (field, a, b) in set_fields {
+        for removed in a.difference(&b) {
+            push(field, removed.clone(), String::new());
+        }
+        for added in b.difference(&a) {
+            push(field, String::new(), added.clone());
+        }
+    }
+
+    changes
+}
+
+/// Row-by-row diff of two generations of a pattern
+///
+/// Rows are matched by position and only rows that differ are listed:
+/// the stitch count on each side, and every instruction whose stitch type
+/// changed. Angular positions are ignored, as in `compare_patterns`, so a
+/// row whose stitches only moved a little shows no change.
+pub fn diff_patterns(before: &CrochetPattern, after: &CrochetPattern) -> Vec<RowDiff> {
+    let row_count = before.rows.len().max(after.rows.len());
+    (0..row_count)
+        .filter_map(|idx| {
+            let (a, b) = (before.rows.get(idx), after.rows.get(idx));
+            let types = |row: Option<&Row>| -> Vec<StitchType> {
+                row.map(|r| r.pattern.iter().map(|s| s.stitch_type).collect())
+                    .unwrap_or_default()
+            };
+            let (a_types, b_types) = (types(a), types(b));
+            let stitch_changes: Vec<StitchChange> = (0..a_types.len().max(b_types.len()))
+                .map(|i| StitchChange {
+                    stitch_index: i,
+                    before: a_types.get(i).copied(),
+                    after: b_types.get(i).copied(),
+                })
+                .filter(|c| c.before != c.after)
+                .collect();
+            let (before_stitches, after_stitches) =
+                (a.map(|r| r.total_stitches), b.map(|r| r.total_stitches));
+            if before_stitches == after_stitches && stitch_changes.is_empty() {
+                return None;
+            }
+            Some(RowDiff {
+                row_number: idx + 1,
+                before_stitches,
+                after_stitches,
+                stitch_changes,
+            })
+        })
+        .collect()
+}
+
+/// "18: 2 SC, (SC, INC) x 8" - what a crocheter works for the row
+fn row_summary(row: &Row) -> String {
+    format!("{}: {}", row.total_stitches, row.pattern_string())
+}
+
+fn anchor_summary(anchor: &Anchor) -> String {
+    format!(
+        "{} (row {}, stitch {})",
+        anchor.name, anchor.row_number, anchor.stitch
+    )
+}
+
+fn section_summary(section: &Section) -> String {
+    format!(
+        "{} (rows {}-{})",
+        section.name, section.first_row, section.last_row
+    )
+}
+
+fn color_summary(change: &ColorChange) -> String {
+    format!("{} (row {})", change.color, change.row_number)
+}
+
+fn set_of(items: &[String]) -> BTreeSet<String> {
+    items.iter().cloned().collect()
+}
+
+fn nearly_equal(a: f64, b: f64) -> bool {
+    (a - b).abs() <= FLOAT_TOLERANCE * a.abs().max(b.abs()).max(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_pattern;
+    use crate::sections::add_section;
+
+    fn create_test_pattern() -> CrochetPattern {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, 1.0),
+                control2: Point2D::new(3.0, 5.0),
+                end: Point2D::new(2.0, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 2.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: 6.0,
+            ..Default::default()
+        };
+        generate_pattern(&curve, &config).unwrap()
+    }
+
+    #[test]
+    fn test_float_noise_and_ordering_ignored() {
+        let before = create_test_pattern();
+        let mut after = before.clone();
+        for stitch in &mut after.rows[3].pattern {
+            stitch.angular_position += 1e-9;
+        }
+        after.metadata.yarn_length_meters *= 1.0 + 1e-9;
+        after.notes = vec!["Stuff firmly.".to_string(), "Use a marker.".to_string()];
+        let mut reordered = before.clone();
+        reordered.notes = vec!["Use a marker.".to_string(), "Stuff firmly.".to_string()];
+        after.row_groups.clear();
+
+        assert!(compare_patterns(&before, &before).is_empty());
+        assert!(compare_patterns(&reordered, &after).is_empty());
+    }
+
+    #[test]
+    fn test_semantic_changes_reported() {
+        let before = create_test_pattern();
+        let mut after = before.clone();
+        after.rows.pop();
+        after.metadata.total_stitches -= 1;
+        add_section(&mut after, "Head", 1, 3).unwrap();
+
+        let changes = compare_patterns(&before, &after);
+        let fields: Vec<&str> = changes.iter().map(|c| c.field.as_str()).collect();
+
+        assert!(fields.contains(&"rows"));
+        assert!(fields.contains(&"metadata.total_stitches"));
+        let last_row = changes
+            .iter()
+            .find(|c| c.field == format!("row {}", before.rows.len()))
+            .unwrap();
+        assert!(last_row.after.is_empty());
+        assert!(changes
+            .iter()
+            .any(|c| c.field == "sections" && c.after == "Head (rows 1-3)"));
+    }
+
+    #[test]
+    fn test_row_diff_lists_changed_stitches() {
+        let before = create_test_pattern();
+        let mut after = before.clone();
+        after.rows[2].pattern[0].stitch_type = StitchType::INC;
+        after.rows[2].total_stitches += 1;
+        after.rows.pop();
+        for stitch in &mut after.rows[4].pattern {
+            stitch.angular_position += 0.01;
+        }
+
+        let diff = diff_patterns(&before, &after);
+
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff[0].row_number, 3);
+        assert_eq!(
+            (diff[0].before_stitches, diff[0].after_stitches),
+            (
+                Some(before.rows[2].total_stitches),
+                Some(before.rows[2].total_stitches + 1)
+            )
+        );
+        assert_eq!(
+            diff[0].stitch_changes,
+            [StitchChange {
+                stitch_index: 0,
+                before: Some(before.rows[2].pattern[0].stitch_type),
+                after: Some(StitchType::INC),
+            }]
+        );
+        let removed = &diff[1];
+        assert_eq!(removed.row_number, before.rows.len());
+        assert_eq!(removed.after_stitches, None);
+        assert!(removed.stitch_changes.iter().all(|c| c.after.is_none()));
+
+        assert!(diff_patterns(&before, &before).is_empty());
+    }
+}
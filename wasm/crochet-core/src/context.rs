@@ -0,0 +1,125 @@
+use crochet_types::*;
+use std::collections::BTreeMap;
+
+use crate::generator::generate_pattern;
+
+/// Named configuration presets shared between generation requests
+///
+/// Generation keeps no state between calls (the placement optimizer seeds
+/// a fresh RNG per pattern), so one context can sit behind an `Arc` or a
+/// `static` and serve any number of threads at once; only the preset a
+/// request names is read, nothing is cloned per call.
+#[derive(Debug, Clone, Default)]
+pub struct GenerationContext {
+    presets: BTreeMap<String, AmigurumiConfig>,
+}
+
+impl GenerationContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace a preset
+    pub fn with_preset(mut self, name: &str, config: AmigurumiConfig) -> Self {
+        self.presets.insert(name.to_string(), config);
+        self
+    }
+
+    pub fn preset(&self, name: &str) -> Option<&AmigurumiConfig> {
+        self.presets.get(name)
+    }
+
+    pub fn preset_names(&self) -> impl Iterator<Item = &str> {
+        self.presets.keys().map(String::as_str)
+    }
+
+    /// Generate a pattern for `curve` with the named preset
+    pub fn generate(&self, preset: &str, curve: &ProfileCurve) -> Result<CrochetPattern> {
+        let config = self.preset(preset).ok_or_else(|| {
+            PatternError::InvalidConfiguration(format!("Unknown preset: {}", preset))
+        })?;
+        generate_pattern(curve, config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram::DiagramGenerator;
+    use crate::formatter::PatternFormatter;
+    use crate::optimization::PlacementOptions;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    fn create_test_curve(end_radius: f64) -> ProfileCurve {
+        ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, 1.0),
+                control2: Point2D::new(3.0, 5.0),
+                end: Point2D::new(end_radius, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius,
+        }
+    }
+
+    #[test]
+    fn test_shared_types_are_send_sync() {
+        assert_send_sync::<GenerationContext>();
+        assert_send_sync::<PatternFormatter>();
+        assert_send_sync::<DiagramGenerator>();
+        assert_send_sync::<PlacementOptions>();
+    }
+
+    #[test]
+    fn test_concurrent_generation_matches_sequential() {
+        let context = GenerationContext::new()
+            .with_preset(
+                "small",
+                AmigurumiConfig {
+                    total_height_cm: 6.0,
+                    ..Default::default()
+                },
+            )
+            .with_preset(
+                "balanced",
+                AmigurumiConfig {
+                    total_height_cm: 6.0,
+                    balance_decreases: true,
+                    ..Default::default()
+                },
+            );
+        let curves: Vec<ProfileCurve> = (1..=4).map(|r| create_test_curve(r as f64)).collect();
+
+        let stitches = |pattern: &CrochetPattern| -> Vec<Vec<StitchType>> {
+            pattern
+                .rows
+                .iter()
+                .map(|r| r.pattern.iter().map(|s| s.stitch_type).collect())
+                .collect()
+        };
+        let sequential: Vec<_> = curves
+            .iter()
+            .map(|curve| stitches(&context.generate("balanced", curve).unwrap()))
+            .collect();
+
+        let concurrent: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = curves
+                .iter()
+                .map(|curve| {
+                    let context = &context;
+                    scope.spawn(move || stitches(&context.generate("balanced", curve).unwrap()))
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        assert_eq!(sequential, concurrent);
+        assert_eq!(
+            context.preset_names().collect::<Vec<_>>(),
+            ["balanced", "small"]
+        );
+        assert!(context.generate("missing", &curves[0]).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use crochet_types::*;
+use std::f64::consts::PI;
+
+use crate::generator::generate_pattern;
+use crate::parser::parse_written_pattern;
+
+/// Canonical pattern bundled with the crate
+#[derive(Debug, Clone, Copy)]
+pub struct CorpusShape {
+    pub name: &'static str,
+    /// Written pattern in US terms, readable by `parse_written_pattern`
+    pub text: &'static str,
+}
+
+/// Well-known shapes that the generator should reproduce closely
+pub const CORPUS: [CorpusShape; 3] = [
+    CorpusShape {
+        name: "sphere",
+        text: include_str!("../corpus/sphere.txt"),
+    },
+    CorpusShape {
+        name: "cone",
+        text: include_str!("../corpus/cone.txt"),
+    },
+    CorpusShape {
+        name: "egg",
+        text: include_str!("../corpus/egg.txt"),
+    },
+];
+
+/// Look up a corpus shape by name
+pub fn corpus_shape(name: &str) -> Option<CorpusShape> {
+    CORPUS.iter().copied().find(|shape| shape.name == name)
+}
+
+/// Parse a corpus pattern, checking that its counts add up
+pub fn load_corpus_pattern(
+    shape: &CorpusShape,
+    config: &AmigurumiConfig,
+) -> Result<CrochetPattern> {
+    parse_written_pattern(shape.text, config, Terminology::US)
+}
+
+/// Profile curve of the shape a pattern crochets up into at `yarn`'s gauge
+///
+/// Each row becomes a point whose radius comes from its stitch count and
+/// whose height is its row number; neighbouring points are joined by
+/// straight segments.
+pub fn pattern_profile(pattern: &CrochetPattern, yarn: &YarnSpec) -> Result<ProfileCurve> {
+    if pattern.rows.len() < 2 {
+        return Err(PatternError::InvalidConfiguration(
+            "A profile needs at least two rows".to_string(),
+        ));
+    }
+
+    let points: Vec<Point2D> = pattern
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            Point2D::new(
+                row.total_stitches as f64 / yarn.gauge_stitches_per_cm / (2.0 * PI),
+                i as f64 / yarn.gauge_rows_per_cm,
+            )
+        })
+        .collect();
+
+    let segments = points
+        .windows(2)
+        .map(|pair| {
+            let (a, b) = (pair[0], pair[1]);
+            SplineSegment {
+                start: a,
+                control1: Point2D::new(a.x + (b.x - a.x) / 3.0, a.y + (b.y - a.y) / 3.0),
+                control2: Point2D::new(
+                    a.x + 2.0 * (b.x - a.x) / 3.0,
+                    a.y + 2.0 * (b.y - a.y) / 3.0,
+                ),
+                end: b,
+            }
+        })
+        .collect();
+
+    Ok(ProfileCurve {
+        segments,
+        start_radius: points[0].x,
+        end_radius: points[points.len() - 1].x,
+    })
+}
+
+/// Generate a corpus shape from its profile and compare with the original
+///
+/// The generator gets the canonical pattern's height and the gauge from
+/// `config`; everything else in `config` is used as given.
+pub fn compare_with_corpus(
+    shape: &CorpusShape,
+    config: &AmigurumiConfig,
+) -> Result<CorpusComparison> {
+    let canonical = load_corpus_pattern(shape, config)?;
+    let curve = pattern_profile(&canonical, &config.yarn)?;
+    let config = AmigurumiConfig {
+        total_height_cm: canonical.rows.len() as f64 / config.yarn.gauge_rows_per_cm,
+        ..config.clone()
+    };
+    let generated = generate_pattern(&curve, &config)?;
+
+    let expected_counts: Vec<usize> = canonical.rows.iter().map(|r| r.total_stitches).collect();
+    let generated_counts: Vec<usize> = generated.rows.iter().map(|r| r.total_stitches).collect();
+
+    // Rows are matched by relative height in case the row counts differ
+    let max_row_difference = generated_counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            let j = if generated_counts.len() > 1 {
+                i * (expected_counts.len() - 1) / (generated_counts.len() - 1)
+            } else {
+                0
+            };
+            count.abs_diff(expected_counts[j])
+        })
+        .max()
+        .unwrap_or(0);
+
+    Ok(CorpusComparison {
+        name: shape.name.to_string(),
+        total_stitch_difference: generated_counts.iter().sum::<usize>() as i64
+            - expected_counts.iter().sum::<usize>() as i64,
+        expected_counts,
+        generated_counts,
+        max_row_difference,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corpus_patterns_parse() {
+        let config = AmigurumiConfig::default();
+        for shape in &CORPUS {
+            let pattern = load_corpus_pattern(shape, &config).unwrap();
+            assert_eq!(pattern.rows[0].total_stitches, 6, "{}", shape.name);
+        }
+        let sphere = load_corpus_pattern(&corpus_shape("sphere").unwrap(), &config).unwrap();
+        assert_eq!(sphere.rows.iter().map(|r| r.total_stitches).max(), Some(60));
+    }
+
+    #[test]
+    fn test_generator_matches_corpus() {
+        let config = AmigurumiConfig::default();
+        for shape in &CORPUS {
+            let comparison = compare_with_corpus(shape, &config).unwrap();
+            assert_eq!(
+                comparison.generated_counts.len(),
+                comparison.expected_counts.len()
+            );
+            assert!(comparison.max_row_difference <= 1, "{:?}", comparison);
+        }
+    }
+}
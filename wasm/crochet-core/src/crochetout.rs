@@ -0,0 +1,157 @@
+use crochet_types::*;
+
+use crate::markers::marker_instruction;
+
+/// Version written in the `;!crochetout-N` magic line
+pub const CROCHETOUT_VERSION: u32 = 1;
+
+/// Machine-readable instruction stream, in the spirit of Knitout
+///
+/// The stream opens with a `;!crochetout-1` magic line and `;;Key: value`
+/// headers, followed by one operation per line:
+///
+/// - `mr <n>`: magic ring that the first round's `n` stitches are worked into
+/// - `ch <n>`: foundation chain of `n` chains (flat work, or a chain ring)
+/// - `join`: slip stitch the chain just made into a ring
+/// - `fsc ...`: in place of `sc` on a first row of foundation single crochet,
+///   which has no separate chain
+/// - `<stitch> <row> <index> <consumes> <produces> <angle>`: one stitch, where
+///   `stitch` is `sc`, `inc`, `dec` or `invdec`, `consumes` is how many
+///   stitches of the previous row it is worked into, `produces` is how many
+///   it adds to this row and `angle` is its position in radians
+/// - `turn`: turn the work and chain 1 (flat work, between rows)
+///
+/// Comments start with `;` and may be ignored by readers; `; Section: <name>`
+/// comments mark where each of the pattern's sections begins, and
+/// `; Marker: <hint>` comments give a row's stitch-marker hints.
+pub fn to_crochetout(pattern: &CrochetPattern) -> String {
+    let mut out = format!(";!crochetout-{}\n", CROCHETOUT_VERSION);
+    let construction = match pattern.construction {
+        Construction::Round => "round",
+        Construction::Flat => "flat",
+    };
+    out.push_str(&format!(";;Construction: {}\n", construction));
+    out.push_str(&format!(";;Rows: {}\n", pattern.rows.len()));
+    out.push_str(&format!(";;Stitches: {}\n", pattern.metadata.total_stitches));
+    out.push_str(";;Columns: op row index consumes produces angle\n");
+
+    for (row_idx, row) in pattern.rows.iter().enumerate() {
+        if let Some(section) = pattern.sections.iter().find(|s| s.first_row == row.row_number) {
+            out.push_str(&format!("; Section: {}\n", section.name));
+        }
+        for marker in &row.markers {
+            out.push_str(&format!("; Marker: {}\n", marker_instruction(marker)));
+        }
+
+        let foundation_row = row_idx == 0
+            && pattern.construction == Construction::Flat
+            && pattern.foundation == Foundation::SingleCrochet;
+        if row_idx == 0 && !foundation_row {
+            // The first row is worked into a ring or chain that has no stitches of its own
+            if pattern.foundation == Foundation::ChainRing {
+                out.push_str(&format!("ch {}\njoin\n", row.total_stitches));
+            } else {
+                let base = match pattern.construction {
+                    Construction::Round => "mr",
+                    Construction::Flat => "ch",
+                };
+                out.push_str(&format!("{} {}\n", base, row.total_stitches));
+            }
+        } else if row_idx > 0 && pattern.construction == Construction::Flat {
+            out.push_str("turn\n");
+        }
+
+        for stitch in &row.pattern {
+            let (consumes, produces) = if row_idx == 0 {
+                (0, 1)
+            } else {
+                stitch_io(stitch.stitch_type)
+            };
+            let op = if foundation_row {
+                "fsc".to_string()
+            } else {
+                stitch.stitch_type.to_string().to_lowercase()
+            };
+            out.push_str(&format!(
+                "{} {} {} {} {} {:.4}\n",
+                op,
+                row.row_number,
+                stitch.stitch_index,
+                consumes,
+                produces,
+                stitch.angular_position
+            ));
+        }
+    }
+
+    out
+}
+
+/// Stitches consumed from the previous row and produced in this one
+fn stitch_io(stitch_type: StitchType) -> (usize, usize) {
+    match stitch_type {
+        StitchType::SC => (1, 1),
+        StitchType::INC => (1, 2),
+        StitchType::DEC | StitchType::INVDEC => (2, 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_pattern;
+    use crate::swatch::generate_gauge_swatch;
+
+    /// Stitch lines as (row, consumes, produces)
+    fn stitch_lines(stream: &str) -> Vec<(usize, usize, usize)> {
+        stream
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .filter(|fields| fields.len() == 6)
+            .map(|f| (f[1].parse().unwrap(), f[3].parse().unwrap(), f[4].parse().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_rows_consume_previous_row() {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, 1.0),
+                control2: Point2D::new(3.0, 5.0),
+                end: Point2D::new(1.0, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 1.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: 6.0,
+            ..Default::default()
+        };
+        let pattern = generate_pattern(&curve, &config).unwrap();
+
+        let stream = to_crochetout(&pattern);
+        assert!(stream.starts_with(";!crochetout-1\n"));
+        assert!(stream.contains("\nmr 6\n"));
+
+        let lines = stitch_lines(&stream);
+        assert_eq!(lines.len(), pattern.rows.iter().map(|r| r.pattern.len()).sum::<usize>());
+        for pair in pattern.rows.windows(2) {
+            let (prev, row) = (&pair[0], &pair[1]);
+            let ops: Vec<_> = lines.iter().filter(|l| l.0 == row.row_number).collect();
+            assert_eq!(ops.iter().map(|l| l.1).sum::<usize>(), prev.total_stitches);
+            assert_eq!(ops.iter().map(|l| l.2).sum::<usize>(), row.total_stitches);
+        }
+    }
+
+    #[test]
+    fn test_flat_work_chains_and_turns() {
+        let pattern = generate_gauge_swatch(&YarnSpec::default()).unwrap();
+
+        let stream = to_crochetout(&pattern);
+
+        assert!(stream.contains(";;Construction: flat"));
+        assert!(stream.contains(&format!("\nch {}\n", pattern.rows[0].total_stitches)));
+        assert_eq!(stream.matches("\nturn\n").count(), pattern.rows.len() - 1);
+    }
+}
@@ -0,0 +1,1055 @@
+use crochet_types::*;
+use std::f64::consts::PI;
+
+use crate::formatter::{escape_html, PatternFormatter, LEGEND_STITCHES};
+use crate::parser::consumes;
+
+/// Margin around the chart, in SVG user units
+const CHART_MARGIN: f64 = 20.0;
+const LEGEND_ROW_HEIGHT: f64 = 22.0;
+/// Legend row height and sketch box width when how-to sketches are drawn
+const THUMBNAIL_SIZE: f64 = 36.0;
+const LABEL_EVERY: usize = 5;
+/// Size of one row's cell in the stitch-count ticker
+const TICKER_CELL_WIDTH: f64 = 36.0;
+const TICKER_CELL_HEIGHT: f64 = 44.0;
+
+/// Renders stitch diagrams as SVG
+#[derive(Debug, Clone)]
+pub struct DiagramGenerator {
+    pub terminology: Terminology,
+    /// Distance between neighbouring rounds or stitches
+    pub stitch_spacing: f64,
+    pub palette: DiagramPalette,
+    /// Draw a how-to sketch of each legend stitch (and of the starting
+    /// ring for work in the round)
+    pub legend_thumbnails: bool,
+}
+
+impl Default for DiagramGenerator {
+    fn default() -> Self {
+        Self {
+            terminology: Terminology::US,
+            stitch_spacing: 14.0,
+            palette: DiagramPalette::Standard,
+            legend_thumbnails: false,
+        }
+    }
+}
+
+impl DiagramGenerator {
+    pub fn new(terminology: Terminology) -> Self {
+        Self {
+            terminology,
+            ..Default::default()
+        }
+    }
+
+    /// Standard crochet symbol chart (x for sc, V for inc, A for dec)
+    ///
+    /// Work in the round is drawn as concentric rounds starting at the top
+    /// and running clockwise, with each symbol pointing away from the
+    /// centre. Flat work is drawn as rows from the bottom up, alternating
+    /// direction as the work is turned. A legend is placed under the chart.
+    pub fn generate_symbol_chart(&self, pattern: &CrochetPattern) -> String {
+        let s = self.stitch_spacing;
+        let mut body = String::new();
+
+        let (chart_width, chart_height) = match pattern.construction {
+            Construction::Round => {
+                let extent = (pattern.rows.len() as f64 + 0.5) * s;
+                let center = CHART_MARGIN + extent;
+
+                for (ring, row) in pattern.rows.iter().enumerate() {
+                    let radius = (ring as f64 + 0.5) * s;
+                    body.push_str(&row_group_start(row.row_number));
+                    for stitch in &row.pattern {
+                        let theta = stitch.angular_position;
+                        let x = center + radius * theta.sin();
+                        let y = center - radius * theta.cos();
+                        body.push_str(&self.chart_symbol(row, stitch, x, y, theta.to_degrees()));
+                    }
+                    if row.row_number == 1 || row.row_number % LABEL_EVERY == 0 {
+                        body.push_str(&row_label(row.row_number, center - s * 0.6, center - radius));
+                    }
+                    body.push_str("</g>\n");
+                }
+
+                (2.0 * (CHART_MARGIN + extent), 2.0 * (CHART_MARGIN + extent))
+            }
+            Construction::Flat => {
+                let widest = pattern.rows.iter().map(|r| r.pattern.len()).max().unwrap_or(0);
+                let left = CHART_MARGIN + s;
+                let rows = pattern.rows.len();
+
+                for (i, row) in pattern.rows.iter().enumerate() {
+                    let y = CHART_MARGIN + (rows - i) as f64 * s - s / 2.0;
+                    let len = row.pattern.len();
+                    body.push_str(&row_group_start(row.row_number));
+                    for (j, stitch) in row.pattern.iter().enumerate() {
+                        // Even rows are worked back across after turning
+                        let column = if i % 2 == 0 { j } else { len - 1 - j };
+                        let x = left + (column as f64 + 0.5) * s;
+                        body.push_str(&self.chart_symbol(row, stitch, x, y, 0.0));
+                    }
+                    body.push_str(&row_label(row.row_number, left - s * 0.3, y));
+                    body.push_str("</g>\n");
+                }
+
+                (
+                    left + widest as f64 * s + CHART_MARGIN,
+                    2.0 * CHART_MARGIN + rows as f64 * s,
+                )
+            }
+        };
+
+        let legend_top = chart_height;
+        let (row_height, thumbnail_width) = if self.legend_thumbnails {
+            (THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        } else {
+            (LEGEND_ROW_HEIGHT, 0.0)
+        };
+        let thumbnail_x = CHART_MARGIN + THUMBNAIL_SIZE / 2.0;
+        let symbol_x = CHART_MARGIN + thumbnail_width + s / 2.0;
+        let text_x = CHART_MARGIN + thumbnail_width + s * 1.5;
+        let legend_row = |i: usize| legend_top + (i as f64 + 0.5) * row_height;
+        let mut legend_rows = 0;
+        for (stitch_type, (abbr, description)) in LEGEND_STITCHES
+            .iter()
+            .zip(PatternFormatter::new(self.terminology).abbreviation_legend())
+        {
+            let y = legend_row(legend_rows);
+            if self.legend_thumbnails {
+                body.push_str(&self.stitch_thumbnail(*stitch_type, thumbnail_x, y));
+            }
+            body.push_str(&self.symbol(*stitch_type, symbol_x, y, 0.0));
+            body.push_str(&legend_text(
+                text_x,
+                y,
+                &format!("{}: {}", escape_html(abbr), escape_html(description)),
+            ));
+            legend_rows += 1;
+        }
+        if self.legend_thumbnails && pattern.construction == Construction::Round {
+            let y = legend_row(legend_rows);
+            let (sketch, label) = ring_thumbnail(pattern.foundation, thumbnail_x, y);
+            body.push_str(&sketch);
+            body.push_str(&legend_text(symbol_x, y, label));
+            legend_rows += 1;
+        }
+
+        let width = chart_width.max(320.0);
+        let height = legend_top + legend_rows as f64 * row_height + CHART_MARGIN;
+
+        let stroke_width = self.stroke_width();
+
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" viewBox=\"0 0 {w:.2} {h:.2}\" \
+             font-family=\"sans-serif\" role=\"img\" aria-label=\"{title}\">\n<title>{title}</title>\n\
+             <g fill=\"none\" stroke=\"#000\" stroke-width=\"{sw}\" stroke-linecap=\"round\" stroke-linejoin=\"round\">\n\
+             {body}</g>\n</svg>\n",
+            w = width,
+            h = height,
+            title = escape_html(&chart_description(pattern)),
+            sw = stroke_width,
+            body = body
+        )
+    }
+
+    /// One small chart per row, to show beside its written instruction
+    ///
+    /// The stitches of the row below are dots, in working order from the
+    /// top for work in the round or from the left for flat work, and each
+    /// symbol of the row sits over the stitches it is worked into, linked
+    /// to them. The first round is drawn around its starting ring. Returns
+    /// one SVG per row, in row order.
+    pub fn generate_row_diagrams(&self, pattern: &CrochetPattern) -> Vec<String> {
+        pattern
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(idx, row)| {
+                let below = idx.checked_sub(1).map(|i| pattern.rows[i].total_stitches);
+                match pattern.construction {
+                    Construction::Round => self.round_diagram(row, below),
+                    // Turned rows are read the other way
+                    Construction::Flat => self.flat_diagram(row, below, idx % 2 == 1),
+                }
+            })
+            .collect()
+    }
+
+    fn round_diagram(&self, row: &Row, below: Option<usize>) -> String {
+        let s = self.stitch_spacing;
+        let below_count = below.unwrap_or(0);
+        let around = row.pattern.len().max(below_count).max(6);
+        let outer = around as f64 * s / (2.0 * PI) + s;
+        let inner = outer - s;
+        let center = CHART_MARGIN + outer + s / 2.0;
+        let point = |radius: f64, theta: f64| {
+            (center + radius * theta.sin(), center - radius * theta.cos())
+        };
+        let anchor_angle = |k: f64| 2.0 * PI * (k + 0.5) / below_count as f64;
+
+        let mut body = String::new();
+        if below.is_none() {
+            body.push_str(&format!(
+                "<circle class=\"ring\" cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\"/>\n",
+                center, center, inner
+            ));
+        }
+        for k in 0..below_count {
+            let (x, y) = point(inner, anchor_angle(k as f64));
+            body.push_str(&anchor_dot(x, y));
+        }
+        let mut worked = 0;
+        for (i, stitch) in row.pattern.iter().enumerate() {
+            let theta = match below {
+                Some(_) => {
+                    let used = consumes(stitch.stitch_type);
+                    let theta = anchor_angle(worked as f64 + (used as f64 - 1.0) / 2.0);
+                    let mut links = String::new();
+                    for k in worked..worked + used {
+                        let (x1, y1) = point(inner, anchor_angle(k as f64));
+                        let (x2, y2) = point(outer - s * 0.35, theta);
+                        links.push_str(&format!("M{:.2},{:.2}L{:.2},{:.2}", x1, y1, x2, y2));
+                    }
+                    body.push_str(&link_path(&links));
+                    worked += used;
+                    theta
+                }
+                None => 2.0 * PI * (i as f64 + 0.5) / row.pattern.len() as f64,
+            };
+            let (x, y) = point(outer, theta);
+            body.push_str(&self.symbol(stitch.stitch_type, x, y, theta.to_degrees()));
+        }
+        body.push_str(&format!(
+            "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"10\" fill=\"#666\" stroke=\"none\" \
+             text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>\n",
+            center, center, row.row_number
+        ));
+
+        mini_svg(row, 2.0 * center, 2.0 * center, self.stroke_width(), &body)
+    }
+
+    fn flat_diagram(&self, row: &Row, below: Option<usize>, turned: bool) -> String {
+        let s = self.stitch_spacing;
+        let below_count = below.unwrap_or(0);
+        let across = row.pattern.len().max(below_count);
+        let width = across as f64 * s + 2.0 * CHART_MARGIN;
+        let x = |k: f64, count: usize| {
+            let k = if turned { count as f64 - 1.0 - k } else { k };
+            CHART_MARGIN + (k + 0.5) * s
+        };
+        let (anchor_y, symbol_y) = (CHART_MARGIN + 1.5 * s, CHART_MARGIN + s / 2.0);
+
+        let mut body = String::new();
+        for k in 0..below_count {
+            body.push_str(&anchor_dot(x(k as f64, below_count), anchor_y));
+        }
+        let mut worked = 0;
+        for (i, stitch) in row.pattern.iter().enumerate() {
+            let cx = match below {
+                Some(_) => {
+                    let used = consumes(stitch.stitch_type);
+                    let cx = x(worked as f64 + (used as f64 - 1.0) / 2.0, below_count);
+                    let mut links = String::new();
+                    for k in worked..worked + used {
+                        links.push_str(&format!(
+                            "M{:.2},{:.2}L{:.2},{:.2}",
+                            x(k as f64, below_count),
+                            anchor_y,
+                            cx,
+                            symbol_y + s * 0.35
+                        ));
+                    }
+                    body.push_str(&link_path(&links));
+                    worked += used;
+                    cx
+                }
+                None => x(i as f64, row.pattern.len()),
+            };
+            body.push_str(&self.symbol(stitch.stitch_type, cx, symbol_y, 0.0));
+        }
+
+        let height = 2.0 * CHART_MARGIN + 2.0 * s;
+        mini_svg(row, width, height, self.stroke_width(), &body)
+    }
+
+    /// Element ids the symbol chart gives each row and stitch
+    ///
+    /// Every row of `generate_symbol_chart` is a `<g>` carrying
+    /// `data-row`, and every stitch symbol in it carries `data-row` and
+    /// `data-stitch-id`. The ids depend only on row numbers and stitch
+    /// positions, so they stay the same when the chart is redrawn.
+    pub fn symbol_chart_index(&self, pattern: &CrochetPattern) -> Vec<ChartRowIndex> {
+        pattern
+            .rows
+            .iter()
+            .map(|row| ChartRowIndex {
+                row_number: row.row_number,
+                group_id: row_group_id(row.row_number),
+                stitch_ids: row
+                    .pattern
+                    .iter()
+                    .map(|stitch| stitch_id(row.row_number, stitch.stitch_index))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Compact strip with one cell per row: row number, stitch count, and
+    /// "+n" / "-n" badges for the increases and decreases worked in it
+    pub fn generate_stitch_ticker(&self, pattern: &CrochetPattern) -> String {
+        let mut body = String::new();
+
+        for (i, row) in pattern.rows.iter().enumerate() {
+            let x = i as f64 * TICKER_CELL_WIDTH;
+            let mid = x + TICKER_CELL_WIDTH / 2.0;
+            body.push_str(&format!(
+                "<g class=\"row\"><title>Row {n}: {t} stitches</title>\
+                 <rect x=\"{x:.2}\" y=\"0\" width=\"{w:.2}\" height=\"{h:.2}\" fill=\"#fff\" stroke=\"#999\"/>\
+                 <text x=\"{mid:.2}\" y=\"10\" font-size=\"8\" fill=\"#666\">{n}</text>\
+                 <text x=\"{mid:.2}\" y=\"25\" font-size=\"13\" font-weight=\"bold\" fill=\"#000\">{t}</text>",
+                n = row.row_number,
+                t = row.total_stitches,
+                x = x,
+                w = TICKER_CELL_WIDTH,
+                h = TICKER_CELL_HEIGHT,
+                mid = mid
+            ));
+
+            let count = |types: &[StitchType]| {
+                row.pattern
+                    .iter()
+                    .filter(|s| types.contains(&s.stitch_type))
+                    .count()
+            };
+            let badges = [
+                (StitchType::INC, "+", count(&[StitchType::INC])),
+                (StitchType::DEC, "-", count(&[StitchType::DEC, StitchType::INVDEC])),
+            ];
+            let shown: Vec<_> = badges.iter().filter(|(_, _, n)| *n > 0).collect();
+            for (j, (stitch_type, sign, n)) in shown.iter().enumerate() {
+                let (r, g, b) = self.palette.stitch_color(*stitch_type);
+                let badge_x = mid + (j as f64 - (shown.len() - 1) as f64 / 2.0) * 16.0;
+                body.push_str(&format!(
+                    "<text class=\"{}\" x=\"{:.2}\" y=\"38\" font-size=\"8\" fill=\"#{:02x}{:02x}{:02x}\">{}{}</text>",
+                    stitch_type.to_string().to_lowercase(),
+                    badge_x,
+                    (r * 255.0).round() as u8,
+                    (g * 255.0).round() as u8,
+                    (b * 255.0).round() as u8,
+                    sign,
+                    n
+                ));
+            }
+            body.push_str("</g>\n");
+        }
+
+        let width = pattern.rows.len() as f64 * TICKER_CELL_WIDTH;
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" viewBox=\"0 0 {w:.2} {h:.2}\" \
+             font-family=\"sans-serif\" text-anchor=\"middle\" role=\"img\" aria-label=\"{title}\">\n\
+             <title>{title}</title>\n{body}</svg>\n",
+            w = width,
+            h = TICKER_CELL_HEIGHT,
+            title = format!("Stitch counts of {} rows", pattern.rows.len()),
+            body = body
+        )
+    }
+
+    /// Fabric flow: every stitch linked to the stitches below it was worked into
+    ///
+    /// Each round is unrolled into a row across the full width, first
+    /// round at the bottom, so a column reads straight up the diagram;
+    /// increases show as links forking apart and decreases as links
+    /// merging, colored by stitch type. Every fifth round is labelled.
+    pub fn generate_column_flow(&self, flow: &ColumnFlow) -> String {
+        let s = self.stitch_spacing;
+        let widest = flow.rows.iter().map(|r| r.stitches.len()).max().unwrap_or(0);
+        let width = widest as f64 * s / 2.0;
+        let row_count = flow.rows.len();
+        let y = |idx: usize| CHART_MARGIN + (row_count - 1 - idx) as f64 * s;
+        let x = |stitch: usize, total: usize| {
+            CHART_MARGIN + (stitch as f64 + 0.5) / total as f64 * width
+        };
+
+        let mut body = String::new();
+        for (idx, row) in flow.rows.iter().enumerate() {
+            body.push_str(&format!(
+                "<g class=\"row\"><title>Row {}: {} stitches</title>\n",
+                row.row_number,
+                row.stitches.len()
+            ));
+            if row.row_number % LABEL_EVERY == 0 || idx == 0 {
+                body.push_str(&row_label(row.row_number, CHART_MARGIN - 4.0, y(idx)));
+            }
+            if idx > 0 {
+                let below = flow.rows[idx - 1].stitches.len();
+                for stitch_type in LEGEND_STITCHES {
+                    let mut d = String::new();
+                    for (i, stitch) in row.stitches.iter().enumerate() {
+                        if stitch.stitch_type != stitch_type {
+                            continue;
+                        }
+                        for &from in &stitch.worked_into {
+                            d.push_str(&format!(
+                                "M{:.2},{:.2}L{:.2},{:.2}",
+                                x(from - 1, below),
+                                y(idx - 1),
+                                x(i, row.stitches.len()),
+                                y(idx)
+                            ));
+                        }
+                    }
+                    if d.is_empty() {
+                        continue;
+                    }
+                    let (r, g, b) = self.palette.stitch_color(stitch_type);
+                    body.push_str(&format!(
+                        "<path class=\"{}\" stroke=\"#{:02x}{:02x}{:02x}\" d=\"{}\"/>\n",
+                        stitch_type.to_string().to_lowercase(),
+                        (r * 255.0).round() as u8,
+                        (g * 255.0).round() as u8,
+                        (b * 255.0).round() as u8,
+                        d
+                    ));
+                }
+            }
+            body.push_str("</g>\n");
+        }
+
+        let total_width = width + 2.0 * CHART_MARGIN;
+        let total_height = row_count.saturating_sub(1) as f64 * s + 2.0 * CHART_MARGIN;
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" viewBox=\"0 0 {w:.2} {h:.2}\" \
+             font-family=\"sans-serif\" fill=\"none\" stroke-width=\"0.8\" stroke-linecap=\"round\" \
+             role=\"img\" aria-label=\"{title}\">\n<title>{title}</title>\n{body}</svg>\n",
+            w = total_width,
+            h = total_height,
+            title = format!("Stitch columns through {} rounds", row_count),
+            body = body
+        )
+    }
+
+    /// Grid chart of a two-color texture, first row at the bottom
+    ///
+    /// Short stitches are drawn as an x and long stitches as a barred line
+    /// reaching down into the row below, each in its row's yarn color.
+    /// Every fifth row is labelled and a legend goes under the chart.
+    pub fn generate_texture_chart(&self, pattern: &TexturePattern) -> String {
+        let s = self.stitch_spacing;
+        let h = s * 0.35;
+        let row_count = pattern.rows.len();
+        let widest = pattern.rows.iter().map(|r| r.stitches).max().unwrap_or(0);
+        let y = |idx: usize| CHART_MARGIN + (row_count - idx) as f64 * s - s / 2.0;
+        let x = |stitch: usize| CHART_MARGIN + (stitch as f64 + 0.5) * s;
+        let color = |c: TextureColor| {
+            escape_html(match c {
+                TextureColor::A => &pattern.color_a,
+                TextureColor::B => &pattern.color_b,
+            })
+        };
+        let symbol = |stitch: TextureStitch, cx: f64, cy: f64| match stitch {
+            TextureStitch::Short => format!(
+                "M{:.2},{:.2}L{:.2},{:.2}M{:.2},{:.2}L{:.2},{:.2}",
+                cx - h * 0.8,
+                cy - h * 0.8,
+                cx + h * 0.8,
+                cy + h * 0.8,
+                cx - h * 0.8,
+                cy + h * 0.8,
+                cx + h * 0.8,
+                cy - h * 0.8
+            ),
+            // Reaches down past its own row into the stitch below
+            TextureStitch::Long => format!(
+                "M{:.2},{:.2}L{:.2},{:.2}M{:.2},{:.2}L{:.2},{:.2}",
+                cx,
+                cy - h,
+                cx,
+                cy + s,
+                cx - h * 0.6,
+                cy - h,
+                cx + h * 0.6,
+                cy - h
+            ),
+        };
+
+        let mut body = String::new();
+        for (idx, row) in pattern.rows.iter().enumerate() {
+            if row.row_number % LABEL_EVERY == 0 || idx == 0 {
+                body.push_str(&row_label(row.row_number, CHART_MARGIN - 4.0, y(idx)));
+            }
+            let d: String = (0..row.stitches)
+                .map(|i| symbol(row.stitch, x(i), y(idx)))
+                .collect();
+            body.push_str(&format!(
+                "<path class=\"{}\" stroke=\"{}\" d=\"{}\"/>\n",
+                match row.stitch {
+                    TextureStitch::Short => "short",
+                    TextureStitch::Long => "long",
+                },
+                color(row.color),
+                d
+            ));
+        }
+
+        let mut legend_y = CHART_MARGIN + row_count as f64 * s + LEGEND_ROW_HEIGHT;
+        for stitch in [TextureStitch::Short, TextureStitch::Long] {
+            body.push_str(&format!(
+                "<path stroke=\"#000\" d=\"{}\"/>\n",
+                symbol(stitch, CHART_MARGIN + s / 2.0, legend_y - s / 2.0)
+            ));
+            body.push_str(&legend_text(
+                CHART_MARGIN + s * 1.5,
+                legend_y - s / 2.0,
+                &format!(
+                    "{}: {}",
+                    escape_html(&stitch.abbreviation(self.terminology)),
+                    escape_html(&stitch.description(self.terminology))
+                ),
+            ));
+            legend_y += LEGEND_ROW_HEIGHT;
+        }
+        for (label, c) in [("Color A", TextureColor::A), ("Color B", TextureColor::B)] {
+            body.push_str(&format!(
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\" stroke=\"none\"/>\n",
+                CHART_MARGIN,
+                legend_y - s,
+                s,
+                s,
+                color(c)
+            ));
+            body.push_str(&legend_text(CHART_MARGIN + s * 1.5, legend_y - s / 2.0, label));
+            legend_y += LEGEND_ROW_HEIGHT;
+        }
+
+        let total_width = (widest as f64 * s + 2.0 * CHART_MARGIN).max(300.0);
+        let total_height = legend_y + CHART_MARGIN - LEGEND_ROW_HEIGHT;
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" viewBox=\"0 0 {w:.2} {h:.2}\" \
+             font-family=\"sans-serif\" fill=\"none\" stroke-width=\"1.2\" stroke-linecap=\"round\" \
+             role=\"img\" aria-label=\"{title}\">\n<title>{title}</title>\n{body}</svg>\n",
+            w = total_width,
+            h = total_height,
+            title = format!(
+                "Texture chart of {} rows in {} and {}",
+                row_count,
+                color(TextureColor::A),
+                color(TextureColor::B)
+            ),
+            body = body
+        )
+    }
+
+    /// One stitch symbol centred on (x, y); local "up" points away from the centre
+    fn symbol(&self, stitch_type: StitchType, x: f64, y: f64, rotation_deg: f64) -> String {
+        let h = self.stitch_spacing * 0.35;
+        let path = match stitch_type {
+            StitchType::SC => format!(
+                "M{a:.2},{a:.2}L{b:.2},{b:.2}M{a:.2},{b:.2}L{b:.2},{a:.2}",
+                a = -h * 0.8,
+                b = h * 0.8
+            ),
+            StitchType::INC => format!("M{:.2},{:.2}L0,{:.2}L{:.2},{:.2}", -h, -h, h, h, -h),
+            StitchType::DEC => format!("M{:.2},{:.2}L0,{:.2}L{:.2},{:.2}", -h, h, -h, h, h),
+            // Decrease symbol with a bar under it to mark the front-loop method
+            StitchType::INVDEC => format!(
+                "M{:.2},{:.2}L0,{:.2}L{:.2},{:.2}M{:.2},{:.2}L{:.2},{:.2}",
+                -h,
+                h * 0.6,
+                -h,
+                h,
+                h * 0.6,
+                -h,
+                h,
+                h,
+                h
+            ),
+        };
+        format!(
+            "<path class=\"{}\"{} transform=\"translate({:.2} {:.2}) rotate({:.2})\" d=\"{}\"/>\n",
+            stitch_type.to_string().to_lowercase(),
+            self.stroke(stitch_type),
+            x,
+            y,
+            rotation_deg,
+            path
+        )
+    }
+
+    /// Stitch symbol of the chart, tagged with its row and stitch
+    fn chart_symbol(
+        &self,
+        row: &Row,
+        stitch: &StitchInstruction,
+        x: f64,
+        y: f64,
+        rotation_deg: f64,
+    ) -> String {
+        let symbol = self.symbol(stitch.stitch_type, x, y, rotation_deg);
+        let id = stitch_id(row.row_number, stitch.stitch_index);
+        symbol.replacen(
+            " transform=",
+            &format!(
+                " id=\"{}\" data-row=\"{}\" data-stitch-id=\"{}\" transform=",
+                id, row.row_number, id
+            ),
+            1,
+        )
+    }
+
+    fn stroke_width(&self) -> f64 {
+        match self.palette {
+            DiagramPalette::HighContrast => 2.0,
+            _ => 1.2,
+        }
+    }
+
+    /// Stroke color attribute for a stitch; the standard chart is plain black
+    fn stroke(&self, stitch_type: StitchType) -> String {
+        match self.palette {
+            DiagramPalette::Standard => String::new(),
+            palette => {
+                let (r, g, b) = palette.stitch_color(stitch_type);
+                format!(
+                    " stroke=\"#{:02x}{:02x}{:02x}\"",
+                    (r * 255.0).round() as u8,
+                    (g * 255.0).round() as u8,
+                    (b * 255.0).round() as u8
+                )
+            }
+        }
+    }
+
+    /// How-to sketch of a stitch centred on (x, y)
+    ///
+    /// The loops of the row below are drawn as ovals at the bottom and the
+    /// new stitches as strokes rising from them, with the hook's path
+    /// shown as an arrow.
+    fn stitch_thumbnail(&self, stitch_type: StitchType, x: f64, y: f64) -> String {
+        let sketch = match stitch_type {
+            // Hook goes under both loops of one stitch
+            StitchType::SC => concat!(
+                "<ellipse cx=\"-6\" cy=\"10\" rx=\"5\" ry=\"2.5\"/>",
+                "<ellipse cx=\"6\" cy=\"10\" rx=\"5\" ry=\"2.5\"/>",
+                "<path d=\"M-6,-12L-6,7M-8.5,4L-6,7L-3.5,4\"/>",
+                "<path d=\"M2,-10L10,-2M2,-2L10,-10\"/>"
+            ),
+            // Two stitches worked into the same stitch
+            StitchType::INC => concat!(
+                "<ellipse cx=\"0\" cy=\"10\" rx=\"5\" ry=\"2.5\"/>",
+                "<path d=\"M-9,-10L0,7L9,-10\"/>",
+                "<path d=\"M-12,-10L-6,-10M6,-10L12,-10\"/>"
+            ),
+            // One stitch worked across two, joined at the top
+            StitchType::DEC => concat!(
+                "<ellipse cx=\"-7\" cy=\"10\" rx=\"5\" ry=\"2.5\"/>",
+                "<ellipse cx=\"7\" cy=\"10\" rx=\"5\" ry=\"2.5\"/>",
+                "<path d=\"M-7,7L0,-10L7,7\"/>",
+                "<path d=\"M-3,-10L3,-10\"/>"
+            ),
+            // Front loops only (solid); the back loops (dashed) are left alone
+            StitchType::INVDEC => concat!(
+                "<path d=\"M-12,10A5,2.5 0 0 0 -2,10M2,10A5,2.5 0 0 0 12,10\" stroke-width=\"2\"/>",
+                "<path d=\"M-12,10A5,2.5 0 0 1 -2,10M2,10A5,2.5 0 0 1 12,10\" stroke-dasharray=\"1.5 1.5\"/>",
+                "<path d=\"M-15,14L15,14M12,11.5L15,14L12,16.5\"/>",
+                "<path d=\"M-7,9L0,-10L7,9\"/>"
+            ),
+        };
+        format!(
+            "<g class=\"howto-{}\"{} transform=\"translate({:.2} {:.2})\">{}</g>\n",
+            stitch_type.to_string().to_lowercase(),
+            self.stroke(stitch_type),
+            x,
+            y,
+            sketch
+        )
+    }
+}
+
+/// How-to sketch of the starting ring for work in the round, and its label
+fn ring_thumbnail(foundation: Foundation, x: f64, y: f64) -> (String, &'static str) {
+    let (class, sketch, label) = match foundation {
+        Foundation::ChainRing => (
+            "howto-chain-ring",
+            (0..8)
+                .map(|i| {
+                    let angle = i as f64 * 45.0;
+                    format!(
+                        "<ellipse cx=\"0\" cy=\"-9\" rx=\"3.5\" ry=\"2\" transform=\"rotate({:.0})\"/>",
+                        angle
+                    )
+                })
+                .collect::<String>()
+                + "<circle cx=\"0\" cy=\"-9\" r=\"1.2\" fill=\"#000\"/>",
+            "Chain ring: chains joined into a ring with a sl st",
+        ),
+        // Any other start in the round is a magic ring
+        _ => (
+            "howto-magic-ring",
+            (0..6)
+                .map(|i| {
+                    let angle = i as f64 * 60.0;
+                    format!("<path d=\"M0,-7L0,-12\" transform=\"rotate({:.0})\"/>", angle)
+                })
+                .collect::<String>()
+                + "<circle cx=\"0\" cy=\"0\" r=\"7\"/><path d=\"M5,5Q10,9 14,14\"/>",
+            "Magic ring: work round 1 into an adjustable loop, then pull the tail to close it",
+        ),
+    };
+    (
+        format!(
+            "<g class=\"{}\" transform=\"translate({:.2} {:.2})\">{}</g>\n",
+            class, x, y, sketch
+        ),
+        label,
+    )
+}
+
+/// Text alternative for a pattern's symbol chart, for screen readers and
+/// the alt text of exported documents
+pub fn chart_description(pattern: &CrochetPattern) -> String {
+    let (unit, worked) = match pattern.construction {
+        Construction::Round => ("rounds", "worked in the round"),
+        Construction::Flat => ("rows", "worked flat"),
+    };
+    let counts = pattern.rows.iter().map(|r| r.total_stitches);
+    format!(
+        "Stitch chart of {} {} {}, with {} to {} stitches each",
+        pattern.rows.len(),
+        unit,
+        worked,
+        counts.clone().min().unwrap_or(0),
+        counts.max().unwrap_or(0)
+    )
+}
+
+fn legend_text(x: f64, y: f64, text: &str) -> String {
+    format!(
+        "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"12\" fill=\"#000\" stroke=\"none\" \
+         dominant-baseline=\"middle\">{}</text>\n",
+        x, y, text
+    )
+}
+
+/// A stitch of the row below in a row diagram
+fn anchor_dot(x: f64, y: f64) -> String {
+    format!(
+        "<circle class=\"anchor\" cx=\"{:.2}\" cy=\"{:.2}\" r=\"1.5\" fill=\"#999\" stroke=\"none\"/>\n",
+        x, y
+    )
+}
+
+fn link_path(d: &str) -> String {
+    format!(
+        "<path class=\"link\" stroke=\"#bbb\" stroke-width=\"0.6\" d=\"{}\"/>\n",
+        d
+    )
+}
+
+fn mini_svg(row: &Row, width: f64, height: f64, stroke_width: f64, body: &str) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" viewBox=\"0 0 {w:.2} {h:.2}\" \
+         font-family=\"sans-serif\" data-row=\"{n}\" role=\"img\" aria-label=\"Row {n}\">\n<title>Row {n}</title>\n\
+         <g fill=\"none\" stroke=\"#000\" stroke-width=\"{sw}\" stroke-linecap=\"round\" stroke-linejoin=\"round\">\n\
+         {body}</g>\n</svg>\n",
+        w = width,
+        h = height,
+        n = row.row_number,
+        sw = stroke_width,
+        body = body
+    )
+}
+
+fn row_group_id(row_number: usize) -> String {
+    format!("row-{}", row_number)
+}
+
+fn stitch_id(row_number: usize, stitch_index: usize) -> String {
+    format!("row-{}-stitch-{}", row_number, stitch_index)
+}
+
+fn row_group_start(row_number: usize) -> String {
+    format!(
+        "<g id=\"{}\" class=\"row\" data-row=\"{}\">\n",
+        row_group_id(row_number),
+        row_number
+    )
+}
+
+fn row_label(row_number: usize, x: f64, y: f64) -> String {
+    format!(
+        "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"8\" fill=\"#666\" stroke=\"none\" \
+         text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>\n",
+        x, y, row_number
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_pattern;
+    use crate::swatch::generate_gauge_swatch;
+
+    #[test]
+    fn test_round_chart_has_symbol_per_stitch() {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, 1.0),
+                control2: Point2D::new(3.0, 5.0),
+                end: Point2D::new(2.0, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 2.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: 6.0,
+            ..Default::default()
+        };
+        let pattern = generate_pattern(&curve, &config).unwrap();
+
+        let svg = DiagramGenerator::default().generate_symbol_chart(&pattern);
+
+        let stitches: usize = pattern.rows.iter().map(|r| r.pattern.len()).sum();
+        // One symbol per stitch plus one per legend entry
+        assert_eq!(svg.matches("<path ").count(), stitches + LEGEND_STITCHES.len());
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains(">SC: Single Crochet</text>"));
+        // Announced as one image with a text alternative
+        let description = chart_description(&pattern);
+        assert!(description.starts_with("Stitch chart of "));
+        assert!(description.contains("worked in the round, with 6 to "));
+        assert!(svg.contains(&format!(
+            "role=\"img\" aria-label=\"{0}\">\n<title>{0}</title>",
+            description
+        )));
+    }
+
+    #[test]
+    fn test_rows_are_grouped_for_highlighting() {
+        let pattern = generate_gauge_swatch(&YarnSpec::default()).unwrap();
+        let generator = DiagramGenerator::default();
+
+        let svg = generator.generate_symbol_chart(&pattern);
+        let index = generator.symbol_chart_index(&pattern);
+
+        assert_eq!(index.len(), pattern.rows.len());
+        assert_eq!(svg.matches("<g id=\"row-").count(), pattern.rows.len());
+        for (row, entry) in pattern.rows.iter().zip(&index) {
+            assert_eq!(entry.row_number, row.row_number);
+            assert!(svg.contains(&format!(
+                "<g id=\"{}\" class=\"row\" data-row=\"{}\">",
+                entry.group_id, row.row_number
+            )));
+            assert_eq!(entry.stitch_ids.len(), row.pattern.len());
+            for id in &entry.stitch_ids {
+                assert_eq!(svg.matches(&format!(" id=\"{}\"", id)).count(), 1);
+            }
+        }
+        assert!(
+            svg.contains("id=\"row-2-stitch-0\" data-row=\"2\" data-stitch-id=\"row-2-stitch-0\"")
+        );
+        // Legend symbols are not part of any row
+        let stitches: usize = pattern.rows.iter().map(|r| r.pattern.len()).sum();
+        assert_eq!(svg.matches("data-stitch-id=").count(), stitches);
+    }
+
+    #[test]
+    fn test_palette_colors_symbols() {
+        let pattern = generate_gauge_swatch(&YarnSpec::default()).unwrap();
+
+        let standard = DiagramGenerator::default().generate_symbol_chart(&pattern);
+        assert!(!standard.contains("<path class=\"sc\" stroke="));
+
+        let generator = DiagramGenerator {
+            palette: DiagramPalette::ColorblindSafe,
+            ..Default::default()
+        };
+        let svg = generator.generate_symbol_chart(&pattern);
+        assert!(svg.contains("<path class=\"inc\" stroke=\"#0073b3\""));
+        // Shapes still tell the stitches apart
+        let sc = svg.lines().find(|l| l.contains("class=\"sc\"")).unwrap();
+        let inc = svg.lines().find(|l| l.contains("class=\"inc\"")).unwrap();
+        assert_ne!(sc.split(" d=").nth(1), inc.split(" d=").nth(1));
+    }
+
+    #[test]
+    fn test_legend_thumbnails() {
+        let swatch = generate_gauge_swatch(&YarnSpec::default()).unwrap();
+        let plain = DiagramGenerator::default().generate_symbol_chart(&swatch);
+        assert!(!plain.contains("class=\"howto-"));
+
+        let generator = DiagramGenerator {
+            legend_thumbnails: true,
+            ..Default::default()
+        };
+        let svg = generator.generate_symbol_chart(&swatch);
+        for stitch_type in LEGEND_STITCHES {
+            let class = format!("class=\"howto-{}\"", stitch_type.to_string().to_lowercase());
+            assert_eq!(svg.matches(&class).count(), 1);
+        }
+        // Flat work has no starting ring to explain
+        assert!(!svg.contains("ring"));
+        // Symbols are unchanged, so the chart itself still has one per stitch
+        let stitches: usize = swatch.rows.iter().map(|r| r.pattern.len()).sum();
+        assert_eq!(svg.matches("<path class=\"sc\"").count(), stitches + 1);
+
+        let mut round = swatch.clone();
+        round.construction = Construction::Round;
+        let svg = generator.generate_symbol_chart(&round);
+        assert!(svg.contains("class=\"howto-magic-ring\""));
+        assert!(svg.contains(">Magic ring: "));
+        round.foundation = Foundation::ChainRing;
+        let svg = generator.generate_symbol_chart(&round);
+        assert!(svg.contains("class=\"howto-chain-ring\""));
+    }
+
+    #[test]
+    fn test_ticker_has_cell_per_row() {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, 1.0),
+                control2: Point2D::new(3.0, 5.0),
+                end: Point2D::new(1.0, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 1.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: 6.0,
+            ..Default::default()
+        };
+        let pattern = generate_pattern(&curve, &config).unwrap();
+
+        let svg = DiagramGenerator::default().generate_stitch_ticker(&pattern);
+
+        assert_eq!(svg.matches("<g class=\"row\">").count(), pattern.rows.len());
+        assert!(svg.contains("<title>Row 1: 6 stitches</title>"));
+        assert!(svg.contains(">+6</text>"));
+        let decreasing = pattern
+            .rows
+            .windows(2)
+            .any(|pair| pair[1].total_stitches < pair[0].total_stitches);
+        assert_eq!(svg.contains("class=\"dec\""), decreasing);
+    }
+
+    #[test]
+    fn test_column_flow_links_rounds() {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, 1.0),
+                control2: Point2D::new(3.0, 5.0),
+                end: Point2D::new(1.0, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 1.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: 6.0,
+            ..Default::default()
+        };
+        let pattern = generate_pattern(&curve, &config).unwrap();
+        let flow = crate::lineage::column_flow(&pattern).unwrap();
+
+        let svg = DiagramGenerator::default().generate_column_flow(&flow);
+
+        assert_eq!(svg.matches("<g class=\"row\">").count(), pattern.rows.len());
+        assert!(svg.contains("<title>Row 1: 6 stitches</title>"));
+        // Round 2 of a magic ring start is all increases: 12 links forking from 6
+        let round_2 = svg.split("<title>Row 2:").nth(1).unwrap();
+        let round_2 = &round_2[..round_2.find("</g>").unwrap()];
+        assert_eq!(round_2.matches('M').count(), 12);
+        assert!(round_2.contains("class=\"inc\""));
+        assert!(svg.contains("class=\"dec\"") || svg.contains("class=\"invdec\""));
+    }
+
+    #[test]
+    fn test_row_diagrams_link_stitches_to_the_row_below() {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, 1.0),
+                control2: Point2D::new(3.0, 5.0),
+                end: Point2D::new(1.0, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 1.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: 6.0,
+            ..Default::default()
+        };
+        let pattern = generate_pattern(&curve, &config).unwrap();
+
+        let diagrams = DiagramGenerator::default().generate_row_diagrams(&pattern);
+
+        assert_eq!(diagrams.len(), pattern.rows.len());
+        assert!(diagrams[0].contains("class=\"ring\""));
+        assert!(!diagrams[0].contains("class=\"anchor\""));
+        for (idx, (row, svg)) in pattern.rows.iter().zip(&diagrams).enumerate().skip(1) {
+            assert!(svg.starts_with("<svg "));
+            assert!(svg.contains(&format!("<title>Row {}</title>", row.row_number)));
+            let below = pattern.rows[idx - 1].total_stitches;
+            assert_eq!(svg.matches("class=\"anchor\"").count(), below);
+            assert_eq!(
+                svg.matches("<path class=\"link\"").count(),
+                row.pattern.len()
+            );
+            // Every stitch of the row below is worked into exactly once
+            let links: usize = svg
+                .lines()
+                .filter(|l| l.starts_with("<path class=\"link\""))
+                .map(|l| l.matches('M').count())
+                .sum();
+            assert_eq!(links, below);
+        }
+
+        let swatch = generate_gauge_swatch(&YarnSpec::default()).unwrap();
+        let flat = DiagramGenerator::default().generate_row_diagrams(&swatch);
+        assert_eq!(flat.len(), swatch.rows.len());
+        assert_eq!(
+            flat[1].matches("class=\"anchor\"").count(),
+            swatch.rows[0].total_stitches
+        );
+    }
+
+    #[test]
+    fn test_flat_chart_uses_uk_legend() {
+        let pattern = generate_gauge_swatch(&YarnSpec::default()).unwrap();
+
+        let svg = DiagramGenerator::new(Terminology::UK).generate_symbol_chart(&pattern);
+
+        assert!(svg.contains(">DC: Double Crochet (US single crochet)</text>"));
+        let stitches: usize = pattern.rows.iter().map(|r| r.pattern.len()).sum();
+        assert_eq!(svg.matches("class=\"sc\"").count(), stitches + 1);
+    }
+
+    #[test]
+    fn test_texture_chart_colors_rows() {
+        let config = TextureConfig {
+            construction: Construction::Flat,
+            width_cm: 5.0,
+            height_cm: 3.0,
+            yarn: YarnSpec::default(),
+            color_a: "#1f4e79".to_string(),
+            color_b: "#f2c14e".to_string(),
+            palette: None,
+        };
+        let pattern = crate::texture::generate_texture_pattern(&config).unwrap();
+
+        let svg = DiagramGenerator::default().generate_texture_chart(&pattern);
+
+        assert_eq!(svg.matches("<path class=").count(), pattern.rows.len());
+        let long_rows = pattern.rows.len() / 2;
+        assert_eq!(svg.matches("<path class=\"long\"").count(), long_rows);
+        assert!(svg.contains("<path class=\"short\" stroke=\"#1f4e79\""));
+        assert!(svg.contains("<path class=\"long\" stroke=\"#f2c14e\""));
+        assert!(svg.contains(">Spike SC: Spike Single Crochet, worked into"));
+    }
+}
@@ -0,0 +1,207 @@
+use crochet_types::*;
+
+use crate::parser::consumes;
+
+/// Lowest scores labelled Intermediate and Advanced
+const INTERMEDIATE_SCORE: u32 = 45;
+const ADVANCED_SCORE: u32 = 70;
+
+/// Most points dense shaping can add, approached as a row mixing single
+/// crochet with increases or decreases becomes nearly all shaping
+const DENSITY_POINTS: f64 = 15.0;
+
+/// Rate how hard a pattern is to work and list the skills it needs
+///
+/// Each technique used adds to the score: how the piece is started, the
+/// stitches beyond single crochet, short rows (rows turned before the end
+/// of the row below), color changes and, with more than one of `pieces`,
+/// sewing them together. The densest shaping in a row adds up to
+/// `DENSITY_POINTS` more; rows of a single stitch ("6 INC") are easy to
+/// follow however dense, so they are left out. A plain ball rates Beginner.
+pub fn rate_difficulty(pattern: &CrochetPattern, pieces: usize) -> DifficultyRating {
+    // (skill, points) in the order a crocheter meets them
+    let mut skills: Vec<(&str, u32)> = Vec::new();
+
+    skills.push(match (pattern.construction, pattern.foundation) {
+        (Construction::Round, Foundation::Chain) => ("Magic ring", 10),
+        (Construction::Flat, Foundation::Chain) => ("Foundation chain", 0),
+        (_, Foundation::ChainRing) => ("Chain ring", 5),
+        (_, Foundation::SingleCrochet) => ("Foundation single crochet", 15),
+    });
+    skills.push(match pattern.construction {
+        Construction::Round => ("Working in continuous rounds", 0),
+        Construction::Flat => ("Turning rows", 0),
+    });
+
+    let uses = |stitch: StitchType| {
+        pattern.rows.iter().any(|r| {
+            r.pattern.iter().any(|s| s.stitch_type == stitch)
+                || (stitch == StitchType::SC && r.pattern.is_empty() && r.total_stitches > 0)
+        })
+    };
+    for (stitch, skill, points) in [
+        (StitchType::SC, "Single crochet", 0),
+        (StitchType::INC, "Increase", 5),
+        (StitchType::DEC, "Decrease", 5),
+        (StitchType::INVDEC, "Invisible decrease", 10),
+    ] {
+        if uses(stitch) {
+            skills.push((skill, points));
+        }
+    }
+
+    let short_rows = pattern.rows.windows(2).any(|pair| {
+        let worked: usize = pair[1]
+            .pattern
+            .iter()
+            .map(|s| consumes(s.stitch_type))
+            .sum();
+        !pair[1].pattern.is_empty() && worked < pair[0].total_stitches
+    });
+    if short_rows {
+        skills.push(("Short rows", 20));
+    }
+
+    let first_color = pattern.color_changes.first().map(|c| &c.color);
+    if pattern
+        .color_changes
+        .iter()
+        .any(|c| Some(&c.color) != first_color)
+    {
+        skills.push(("Changing colors", 5));
+    }
+    if pattern.color_changes.iter().any(|c| c.jog.is_some()) {
+        skills.push(("Jogless color changes", 10));
+    }
+
+    if pieces > 1 {
+        let extra = (pieces - 1).min(5) as u32;
+        skills.push(("Sewing pieces together", 5 + 2 * extra));
+    }
+
+    let density = pattern
+        .rows
+        .iter()
+        .filter(|r| {
+            r.pattern
+                .iter()
+                .any(|s| s.stitch_type != r.pattern[0].stitch_type)
+        })
+        .map(|r| {
+            let shaping = r
+                .pattern
+                .iter()
+                .filter(|s| s.stitch_type != StitchType::SC)
+                .count();
+            shaping as f64 / r.pattern.len() as f64
+        })
+        .fold(0.0, f64::max);
+
+    let score = skills.iter().map(|&(_, points)| points).sum::<u32>()
+        + (density * DENSITY_POINTS).round() as u32;
+    let score = score.min(100);
+    let level = if score >= ADVANCED_SCORE {
+        Difficulty::Advanced
+    } else if score >= INTERMEDIATE_SCORE {
+        Difficulty::Intermediate
+    } else {
+        Difficulty::Beginner
+    };
+
+    DifficultyRating {
+        score,
+        level,
+        skills: skills
+            .into_iter()
+            .map(|(skill, _)| skill.to_string())
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_pattern;
+    use crate::parser::parse_written_pattern;
+    use crate::stripes::add_stripes;
+
+    fn create_pattern() -> CrochetPattern {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, 1.0),
+                control2: Point2D::new(3.0, 5.0),
+                end: Point2D::new(0.0, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 0.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: 6.0,
+            ..Default::default()
+        };
+        generate_pattern(&curve, &config).unwrap()
+    }
+
+    #[test]
+    fn test_plain_ball_is_beginner() {
+        let pattern = create_pattern();
+        let rating = pattern.metadata.difficulty.clone().unwrap();
+
+        assert_eq!(rating, rate_difficulty(&pattern, 1));
+        assert_eq!(rating.level, Difficulty::Beginner);
+        assert_eq!(
+            rating.skills[..4],
+            [
+                "Magic ring",
+                "Working in continuous rounds",
+                "Single crochet",
+                "Increase"
+            ]
+        );
+        assert!(rating.skills.contains(&"Invisible decrease".to_string()));
+    }
+
+    #[test]
+    fn test_techniques_raise_the_rating() {
+        let mut pattern = create_pattern();
+        let plain = rate_difficulty(&pattern, 1);
+
+        let stripes = StripeConfig {
+            colors: vec!["Honey".to_string(), "Sage".to_string()],
+            rows_per_stripe: 3,
+            jogless: Some(JogMethod::SlipStitch),
+        };
+        add_stripes(&mut pattern, &stripes).unwrap();
+        let striped = rate_difficulty(&pattern, 3);
+
+        assert!(striped.score > plain.score);
+        assert!(striped.level > plain.level);
+        for skill in [
+            "Changing colors",
+            "Jogless color changes",
+            "Sewing pieces together",
+        ] {
+            assert!(striped.skills.contains(&skill.to_string()), "{}", skill);
+        }
+    }
+
+    #[test]
+    fn test_short_rows_are_found() {
+        let text = "Row 1: 10 sc (10)\nRows 2-4: ch 1, turn, sc across (10)";
+        let mut flat =
+            parse_written_pattern(text, &AmigurumiConfig::default(), Terminology::US).unwrap();
+        assert_eq!(
+            flat.metadata.difficulty.as_ref().unwrap().skills,
+            ["Foundation chain", "Turning rows", "Single crochet"]
+        );
+
+        // Turn row 3 after six stitches
+        flat.rows[2].pattern.truncate(6);
+        flat.rows[2].total_stitches = 6;
+        let rating = rate_difficulty(&flat, 1);
+
+        assert!(rating.skills.contains(&"Short rows".to_string()));
+        assert_eq!(rating.score, 20);
+    }
+}
@@ -0,0 +1,519 @@
+use crochet_types::*;
+
+use crate::formatter::{escape_html, PatternFormatter, ESTIMATED_GAUGE_NOTE};
+use crate::materials::{build_shopping_list, ShoppingListOptions};
+
+/// Stylesheet shared by every chapter; sized for phone and e-ink screens
+const STYLESHEET: &str = "body { font-family: serif; line-height: 1.4; margin: 0 0.5em; }\n\
+    h1, h2 { font-family: sans-serif; }\n\
+    ol.rows { padding-left: 0; list-style: none; }\n\
+    ol.rows li { margin: 0.4em 0; }\n\
+    figure { margin: 1em 0; text-align: center; }\n\
+    figure svg { max-width: 100%; height: auto; }\n\
+    dt { font-weight: bold; }\n";
+
+/// EPUB Accessibility 1.1 metadata: every chart is also written out as
+/// row instructions, so the book reads fully as text
+const ACCESSIBILITY_METADATA: &str = "<meta property=\"schema:accessMode\">textual</meta>\n\
+    <meta property=\"schema:accessMode\">visual</meta>\n\
+    <meta property=\"schema:accessModeSufficient\">textual</meta>\n\
+    <meta property=\"schema:accessibilityFeature\">alternativeText</meta>\n\
+    <meta property=\"schema:accessibilityFeature\">structuralNavigation</meta>\n\
+    <meta property=\"schema:accessibilityFeature\">tableOfContents</meta>\n\
+    <meta property=\"schema:accessibilityHazard\">none</meta>\n\
+    <meta property=\"schema:accessibilitySummary\">Each piece is written out row by row \
+    under headings, and every stitch chart has a text alternative.</meta>\n";
+
+/// One XHTML document of the book
+struct Chapter {
+    title: String,
+    body: String,
+    /// Whether the body embeds SVG (the package must declare it)
+    svg: bool,
+}
+
+/// Render a project as an EPUB 3 book
+///
+/// The first chapter lists materials for every piece and the
+/// abbreviations. Each piece then gets a chapter, or one per section when
+/// it has sections, with its stitch chart embedded as SVG at the end of
+/// its last chapter. Entries are stored uncompressed, which every reader
+/// accepts and keeps the writer dependency-free. The metadata's
+/// `modified` time must be written "YYYY-MM-DDThh:mm:ssZ".
+pub fn export_epub(
+    pieces: &[ProjectPiece],
+    metadata: &EpubMetadata,
+    formatter: &PatternFormatter,
+) -> Result<Vec<u8>> {
+    if !is_epub_timestamp(&metadata.modified) {
+        return Err(PatternError::InvalidConfiguration(format!(
+            "Modified time must be written YYYY-MM-DDThh:mm:ssZ, not {}",
+            metadata.modified
+        )));
+    }
+
+    let mut chapters = vec![materials_chapter(pieces, formatter)];
+    for piece in pieces {
+        chapters.extend(piece_chapters(piece, formatter));
+    }
+
+    let mut zip = ZipWriter::default();
+    // The mimetype must come first so readers can sniff it
+    zip.add("mimetype", b"application/epub+zip");
+    zip.add(
+        "META-INF/container.xml",
+        b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+          <container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n\
+          <rootfiles><rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/></rootfiles>\n\
+          </container>\n",
+    );
+    zip.add("OEBPS/style.css", STYLESHEET.as_bytes());
+    zip.add(
+        "OEBPS/nav.xhtml",
+        xhtml_document("Contents", &navigation(&chapters), &metadata.language).as_bytes(),
+    );
+    for (i, chapter) in chapters.iter().enumerate() {
+        zip.add(
+            &chapter_file(i),
+            xhtml_document(&chapter.title, &chapter.body, &metadata.language).as_bytes(),
+        );
+    }
+    zip.add("OEBPS/content.opf", package(&chapters, metadata).as_bytes());
+    Ok(zip.finish())
+}
+
+/// UTC time as `dcterms:modified` requires, e.g. "2024-03-01T09:30:00Z"
+fn is_epub_timestamp(time: &str) -> bool {
+    // A digit wherever the shape has a 0
+    const SHAPE: &[u8] = b"0000-00-00T00:00:00Z";
+    let bytes = time.as_bytes();
+    let shaped = bytes.len() == SHAPE.len()
+        && bytes.iter().zip(SHAPE).all(|(&b, &s)| match s {
+            b'0' => b.is_ascii_digit(),
+            _ => b == s,
+        });
+    if !shaped {
+        return false;
+    }
+    // Two-digit field starting at `start`
+    let number = |start: usize| (bytes[start] - b'0') * 10 + (bytes[start + 1] - b'0');
+    (1..=12).contains(&number(5))
+        && (1..=31).contains(&number(8))
+        && number(11) <= 23
+        && number(14) <= 59
+        && number(17) <= 59
+}
+
+fn chapter_file(index: usize) -> String {
+    format!("OEBPS/chapter-{}.xhtml", index + 1)
+}
+
+fn materials_chapter(pieces: &[ProjectPiece], formatter: &PatternFormatter) -> Chapter {
+    let list = build_shopping_list(pieces, &ShoppingListOptions::default());
+    let mut body = String::from("<h1>Materials</h1>\n<ul>\n");
+    for yarn in &list.yarn {
+        body.push_str(&format!(
+            "<li>{}: {:.1}m ({} skein{})</li>\n",
+            escape_html(&yarn.color),
+            yarn.meters,
+            yarn.skeins,
+            if yarn.skeins == 1 { "" } else { "s" }
+        ));
+    }
+    let hooks: Vec<String> = list
+        .hook_sizes_mm
+        .iter()
+        .map(|h| format!("{}mm", h))
+        .collect();
+    body.push_str(&format!("<li>Hook: {}</li>\n", hooks.join(", ")));
+    body.push_str(&format!(
+        "<li>Stitch markers: {}</li>\n",
+        list.stitch_markers
+    ));
+    if list.safety_eyes > 0 {
+        body.push_str(&format!("<li>Safety eyes: {}</li>\n", list.safety_eyes));
+    }
+    if list.stuffing_grams > 0.0 {
+        body.push_str(&format!(
+            "<li>Polyester fiberfill: {:.0}g</li>\n",
+            list.stuffing_grams
+        ));
+    }
+    body.push_str("<li>Yarn needle</li>\n</ul>\n");
+
+    body.push_str("<h2>Abbreviations</h2>\n<dl>\n");
+    for (abbr, description) in formatter.abbreviation_legend() {
+        body.push_str(&format!(
+            "<dt><abbr title=\"{1}\">{0}</abbr></dt><dd>{1}</dd>\n",
+            escape_html(abbr),
+            escape_html(description)
+        ));
+    }
+    body.push_str("</dl>\n");
+
+    Chapter {
+        title: "Materials".to_string(),
+        body,
+        svg: false,
+    }
+}
+
+fn piece_chapters(piece: &ProjectPiece, formatter: &PatternFormatter) -> Vec<Chapter> {
+    let pattern = &piece.pattern;
+    let name = if piece.quantity > 1 {
+        format!("{} (make {})", piece.name, piece.quantity)
+    } else {
+        piece.name.clone()
+    };
+
+    let mut intro = format!("<h1>{}</h1>\n<ul>\n", escape_html(&name));
+    intro.push_str(&format!(
+        "<li>Yarn: {}, {:.1}m per piece</li>\n",
+        escape_html(&piece.color),
+        pattern.metadata.yarn_length_meters
+    ));
+    intro.push_str(&format!(
+        "<li>Hook: {}mm</li>\n",
+        piece.yarn.recommended_hook_size_mm
+    ));
+    intro.push_str(&format!(
+        "<li>Gauge: {} stitches and {} rows per cm</li>\n",
+        piece.yarn.gauge_stitches_per_cm, piece.yarn.gauge_rows_per_cm
+    ));
+    if pattern.metadata.gauge_estimated {
+        intro.push_str(&format!("<li><em>{}</em></li>\n", ESTIMATED_GAUGE_NOTE));
+    }
+    let size = &pattern.metadata.finished_size;
+    intro.push_str(&format!(
+        "<li>Finished size: {:.1} cm tall, {:.1} cm wide</li>\n</ul>\n",
+        size.height_cm, size.width_cm
+    ));
+    for note in &pattern.notes {
+        intro.push_str(&format!("<p>{}</p>\n", escape_html(note)));
+    }
+
+    let blocks = formatter.instruction_sections(pattern);
+    let mut chapters: Vec<Chapter> = Vec::with_capacity(blocks.len());
+    for (i, (section, lines)) in blocks.into_iter().enumerate() {
+        let mut body = if i == 0 { intro.clone() } else { String::new() };
+        let title = match &section {
+            Some(section) => {
+                body.push_str(&format!("<h2>{}</h2>\n", escape_html(section)));
+                format!("{}: {}", piece.name, section)
+            }
+            None => piece.name.clone(),
+        };
+        body.push_str("<ol class=\"rows\">\n");
+        for (label, instruction) in lines {
+            body.push_str(&format!(
+                "<li><strong>{}:</strong> {}</li>\n",
+                escape_html(&label),
+                escape_html(&instruction)
+            ));
+        }
+        body.push_str("</ol>\n");
+        chapters.push(Chapter {
+            title,
+            body,
+            svg: false,
+        });
+    }
+    if chapters.is_empty() {
+        chapters.push(Chapter {
+            title: piece.name.clone(),
+            body: intro,
+            svg: false,
+        });
+    }
+
+    let last = chapters.last_mut().unwrap();
+    last.body.push_str("<figure>\n");
+    last.body
+        .push_str(&formatter.diagram_generator().generate_symbol_chart(pattern));
+    last.body.push_str(&format!(
+        "<figcaption>Stitch chart: {}</figcaption>\n</figure>\n",
+        escape_html(&piece.name)
+    ));
+    last.svg = true;
+    chapters
+}
+
+fn navigation(chapters: &[Chapter]) -> String {
+    let mut body = String::from("<nav epub:type=\"toc\" id=\"toc\">\n<h1>Contents</h1>\n<ol>\n");
+    for (i, chapter) in chapters.iter().enumerate() {
+        body.push_str(&format!(
+            "<li><a href=\"chapter-{}.xhtml\">{}</a></li>\n",
+            i + 1,
+            escape_html(&chapter.title)
+        ));
+    }
+    body.push_str("</ol>\n</nav>\n");
+    body
+}
+
+fn xhtml_document(title: &str, body: &str, language: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" \
+         lang=\"{lang}\" xml:lang=\"{lang}\">\n\
+         <head>\n<title>{title}</title>\n<link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n\
+         <body>\n{body}</body>\n</html>\n",
+        lang = escape_html(language),
+        title = escape_html(title),
+        body = body
+    )
+}
+
+/// The package document: metadata, manifest and reading order
+fn package(chapters: &[Chapter], metadata: &EpubMetadata) -> String {
+    // Identical projects get identical identifiers
+    let identifier = chapters
+        .iter()
+        .fold(crc32(metadata.title.as_bytes()), |crc, c| {
+            crc ^ crc32(c.body.as_bytes()).rotate_left(7)
+        });
+
+    let mut opf = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\">\n\
+         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n",
+    );
+    opf.push_str(&format!(
+        "<dc:identifier id=\"book-id\">urn:crochet-pattern:{:08x}</dc:identifier>\n",
+        identifier
+    ));
+    opf.push_str(&format!(
+        "<dc:title>{}</dc:title>\n<dc:language>{}</dc:language>\n",
+        escape_html(&metadata.title),
+        escape_html(&metadata.language)
+    ));
+    if let Some(author) = &metadata.author {
+        opf.push_str(&format!(
+            "<dc:creator>{}</dc:creator>\n",
+            escape_html(author)
+        ));
+    }
+    opf.push_str("<dc:subject>Crochet</dc:subject>\n");
+    opf.push_str(ACCESSIBILITY_METADATA);
+    opf.push_str(&format!(
+        "<meta property=\"dcterms:modified\">{}</meta>\n</metadata>\n<manifest>\n",
+        escape_html(&metadata.modified)
+    ));
+    opf.push_str(
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+         <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n",
+    );
+    for (i, chapter) in chapters.iter().enumerate() {
+        opf.push_str(&format!(
+            "<item id=\"chapter-{n}\" href=\"chapter-{n}.xhtml\" media-type=\"application/xhtml+xml\"{}/>\n",
+            if chapter.svg { " properties=\"svg\"" } else { "" },
+            n = i + 1
+        ));
+    }
+    opf.push_str("</manifest>\n<spine>\n");
+    for i in 0..chapters.len() {
+        opf.push_str(&format!("<itemref idref=\"chapter-{}\"/>\n", i + 1));
+    }
+    opf.push_str("</spine>\n</package>\n");
+    opf
+}
+
+/// Minimal writer for a zip archive of stored (uncompressed) entries
+#[derive(Default)]
+struct ZipWriter {
+    data: Vec<u8>,
+    central: Vec<u8>,
+    entries: u16,
+}
+
+impl ZipWriter {
+    fn add(&mut self, name: &str, contents: &[u8]) {
+        let offset = self.data.len() as u32;
+        let crc = crc32(contents);
+        let size = contents.len() as u32;
+
+        // Local file header: version 2.0, no flags, stored, 1980-01-01 00:00
+        push_u32(&mut self.data, 0x0403_4b50);
+        for field in [20u16, 0, 0, 0, 0x21] {
+            push_u16(&mut self.data, field);
+        }
+        for field in [crc, size, size] {
+            push_u32(&mut self.data, field);
+        }
+        push_u16(&mut self.data, name.len() as u16);
+        push_u16(&mut self.data, 0);
+        self.data.extend_from_slice(name.as_bytes());
+        self.data.extend_from_slice(contents);
+
+        push_u32(&mut self.central, 0x0201_4b50);
+        for field in [20u16, 20, 0, 0, 0, 0x21] {
+            push_u16(&mut self.central, field);
+        }
+        for field in [crc, size, size] {
+            push_u32(&mut self.central, field);
+        }
+        for field in [name.len() as u16, 0, 0, 0, 0] {
+            push_u16(&mut self.central, field);
+        }
+        push_u32(&mut self.central, 0);
+        push_u32(&mut self.central, offset);
+        self.central.extend_from_slice(name.as_bytes());
+        self.entries += 1;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let offset = self.data.len() as u32;
+        let size = self.central.len() as u32;
+        self.data.append(&mut self.central);
+        push_u32(&mut self.data, 0x0605_4b50);
+        for field in [0u16, 0, self.entries, self.entries] {
+            push_u16(&mut self.data, field);
+        }
+        push_u32(&mut self.data, size);
+        push_u32(&mut self.data, offset);
+        push_u16(&mut self.data, 0);
+        self.data
+    }
+}
+
+fn push_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+/// CRC-32 (IEEE) as used by zip
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sections::add_section;
+    use crate::swatch::generate_gauge_swatch;
+
+    fn create_piece(name: &str) -> ProjectPiece {
+        let yarn = YarnSpec::default();
+        ProjectPiece {
+            name: name.to_string(),
+            color: "Cream".to_string(),
+            pattern: generate_gauge_swatch(&yarn).unwrap(),
+            yarn,
+            quantity: 1,
+            stuffed: false,
+            safety_eyes: 0,
+        }
+    }
+
+    /// Names and contents of the stored entries, read back from the
+    /// central directory
+    fn entries(zip: &[u8]) -> Vec<(String, String)> {
+        let end = zip.len() - 22;
+        assert_eq!(&zip[end..end + 4], &0x0605_4b50u32.to_le_bytes());
+        let u16_at = |at: usize| u16::from_le_bytes([zip[at], zip[at + 1]]) as usize;
+        let u32_at = |at: usize| u32::from_le_bytes(zip[at..at + 4].try_into().unwrap()) as usize;
+        let mut at = u32_at(end + 16);
+        (0..u16_at(end + 10))
+            .map(|_| {
+                let (crc, size, name_len) = (u32_at(at + 16), u32_at(at + 20), u16_at(at + 28));
+                let name = String::from_utf8(zip[at + 46..at + 46 + name_len].to_vec()).unwrap();
+                let local = u32_at(at + 42);
+                let start = local + 30 + u16_at(local + 26);
+                let contents = &zip[start..start + size];
+                assert_eq!(crc32(contents) as usize, crc, "{}", name);
+                at += 46 + name_len;
+                (name, String::from_utf8(contents.to_vec()).unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_book_has_chapter_per_piece_and_section() {
+        let mut body = create_piece("Body");
+        let last_row = body.pattern.rows.len();
+        add_section(&mut body.pattern, "Top edge", 3, last_row).unwrap();
+        let pieces = vec![create_piece("Arm & hand"), body];
+        let metadata = EpubMetadata {
+            title: "Bear".to_string(),
+            author: None,
+            language: "en".to_string(),
+            modified: "2024-03-01T09:30:00Z".to_string(),
+        };
+
+        let book = export_epub(&pieces, &metadata, &PatternFormatter::default()).unwrap();
+        let entries = entries(&book);
+
+        assert_eq!(
+            entries[0],
+            ("mimetype".to_string(), "application/epub+zip".to_string())
+        );
+        let names: Vec<&str> = entries.iter().map(|(n, _)| n.as_str()).collect();
+        // Materials, the arm, and the body's unnamed rows plus its section
+        for i in 1..=4 {
+            assert!(names.contains(&format!("OEBPS/chapter-{}.xhtml", i).as_str()));
+        }
+        assert!(!names.contains(&"OEBPS/chapter-5.xhtml"));
+
+        let file = |name: &str| &entries.iter().find(|(n, _)| n == name).unwrap().1;
+        assert!(file("OEBPS/chapter-2.xhtml").contains("<h1>Arm &amp; hand</h1>"));
+        assert!(file("OEBPS/chapter-2.xhtml").contains("<svg "));
+        assert!(file("OEBPS/chapter-2.xhtml").contains("role=\"img\""));
+        assert!(file("OEBPS/chapter-1.xhtml").contains(" lang=\"en\" xml:lang=\"en\">"));
+        assert!(!file("OEBPS/chapter-3.xhtml").contains("<svg "));
+        assert!(file("OEBPS/nav.xhtml").contains(">Body: Top edge</a>"));
+        let opf = file("OEBPS/content.opf");
+        assert!(opf.contains("<dc:title>Bear</dc:title>"));
+        assert!(opf.contains("<meta property=\"dcterms:modified\">2024-03-01T09:30:00Z</meta>"));
+        assert!(opf.contains("<meta property=\"schema:accessModeSufficient\">textual</meta>"));
+        assert!(opf.contains(
+            "href=\"chapter-4.xhtml\" media-type=\"application/xhtml+xml\" properties=\"svg\""
+        ));
+
+        // Same project, same book
+        assert_eq!(
+            export_epub(&pieces, &metadata, &PatternFormatter::default()).unwrap(),
+            book
+        );
+    }
+
+    #[test]
+    fn test_modified_time_is_checked() {
+        let pieces = vec![create_piece("Body")];
+        for modified in [
+            "",
+            "2024-03-01",
+            "2024-03-01T09:30:00",
+            "2024-03-01 09:30:00Z",
+            "2024-13-01T09:30:00Z",
+            "2024-03-01T24:00:00Z",
+            "2024-03-01T09:3a:00Z",
+        ] {
+            let metadata = EpubMetadata {
+                title: "Bear".to_string(),
+                author: None,
+                language: "en".to_string(),
+                modified: modified.to_string(),
+            };
+            assert!(export_epub(&pieces, &metadata, &PatternFormatter::default()).is_err());
+        }
+    }
+}
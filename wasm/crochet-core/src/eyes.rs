@@ -0,0 +1,173 @@
+use crochet_types::*;
+use std::f64::consts::PI;
+
+/// Work out which stitches of a round the safety eyes go in
+///
+/// The head circumference at `row_number` comes from its stitch count at
+/// the configured gauge. The eyes sit symmetrically about centre front
+/// (angle 0, see `RoundStart`), `eye_distance_cm` apart measured along the
+/// surface, snapped to the nearest stitches. The placement is added to the
+/// pattern as `left_eye`/`right_eye` anchors and a note, replacing any
+/// earlier eye placement. The row's stitches must be spelled out, making
+/// its full stitch count, so their positions are known.
+pub fn place_safety_eyes(
+    pattern: &mut CrochetPattern,
+    config: &AmigurumiConfig,
+    row_number: usize,
+    eye_distance_cm: f64,
+) -> Result<EyePlacement> {
+    let row = pattern
+        .rows
+        .iter()
+        .find(|r| r.row_number == row_number)
+        .ok_or_else(|| {
+            PatternError::InvalidConfiguration(format!("Pattern has no row {}", row_number))
+        })?;
+
+    let circumference_cm = row.total_stitches as f64 / config.yarn.gauge_stitches_per_cm;
+    if !(eye_distance_cm > 0.0 && eye_distance_cm < circumference_cm / 2.0) {
+        return Err(PatternError::InvalidConfiguration(format!(
+            "Eye distance must be between 0 and {:.1} cm (half the circumference of row {})",
+            circumference_cm / 2.0,
+            row_number
+        )));
+    }
+
+    let angles = stitch_angles(row);
+    if angles.is_empty() || angles.len() != row.total_stitches {
+        return Err(PatternError::InvalidConfiguration(format!(
+            "Row {} lists {} stitches but says it has {}",
+            row_number,
+            angles.len(),
+            row.total_stitches
+        )));
+    }
+    let half_angle = PI * eye_distance_cm / circumference_cm;
+    let left = nearest_stitch(&angles, half_angle);
+    let mut right = nearest_stitch(&angles, -half_angle);
+    if right == left {
+        right = (left + angles.len() - 1) % angles.len();
+    }
+
+    let gap = left.abs_diff(right);
+    let stitches_apart = gap.min(angles.len() - gap);
+    let placement = EyePlacement {
+        row_number,
+        left_stitch: left + 1,
+        right_stitch: right + 1,
+        stitches_apart,
+        distance_cm: stitches_apart as f64 / config.yarn.gauge_stitches_per_cm,
+    };
+
+    pattern
+        .anchors
+        .retain(|a| a.name != "left_eye" && a.name != "right_eye");
+    pattern.anchors.push(Anchor {
+        name: "left_eye".to_string(),
+        row_number,
+        stitch: placement.left_stitch,
+    });
+    pattern.anchors.push(Anchor {
+        name: "right_eye".to_string(),
+        row_number,
+        stitch: placement.right_stitch,
+    });
+    pattern.notes.retain(|n| !n.starts_with(EYE_NOTE_PREFIX));
+    pattern.notes.push(format!(
+        "{} between rows {} and {}, in stitches {} and {} of row {} ({} stitches apart, about {:.1} cm).",
+        EYE_NOTE_PREFIX,
+        row_number,
+        row_number + 1,
+        placement.left_stitch,
+        placement.right_stitch,
+        row_number,
+        placement.stitches_apart,
+        placement.distance_cm
+    ));
+
+    Ok(placement)
+}
+
+const EYE_NOTE_PREFIX: &str = "Safety eyes:";
+
+/// Angle of every stitch a row produces (an increase makes two at one spot)
+fn stitch_angles(row: &Row) -> Vec<f64> {
+    row.pattern
+        .iter()
+        .flat_map(|s| {
+            let count = if s.stitch_type == StitchType::INC { 2 } else { 1 };
+            std::iter::repeat_n(s.angular_position, count)
+        })
+        .collect()
+}
+
+fn nearest_stitch(angles: &[f64], target: f64) -> usize {
+    let distance = |a: f64| {
+        let d = (a - target).rem_euclid(2.0 * PI);
+        d.min(2.0 * PI - d)
+    };
+    angles
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| distance(**a).total_cmp(&distance(**b)))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_pattern;
+
+    fn create_head() -> (CrochetPattern, AmigurumiConfig) {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(5.0, 1.0),
+                control2: Point2D::new(5.0, 7.0),
+                end: Point2D::new(1.0, 8.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 1.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: 8.0,
+            ..Default::default()
+        };
+        (generate_pattern(&curve, &config).unwrap(), config)
+    }
+
+    #[test]
+    fn test_eyes_are_symmetric_about_front() {
+        let (mut pattern, config) = create_head();
+        let row_number = pattern.rows.len() / 2;
+
+        let placement = place_safety_eyes(&mut pattern, &config, row_number, 3.0).unwrap();
+
+        // 3 cm at 3 stitches per cm
+        assert!(placement.stitches_apart.abs_diff(9) <= 1);
+        assert!((placement.distance_cm - 3.0).abs() < 0.5);
+        assert_eq!(pattern.anchors.len(), 2);
+        assert!(pattern.notes.iter().any(|n| n.starts_with("Safety eyes:")));
+
+        // Placing again replaces the old anchors
+        place_safety_eyes(&mut pattern, &config, row_number, 2.0).unwrap();
+        assert_eq!(pattern.anchors.len(), 2);
+        assert_eq!(pattern.notes.iter().filter(|n| n.starts_with("Safety eyes:")).count(), 1);
+    }
+
+    #[test]
+    fn test_invalid_eye_requests() {
+        let (mut pattern, config) = create_head();
+
+        assert!(place_safety_eyes(&mut pattern, &config, 999, 2.0).is_err());
+        assert!(place_safety_eyes(&mut pattern, &config, 3, 0.0).is_err());
+        assert!(place_safety_eyes(&mut pattern, &config, 3, 100.0).is_err());
+
+        // A plain row written as shorthand has no stitch positions
+        pattern.rows[5].pattern.clear();
+        assert!(place_safety_eyes(&mut pattern, &config, 6, 2.0).is_err());
+        pattern.rows[6].pattern.pop();
+        assert!(place_safety_eyes(&mut pattern, &config, 7, 2.0).is_err());
+    }
+}
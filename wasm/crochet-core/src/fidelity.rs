@@ -0,0 +1,179 @@
+use crochet_types::{
+    AmigurumiConfig, Construction, Dimensions, GaugeVariation, Row, ShapeFidelity, YarnSpec,
+};
+use std::f64::consts::PI;
+
+/// Radius (cm) a row will actually have given its stitch count and gauge
+pub fn achievable_radius(total_stitches: usize, config: &AmigurumiConfig) -> f64 {
+    let circumference = total_stitches as f64 / config.yarn.gauge_stitches_per_cm;
+    circumference / (2.0 * PI)
+}
+
+/// Finished size implied by stitch and row counts at the configured gauge
+pub fn expected_finished_size(
+    rows: &[Row],
+    config: &AmigurumiConfig,
+    construction: Construction,
+) -> Dimensions {
+    let widest = rows.iter().map(|r| r.total_stitches).max().unwrap_or(0);
+    let width_cm = match construction {
+        Construction::Round => 2.0 * achievable_radius(widest, config),
+        Construction::Flat => widest as f64 / config.yarn.gauge_stitches_per_cm,
+    };
+
+    Dimensions {
+        height_cm: rows.len() as f64 / config.yarn.gauge_rows_per_cm,
+        width_cm,
+    }
+}
+
+/// Gauge deviations shown in the gauge table, in percent
+pub const GAUGE_TABLE_DEVIATIONS: [f64; 3] = [-10.0, 0.0, 10.0];
+
+/// Finished size at each of `GAUGE_TABLE_DEVIATIONS` from the configured gauge
+pub fn gauge_table(
+    rows: &[Row],
+    config: &AmigurumiConfig,
+    construction: Construction,
+) -> Vec<GaugeVariation> {
+    GAUGE_TABLE_DEVIATIONS
+        .iter()
+        .map(|&deviation_percent| {
+            let scale = 1.0 + deviation_percent / 100.0;
+            let yarn = YarnSpec {
+                gauge_stitches_per_cm: config.yarn.gauge_stitches_per_cm * scale,
+                gauge_rows_per_cm: config.yarn.gauge_rows_per_cm * scale,
+                ..config.yarn.clone()
+            };
+            let scaled = AmigurumiConfig {
+                yarn,
+                ..config.clone()
+            };
+            GaugeVariation {
+                deviation_percent,
+                stitches_per_cm: scaled.yarn.gauge_stitches_per_cm,
+                rows_per_cm: scaled.yarn.gauge_rows_per_cm,
+                finished_size: expected_finished_size(rows, &scaled, construction),
+            }
+        })
+        .collect()
+}
+
+/// Compare achievable row radii against the radii requested by the profile
+///
+/// `target_radii` holds one requested radius per row, in the same order as `rows`.
+pub fn measure_shape_fidelity(
+    target_radii: &[f64],
+    rows: &[Row],
+    config: &AmigurumiConfig,
+) -> ShapeFidelity {
+    let row_errors_cm: Vec<f64> = rows
+        .iter()
+        .zip(target_radii)
+        .map(|(row, &target)| achievable_radius(row.total_stitches, config) - target)
+        .collect();
+
+    if row_errors_cm.is_empty() {
+        return ShapeFidelity::default();
+    }
+
+    let max_deviation_cm = row_errors_cm.iter().fold(0.0f64, |acc, e| acc.max(e.abs()));
+    let sum_sq: f64 = row_errors_cm.iter().map(|e| e * e).sum();
+    let rms_deviation_cm = (sum_sq / row_errors_cm.len() as f64).sqrt();
+
+    ShapeFidelity {
+        row_errors_cm,
+        max_deviation_cm,
+        rms_deviation_cm,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use crochet_types::YarnSpec;
+
+    fn create_test_config() -> AmigurumiConfig {
+        AmigurumiConfig {
+            total_height_cm: 10.0,
+            yarn: YarnSpec {
+                gauge_stitches_per_cm: 3.0,
+                gauge_rows_per_cm: 3.0,
+                recommended_hook_size_mm: 3.5,
+            },
+            ..Default::default()
+        }
+    }
+
+    fn create_test_row(row_number: usize, total_stitches: usize) -> Row {
+        Row {
+            row_number,
+            total_stitches,
+            pattern: vec![],
+            markers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_gauge_table_brackets_stated_size() {
+        let config = create_test_config();
+        let rows: Vec<Row> = (1..=30).map(|n| create_test_row(n, 36)).collect();
+
+        let table = gauge_table(&rows, &config, Construction::Round);
+
+        assert_eq!(table.len(), 3);
+        let stated = expected_finished_size(&rows, &config, Construction::Round);
+        assert_eq!(table[1].finished_size.height_cm, stated.height_cm);
+        // Looser work comes out bigger, tighter work smaller
+        assert!((table[0].finished_size.height_cm - stated.height_cm / 0.9).abs() < 1e-9);
+        assert!((table[2].finished_size.width_cm - stated.width_cm / 1.1).abs() < 1e-9);
+        assert!((table[2].stitches_per_cm - 3.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_exact_match_has_no_deviation() {
+        let config = create_test_config();
+        let rows = vec![create_test_row(1, 6), create_test_row(2, 12)];
+        let targets: Vec<f64> = rows
+            .iter()
+            .map(|r| achievable_radius(r.total_stitches, &config))
+            .collect();
+
+        let fidelity = measure_shape_fidelity(&targets, &rows, &config);
+
+        assert_eq!(fidelity.row_errors_cm.len(), 2);
+        assert_relative_eq!(fidelity.max_deviation_cm, 0.0, epsilon = 1e-12);
+        assert_relative_eq!(fidelity.rms_deviation_cm, 0.0, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_finished_size_from_counts() {
+        let config = create_test_config();
+        let rows: Vec<Row> = (1..=30).map(|n| create_test_row(n, 36)).collect();
+
+        let round = expected_finished_size(&rows, &config, Construction::Round);
+        assert_relative_eq!(round.height_cm, 10.0, epsilon = 1e-9);
+        assert_relative_eq!(round.width_cm, 12.0 / PI, epsilon = 1e-9);
+
+        let flat = expected_finished_size(&rows, &config, Construction::Flat);
+        assert_relative_eq!(flat.width_cm, 12.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_reports_max_and_rms() {
+        let config = create_test_config();
+        let rows = vec![create_test_row(1, 6), create_test_row(2, 12)];
+        let targets = vec![
+            achievable_radius(6, &config) + 1.0,
+            achievable_radius(12, &config) - 2.0,
+        ];
+
+        let fidelity = measure_shape_fidelity(&targets, &rows, &config);
+
+        assert_relative_eq!(fidelity.row_errors_cm[0], -1.0, epsilon = 1e-9);
+        assert_relative_eq!(fidelity.row_errors_cm[1], 2.0, epsilon = 1e-9);
+        assert_relative_eq!(fidelity.max_deviation_cm, 2.0, epsilon = 1e-9);
+        assert_relative_eq!(fidelity.rms_deviation_cm, 2.5f64.sqrt(), epsilon = 1e-9);
+    }
+}
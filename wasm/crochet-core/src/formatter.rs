@@ -0,0 +1,1122 @@
+use crochet_types::*;
+use std::collections::BTreeMap;
+
+use crate::diagram::DiagramGenerator;
+use crate::grouping::group_rows;
+use crate::markers::marker_instruction;
+use crate::stripes::color_change_instruction;
+use crate::yarn::MAIN_COLOR;
+
+/// Stitch types listed in the abbreviation legend, in display order
+pub(crate) const LEGEND_STITCHES: [StitchType; 4] = [
+    StitchType::SC,
+    StitchType::INC,
+    StitchType::DEC,
+    StitchType::INVDEC,
+];
+
+/// Shown under the gauge when it was estimated rather than measured
+pub(crate) const ESTIMATED_GAUGE_NOTE: &str =
+    "Estimated from hook size and yarn weight - crochet a swatch to confirm";
+
+/// Section name (if any) and the labelled instruction lines under it
+pub type InstructionBlock = (Option<String>, Vec<(String, String)>);
+
+/// Renders a generated pattern as written instructions
+#[derive(Debug, Clone, Default)]
+pub struct PatternFormatter {
+    pub terminology: Terminology,
+    pub branding: Branding,
+    /// Follow each instruction with what it is worked into
+    pub worked_into: bool,
+    /// Colors for embedded stitch charts
+    pub palette: DiagramPalette,
+    /// How-to sketches in the embedded chart's legend
+    pub legend_thumbnails: bool,
+}
+
+impl PatternFormatter {
+    pub fn new(terminology: Terminology) -> Self {
+        Self {
+            terminology,
+            branding: Branding::default(),
+            worked_into: false,
+            palette: DiagramPalette::default(),
+            legend_thumbnails: false,
+        }
+    }
+
+    pub fn from_options(options: &ExportOptions) -> Self {
+        Self {
+            terminology: options.terminology,
+            branding: options.branding.clone(),
+            worked_into: options.worked_into,
+            palette: options.palette,
+            legend_thumbnails: options.legend_thumbnails,
+        }
+    }
+
+    /// Placeholder values for branding templates
+    ///
+    /// Built-in pattern values come first so user variables can override them.
+    pub fn template_variables(
+        &self,
+        pattern: &CrochetPattern,
+        config: &AmigurumiConfig,
+    ) -> BTreeMap<String, String> {
+        let mut vars = BTreeMap::new();
+        vars.insert("total_rows".to_string(), pattern.metadata.total_rows.to_string());
+        vars.insert(
+            "total_stitches".to_string(),
+            pattern.metadata.total_stitches.to_string(),
+        );
+        vars.insert("hook_mm".to_string(), config.yarn.recommended_hook_size_mm.to_string());
+        vars.insert("height_cm".to_string(), config.total_height_cm.to_string());
+        for (key, value) in &self.branding.variables {
+            vars.insert(key.clone(), value.clone());
+        }
+        vars
+    }
+
+    /// Abbreviation legend as (abbreviation, description) pairs
+    pub fn abbreviation_legend(&self) -> Vec<(&'static str, &'static str)> {
+        LEGEND_STITCHES
+            .iter()
+            .map(|s| (s.abbreviation(self.terminology), s.description(self.terminology)))
+            .collect()
+    }
+
+    /// Instruction text for a single row, including its stitch count
+    pub fn row_instruction(&self, pattern: &CrochetPattern, row: &Row) -> String {
+        let sc = StitchType::SC.abbreviation(self.terminology);
+        if row.row_number == 1 && pattern.foundation == Foundation::ChainRing {
+            return format!(
+                "Ch {n}, join with sl st to form a ring, {} in each ch around ({n})",
+                sc,
+                n = row.total_stitches
+            );
+        }
+        if row.row_number == 1 && pattern.construction == Construction::Round {
+            return format!("{} {} in magic ring ({})", row.total_stitches, sc, row.total_stitches);
+        }
+        if row.row_number == 1 && pattern.foundation == Foundation::SingleCrochet {
+            return format!("{} F{} ({})", row.total_stitches, sc, row.total_stitches);
+        }
+        if !row.pattern.is_empty() && row.pattern.iter().all(|s| s.stitch_type == StitchType::SC) {
+            let direction = match pattern.construction {
+                Construction::Round => "around",
+                Construction::Flat => "across",
+            };
+            return format!("{} {} ({})", sc, direction, row.total_stitches);
+        }
+        format!(
+            "{} ({})",
+            row.pattern_string_with(self.terminology),
+            row.total_stitches
+        )
+    }
+
+    /// What a row's stitches are worked into, from the row below
+    ///
+    /// Returns `None` for a magic ring or foundation single crochet, which
+    /// are not worked into anything.
+    pub fn worked_into_note(&self, pattern: &CrochetPattern, row: &Row) -> Option<String> {
+        let below = pattern
+            .rows
+            .iter()
+            .find(|r| r.row_number + 1 == row.row_number);
+        match (below, pattern.construction) {
+            (None, _) if pattern.foundation == Foundation::ChainRing => Some(format!(
+                "Work into the {} chains of the ring.",
+                row.pattern.len()
+            )),
+            (None, Construction::Round) => None,
+            (None, Construction::Flat) if pattern.foundation == Foundation::SingleCrochet => None,
+            (None, Construction::Flat) => Some(format!(
+                "Work into the {} chains of the foundation chain.",
+                row.pattern.len()
+            )),
+            (Some(below), _) => Some(format!(
+                "Work into the {} stitches of row {}.",
+                below.total_stitches, below.row_number
+            )),
+        }
+    }
+
+    /// Labelled instruction lines with identical consecutive rows merged
+    /// ("Rows 5-12: SC around (36)")
+    pub fn instruction_lines(&self, pattern: &CrochetPattern) -> Vec<(String, String)> {
+        self.grouped_lines(pattern)
+            .into_iter()
+            .map(|(_, label, instruction)| (label, instruction))
+            .collect()
+    }
+
+    /// Instruction lines split into the pattern's sections
+    ///
+    /// Each block carries the name of the section its rows belong to, or
+    /// `None` for rows outside every section.
+    pub fn instruction_sections(
+        &self,
+        pattern: &CrochetPattern,
+    ) -> Vec<InstructionBlock> {
+        let mut blocks: Vec<InstructionBlock> = Vec::new();
+        for (group, label, instruction) in self.grouped_lines(pattern) {
+            let name = pattern
+                .sections
+                .iter()
+                .find(|s| s.contains(group.first_row))
+                .map(|s| s.name.clone());
+            match blocks.last_mut() {
+                Some((current, lines)) if *current == name => lines.push((label, instruction)),
+                _ => blocks.push((name, vec![(label, instruction)])),
+            }
+        }
+        blocks
+    }
+
+    /// Instruction lines produced one at a time, as they are read
+    ///
+    /// Gives the same lines as `instruction_lines`, but only the row range
+    /// boundaries are worked out up front; each line is formatted when the
+    /// iterator reaches it, so a very large pattern can be written out
+    /// without holding the whole document in memory.
+    pub fn stream_instruction_lines<'a>(
+        &'a self,
+        pattern: &'a CrochetPattern,
+    ) -> impl Iterator<Item = (String, String)> + 'a {
+        self.line_groups(pattern)
+            .into_iter()
+            .filter_map(move |group| self.group_line(pattern, group))
+            .map(|(_, label, instruction)| (label, instruction))
+    }
+
+    /// Stream the instruction lines to `out`, one "Label: instruction" per line
+    pub fn write_instructions<W: std::io::Write>(
+        &self,
+        pattern: &CrochetPattern,
+        out: &mut W,
+    ) -> std::io::Result<()> {
+        for (label, instruction) in self.stream_instruction_lines(pattern) {
+            writeln!(out, "{}: {}", label, instruction)?;
+        }
+        Ok(())
+    }
+
+    fn grouped_lines(&self, pattern: &CrochetPattern) -> Vec<(RowGroup, String, String)> {
+        self.line_groups(pattern)
+            .into_iter()
+            .filter_map(|group| self.group_line(pattern, group))
+            .collect()
+    }
+
+    /// Row ranges that each get one instruction line
+    ///
+    /// Always regrouped from the rows themselves: stored `row_groups` go
+    /// stale as soon as a row is edited.
+    fn line_groups(&self, pattern: &CrochetPattern) -> Vec<RowGroup> {
+        let mut groups = group_rows(&pattern.rows);
+
+        // The magic ring and a foundation row read differently from the rows
+        // after them, and a flat first row needs its own worked-into note
+        let distinct_first_row = pattern.construction == Construction::Round
+            || pattern.foundation == Foundation::SingleCrochet;
+        if distinct_first_row || self.worked_into {
+            if let Some(first) = groups.first_mut() {
+                if first.first_row == 1 && first.last_row > 1 {
+                    let rest = RowGroup {
+                        first_row: 2,
+                        ..*first
+                    };
+                    first.last_row = 1;
+                    groups.insert(1, rest);
+                }
+            }
+        }
+
+        // A range may not run across the start or end of a section
+        for section in &pattern.sections {
+            split_groups_before(&mut groups, section.first_row);
+            split_groups_before(&mut groups, section.last_row + 1);
+        }
+        // ...or past a change of color
+        for change in &pattern.color_changes {
+            split_groups_before(&mut groups, change.row_number);
+        }
+        // ...and rows with marker hints get a line of their own
+        for row in pattern.rows.iter().filter(|r| !r.markers.is_empty()) {
+            split_groups_before(&mut groups, row.row_number);
+            split_groups_before(&mut groups, row.row_number + 1);
+        }
+
+        groups
+    }
+
+    fn group_line(
+        &self,
+        pattern: &CrochetPattern,
+        group: RowGroup,
+    ) -> Option<(RowGroup, String, String)> {
+        let row = pattern.rows.iter().find(|r| r.row_number == group.first_row)?;
+        let label = if group.row_count() > 1 {
+            format!("Rows {}-{}", group.first_row, group.last_row)
+        } else {
+            format!("Row {}", group.first_row)
+        };
+        let mut instruction = self.row_instruction(pattern, row);
+        if let Some(change) = pattern
+            .color_changes
+            .iter()
+            .find(|c| c.row_number == group.first_row)
+        {
+            let first_row = pattern.rows.first().map(|r| r.row_number) == Some(row.row_number);
+            instruction = format!(
+                "{} {}",
+                color_change_instruction(change, self.terminology, first_row),
+                instruction
+            );
+        }
+        if self.worked_into {
+            let note = if group.row_count() > 1 {
+                Some(format!(
+                    "Work each row into the {} stitches of the row before.",
+                    row.total_stitches
+                ))
+            } else {
+                self.worked_into_note(pattern, row)
+            };
+            if let Some(note) = note {
+                instruction.push_str(". ");
+                instruction.push_str(&note);
+            }
+        }
+        for marker in &row.markers {
+            let separator = if instruction.ends_with('.') {
+                " "
+            } else {
+                ". "
+            };
+            instruction.push_str(separator);
+            instruction.push_str(&marker_instruction(marker));
+        }
+        Some((group, label, instruction))
+    }
+
+    /// Plain-text pattern with materials, gauge, legend and row instructions
+    pub fn to_text(&self, pattern: &CrochetPattern, config: &AmigurumiConfig) -> String {
+        let rule = "=".repeat(50);
+        let vars = self.template_variables(pattern, config);
+        let mut text = String::new();
+        if let Some(header) = &self.branding.header {
+            text.push_str(&render_template(header, &vars));
+            text.push_str("\n\n");
+        }
+        text.push_str("CROCHET AMIGURUMI PATTERN\n");
+        text.push_str(&rule);
+        text.push_str("\n\n");
+
+        text.push_str("MATERIALS:\n");
+        for (item, amount) in yarn_materials(pattern) {
+            text.push_str(&format!("- {}: {}\n", item, amount));
+        }
+        text.push_str(&format!("- Hook: {}mm\n", config.yarn.recommended_hook_size_mm));
+        text.push_str("- Stitch marker\n- Yarn needle\n- Polyester fiberfill stuffing\n\n");
+
+        text.push_str("GAUGE:\n");
+        text.push_str(&format!(
+            "- {} stitches per cm\n- {} rows per cm\n",
+            config.yarn.gauge_stitches_per_cm, config.yarn.gauge_rows_per_cm
+        ));
+        if pattern.metadata.gauge_estimated {
+            text.push_str(&format!("- {}\n", ESTIMATED_GAUGE_NOTE));
+        }
+        text.push('\n');
+
+        let size = &pattern.metadata.finished_size;
+        text.push_str("FINISHED SIZE:\n");
+        text.push_str(&format!(
+            "- Height: {:.1} cm\n- Width: {:.1} cm\n\n",
+            size.height_cm, size.width_cm
+        ));
+
+        if !pattern.metadata.gauge_table.is_empty() {
+            text.push_str("IF YOUR GAUGE IS OFF:\n");
+            for variation in &pattern.metadata.gauge_table {
+                text.push_str(&format!(
+                    "- {} ({:.2} sts/cm): {:.1} x {:.1} cm\n",
+                    gauge_label(variation),
+                    variation.stitches_per_cm,
+                    variation.finished_size.height_cm,
+                    variation.finished_size.width_cm
+                ));
+            }
+            text.push('\n');
+        }
+
+        text.push_str(&format!("ABBREVIATIONS ({}):\n", self.terminology_label()));
+        for (abbr, description) in self.abbreviation_legend() {
+            text.push_str(&format!("- {}: {}\n", abbr, description));
+        }
+        text.push('\n');
+
+        if !pattern.notes.is_empty() {
+            text.push_str("NOTES:\n");
+            for note in &pattern.notes {
+                text.push_str(&format!("- {}\n", note));
+            }
+            text.push('\n');
+        }
+
+        for section in &self.branding.sections {
+            text.push_str(&format!("{}:\n", section.title.to_uppercase()));
+            text.push_str(&render_template(&section.body, &vars));
+            text.push_str("\n\n");
+        }
+
+        text.push_str("PATTERN:\n");
+        text.push_str(&"-".repeat(50));
+        text.push_str("\n\n");
+        for (i, (section, lines)) in self.instruction_sections(pattern).iter().enumerate() {
+            if let Some(name) = section {
+                if i > 0 {
+                    text.push('\n');
+                }
+                text.push_str(&format!("{}:\n", name.to_uppercase()));
+            }
+            for (label, instruction) in lines {
+                text.push_str(&format!("{}: {}\n", label, instruction));
+            }
+        }
+
+        text.push('\n');
+        text.push_str(&rule);
+        text.push('\n');
+        text.push_str(&format!("Total Rows: {}\n", pattern.metadata.total_rows));
+        text.push_str(&format!("Total Stitches: {}\n", pattern.metadata.total_stitches));
+        text.push_str(&format!(
+            "Estimated Time: {} minutes\n",
+            pattern.metadata.estimated_time_minutes.round()
+        ));
+
+        if let Some(footer) = &self.branding.footer {
+            text.push('\n');
+            text.push_str(&render_template(footer, &vars));
+            text.push('\n');
+        }
+
+        text
+    }
+
+    /// Materials as (item, amount) pairs for tabular output
+    pub fn materials(
+        &self,
+        pattern: &CrochetPattern,
+        config: &AmigurumiConfig,
+    ) -> Vec<(String, String)> {
+        let mut materials = yarn_materials(pattern);
+        materials.extend([
+            (
+                "Hook".to_string(),
+                format!("{}mm", config.yarn.recommended_hook_size_mm),
+            ),
+            ("Stitch marker".to_string(), "1".to_string()),
+            ("Yarn needle".to_string(), "1".to_string()),
+            ("Polyester fiberfill stuffing".to_string(), "As needed".to_string()),
+        ]);
+        materials
+    }
+
+    /// Markdown pattern with a materials table, row list and embedded chart
+    ///
+    /// The chart is an SVG data URI image so the output stays a single
+    /// self-contained document.
+    pub fn to_markdown(&self, pattern: &CrochetPattern, config: &AmigurumiConfig) -> String {
+        let vars = self.template_variables(pattern, config);
+        let mut md = String::new();
+        if let Some(header) = &self.branding.header {
+            md.push_str(&render_template(header, &vars));
+            md.push_str("\n\n");
+        }
+        md.push_str("# Crochet Amigurumi Pattern\n\n");
+
+        md.push_str("## Materials\n\n| Item | Amount |\n| --- | --- |\n");
+        for (item, amount) in self.materials(pattern, config) {
+            md.push_str(&format!("| {} | {} |\n", item, amount));
+        }
+        md.push('\n');
+
+        md.push_str("## Gauge\n\n");
+        md.push_str(&format!(
+            "- {} stitches per cm\n- {} rows per cm\n",
+            config.yarn.gauge_stitches_per_cm, config.yarn.gauge_rows_per_cm
+        ));
+        if pattern.metadata.gauge_estimated {
+            md.push_str(&format!("- *{}*\n", ESTIMATED_GAUGE_NOTE));
+        }
+        md.push('\n');
+
+        let size = &pattern.metadata.finished_size;
+        md.push_str("## Finished Size\n\n");
+        md.push_str(&format!(
+            "- Height: {:.1} cm\n- Width: {:.1} cm\n\n",
+            size.height_cm, size.width_cm
+        ));
+
+        if !pattern.metadata.gauge_table.is_empty() {
+            md.push_str("## If Your Gauge Is Off\n\n");
+            md.push_str("| Gauge | Stitches per cm | Height | Width |\n| --- | --- | --- | --- |\n");
+            for variation in &pattern.metadata.gauge_table {
+                md.push_str(&format!(
+                    "| {} | {:.2} | {:.1} cm | {:.1} cm |\n",
+                    gauge_label(variation),
+                    variation.stitches_per_cm,
+                    variation.finished_size.height_cm,
+                    variation.finished_size.width_cm
+                ));
+            }
+            md.push('\n');
+        }
+
+        md.push_str(&format!("## Abbreviations ({})\n\n", self.terminology_label()));
+        for (abbr, description) in self.abbreviation_legend() {
+            md.push_str(&format!("- **{}**: {}\n", abbr, description));
+        }
+        md.push('\n');
+
+        if !pattern.notes.is_empty() {
+            md.push_str("## Notes\n\n");
+            for note in &pattern.notes {
+                md.push_str(&format!("- {}\n", note));
+            }
+            md.push('\n');
+        }
+
+        for section in &self.branding.sections {
+            md.push_str(&format!("## {}\n\n", section.title));
+            md.push_str(&render_template(&section.body, &vars));
+            md.push_str("\n\n");
+        }
+
+        md.push_str("## Pattern\n\n");
+        for (section, lines) in self.instruction_sections(pattern) {
+            if let Some(name) = section {
+                md.push_str(&format!("### {}\n\n", name));
+            }
+            for (label, instruction) in lines {
+                md.push_str(&format!("- **{}:** {}\n", label, instruction));
+            }
+            md.push('\n');
+        }
+
+        md.push_str("## Stitch Chart\n\n");
+        let chart = self.diagram_generator().generate_symbol_chart(pattern);
+        md.push_str(&format!(
+            "![Stitch chart](data:image/svg+xml;utf8,{})\n\n",
+            percent_encode(&chart)
+        ));
+
+        md.push_str(&format!(
+            "---\n\n**Total Rows:** {}  \n**Total Stitches:** {}  \n**Estimated Time:** {} minutes\n",
+            pattern.metadata.total_rows,
+            pattern.metadata.total_stitches,
+            pattern.metadata.estimated_time_minutes.round()
+        ));
+
+        if let Some(footer) = &self.branding.footer {
+            md.push('\n');
+            md.push_str(&render_template(footer, &vars));
+            md.push('\n');
+        }
+
+        md
+    }
+
+    /// HTML fragment (an `<article>`) ready to paste into a page
+    ///
+    /// Tables carry header cells, rows are an ordered list and the chart
+    /// is an image with a text alternative, so screen readers can follow
+    /// the pattern.
+    pub fn to_html(&self, pattern: &CrochetPattern, config: &AmigurumiConfig) -> String {
+        let vars = self.template_variables(pattern, config);
+        let mut html = format!(
+            "<article class=\"crochet-pattern\" lang=\"{}\">\n",
+            self.language_tag()
+        );
+        if let Some(header) = &self.branding.header {
+            html.push_str(&format!(
+                "<header>{}</header>\n",
+                paragraphs(&render_template(header, &vars))
+            ));
+        }
+        html.push_str("<h1>Crochet Amigurumi Pattern</h1>\n");
+
+        html.push_str("<section>\n<h2>Materials</h2>\n<table>\n");
+        html.push_str("<thead><tr><th scope=\"col\">Item</th><th scope=\"col\">Amount</th></tr></thead>\n");
+        html.push_str("<tbody>\n");
+        for (item, amount) in self.materials(pattern, config) {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&item),
+                escape_html(&amount)
+            ));
+        }
+        html.push_str("</tbody>\n</table>\n</section>\n");
+
+        html.push_str("<section>\n<h2>Gauge</h2>\n<ul>\n");
+        html.push_str(&format!(
+            "<li>{} stitches per cm</li>\n<li>{} rows per cm</li>\n",
+            config.yarn.gauge_stitches_per_cm, config.yarn.gauge_rows_per_cm
+        ));
+        if pattern.metadata.gauge_estimated {
+            html.push_str(&format!("<li><em>{}</em></li>\n", ESTIMATED_GAUGE_NOTE));
+        }
+        html.push_str("</ul>\n</section>\n");
+
+        let size = &pattern.metadata.finished_size;
+        html.push_str("<section>\n<h2>Finished Size</h2>\n<ul>\n");
+        html.push_str(&format!(
+            "<li>Height: {:.1} cm</li>\n<li>Width: {:.1} cm</li>\n",
+            size.height_cm, size.width_cm
+        ));
+        html.push_str("</ul>\n</section>\n");
+
+        if !pattern.metadata.gauge_table.is_empty() {
+            html.push_str("<section>\n<h2>If Your Gauge Is Off</h2>\n<table>\n");
+            html.push_str(
+                "<thead><tr><th scope=\"col\">Gauge</th><th scope=\"col\">Stitches per cm</th>\
+                 <th scope=\"col\">Height</th><th scope=\"col\">Width</th></tr></thead>\n",
+            );
+            html.push_str("<tbody>\n");
+            for variation in &pattern.metadata.gauge_table {
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td>{:.2}</td><td>{:.1} cm</td><td>{:.1} cm</td></tr>\n",
+                    gauge_label(variation),
+                    variation.stitches_per_cm,
+                    variation.finished_size.height_cm,
+                    variation.finished_size.width_cm
+                ));
+            }
+            html.push_str("</tbody>\n</table>\n</section>\n");
+        }
+
+        html.push_str(&format!(
+            "<section>\n<h2>Abbreviations ({})</h2>\n<dl>\n",
+            self.terminology_label()
+        ));
+        for (abbr, description) in self.abbreviation_legend() {
+            html.push_str(&format!(
+                "<dt><abbr title=\"{1}\">{0}</abbr></dt><dd>{1}</dd>\n",
+                escape_html(abbr),
+                escape_html(description)
+            ));
+        }
+        html.push_str("</dl>\n</section>\n");
+
+        if !pattern.notes.is_empty() {
+            html.push_str("<section>\n<h2>Notes</h2>\n<ul>\n");
+            for note in &pattern.notes {
+                html.push_str(&format!("<li>{}</li>\n", escape_html(note)));
+            }
+            html.push_str("</ul>\n</section>\n");
+        }
+
+        for section in &self.branding.sections {
+            html.push_str(&format!(
+                "<section>\n<h2>{}</h2>\n{}</section>\n",
+                escape_html(&section.title),
+                paragraphs(&render_template(&section.body, &vars))
+            ));
+        }
+
+        html.push_str("<section>\n<h2>Pattern</h2>\n");
+        for (section, lines) in self.instruction_sections(pattern) {
+            if let Some(name) = section {
+                html.push_str(&format!("<h3>{}</h3>\n", escape_html(&name)));
+            }
+            html.push_str("<ol class=\"rows\">\n");
+            for (label, instruction) in lines {
+                html.push_str(&format!(
+                    "<li><strong>{}:</strong> {}</li>\n",
+                    escape_html(&label),
+                    escape_html(&instruction)
+                ));
+            }
+            html.push_str("</ol>\n");
+        }
+        html.push_str("</section>\n");
+
+        html.push_str("<figure>\n");
+        html.push_str(&self.diagram_generator().generate_symbol_chart(pattern));
+        html.push_str("<figcaption>Stitch chart</figcaption>\n</figure>\n");
+
+        html.push_str(&format!(
+            "<p>Total Rows: {}<br>Total Stitches: {}<br>Estimated Time: {} minutes</p>\n",
+            pattern.metadata.total_rows,
+            pattern.metadata.total_stitches,
+            pattern.metadata.estimated_time_minutes.round()
+        ));
+
+        if let Some(footer) = &self.branding.footer {
+            html.push_str(&format!(
+                "<footer>{}</footer>\n",
+                paragraphs(&render_template(footer, &vars))
+            ));
+        }
+
+        html.push_str("</article>\n");
+        html
+    }
+
+    pub(crate) fn diagram_generator(&self) -> DiagramGenerator {
+        DiagramGenerator {
+            palette: self.palette,
+            legend_thumbnails: self.legend_thumbnails,
+            ..DiagramGenerator::new(self.terminology)
+        }
+    }
+
+    /// BCP 47 language of the output: UK terms are British English
+    pub(crate) fn language_tag(&self) -> &'static str {
+        match self.terminology {
+            Terminology::US => "en-US",
+            Terminology::UK => "en-GB",
+        }
+    }
+
+    fn terminology_label(&self) -> &'static str {
+        match self.terminology {
+            Terminology::US => "US terms",
+            Terminology::UK => "UK terms",
+        }
+    }
+}
+
+/// Short label for a gauge variation: "10% looser", "As stated" or
+/// "10% tighter"
+pub(crate) fn gauge_label(variation: &GaugeVariation) -> String {
+    let percent = variation.deviation_percent.abs().round();
+    if variation.deviation_percent > 0.0 {
+        format!("{}% tighter", percent)
+    } else if variation.deviation_percent < 0.0 {
+        format!("{}% looser", percent)
+    } else {
+        "As stated".to_string()
+    }
+}
+
+/// Yarn lines for the materials list, one per color when the pattern
+/// has a per-color estimate
+pub(crate) fn yarn_materials(pattern: &CrochetPattern) -> Vec<(String, String)> {
+    if pattern.metadata.yarn_by_color.is_empty() {
+        return vec![(
+            "Yarn".to_string(),
+            format!(
+                "{:.1}m (plus 20% extra)",
+                pattern.metadata.yarn_length_meters
+            ),
+        )];
+    }
+    pattern
+        .metadata
+        .yarn_by_color
+        .iter()
+        .map(|yarn| {
+            let item = if yarn.color == MAIN_COLOR {
+                "Yarn".to_string()
+            } else {
+                format!("Yarn ({})", yarn.color)
+            };
+            let skeins = if yarn.skeins == 1 { "skein" } else { "skeins" };
+            (
+                item,
+                format!(
+                    "{:.1}m, {:.0}g, {} {} (plus 20% extra)",
+                    yarn.meters, yarn.grams, yarn.skeins, skeins
+                ),
+            )
+        })
+        .collect()
+}
+
+/// Split the group containing `row_number` so that row starts a group
+fn split_groups_before(groups: &mut Vec<RowGroup>, row_number: usize) {
+    if let Some(idx) = groups
+        .iter()
+        .position(|g| g.first_row < row_number && row_number <= g.last_row)
+    {
+        let rest = RowGroup {
+            first_row: row_number,
+            ..groups[idx]
+        };
+        groups[idx].last_row = row_number - 1;
+        groups.insert(idx + 1, rest);
+    }
+}
+
+/// Escape text for HTML and SVG markup
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Free text as escaped `<p>` blocks, one per line
+fn paragraphs(text: &str) -> String {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| format!("<p>{}</p>\n", escape_html(line)))
+        .collect()
+}
+
+/// Percent-encode text for use in a data URI
+fn percent_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => out.push(byte as char),
+            b'-' | b'_' | b'.' | b'~' | b'/' | b'=' | b':' | b',' => out.push(byte as char),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// Replace `{{name}}` placeholders; unknown names are left untouched
+pub fn render_template(template: &str, vars: &BTreeMap<String, String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(open) = rest.find("{{") {
+        out.push_str(&rest[..open]);
+        let after_open = &rest[open + 2..];
+        match after_open.find("}}") {
+            Some(close) => {
+                let name = after_open[..close].trim();
+                match vars.get(name) {
+                    Some(value) => out.push_str(value),
+                    None => out.push_str(&rest[open..open + 2 + close + 2]),
+                }
+                rest = &after_open[close + 2..];
+            }
+            None => {
+                out.push_str(&rest[open..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram::chart_description;
+    use crate::generator::generate_pattern;
+    use crate::markers::add_marker_hints;
+    use crate::sections::add_section;
+    use crate::stripes::add_stripes;
+    use crate::swatch::generate_gauge_swatch;
+    use crate::yarn::estimate_yarn_usage;
+
+    fn create_test_curve() -> ProfileCurve {
+        ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, 1.0),
+                control2: Point2D::new(3.0, 5.0),
+                end: Point2D::new(2.0, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 2.0,
+        }
+    }
+
+    fn create_test_pattern() -> (CrochetPattern, AmigurumiConfig) {
+        let config = AmigurumiConfig {
+            total_height_cm: 6.0,
+            ..Default::default()
+        };
+        let pattern = generate_pattern(&create_test_curve(), &config).unwrap();
+        (pattern, config)
+    }
+
+    #[test]
+    fn test_us_text_uses_sc() {
+        let (pattern, config) = create_test_pattern();
+        let text = PatternFormatter::default().to_text(&pattern, &config);
+
+        assert!(text.contains("Row 1: 6 SC in magic ring (6)"));
+        assert!(text.contains("- SC: Single Crochet"));
+        assert!(text.contains("ABBREVIATIONS (US terms)"));
+    }
+
+    #[test]
+    fn test_streamed_lines_match_instruction_lines() {
+        let (mut pattern, _) = create_test_pattern();
+        add_section(&mut pattern, "Crown", 3, 5).unwrap();
+        let formatter = PatternFormatter {
+            worked_into: true,
+            ..Default::default()
+        };
+
+        let streamed: Vec<_> = formatter.stream_instruction_lines(&pattern).collect();
+        assert_eq!(streamed, formatter.instruction_lines(&pattern));
+
+        let mut out = Vec::new();
+        formatter.write_instructions(&pattern, &mut out).unwrap();
+        let written = String::from_utf8(out).unwrap();
+        assert_eq!(written.lines().count(), streamed.len());
+        assert!(written.starts_with("Row 1: 6 SC in magic ring (6)\n"));
+    }
+
+    #[test]
+    fn test_stale_row_groups_ignored() {
+        let (mut pattern, _) = create_test_pattern();
+        let formatter = PatternFormatter::default();
+        let expected = formatter.instruction_lines(&pattern);
+
+        let last_row = pattern.rows.len();
+        pattern.row_groups = vec![
+            RowGroup {
+                first_row: 1,
+                last_row,
+                total_stitches: 6,
+            },
+            RowGroup {
+                first_row: 4,
+                last_row: 2,
+                total_stitches: 6,
+            },
+        ];
+        assert_eq!(formatter.instruction_lines(&pattern), expected);
+    }
+
+    #[test]
+    fn test_uk_text_uses_dc() {
+        let (pattern, config) = create_test_pattern();
+        let text = PatternFormatter::new(Terminology::UK).to_text(&pattern, &config);
+
+        assert!(text.contains("Row 1: 6 DC in magic ring (6)"));
+        assert!(text.contains("- DC: Double Crochet (US single crochet)"));
+        assert!(!text.contains(" SC"));
+    }
+
+    #[test]
+    fn test_identical_rows_are_ranged() {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(2.0, 0.0),
+                control1: Point2D::new(2.0, 3.33),
+                control2: Point2D::new(2.0, 6.67),
+                end: Point2D::new(2.0, 10.0),
+            }],
+            start_radius: 2.0,
+            end_radius: 2.0,
+        };
+        let config = AmigurumiConfig::default();
+        let pattern = generate_pattern(&curve, &config).unwrap();
+
+        let lines = PatternFormatter::default().instruction_lines(&pattern);
+
+        assert_eq!(lines[0].0, "Row 1");
+        let (label, instruction) = lines.last().unwrap();
+        assert!(label.starts_with("Rows "));
+        assert!(label.ends_with(&format!("-{}", pattern.rows.len())));
+        assert!(instruction.starts_with("SC around"));
+    }
+
+    #[test]
+    fn test_sections_split_ranges_and_get_headers() {
+        let mut pattern = generate_gauge_swatch(&YarnSpec::default()).unwrap();
+        let total = pattern.rows.len();
+        add_section(&mut pattern, "Top edge", 5, total).unwrap();
+        let config = AmigurumiConfig::default();
+        let formatter = PatternFormatter::default();
+
+        let sections = formatter.instruction_sections(&pattern);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].0, None);
+        assert_eq!(sections[0].1.last().unwrap().0, "Rows 1-4");
+        assert_eq!(sections[1].0.as_deref(), Some("Top edge"));
+        assert_eq!(sections[1].1[0].0, format!("Rows 5-{}", total));
+
+        assert!(formatter.to_text(&pattern, &config).contains("\nTOP EDGE:\nRows 5-"));
+        assert!(formatter.to_markdown(&pattern, &config).contains("### Top edge\n\n- **Rows 5-"));
+        assert!(formatter.to_html(&pattern, &config).contains("<h3>Top edge</h3>"));
+    }
+
+    #[test]
+    fn test_marker_hints_get_their_own_lines() {
+        let mut pattern = generate_gauge_swatch(&YarnSpec::default()).unwrap();
+        add_marker_hints(&mut pattern, 4);
+        let formatter = PatternFormatter::default();
+
+        let lines = formatter.instruction_lines(&pattern);
+        assert_eq!(lines[0].0, "Rows 1-3");
+        assert_eq!(lines[1].0, "Row 4");
+        assert!(lines[1].1.ends_with(&format!(
+            "). Marker check: after working into {n} stitches, you should be on stitch {n} of {}.",
+            pattern.rows[3].total_stitches,
+            n = pattern.rows[3].total_stitches.div_ceil(2)
+        )));
+        assert_eq!(lines[2].0, "Rows 5-7");
+
+        let (_, mut config) = create_test_pattern();
+        config.marker_every = Some(0);
+        let round = generate_pattern(&create_test_curve(), &config).unwrap();
+        let text = formatter.to_text(&round, &config);
+        assert!(text.contains(
+            "Row 1: 6 SC in magic ring (6). Place a marker in the first stitch and move it up"
+        ));
+    }
+
+    #[test]
+    fn test_yarn_is_listed_per_color() {
+        let (mut pattern, config) = create_test_pattern();
+        let text = PatternFormatter::default().to_text(&pattern, &config);
+        assert!(text.contains("- Yarn: "));
+        assert!(text.contains("g, 1 skein (plus 20% extra)"));
+
+        let stripes = StripeConfig {
+            colors: vec!["Honey".to_string(), "Sage".to_string()],
+            rows_per_stripe: 2,
+            jogless: None,
+        };
+        add_stripes(&mut pattern, &stripes).unwrap();
+        pattern.metadata.yarn_by_color = estimate_yarn_usage(&pattern, &config);
+        let text = PatternFormatter::default().to_text(&pattern, &config);
+
+        assert!(text.contains("- Yarn (Honey): "));
+        assert!(text.contains("- Yarn (Sage): "));
+        assert!(!text.contains("- Yarn: "));
+    }
+
+    #[test]
+    fn test_gauge_table_is_rendered() {
+        let (pattern, config) = create_test_pattern();
+        let formatter = PatternFormatter::default();
+
+        let text = formatter.to_text(&pattern, &config);
+        assert!(text.contains("IF YOUR GAUGE IS OFF:\n- 10% looser ("));
+        assert!(text.contains("- As stated ("));
+        assert!(formatter.to_markdown(&pattern, &config).contains("| 10% tighter | "));
+        assert!(formatter.to_html(&pattern, &config).contains("<tr><td>10% tighter</td>"));
+    }
+
+    #[test]
+    fn test_estimated_gauge_is_flagged() {
+        let (mut pattern, config) = create_test_pattern();
+        let formatter = PatternFormatter::default();
+        assert!(!formatter.to_text(&pattern, &config).contains(ESTIMATED_GAUGE_NOTE));
+
+        pattern.metadata.gauge_estimated = true;
+        assert!(formatter.to_text(&pattern, &config).contains(ESTIMATED_GAUGE_NOTE));
+        assert!(formatter.to_html(&pattern, &config).contains(ESTIMATED_GAUGE_NOTE));
+    }
+
+    #[test]
+    fn test_worked_into_annotations() {
+        let (pattern, config) = create_test_pattern();
+        let formatter = PatternFormatter {
+            worked_into: true,
+            ..Default::default()
+        };
+
+        let lines = formatter.instruction_lines(&pattern);
+
+        assert_eq!(lines[0].1, "6 SC in magic ring (6)");
+        assert!(lines[1].1.ends_with("Work into the 6 stitches of row 1."));
+
+        let swatch = generate_gauge_swatch(&config.yarn).unwrap();
+        let first = &formatter.instruction_lines(&swatch)[0].1;
+        assert!(first.ends_with("chains of the foundation chain."));
+    }
+
+    #[test]
+    fn test_foundation_single_crochet_first_row() {
+        let yarn = YarnSpec::default();
+        let swatch =
+            crate::swatch::generate_gauge_swatch_with_foundation(&yarn, Foundation::SingleCrochet)
+                .unwrap();
+
+        let lines = PatternFormatter::default().instruction_lines(&swatch);
+        assert_eq!(lines[0], ("Row 1".to_string(), "36 FSC (36)".to_string()));
+        assert!(lines[1].0.starts_with("Rows 2-"));
+
+        let uk = PatternFormatter::new(Terminology::UK).instruction_lines(&swatch);
+        assert_eq!(uk[0].1, "36 FDC (36)");
+    }
+
+    #[test]
+    fn test_render_template() {
+        let mut vars = BTreeMap::new();
+        vars.insert("maker".to_string(), "Ada".to_string());
+
+        assert_eq!(render_template("By {{maker}}!", &vars), "By Ada!");
+        assert_eq!(render_template("By {{ maker }}", &vars), "By Ada");
+        assert_eq!(render_template("{{unknown}} {{maker", &vars), "{{unknown}} {{maker");
+    }
+
+    #[test]
+    fn test_branding_in_text() {
+        let (pattern, config) = create_test_pattern();
+        let mut formatter = PatternFormatter::default();
+        formatter.branding.header = Some("Designed by {{maker}} ({{date}})".to_string());
+        formatter.branding.footer = Some("{{total_rows}} rows - (c) {{maker}}".to_string());
+        formatter.branding.variables.insert("maker".to_string(), "Hook & Co".to_string());
+        formatter.branding.variables.insert("date".to_string(), "2026-01-01".to_string());
+        formatter.branding.sections.push(NoteSection {
+            title: "Yarn".to_string(),
+            body: "Use {{yarn_brand}} cotton".to_string(),
+        });
+
+        let text = formatter.to_text(&pattern, &config);
+
+        assert!(text.starts_with("Designed by Hook & Co (2026-01-01)"));
+        assert!(text.contains(&format!("{} rows - (c) Hook & Co", pattern.rows.len())));
+        assert!(text.contains("YARN:\nUse {{yarn_brand}} cotton"));
+    }
+
+    #[test]
+    fn test_markdown_has_table_and_chart() {
+        let (pattern, config) = create_test_pattern();
+        let md = PatternFormatter::default().to_markdown(&pattern, &config);
+
+        assert!(md.starts_with("# Crochet Amigurumi Pattern"));
+        assert!(md.contains("| Item | Amount |"));
+        assert!(md.contains("| Hook | 3.5mm |"));
+        assert!(md.contains("- **Row 1:** 6 SC in magic ring (6)"));
+        assert!(md.contains("![Stitch chart](data:image/svg+xml;utf8,%3Csvg"));
+    }
+
+    #[test]
+    fn test_html_is_escaped_and_structured() {
+        let (pattern, config) = create_test_pattern();
+        let mut formatter = PatternFormatter::new(Terminology::UK);
+        formatter.branding.footer = Some("<b>Hook & Co</b>".to_string());
+
+        let html = formatter.to_html(&pattern, &config);
+
+        assert!(html.starts_with("<article class=\"crochet-pattern\" lang=\"en-GB\">"));
+        assert!(html.contains("<li><strong>Row 1:</strong> 6 DC in magic ring (6)</li>"));
+        assert!(html.contains("<figure>\n<svg "));
+        assert!(html.contains(&format!("<title>{}</title>", chart_description(&pattern))));
+        assert!(html.contains("<footer><p>&lt;b&gt;Hook &amp; Co&lt;/b&gt;</p>"));
+        assert!(html.trim_end().ends_with("</article>"));
+    }
+
+    #[test]
+    fn test_row_pattern_string_terminology() {
+        let row = Row {
+            row_number: 2,
+            total_stitches: 12,
+            pattern: vec![],
+            markers: Vec::new(),
+        };
+        assert_eq!(row.pattern_string(), "12 SC");
+        assert_eq!(row.pattern_string_with(Terminology::UK), "12 DC");
+    }
+}
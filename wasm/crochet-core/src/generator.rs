@@ -1,11 +1,24 @@
 use crochet_types::*;
 use std::f64::consts::PI;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
-use crate::stitch_count::calculate_stitch_counts;
-use crate::optimization::optimize_stitch_placement;
+use crate::optimization::{
+    default_cooling_rate, PlacementOptions, StitchPlacer, DEFAULT_SEED, MAX_ITERATIONS,
+};
+use crate::fidelity::{expected_finished_size, gauge_table, measure_shape_fidelity};
+use crate::grouping::group_rows;
+use crate::lineage::row_connections;
+use crate::row_ops::{mirror_row, offset_row};
+use crate::ridges::{ridge_energy, smooth_ridges, DEFAULT_RIDGE_WINDOW, MAX_RIDGE_WINDOW};
+use crate::difficulty::rate_difficulty;
+use crate::markers::{add_marker_hints, row_marker_hints};
+use crate::plugins::{GaugeShaper, GenerationPlugins};
+use crate::timing::row_minutes;
+use crate::yarn::yarn_by_color;
 
 /// Find the radius at a specific height by searching through the curve
-fn find_radius_at_height(curve: &ProfileCurve, target_height: f64) -> f64 {
+fn find_radius_at_height(curve: &ProfileCurve, target_height: f64, tolerance: f64) -> f64 {
     // Find which segment contains this height
     for segment in &curve.segments {
         let start_height = segment.start.y;
@@ -20,7 +33,7 @@ fn find_radius_at_height(curve: &ProfileCurve, target_height: f64) -> f64 {
         
         if target_height >= min_h && target_height <= max_h {
             // Binary search for the t value that gives us this height
-            let t = find_t_for_height(segment, target_height);
+            let t = find_t_for_height(segment, target_height, tolerance);
             let point = segment.evaluate(t);
             return point.x.max(0.0);
         }
@@ -28,23 +41,23 @@ fn find_radius_at_height(curve: &ProfileCurve, target_height: f64) -> f64 {
     
     // If height is outside curve range, use nearest endpoint
     if target_height < curve.segments[0].start.y {
-        return curve.segments[0].start.x.max(0.0);
+        curve.segments[0].start.x.max(0.0)
     } else {
         let last = curve.segments.last().unwrap();
-        return last.end.x.max(0.0);
+        last.end.x.max(0.0)
     }
 }
 
 /// Find parameter t that gives a specific y-coordinate using binary search
-fn find_t_for_height(segment: &SplineSegment, target_y: f64) -> f64 {
+fn find_t_for_height(segment: &SplineSegment, target_y: f64, tolerance: f64) -> f64 {
     let start_y = segment.start.y;
     let end_y = segment.end.y;
     
     // Handle edge cases
-    if (target_y - start_y).abs() < 1e-6 {
+    if (target_y - start_y).abs() < tolerance {
         return 0.0;
     }
-    if (target_y - end_y).abs() < 1e-6 {
+    if (target_y - end_y).abs() < tolerance {
         return 1.0;
     }
     
@@ -66,11 +79,11 @@ fn find_t_for_height(segment: &SplineSegment, target_y: f64) -> f64 {
     let mut t_max = 1.0;
     
     // Binary search for t value
-    for _ in 0..30 {
+    for _ in 0..60 {
         let t = (t_min + t_max) / 2.0;
         let point = segment.evaluate(t);
         
-        if (point.y - target_y).abs() < 1e-6 {
+        if (point.y - target_y).abs() < tolerance {
             return t;
         }
         
@@ -99,103 +112,467 @@ pub fn generate_pattern(
     curve: &ProfileCurve,
     config: &AmigurumiConfig,
 ) -> Result<CrochetPattern> {
-    validate_curve(curve)?;
-    validate_config(config)?;
-
-    // Step 1: Calculate number of rows
-    let row_height = 1.0 / config.yarn.gauge_rows_per_cm;
-    let num_rows = (config.total_height_cm / row_height).round() as usize;
-    let num_rows = num_rows.max(1);
-
-    // Step 2: Height-based sampling
-    let curve_min_y = curve.segments[0].start.y;
-    let curve_max_y = curve.segments.last().unwrap().end.y;
-    let curve_height = curve_max_y - curve_min_y;
-    
-    if curve_height <= 0.0 {
-        return Err(PatternError::InvalidProfileCurve(
-            "Curve must have positive height".to_string(),
-        ));
+    let mut generation = PatternGeneration::start(curve, config)?;
+    while generation.step() {}
+    generation.finish()
+}
+
+/// Generate a pattern with custom shaping or placement stages
+pub fn generate_pattern_with_plugins(
+    curve: &ProfileCurve,
+    config: &AmigurumiConfig,
+    plugins: GenerationPlugins,
+) -> Result<CrochetPattern> {
+    let mut generation = PatternGeneration::start_with_plugins(curve, config, plugins)?;
+    while generation.step() {}
+    generation.finish()
+}
+
+/// Generate a pattern, reporting progress and stopping early on request
+///
+/// `on_progress` is called with a stage name ("rows", "placement" or
+/// "assembly") and the overall percent done. The token is checked between
+/// stages and after every row, and a cancelled run returns
+/// `PatternError::Cancelled`.
+pub fn generate_pattern_with_progress(
+    curve: &ProfileCurve,
+    config: &AmigurumiConfig,
+    cancel: &CancellationToken,
+    mut on_progress: impl FnMut(&str, f64),
+) -> Result<CrochetPattern> {
+    cancel.check()?;
+    let mut generation = PatternGeneration::start(curve, config)?;
+    on_progress("rows", 0.0);
+    while !generation.is_done() {
+        cancel.check()?;
+        generation.step();
+        on_progress("placement", 100.0 * generation.progress());
+    }
+    cancel.check()?;
+    let pattern = generation.finish()?;
+    on_progress("assembly", 100.0);
+    Ok(pattern)
+}
+
+/// Shared flag for stopping a generation from another thread or callback
+///
+/// Clones share the flag, so one can be handed to the generation and
+/// another kept to cancel it.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// `Err(PatternError::Cancelled)` once cancelled
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(PatternError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Pattern generation that can be paused between rows
+///
+/// `start` does the quick work (row heights and stitch counts); each call
+/// to `step` then optimizes the stitch placement of one row, which is where
+/// nearly all the time goes. Embedders on a single thread can hand control
+/// back to their event loop between steps. Stepping through to the end and
+/// calling `finish` gives the same pattern as `generate_pattern`.
+pub struct PatternGeneration<'a> {
+    curve: &'a ProfileCurve,
+    config: &'a AmigurumiConfig,
+    row_radii: Vec<f64>,
+    warnings: Vec<String>,
+    rows: Vec<Row>,
+    placement: PlacementOptions,
+    placer: StitchPlacer,
+    ridge_window: usize,
+    /// Placed rows after the ridge pass, once every row is placed
+    smoothed: Option<Vec<Row>>,
+}
+
+impl<'a> PatternGeneration<'a> {
+    /// Validate the input and work out every row's stitch counts
+    pub fn start(curve: &'a ProfileCurve, config: &'a AmigurumiConfig) -> Result<Self> {
+        Self::start_with_plugins(curve, config, GenerationPlugins::default())
     }
+
+    /// `start`, with the given stages in place of the built-in ones
+    pub fn start_with_plugins(
+        curve: &'a ProfileCurve,
+        config: &'a AmigurumiConfig,
+        plugins: GenerationPlugins,
+    ) -> Result<Self> {
+        validate_curve(curve, &config.limits)?;
+        validate_config(config)?;
+
+        // Step 1: Calculate number of rows
+        let row_height = 1.0 / config.yarn.gauge_rows_per_cm;
+        let rows_needed = (config.total_height_cm / row_height).round();
+        if rows_needed > config.limits.max_rows as f64 {
+            return Err(PatternError::InvalidConfiguration(format!(
+                "Pattern would need {} rows, limit is {}",
+                rows_needed, config.limits.max_rows
+            )));
+        }
+        let num_rows = (rows_needed as usize).max(1);
+
+        // Step 2: Height-based sampling
+        let curve_min_y = curve.segments[0].start.y;
+        let curve_max_y = curve.segments.last().unwrap().end.y;
+        let curve_height = curve_max_y - curve_min_y;
+    
+        if curve_height <= 0.0 {
+            return Err(PatternError::InvalidProfileCurve(
+                "Curve must have positive height".to_string(),
+            ));
+        }
     
-    let mut row_radii = Vec::with_capacity(num_rows);
+        let mut row_radii = Vec::with_capacity(num_rows);
     
-    // Row 1: Magic ring (standard 6 SC, ~0.67cm radius)
-    row_radii.push(2.0 / config.yarn.gauge_stitches_per_cm);
+        // Row 1: Magic ring (standard 6 SC, ~0.67cm radius; smaller rings scale down)
+        row_radii.push(
+            2.0 * config.min_round_stitches as f64 / 6.0 / config.yarn.gauge_stitches_per_cm,
+        );
     
-    // Rows 2+: Evenly spaced heights
-    for row_idx in 1..num_rows {
-        let t = row_idx as f64 / (num_rows - 1) as f64;
-        let height = curve_min_y + t * curve_height;
-        let radius = find_radius_at_height(curve, height);
-        row_radii.push(radius.max(0.1));
+        // Rows 2+: Evenly spaced heights
+        for row_idx in 1..num_rows {
+            let t = row_idx as f64 / (num_rows - 1) as f64;
+            let height = curve_min_y + t * curve_height;
+            let radius = find_radius_at_height(curve, height, config.quality.curve_tolerance());
+            row_radii.push(radius.max(0.1));
+        }
+
+        if row_radii.is_empty() {
+            return Err(PatternError::InvalidProfileCurve(
+                "No rows generated".to_string(),
+            ));
+        }
+
+        // Step 3: Calculate stitch counts per row
+        let shaper = plugins.shaper.unwrap_or_else(|| Box::new(GaugeShaper));
+        let (stitch_counts, warnings) = shaper.stitch_counts(&row_radii, config);
+        validate_stitch_counts(&stitch_counts, row_radii.len())?;
+        if let Some(&widest) = stitch_counts.iter().max() {
+            if widest > config.limits.max_stitches_per_row {
+                return Err(PatternError::InvalidProfileCurve(format!(
+                    "Widest row would need {} stitches, limit is {}",
+                    widest, config.limits.max_stitches_per_row
+                )));
+            }
+        }
+
+        // Step 4: Generate initial row patterns, with only the stitches
+        // the crocheter knows
+        let allowed = config.stitches.allowed(Construction::Round);
+        let growing = stitch_counts.windows(2).any(|pair| pair[1] > pair[0]);
+        if growing && !allowed.contains(&StitchType::INC) {
+            return Err(PatternError::InvalidConfiguration(
+                "Shape needs increases, but INC is not an allowed stitch".to_string(),
+            ));
+        }
+        let shrinking = stitch_counts.windows(2).any(|pair| pair[1] < pair[0]);
+        let decrease = config.stitches.decrease(Construction::Round);
+        if shrinking && decrease.is_none() {
+            return Err(PatternError::InvalidConfiguration(
+                "Shape needs decreases, but neither DEC nor INVDEC is an allowed stitch"
+                    .to_string(),
+            ));
+        }
+        let decrease = decrease.unwrap_or(StitchType::INVDEC);
+        let mut rows = Vec::with_capacity(stitch_counts.len());
+
+        for (row_idx, &total_stitches) in stitch_counts.iter().enumerate() {
+            let pattern = if row_idx == 0 {
+                // Special case: Row 1 is always the magic circle (all SC)
+                (0..total_stitches)
+                    .map(|i| {
+                        let angle = 2.0 * PI * i as f64 / total_stitches as f64;
+                        StitchInstruction {
+                            stitch_type: StitchType::SC,
+                            angular_position: angle,
+                            stitch_index: i,
+                        }
+                    })
+                    .collect()
+            } else {
+                let prev_stitches = stitch_counts[row_idx - 1];
+                generate_row_pattern(row_idx + 1, prev_stitches, total_stitches, decrease)
+            };
+
+            rows.push(Row {
+                row_number: row_idx + 1,
+                total_stitches,
+                pattern,
+                markers: Vec::new(),
+            });
+        }
+
+        // Step 5: Optimize stitch placement, one row per step
+        let tuning = &config.optimization;
+        let placement = PlacementOptions {
+            iterations: tuning
+                .iterations
+                .unwrap_or_else(|| config.quality.annealing_iterations()),
+            balance_decreases: config.balance_decreases,
+            seed: tuning.seed.unwrap_or(DEFAULT_SEED),
+            cooling_rate: tuning.cooling_rate,
+            weights: tuning.weights,
+            method: tuning.method,
+        };
+
+        Ok(Self {
+            curve,
+            config,
+            row_radii,
+            warnings,
+            rows,
+            placement,
+            placer: match plugins.placement {
+                Some(strategy) => StitchPlacer::with_strategy(strategy),
+                None => StitchPlacer::new(&placement),
+            },
+            ridge_window: tuning.ridge_window.unwrap_or(DEFAULT_RIDGE_WINDOW),
+            smoothed: None,
+        })
     }
 
-    if row_radii.is_empty() {
-        return Err(PatternError::InvalidProfileCurve(
-            "No rows generated".to_string(),
-        ));
+    /// Optimize the next row; returns whether any rows are left
+    ///
+    /// The step that places the last row also runs the ridge pass.
+    pub fn step(&mut self) -> bool {
+        if let Some(row) = self.rows.get(self.placer.rows_placed()) {
+            self.placer.place(row);
+        }
+        if self.is_done() && self.smoothed.is_none() {
+            let mut rows = self.placer.placed_rows().to_vec();
+            smooth_ridges(&mut rows, self.ridge_window, self.config.balance_decreases);
+            self.smoothed = Some(rows);
+        }
+        !self.is_done()
     }
 
-    // Step 3: Calculate stitch counts per row
-    let stitch_counts = calculate_stitch_counts(&row_radii, config);
-
-    // Step 4: Generate initial row patterns
-    let mut rows = Vec::with_capacity(stitch_counts.len());
-
-    for (row_idx, &total_stitches) in stitch_counts.iter().enumerate() {
-        let pattern = if row_idx == 0 {
-            // Special case: Row 1 is always the magic circle (all SC)
-            (0..total_stitches)
-                .map(|i| {
-                    let angle = 2.0 * PI * i as f64 / total_stitches as f64;
-                    StitchInstruction {
-                        stitch_type: StitchType::SC,
-                        angular_position: angle,
-                        stitch_index: i,
-                    }
-                })
-                .collect()
-        } else {
-            let prev_stitches = stitch_counts[row_idx - 1];
-            generate_row_pattern(row_idx + 1, prev_stitches, total_stitches)
+    pub fn is_done(&self) -> bool {
+        self.placer.rows_placed() >= self.rows.len()
+    }
+
+    /// Share of rows placed so far, from 0 to 1
+    pub fn progress(&self) -> f64 {
+        self.placer.rows_placed() as f64 / self.rows.len() as f64
+    }
+
+    /// Rows from index `first` on that are final: turned by the ridge
+    /// pass, validated, oriented and given marker hints exactly as in the
+    /// finished pattern
+    ///
+    /// Rows only ever become final in order, so a caller streaming rows out
+    /// passes the number it has already taken and gets just the new ones.
+    /// The ridge pass weighs every row against its neighbours on both
+    /// sides, so while it is on (the default) no row is final until every
+    /// row is placed; with `ridge_window` set to 0 each row is final as
+    /// soon as it is placed. Rows stop before any that `finish` rejects.
+    pub fn placed_rows(&self, first: usize) -> Vec<Row> {
+        let rows = match &self.smoothed {
+            Some(rows) => rows.as_slice(),
+            None if self.ridge_window == 0 => self.placer.placed_rows(),
+            None => return Vec::new(),
         };
+        let allowed = self.config.stitches.allowed(Construction::Round);
+        let valid = (first.max(1)..rows.len())
+            .find(|&idx| {
+                validate_pattern(&rows[idx], rows[idx - 1].total_stitches, allowed).is_err()
+            })
+            .unwrap_or(rows.len());
 
-        rows.push(Row {
-            row_number: row_idx + 1,
-            total_stitches,
-            pattern,
-        });
+        // Marker hints look back two rows
+        let from = first.saturating_sub(2).min(valid);
+        let oriented: Vec<Row> = rows[from..valid]
+            .iter()
+            .map(|row| orient_row(row, self.config))
+            .collect();
+        (first.max(from)..valid)
+            .map(|idx| {
+                let mut row = oriented[idx - from].clone();
+                if let Some(every) = self.config.marker_every {
+                    row.markers =
+                        row_marker_hints(&oriented, idx - from, Construction::Round, every);
+                }
+                row
+            })
+            .collect()
     }
 
-    // Step 5: Optimize stitch placement
-    let optimized_rows = optimize_stitch_placement(&rows);
+    /// Place any remaining rows and assemble the pattern
+    pub fn finish(mut self) -> Result<CrochetPattern> {
+        while self.step() {}
+        let Self {
+            curve,
+            config,
+            row_radii,
+            warnings,
+            placement,
+            ridge_window,
+            smoothed,
+            ..
+        } = self;
+        let optimized_rows = smoothed.expect("the last step runs the ridge pass");
+        let curve_min_y = curve.segments[0].start.y;
+
+        // Step 5.5: Validate patterns
+        for (idx, row) in optimized_rows.iter().enumerate() {
+            if idx > 0 {
+                let prev_stitches = optimized_rows[idx - 1].total_stitches;
+                validate_pattern(
+                    row,
+                    prev_stitches,
+                    config.stitches.allowed(Construction::Round),
+                )?;
+            }
+        }
+
+        // Step 5.75: Mirror for left-handers and move each round's start
+        let optimized_rows: Vec<Row> = optimized_rows
+            .iter()
+            .map(|row| orient_row(row, config))
+            .collect();
+        let mut notes = match config.handedness {
+            Handedness::Right => vec![],
+            Handedness::Left => {
+                vec!["Left-handed: work each round clockwise, viewed from the outside.".to_string()]
+            }
+        };
+        notes.extend(small_round_notes(&optimized_rows, config.min_round_stitches));
+
+        // Step 6: Compare achievable radii against the drawn profile.
+        // Row 1 is always the magic ring, so measure it against the bottom of the curve.
+        let mut target_radii = row_radii.clone();
+        target_radii[0] =
+            find_radius_at_height(curve, curve_min_y, config.quality.curve_tolerance());
+        let shape_fidelity = measure_shape_fidelity(&target_radii, &optimized_rows, config);
+
+        // Step 7: Calculate metadata
+        let mut metadata = calculate_metadata(&optimized_rows, config, Construction::Round);
+        metadata.shape_fidelity = shape_fidelity;
+        metadata.warnings = warnings;
+        metadata.seed = Some(placement.seed);
+        metadata.iterations = Some(placement.iterations);
+        metadata.cooling_rate = Some(
+            placement
+                .cooling_rate
+                .unwrap_or_else(|| default_cooling_rate(placement.iterations)),
+        );
+        metadata.method = Some(placement.method);
+        // Measured over the configured window; with smoothing off (0) this
+        // still looks at the row directly below
+        metadata.ridge_energy = Some(ridge_energy(&optimized_rows, ridge_window.max(1)));
+        if config.gauge_estimated {
+            metadata.warnings.push(
+                "Gauge is estimated from hook size and yarn weight; crochet a swatch to confirm"
+                    .to_string(),
+            );
+        }
+        metadata.design_size = Dimensions {
+            height_cm: config.total_height_cm,
+            width_cm: 2.0 * target_radii.iter().cloned().fold(0.0, f64::max),
+        };
 
-    // Step 5.5: Validate patterns
-    for (idx, row) in optimized_rows.iter().enumerate() {
-        if idx > 0 {
-            let prev_stitches = optimized_rows[idx - 1].total_stitches;
-            validate_pattern(row, prev_stitches)?;
+        let mut pattern = CrochetPattern {
+            row_groups: group_rows(&optimized_rows),
+            connections: row_connections(&optimized_rows, Construction::Round),
+            rows: optimized_rows,
+            metadata,
+            construction: Construction::Round,
+            foundation: Foundation::Chain,
+            notes,
+            anchors: vec![],
+            sections: vec![],
+            color_changes: vec![],
+        };
+        if let Some(every) = config.marker_every {
+            add_marker_hints(&mut pattern, every);
         }
+        pattern.metadata.difficulty = Some(rate_difficulty(&pattern, 1));
+        Ok(pattern)
     }
+}
 
-    // Step 6: Calculate metadata
-    let metadata = calculate_metadata(&optimized_rows, config);
+/// Left-handed crocheters work around the other way, and every round
+/// starts on the side the configuration asks for
+fn orient_row(row: &Row, config: &AmigurumiConfig) -> Row {
+    let row = match config.handedness {
+        Handedness::Right => row.clone(),
+        Handedness::Left => mirror_row(row),
+    };
+    let start_angle = config.round_start.angle();
+    if start_angle == 0.0 {
+        row
+    } else {
+        offset_row(&row, start_angle)
+    }
+}
 
-    Ok(CrochetPattern {
-        rows: optimized_rows,
-        metadata,
-    })
+/// Working notes for rounds smaller than the standard 6 stitches
+///
+/// A small magic ring leaves a pole that shows unless it is pulled shut
+/// before round 2, and a piece that closes down to the minimum is too
+/// narrow to decrease further, so it is gathered shut instead.
+fn small_round_notes(rows: &[Row], min_round: usize) -> Vec<String> {
+    if min_round >= 6 {
+        return vec![];
+    }
+    let mut notes = vec![format!(
+        "Round 1 has only {} stitches: pull the magic ring tail tight before starting round 2 so the pole closes.",
+        min_round
+    )];
+    if rows.len() > 1 && rows.last().map(|r| r.total_stitches) == Some(min_round) {
+        notes.push(format!(
+            "To close, fasten off leaving a tail, weave it through the front loops of the last {} stitches and pull tight.",
+            min_round
+        ));
+    }
+    notes
 }
 
 /// Validate profile curve
-fn validate_curve(curve: &ProfileCurve) -> Result<()> {
+fn validate_curve(curve: &ProfileCurve, limits: &InputLimits) -> Result<()> {
     if curve.segments.is_empty() {
         return Err(PatternError::InvalidProfileCurve(
             "Curve has no segments".to_string(),
         ));
     }
 
+    if curve.segments.len() > limits.max_segments {
+        return Err(PatternError::InvalidProfileCurve(format!(
+            "Curve has {} segments, limit is {}",
+            curve.segments.len(),
+            limits.max_segments
+        )));
+    }
+
+    let all_finite = curve.segments.iter().all(|seg| {
+        [seg.start, seg.control1, seg.control2, seg.end]
+            .iter()
+            .all(|p| p.x.is_finite() && p.y.is_finite())
+    });
+    if !all_finite {
+        return Err(PatternError::InvalidProfileCurve(
+            "Curve contains non-finite coordinates".to_string(),
+        ));
+    }
+
     if curve.start_radius < 0.0 {
         return Err(PatternError::InvalidProfileCurve(
             "Start radius must be non-negative".to_string(),
@@ -213,8 +590,21 @@ fn validate_curve(curve: &ProfileCurve) -> Result<()> {
     Ok(())
 }
 
-/// Validate configuration
-fn validate_config(config: &AmigurumiConfig) -> Result<()> {
+/// Validate configuration, as `generate_pattern` does before starting
+pub fn validate_config(config: &AmigurumiConfig) -> Result<()> {
+    let values = [
+        config.total_height_cm,
+        config.yarn.gauge_stitches_per_cm,
+        config.yarn.gauge_rows_per_cm,
+        config.yarn.recommended_hook_size_mm,
+        config.max_stitch_delta_fraction,
+    ];
+    if values.iter().any(|v| !v.is_finite()) {
+        return Err(PatternError::InvalidConfiguration(
+            "Configuration contains non-finite values".to_string(),
+        ));
+    }
+
     if config.total_height_cm <= 0.0 {
         return Err(PatternError::InvalidConfiguration(
             "Height must be positive".to_string(),
@@ -239,6 +629,121 @@ fn validate_config(config: &AmigurumiConfig) -> Result<()> {
         ));
     }
 
+    if config.max_stitch_delta_fraction <= 0.0 {
+        return Err(PatternError::InvalidConfiguration(
+            "Max stitch delta fraction must be positive".to_string(),
+        ));
+    }
+
+    if config.max_stitch_delta == Some(0) {
+        return Err(PatternError::InvalidConfiguration(
+            "Max stitch delta must be at least 1".to_string(),
+        ));
+    }
+
+    let tuning = &config.optimization;
+    if tuning.iterations.is_some_and(|n| n > MAX_ITERATIONS) {
+        return Err(PatternError::InvalidConfiguration(format!(
+            "Optimization iterations must be at most {}",
+            MAX_ITERATIONS
+        )));
+    }
+    if tuning
+        .cooling_rate
+        .is_some_and(|rate| !(rate > 0.0 && rate <= 1.0))
+    {
+        return Err(PatternError::InvalidConfiguration(
+            "Cooling rate must be greater than 0 and at most 1".to_string(),
+        ));
+    }
+    if tuning.ridge_window.is_some_and(|w| w > MAX_RIDGE_WINDOW) {
+        return Err(PatternError::InvalidConfiguration(format!(
+            "Ridge window must be at most {}",
+            MAX_RIDGE_WINDOW
+        )));
+    }
+    let weights = [
+        tuning.weights.spacing,
+        tuning.weights.stagger,
+        tuning.weights.drift,
+    ];
+    if weights.iter().any(|w| !(w.is_finite() && *w >= 0.0)) {
+        return Err(PatternError::InvalidConfiguration(
+            "Optimization weights must be finite and non-negative".to_string(),
+        ));
+    }
+
+    if config.min_round_stitches < 3 {
+        return Err(PatternError::InvalidConfiguration(
+            "Rounds need at least 3 stitches".to_string(),
+        ));
+    }
+
+    let speed = &config.crocheter;
+    if !(speed.sc_per_minute > 0.0 && speed.sc_per_minute.is_finite()) {
+        return Err(PatternError::InvalidConfiguration(
+            "Single crochets per minute must be positive".to_string(),
+        ));
+    }
+    let pauses = [
+        speed.inc_penalty,
+        speed.dec_penalty,
+        speed.color_change_seconds,
+        speed.row_start_seconds,
+    ];
+    if pauses.iter().any(|s| !(s.is_finite() && *s >= 0.0)) {
+        return Err(PatternError::InvalidConfiguration(
+            "Crocheter time penalties must be finite and non-negative".to_string(),
+        ));
+    }
+
+    let estimate = &config.yarn_estimate;
+    if !(estimate.skein_grams > 0.0 && estimate.skein_grams.is_finite()) {
+        return Err(PatternError::InvalidConfiguration(
+            "Skein weight must be positive".to_string(),
+        ));
+    }
+    if !(estimate.calibration > 0.0 && estimate.calibration.is_finite()) {
+        return Err(PatternError::InvalidConfiguration(
+            "Yarn calibration must be positive".to_string(),
+        ));
+    }
+
+    if !config.stitches.round.contains(&StitchType::SC) {
+        return Err(PatternError::InvalidConfiguration(
+            "SC must be an allowed stitch".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check a row shaper's counts can be worked: one per row, none empty,
+/// and each reachable from the row before with increases or decreases
+fn validate_stitch_counts(counts: &[usize], rows: usize) -> Result<()> {
+    if counts.len() != rows {
+        return Err(PatternError::InternalError(format!(
+            "Row shaper gave {} stitch counts for {} rows",
+            counts.len(),
+            rows
+        )));
+    }
+    if let Some(row) = counts.iter().position(|&c| c == 0) {
+        return Err(PatternError::InternalError(format!(
+            "Row shaper gave row {} no stitches",
+            row + 1
+        )));
+    }
+    for (idx, pair) in counts.windows(2).enumerate() {
+        if pair[1] > 2 * pair[0] || 2 * pair[1] < pair[0] {
+            return Err(PatternError::InternalError(format!(
+                "Row shaper asked row {} for {} stitches after {}; a row can at most double or halve",
+                idx + 2,
+                pair[1],
+                pair[0]
+            )));
+        }
+    }
     Ok(())
 }
 
@@ -249,11 +754,12 @@ fn validate_config(config: &AmigurumiConfig) -> Result<()> {
 /// - each instruction consumes stitches from prev row and produces stitches in current row
 /// - SC: consumes 1, produces 1
 /// - INC: consumes 1, produces 2
-/// - INVDEC: consumes 2, produces 1
+/// - DEC / INVDEC (`decrease`): consumes 2, produces 1
 fn generate_row_pattern(
     _row_number: usize,
     prev_stitches: usize,
     total_stitches: usize,
+    decrease: StitchType,
 ) -> Vec<StitchInstruction> {
     let delta = total_stitches as i32 - prev_stitches as i32;
 
@@ -281,7 +787,7 @@ fn generate_row_pattern(
             let angle = 2.0 * PI * i as f64 / prev_stitches as f64;
             
             // How many increases should we have placed by position i+1?
-            let target_inc_count = ((i + 1) * num_increases + prev_stitches - 1) / prev_stitches;
+            let target_inc_count = ((i + 1) * num_increases).div_ceil(prev_stitches);
             
             // If we need more increases, place one here
             let should_inc = inc_count < target_inc_count;
@@ -301,7 +807,7 @@ fn generate_row_pattern(
         }
         pattern
     } else {
-        // Decreases needed: INVDEC consumes 2 stitches, produces 1
+        // Decreases needed: a decrease consumes 2 stitches, produces 1
         let num_decreases = (-delta) as usize;
         
         let mut pattern = Vec::new();
@@ -312,19 +818,19 @@ fn generate_row_pattern(
             let angle = 2.0 * PI * i as f64 / prev_stitches as f64;
             
             // How many decreases should we have placed by consuming position i+1?
-            let target_dec_count = ((i + 1) * num_decreases + prev_stitches - 1) / prev_stitches;
+            let target_dec_count = ((i + 1) * num_decreases).div_ceil(prev_stitches);
             
             let should_dec = dec_count < target_dec_count && i + 1 < prev_stitches;
 
             if should_dec {
-                // INVDEC: work into this stitch and the next
+                // Decrease: work into this stitch and the next
                 pattern.push(StitchInstruction {
-                    stitch_type: StitchType::INVDEC,
+                    stitch_type: decrease,
                     angular_position: angle,
                     stitch_index: i,
                 });
                 dec_count += 1;
-                i += 2; // Skip next stitch (it's consumed by INVDEC)
+                i += 2; // Skip next stitch (it's consumed by the decrease)
             } else {
                 // SC: work into this stitch normally
                 pattern.push(StitchInstruction {
@@ -340,13 +846,55 @@ fn generate_row_pattern(
     }
 }
 
+/// Rework a pattern with only the stitches in `repertoire`
+///
+/// Decreases are switched to the allowed kind for the pattern's
+/// construction (invisible where both are allowed); stitch counts and
+/// positions are unchanged. Fails, leaving the pattern untouched, when a
+/// row needs a stitch that has no allowed substitute.
+pub fn apply_repertoire(pattern: &mut CrochetPattern, repertoire: &StitchRepertoire) -> Result<()> {
+    let allowed = repertoire.allowed(pattern.construction);
+    let decrease = repertoire.decrease(pattern.construction);
+    let swap = |stitch_type: StitchType| match (stitch_type, decrease) {
+        (StitchType::DEC | StitchType::INVDEC, Some(decrease)) => decrease,
+        _ => stitch_type,
+    };
+    for row in &pattern.rows {
+        if let Some(s) = row
+            .pattern
+            .iter()
+            .find(|s| !allowed.contains(&swap(s.stitch_type)))
+        {
+            return Err(PatternError::InvalidConfiguration(format!(
+                "Row {} needs {}, which is not an allowed stitch",
+                row.row_number,
+                s.stitch_type.to_string()
+            )));
+        }
+    }
+
+    for instruction in pattern.rows.iter_mut().flat_map(|r| r.pattern.iter_mut()) {
+        instruction.stitch_type = swap(instruction.stitch_type);
+    }
+    pattern.row_groups = group_rows(&pattern.rows);
+    pattern.connections = row_connections(&pattern.rows, pattern.construction);
+    Ok(())
+}
+
 /// Validate pattern correctness
-fn validate_pattern(row: &Row, prev_row_stitches: usize) -> Result<()> {
+fn validate_pattern(row: &Row, prev_row_stitches: usize, allowed: &[StitchType]) -> Result<()> {
     // Calculate how many stitches from previous row are consumed
     let mut prev_consumed = 0;
     let mut current_produced = 0;
     
     for instruction in &row.pattern {
+        if !allowed.contains(&instruction.stitch_type) {
+            return Err(PatternError::InternalError(format!(
+                "Row {}: uses {}, which is not an allowed stitch",
+                row.row_number,
+                instruction.stitch_type.to_string()
+            )));
+        }
         match instruction.stitch_type {
             StitchType::SC => {
                 prev_consumed += 1;
@@ -387,30 +935,43 @@ fn validate_pattern(row: &Row, prev_row_stitches: usize) -> Result<()> {
 }
 
 /// Calculate pattern metadata
-fn calculate_metadata(rows: &[Row], config: &AmigurumiConfig) -> PatternMetadata {
+///
+/// Shape fidelity, warnings and design size depend on the source of the
+/// rows and are left empty for the caller to fill in.
+pub(crate) fn calculate_metadata(
+    rows: &[Row],
+    config: &AmigurumiConfig,
+    construction: Construction,
+) -> PatternMetadata {
     let total_rows = rows.len();
     let total_stitches: usize = rows.iter().map(|r| r.total_stitches).sum();
 
-    // Estimate time: ~2 seconds per stitch
-    let estimated_time_minutes = (total_stitches as f64 * 2.0) / 60.0;
+    // Time at the crocheter's own speed, in one color for now like the yarn
+    let row_time_minutes = row_minutes(rows, &[], &config.crocheter);
 
-    // Estimate yarn length
-    // Average stitch uses ~1cm of yarn, plus circumference for each row
-    let mut yarn_length_cm = 0.0;
-    for row in rows.iter() {
-        // Estimate radius from stitch count (reverse of stitch calculation)
-        let circumference = row.total_stitches as f64 / config.yarn.gauge_stitches_per_cm;
-        let radius = circumference / (2.0 * PI);
-        
-        // Yarn used = circumference + ~1cm per stitch
-        yarn_length_cm += circumference + row.total_stitches as f64 * 1.0;
-    }
+    // Yarn from the stitches worked, all in one color until stripes or
+    // other color changes are added
+    let yarn_by_color = yarn_by_color(rows, &[], config);
 
     PatternMetadata {
         total_rows,
         total_stitches,
-        estimated_time_minutes,
-        yarn_length_meters: yarn_length_cm / 100.0,
+        estimated_time_minutes: row_time_minutes.iter().sum(),
+        row_time_minutes,
+        yarn_length_meters: yarn_by_color.iter().map(|y| y.meters).sum(),
+        shape_fidelity: ShapeFidelity::default(),
+        warnings: vec![],
+        design_size: Dimensions::default(),
+        finished_size: expected_finished_size(rows, config, construction),
+        gauge_estimated: config.gauge_estimated,
+        seed: None,
+        iterations: None,
+        cooling_rate: None,
+        method: None,
+        gauge_table: gauge_table(rows, config, construction),
+        ridge_energy: None,
+        yarn_by_color,
+        difficulty: None,
     }
 }
 
@@ -439,6 +1000,7 @@ mod tests {
                 gauge_rows_per_cm: 3.0,
                 recommended_hook_size_mm: 3.5,
             },
+            ..Default::default()
         }
     }
 
@@ -451,10 +1013,124 @@ mod tests {
         assert!(result.is_ok());
 
         let pattern = result.unwrap();
-        assert!(pattern.rows.len() > 0);
+        assert!(!pattern.rows.is_empty());
         assert_eq!(pattern.metadata.total_rows, pattern.rows.len());
     }
 
+    #[test]
+    fn test_shape_fidelity_reported_per_row() {
+        let curve = create_test_curve();
+        let config = create_test_config();
+
+        let pattern = generate_pattern(&curve, &config).unwrap();
+        let fidelity = &pattern.metadata.shape_fidelity;
+
+        assert_eq!(fidelity.row_errors_cm.len(), pattern.rows.len());
+        assert!(fidelity.rms_deviation_cm <= fidelity.max_deviation_cm);
+
+        // Once the magic ring has grown out, a cylinder is reproduced closely
+        let last_error = fidelity.row_errors_cm.last().unwrap();
+        assert!(last_error.abs() < 0.1);
+    }
+
+    #[test]
+    fn test_design_and_finished_size() {
+        let curve = create_test_curve();
+        let config = create_test_config();
+
+        let metadata = generate_pattern(&curve, &config).unwrap().metadata;
+
+        assert!((metadata.design_size.height_cm - 10.0).abs() < 1e-9);
+        assert!((metadata.design_size.width_cm - 4.0).abs() < 1e-6);
+        assert!((metadata.finished_size.height_cm - 10.0).abs() < 0.5);
+        assert!((metadata.finished_size.width_cm - 4.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_stepped_generation_matches_generate_pattern() {
+        let curve = create_test_curve();
+        let config = create_test_config();
+
+        let mut generation = PatternGeneration::start(&curve, &config).unwrap();
+        let mut last_progress = generation.progress();
+        while generation.step() {
+            assert!(generation.progress() > last_progress);
+            last_progress = generation.progress();
+        }
+        assert!(generation.is_done());
+        assert_eq!(generation.progress(), 1.0);
+
+        let stepped = generation.finish().unwrap();
+        let direct = generate_pattern(&curve, &config).unwrap();
+        for (a, b) in stepped.rows.iter().zip(&direct.rows) {
+            assert_eq!(a.pattern_string(), b.pattern_string());
+        }
+        assert_eq!(stepped.rows.len(), direct.rows.len());
+    }
+
+    #[test]
+    fn test_streamed_rows_match_finished_pattern() {
+        let curve = create_test_curve();
+        let mut config = create_test_config();
+        config.handedness = Handedness::Left;
+        config.round_start = RoundStart::Back;
+        config.marker_every = Some(3);
+
+        for ridge_window in [None, Some(0)] {
+            config.optimization.ridge_window = ridge_window;
+            let mut generation = PatternGeneration::start(&curve, &config).unwrap();
+            let mut streamed = Vec::new();
+            let mut streamed_early = 0;
+            while generation.step() {
+                if generation.progress() > 0.5 {
+                    streamed.extend(generation.placed_rows(streamed.len()));
+                    streamed_early = streamed.len();
+                }
+            }
+            streamed.extend(generation.placed_rows(streamed.len()));
+            let pattern = generation.finish().unwrap();
+
+            // The ridge pass can turn any row until every row is placed
+            assert_eq!(streamed_early > 0, ridge_window == Some(0));
+            assert_eq!(streamed.len(), pattern.rows.len());
+            for (a, b) in streamed.iter().zip(&pattern.rows) {
+                assert_eq!(a.row_number, b.row_number);
+                assert_eq!(a.pattern_string(), b.pattern_string());
+                assert_eq!(a.pattern[0].angular_position, b.pattern[0].angular_position);
+                assert_eq!(a.markers, b.markers);
+            }
+        }
+    }
+
+    #[test]
+    fn test_progress_reported_and_cancellation_stops() {
+        let curve = create_test_curve();
+        let config = create_test_config();
+
+        let mut reports = Vec::new();
+        let token = CancellationToken::new();
+        let pattern = generate_pattern_with_progress(&curve, &config, &token, |stage, percent| {
+            reports.push((stage.to_string(), percent))
+        })
+        .unwrap();
+        assert_eq!(reports.first().unwrap().0, "rows");
+        assert_eq!(reports.last().unwrap(), &("assembly".to_string(), 100.0));
+        assert_eq!(
+            reports.iter().filter(|(stage, _)| stage == "placement").count(),
+            pattern.rows.len()
+        );
+        assert!(reports.windows(2).all(|w| w[0].1 <= w[1].1));
+
+        let token = CancellationToken::new();
+        let handle = token.clone();
+        let result = generate_pattern_with_progress(&curve, &config, &token, |_, percent| {
+            if percent >= 50.0 {
+                handle.cancel();
+            }
+        });
+        assert!(matches!(result, Err(PatternError::Cancelled)));
+    }
+
     #[test]
     fn test_validate_empty_curve() {
         let curve = ProfileCurve {
@@ -463,7 +1139,156 @@ mod tests {
             end_radius: 2.0,
         };
 
-        assert!(validate_curve(&curve).is_err());
+        assert!(validate_curve(&curve, &InputLimits::default()).is_err());
+    }
+
+    #[test]
+    fn test_quality_presets_generate_valid_patterns() {
+        let curve = create_test_curve();
+        let balanced = generate_pattern(&curve, &create_test_config()).unwrap();
+
+        for quality in [Quality::Fast, Quality::Best] {
+            let config = AmigurumiConfig {
+                quality,
+                ..create_test_config()
+            };
+            let pattern = generate_pattern(&curve, &config).unwrap();
+
+            assert_eq!(pattern.rows.len(), balanced.rows.len());
+            assert_eq!(pattern.metadata.total_stitches, balanced.metadata.total_stitches);
+        }
+    }
+
+    #[test]
+    fn test_recorded_seed_reproduces_layout() {
+        let curve = create_test_curve();
+        let mut config = create_test_config();
+        config.optimization.seed = Some(7);
+        let original = generate_pattern(&curve, &config).unwrap();
+        assert_eq!(original.metadata.seed, Some(7));
+
+        // The recorded settings, defaults included, pin the layout down
+        let metadata = &original.metadata;
+        let mut config = create_test_config();
+        config.optimization.seed = metadata.seed;
+        config.optimization.iterations = metadata.iterations;
+        config.optimization.cooling_rate = metadata.cooling_rate;
+        config.optimization.method = metadata.method.unwrap();
+        let regenerated = generate_pattern(&curve, &config).unwrap();
+
+        let stitches = |p: &CrochetPattern| -> Vec<Vec<StitchType>> {
+            p.rows
+                .iter()
+                .map(|r| r.pattern.iter().map(|s| s.stitch_type).collect())
+                .collect()
+        };
+        assert_eq!(stitches(&original), stitches(&regenerated));
+    }
+
+    #[test]
+    fn test_optimization_config_is_reproducible_and_validated() {
+        let curve = create_test_curve();
+        let mut config = create_test_config();
+        config.optimization = OptimizationConfig {
+            seed: Some(11),
+            iterations: Some(50),
+            cooling_rate: Some(0.9),
+            weights: OptimizationWeights {
+                stagger: 3.0,
+                ..Default::default()
+            },
+            method: PlacementMethod::Annealing,
+            ridge_window: Some(2),
+        };
+
+        let first = generate_pattern(&curve, &config).unwrap();
+        let second = generate_pattern(&curve, &config).unwrap();
+        assert_eq!(first.metadata.seed, Some(11));
+        assert_eq!(first.metadata.iterations, Some(50));
+        assert_eq!(first.metadata.cooling_rate, Some(0.9));
+        assert_eq!(first.metadata.method, Some(PlacementMethod::Annealing));
+        for (a, b) in first.rows.iter().zip(&second.rows) {
+            assert_eq!(a.pattern_string(), b.pattern_string());
+        }
+
+        config.optimization.cooling_rate = Some(1.5);
+        assert!(generate_pattern(&curve, &config).is_err());
+        config.optimization.cooling_rate = None;
+        config.optimization.weights.spacing = -1.0;
+        assert!(generate_pattern(&curve, &config).is_err());
+        config.optimization.weights.spacing = 1.0;
+        config.optimization.iterations = Some(MAX_ITERATIONS + 1);
+        assert!(generate_pattern(&curve, &config).is_err());
+    }
+
+    #[test]
+    fn test_ridge_pass_reported_and_lowers_energy() {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(4.0, 2.0),
+                control2: Point2D::new(4.0, 8.0),
+                end: Point2D::new(0.5, 10.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 0.5,
+        };
+        let mut config = create_test_config();
+
+        let smoothed = generate_pattern(&curve, &config).unwrap();
+        config.optimization.ridge_window = Some(0);
+        let plain = generate_pattern(&curve, &config).unwrap();
+
+        let smoothed_energy = ridge_energy(&smoothed.rows, DEFAULT_RIDGE_WINDOW);
+        assert!(smoothed_energy <= ridge_energy(&plain.rows, DEFAULT_RIDGE_WINDOW));
+        for (a, b) in smoothed.rows.iter().zip(&plain.rows) {
+            assert_eq!(a.total_stitches, b.total_stitches);
+        }
+
+        // Reported energy follows the configured window
+        let reported = |pattern: &CrochetPattern, window| {
+            assert_eq!(
+                pattern.metadata.ridge_energy,
+                Some(ridge_energy(&pattern.rows, window))
+            );
+        };
+        reported(&smoothed, DEFAULT_RIDGE_WINDOW);
+        reported(&plain, 1);
+        config.optimization.ridge_window = Some(MAX_RIDGE_WINDOW);
+        reported(
+            &generate_pattern(&curve, &config).unwrap(),
+            MAX_RIDGE_WINDOW,
+        );
+
+        config.optimization.ridge_window = Some(MAX_RIDGE_WINDOW + 1);
+        assert!(generate_pattern(&curve, &config).is_err());
+    }
+
+    #[test]
+    fn test_limits_reject_oversized_input() {
+        let curve = create_test_curve();
+
+        let mut config = create_test_config();
+        config.total_height_cm = 1e12;
+        assert!(generate_pattern(&curve, &config).is_err());
+
+        let mut config = create_test_config();
+        config.limits.max_stitches_per_row = 10;
+        assert!(generate_pattern(&curve, &config).is_err());
+
+        let limits = InputLimits {
+            max_segments: 0,
+            ..Default::default()
+        };
+        assert!(validate_curve(&curve, &limits).is_err());
+
+        let mut curve = create_test_curve();
+        curve.segments[0].control1.x = f64::NAN;
+        assert!(validate_curve(&curve, &InputLimits::default()).is_err());
+
+        let mut config = create_test_config();
+        config.total_height_cm = f64::INFINITY;
+        assert!(validate_config(&config).is_err());
     }
 
     #[test]
@@ -474,9 +1299,162 @@ mod tests {
         assert!(validate_config(&config).is_err());
     }
 
+    #[test]
+    fn test_validate_delta_limits() {
+        let mut config = create_test_config();
+        config.max_stitch_delta_fraction = 0.0;
+        assert!(validate_config(&config).is_err());
+
+        let mut config = create_test_config();
+        config.max_stitch_delta = Some(0);
+        assert!(validate_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_capped_rows_surface_warnings() {
+        let curve = create_test_curve();
+        let mut config = create_test_config();
+        config.max_stitch_delta = Some(2);
+
+        let pattern = generate_pattern(&curve, &config).unwrap();
+        assert!(!pattern.metadata.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_mirror_row_reflects_order_and_angles() {
+        let row = Row {
+            row_number: 2,
+            total_stitches: 8,
+            pattern: generate_row_pattern(2, 6, 8, StitchType::INVDEC),
+            markers: Vec::new(),
+        };
+        let mirrored = mirror_row(&row);
+
+        assert_eq!(mirrored.pattern.len(), 6);
+        assert_eq!(mirrored.pattern[0].stitch_type, row.pattern[0].stitch_type);
+        for j in 1..6 {
+            assert_eq!(mirrored.pattern[j].stitch_type, row.pattern[6 - j].stitch_type);
+            assert_eq!(mirrored.pattern[j].stitch_index, j);
+            let expected = 2.0 * PI * j as f64 / 6.0;
+            assert!((mirrored.pattern[j].angular_position - expected).abs() < 1e-9);
+        }
+        assert!(validate_pattern(&mirrored, 6, &StitchRepertoire::default().round).is_ok());
+    }
+
+    #[test]
+    fn test_left_handed_pattern_is_valid_and_noted() {
+        let curve = create_test_curve();
+        let mut config = create_test_config();
+        config.handedness = Handedness::Left;
+
+        let left = generate_pattern(&curve, &config).unwrap();
+        let right = generate_pattern(&curve, &create_test_config()).unwrap();
+
+        assert_eq!(left.rows.len(), right.rows.len());
+        for (l, r) in left.rows.iter().zip(&right.rows) {
+            assert_eq!(l.total_stitches, r.total_stitches);
+        }
+        assert!(left.notes.iter().any(|n| n.contains("clockwise")));
+    }
+
+    #[test]
+    fn test_small_rounds_for_tiny_limbs() {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(0.6, 0.5),
+                control2: Point2D::new(0.6, 2.5),
+                end: Point2D::new(0.0, 3.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 0.0,
+        };
+        let mut config = create_test_config();
+        config.total_height_cm = 3.0;
+        config.min_round_stitches = 4;
+
+        let pattern = generate_pattern(&curve, &config).unwrap();
+
+        assert_eq!(pattern.rows[0].total_stitches, 4);
+        assert_eq!(pattern.rows.last().unwrap().total_stitches, 4);
+        assert!(pattern.rows.iter().all(|r| r.total_stitches >= 4));
+        assert!(pattern.metadata.warnings[0].starts_with("Rounds as small as 4 stitches"));
+        assert!(pattern.notes.iter().any(|n| n.contains("pole closes")));
+        assert!(pattern
+            .notes
+            .iter()
+            .any(|n| n.contains("front loops of the last 4 stitches")));
+
+        let standard = generate_pattern(&curve, &create_test_config()).unwrap();
+        assert!(standard.notes.is_empty());
+
+        config.min_round_stitches = 2;
+        assert!(generate_pattern(&curve, &config).is_err());
+    }
+
+    #[test]
+    fn test_stitch_repertoire_is_honored() {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, 1.0),
+                control2: Point2D::new(3.0, 5.0),
+                end: Point2D::new(0.5, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 0.5,
+        };
+        let mut config = create_test_config();
+        config.total_height_cm = 6.0;
+        let uses = |pattern: &CrochetPattern, stitch_type| {
+            pattern
+                .rows
+                .iter()
+                .flat_map(|r| &r.pattern)
+                .any(|s| s.stitch_type == stitch_type)
+        };
+
+        let standard = generate_pattern(&curve, &config).unwrap();
+        assert!(uses(&standard, StitchType::INVDEC));
+        assert!(!uses(&standard, StitchType::DEC));
+
+        config.stitches.round = vec![StitchType::SC, StitchType::INC, StitchType::DEC];
+        let plain = generate_pattern(&curve, &config).unwrap();
+        assert!(uses(&plain, StitchType::DEC));
+        assert!(!uses(&plain, StitchType::INVDEC));
+
+        let mut converted = standard.clone();
+        apply_repertoire(&mut converted, &config.stitches).unwrap();
+        assert_eq!(
+            converted.rows[5].pattern_string(),
+            plain.rows[5].pattern_string().replace("INVDEC", "DEC")
+        );
+        assert!(!uses(&converted, StitchType::INVDEC));
+
+        config.stitches.round = vec![StitchType::SC, StitchType::INC];
+        assert!(generate_pattern(&curve, &config).is_err());
+        assert!(apply_repertoire(&mut converted, &config.stitches).is_err());
+        config.stitches.round = vec![StitchType::INC, StitchType::INVDEC];
+        assert!(generate_pattern(&curve, &config).is_err());
+    }
+
+    #[test]
+    fn test_round_start_offsets_angles() {
+        let curve = create_test_curve();
+        let mut config = create_test_config();
+        config.round_start = RoundStart::Back;
+
+        let pattern = generate_pattern(&curve, &config).unwrap();
+
+        for row in &pattern.rows {
+            assert!((row.pattern[0].angular_position - PI).abs() < 1e-9);
+            assert_eq!(row.pattern[0].stitch_index, 0);
+        }
+    }
+
     #[test]
     fn test_generate_row_pattern_no_change() {
-        let pattern = generate_row_pattern(1, 12, 12);
+        let pattern = generate_row_pattern(1, 12, 12, StitchType::INVDEC);
         assert_eq!(pattern.len(), 12);
 
         for stitch in &pattern {
@@ -487,7 +1465,7 @@ mod tests {
     #[test]
     fn test_generate_row_pattern_increases() {
         // Row has 12 stitches, next needs 18 (delta = +6)
-        let pattern = generate_row_pattern(2, 12, 18);
+        let pattern = generate_row_pattern(2, 12, 18, StitchType::INVDEC);
         
         // Should have 12 instructions (one per previous stitch)
         assert_eq!(pattern.len(), 12);
@@ -521,7 +1499,7 @@ mod tests {
     #[test]
     fn test_generate_row_pattern_decreases() {
         // Row has 18 stitches, next needs 12 (delta = -6)
-        let pattern = generate_row_pattern(3, 18, 12);
+        let pattern = generate_row_pattern(3, 18, 12, StitchType::INVDEC);
         
         // Count stitches consumed from previous row
         let consumed: usize = pattern
@@ -0,0 +1,108 @@
+use crochet_types::{Row, RowGroup};
+
+/// Merge consecutive rows with identical instructions into ranges
+///
+/// Two rows match when they produce the same number of stitches from the
+/// same sequence of stitch types and are numbered one after the other.
+/// Every row belongs to exactly one group.
+pub fn group_rows(rows: &[Row]) -> Vec<RowGroup> {
+    let mut groups: Vec<RowGroup> = Vec::new();
+
+    for (idx, row) in rows.iter().enumerate() {
+        let extends_previous = idx > 0
+            && row.row_number == rows[idx - 1].row_number + 1
+            && same_instructions(&rows[idx - 1], row);
+        match groups.last_mut() {
+            Some(group) if extends_previous => group.last_row = row.row_number,
+            _ => groups.push(RowGroup {
+                first_row: row.row_number,
+                last_row: row.row_number,
+                total_stitches: row.total_stitches,
+            }),
+        }
+    }
+
+    groups
+}
+
+fn same_instructions(a: &Row, b: &Row) -> bool {
+    a.total_stitches == b.total_stitches
+        && a.pattern.len() == b.pattern.len()
+        && a
+            .pattern
+            .iter()
+            .zip(&b.pattern)
+            .all(|(x, y)| x.stitch_type == y.stitch_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crochet_types::{StitchInstruction, StitchType};
+
+    fn create_test_row(row_number: usize, types: &[StitchType], total_stitches: usize) -> Row {
+        Row {
+            row_number,
+            total_stitches,
+            pattern: types
+                .iter()
+                .enumerate()
+                .map(|(i, &stitch_type)| StitchInstruction {
+                    stitch_type,
+                    angular_position: 0.0,
+                    stitch_index: i,
+                })
+                .collect(),
+            markers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_groups_identical_runs() {
+        use StitchType::*;
+        let rows = vec![
+            create_test_row(1, &[SC; 6], 6),
+            create_test_row(2, &[INC; 6], 12),
+            create_test_row(3, &[SC; 12], 12),
+            create_test_row(4, &[SC; 12], 12),
+            create_test_row(5, &[SC; 12], 12),
+            create_test_row(6, &[SC, INC, SC, INC, SC, INC, SC, INC, SC, INC, SC, INC], 18),
+        ];
+
+        let groups = group_rows(&rows);
+
+        assert_eq!(groups.len(), 4);
+        assert_eq!(groups[2].first_row, 3);
+        assert_eq!(groups[2].last_row, 5);
+        assert_eq!(groups[2].row_count(), 3);
+        assert_eq!(groups[3].first_row, 6);
+    }
+
+    #[test]
+    fn test_same_count_different_layout_not_merged() {
+        use StitchType::*;
+        let rows = vec![
+            create_test_row(1, &[SC, INC, SC, SC], 5),
+            create_test_row(2, &[INC, SC, SC, SC], 5),
+        ];
+
+        assert_eq!(group_rows(&rows).len(), 2);
+    }
+
+    #[test]
+    fn test_out_of_order_rows_not_merged() {
+        use StitchType::*;
+        let rows = vec![
+            create_test_row(3, &[SC; 6], 6),
+            create_test_row(1, &[SC; 6], 6),
+            create_test_row(2, &[SC; 6], 6),
+            create_test_row(5, &[SC; 6], 6),
+        ];
+
+        let groups = group_rows(&rows);
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!((groups[1].first_row, groups[1].last_row), (1, 2));
+        assert!(groups.iter().all(|g| g.first_row <= g.last_row));
+    }
+}
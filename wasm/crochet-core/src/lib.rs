@@ -3,6 +3,38 @@ pub mod radius;
 pub mod row_mapping;
 pub mod stitch_count;
 pub mod optimization;
+pub mod plugins;
+pub mod fidelity;
 pub mod generator;
+pub mod context;
+pub mod swatch;
+pub mod materials;
+pub mod yarn;
+pub mod timing;
+pub mod formatter;
+pub mod pdf;
+pub mod epub;
+pub mod speech;
+pub mod diagram;
+pub mod scene;
+pub mod preview;
+pub mod crochetout;
+pub mod parser;
+pub mod corpus;
+pub mod eyes;
+pub mod base;
+pub mod grouping;
+pub mod row_ops;
+pub mod sections;
+pub mod compare;
+pub mod verify;
+pub mod difficulty;
+pub mod lineage;
+pub mod ridges;
+pub mod texture;
+pub mod palettes;
+pub mod stripes;
+pub mod markers;
+pub mod tapestry;
 
 pub use crochet_types::*;
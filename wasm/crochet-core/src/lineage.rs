@@ -0,0 +1,415 @@
+use crochet_types::*;
+use std::f64::consts::PI;
+
+use crate::grouping::group_rows;
+use crate::parser::{consumes, produces};
+
+/// Anchor a feature at an angle and keep its column from drifting
+///
+/// `angle_degrees` is measured like the eye placement: 0 is centre front
+/// and positive angles go to the left. The anchor goes on the stitch of
+/// `row_number` nearest that angle, replacing any anchor of the same name.
+/// The column of stitches worked up from it is then held at that angle:
+/// in each later round whose increases and decreases would carry it more
+/// than half a stitch sideways, the round's stitches are turned by the
+/// fewest places that bring it back. Counts and shaping are unchanged;
+/// only where each round's increases or decreases fall moves. The rounds
+/// from `row_number` up must spell out their stitches, making the count
+/// each one states.
+pub fn anchor_at_angle(
+    pattern: &mut CrochetPattern,
+    name: &str,
+    row_number: usize,
+    angle_degrees: f64,
+) -> Result<Anchor> {
+    if pattern.construction != Construction::Round {
+        return Err(PatternError::InvalidConfiguration(
+            "Only pieces worked in the round have angular columns".to_string(),
+        ));
+    }
+    if !angle_degrees.is_finite() {
+        return Err(PatternError::InvalidConfiguration(
+            "Anchor angle must be finite".to_string(),
+        ));
+    }
+    let row_idx = pattern
+        .rows
+        .iter()
+        .position(|r| r.row_number == row_number)
+        .ok_or_else(|| {
+            PatternError::InvalidConfiguration(format!("Pattern has no row {}", row_number))
+        })?;
+    if let Some(row) = pattern.rows[row_idx..].iter().find(|r| {
+        r.pattern
+            .iter()
+            .map(|s| produces(s.stitch_type))
+            .sum::<usize>()
+            != r.total_stitches
+    }) {
+        return Err(PatternError::InvalidConfiguration(format!(
+            "Row {} does not make the {} stitches it says it has",
+            row.row_number, row.total_stitches
+        )));
+    }
+
+    let target = angle_degrees.to_radians().rem_euclid(2.0 * PI);
+    let row = &pattern.rows[row_idx];
+    let mut column = (0..row.total_stitches)
+        .min_by(|&a, &b| {
+            let offset = |s| angle_offset(row, s, target);
+            offset(a).total_cmp(&offset(b))
+        })
+        .unwrap_or(0);
+    let anchor = Anchor {
+        name: name.to_string(),
+        row_number,
+        stitch: column + 1,
+    };
+
+    for row in pattern.rows.iter_mut().skip(row_idx + 1) {
+        hold_column(row, column, target);
+        column = column_above(row, column);
+    }
+    pattern.row_groups = group_rows(&pattern.rows);
+    pattern.connections = row_connections(&pattern.rows, pattern.construction);
+
+    pattern.anchors.retain(|a| a.name != name);
+    pattern.anchors.push(anchor.clone());
+    Ok(anchor)
+}
+
+/// 1-based stitch of every round from `row_number` up that sits on the
+/// column starting at `stitch`
+pub fn trace_column(
+    pattern: &CrochetPattern,
+    row_number: usize,
+    stitch: usize,
+) -> Result<Vec<usize>> {
+    let row_idx = pattern
+        .rows
+        .iter()
+        .position(|r| r.row_number == row_number)
+        .ok_or_else(|| {
+            PatternError::InvalidConfiguration(format!("Pattern has no row {}", row_number))
+        })?;
+    if stitch == 0 || stitch > pattern.rows[row_idx].total_stitches {
+        return Err(PatternError::InvalidConfiguration(format!(
+            "Row {} has no stitch {}",
+            row_number, stitch
+        )));
+    }
+
+    let mut column = stitch - 1;
+    let mut stitches = vec![stitch];
+    for row in &pattern.rows[row_idx + 1..] {
+        column = column_above(row, column);
+        stitches.push(column + 1);
+    }
+    Ok(stitches)
+}
+
+/// Which stitches of the round below every stitch was worked into
+pub fn column_flow(pattern: &CrochetPattern) -> Result<ColumnFlow> {
+    if pattern.construction != Construction::Round {
+        return Err(PatternError::InvalidConfiguration(
+            "Only pieces worked in the round have angular columns".to_string(),
+        ));
+    }
+
+    let rows = pattern
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(idx, row)| {
+            let mut below = 0;
+            let mut stitches = Vec::with_capacity(row.total_stitches);
+            for instruction in &row.pattern {
+                let worked_into: Vec<usize> = if idx == 0 {
+                    vec![]
+                } else {
+                    (below + 1..=below + consumes(instruction.stitch_type)).collect()
+                };
+                below += consumes(instruction.stitch_type);
+                for _ in 0..produces(instruction.stitch_type) {
+                    stitches.push(FlowStitch {
+                        stitch_type: instruction.stitch_type,
+                        worked_into: worked_into.clone(),
+                    });
+                }
+            }
+            ColumnFlowRow {
+                row_number: row.row_number,
+                stitches,
+            }
+        })
+        .collect();
+
+    Ok(ColumnFlow { rows })
+}
+
+/// Which stitches of the row below each instruction works into
+///
+/// Rounds carry on in the same direction, so an instruction works into
+/// the next stitches of the round below. A flat row is turned first, so
+/// it starts in the last stitch of the row below and works back.
+pub fn row_connections(rows: &[Row], construction: Construction) -> Vec<RowConnections> {
+    rows.iter()
+        .enumerate()
+        .map(|(idx, row)| {
+            let below_total = idx.checked_sub(1).map_or(0, |i| rows[i].total_stitches);
+            let mut below = 0;
+            let worked_into = row
+                .pattern
+                .iter()
+                .map(|instruction| {
+                    let used = if idx == 0 {
+                        0
+                    } else {
+                        consumes(instruction.stitch_type)
+                    };
+                    let stitches = (below..below + used)
+                        .map(|b| match construction {
+                            Construction::Round => b + 1,
+                            Construction::Flat => below_total.saturating_sub(b),
+                        })
+                        .collect();
+                    below += used;
+                    stitches
+                })
+                .collect();
+            RowConnections {
+                row_number: row.row_number,
+                worked_into,
+            }
+        })
+        .collect()
+}
+
+/// 0-based stitch of `row` worked into stitch `below` of the previous row
+///
+/// An increase makes two stitches in one; the column carries on from the
+/// first. A decrease joins two columns into one.
+fn column_above(row: &Row, below: usize) -> usize {
+    let mut consumed = 0;
+    let mut produced = 0;
+    for instruction in &row.pattern {
+        consumed += consumes(instruction.stitch_type);
+        if below < consumed {
+            return produced;
+        }
+        produced += produces(instruction.stitch_type);
+    }
+    produced.saturating_sub(1)
+}
+
+/// Angle between stitch `stitch` of `row` and `target`
+///
+/// A round's stitches sit evenly around the piece, starting at the angle
+/// of its first instruction.
+fn angle_offset(row: &Row, stitch: usize, target: f64) -> f64 {
+    let start = row.pattern.first().map_or(0.0, |s| s.angular_position);
+    let angle = start + 2.0 * PI * stitch as f64 / row.total_stitches as f64;
+    let d = (angle - target).rem_euclid(2.0 * PI);
+    d.min(2.0 * PI - d)
+}
+
+/// Turn the stitches of `row` so stitch `below` of the previous row
+/// carries on within half a stitch of `target`
+fn hold_column(row: &mut Row, below: usize, target: f64) {
+    let n = row.pattern.len();
+    let half_stitch = PI / row.total_stitches as f64;
+    let offset = |row: &Row| angle_offset(row, column_above(row, below), target);
+    if n == 0 || offset(row) <= half_stitch {
+        return;
+    }
+
+    let types: Vec<StitchType> = row.pattern.iter().map(|s| s.stitch_type).collect();
+    let turned = |shift: usize| {
+        let mut turned = row.clone();
+        for (i, instruction) in turned.pattern.iter_mut().enumerate() {
+            instruction.stitch_type = types[(i + shift) % n];
+        }
+        turned
+    };
+    // Fewest places first, so the round changes as little as it can
+    let best = (1..n)
+        .map(|shift| (shift.min(n - shift), shift))
+        .map(|(places, shift)| (offset(&turned(shift)), places, shift))
+        .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+    if let Some((best_offset, _, shift)) = best {
+        if best_offset < offset(row) {
+            *row = turned(shift);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_pattern;
+    use crate::swatch::generate_gauge_swatch;
+
+    fn create_head() -> CrochetPattern {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(5.0, 1.0),
+                control2: Point2D::new(5.0, 7.0),
+                end: Point2D::new(1.0, 8.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 1.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: 8.0,
+            ..Default::default()
+        };
+        generate_pattern(&curve, &config).unwrap()
+    }
+
+    #[test]
+    fn test_anchored_column_holds_its_angle() {
+        let mut pattern = create_head();
+        let before = pattern.clone();
+
+        let anchor = anchor_at_angle(&mut pattern, "eye", 4, 90.0).unwrap();
+        let column = trace_column(&pattern, 4, anchor.stitch).unwrap();
+
+        assert_eq!(column.len(), pattern.rows.len() - 3);
+        for (row, &stitch) in pattern.rows[3..].iter().zip(&column) {
+            assert!(
+                angle_offset(row, stitch - 1, PI / 2.0) <= PI / row.total_stitches as f64 + 1e-9,
+                "row {} drifted",
+                row.row_number
+            );
+        }
+        for (a, b) in pattern.rows.iter().zip(&before.rows) {
+            assert_eq!(a.total_stitches, b.total_stitches);
+            assert_eq!(a.pattern.len(), b.pattern.len());
+            if a.row_number <= 4 {
+                assert_eq!(a.pattern_string(), b.pattern_string());
+            }
+        }
+        assert_eq!(pattern.anchors, vec![anchor]);
+    }
+
+    #[test]
+    fn test_column_follows_increases_and_decreases() {
+        let row = |types: &[StitchType]| Row {
+            row_number: 2,
+            total_stitches: types.iter().map(|&t| produces(t)).sum(),
+            pattern: types
+                .iter()
+                .enumerate()
+                .map(|(i, &stitch_type)| StitchInstruction {
+                    stitch_type,
+                    angular_position: 2.0 * PI * i as f64 / types.len() as f64,
+                    stitch_index: i,
+                })
+                .collect(),
+            markers: Vec::new(),
+        };
+        let inc = row(&[StitchType::INC, StitchType::SC, StitchType::INC]);
+        let dec = row(&[StitchType::INVDEC, StitchType::SC, StitchType::SC]);
+
+        assert_eq!(column_above(&inc, 0), 0);
+        assert_eq!(column_above(&inc, 1), 2);
+        assert_eq!(column_above(&inc, 2), 3);
+        assert_eq!(column_above(&dec, 1), 0);
+        assert_eq!(column_above(&dec, 3), 2);
+    }
+
+    #[test]
+    fn test_column_flow_links_every_stitch() {
+        let pattern = create_head();
+
+        let flow = column_flow(&pattern).unwrap();
+
+        assert_eq!(flow.rows.len(), pattern.rows.len());
+        assert!(flow.rows[0]
+            .stitches
+            .iter()
+            .all(|s| s.worked_into.is_empty()));
+        for (pair, flow_row) in pattern.rows.windows(2).zip(&flow.rows[1..]) {
+            let (below, row) = (&pair[0], &pair[1]);
+            assert_eq!(flow_row.stitches.len(), row.total_stitches);
+            // Every stitch below is worked into, in order, by at least one stitch
+            let mut covered: Vec<usize> = flow_row
+                .stitches
+                .iter()
+                .flat_map(|s| s.worked_into.iter().copied())
+                .collect();
+            covered.dedup();
+            assert_eq!(covered, (1..=below.total_stitches).collect::<Vec<_>>());
+            // ...and agrees with the column traced up from it
+            for b in 0..below.total_stitches {
+                let above = &flow_row.stitches[column_above(row, b)];
+                assert!(above.worked_into.contains(&(b + 1)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_connections_follow_the_work() {
+        let mut pattern = create_head();
+        let flow = column_flow(&pattern).unwrap();
+
+        assert_eq!(pattern.connections.len(), pattern.rows.len());
+        for ((row, connections), flow_row) in pattern
+            .rows
+            .iter()
+            .zip(&pattern.connections)
+            .zip(&flow.rows)
+        {
+            assert_eq!(connections.row_number, row.row_number);
+            assert_eq!(connections.worked_into.len(), row.pattern.len());
+            // Both stitches of an increase share their instruction's stitch
+            let per_stitch: Vec<&Vec<usize>> = row
+                .pattern
+                .iter()
+                .zip(&connections.worked_into)
+                .flat_map(|(s, into)| std::iter::repeat_n(into, produces(s.stitch_type)))
+                .collect();
+            let flowed: Vec<&Vec<usize>> =
+                flow_row.stitches.iter().map(|s| &s.worked_into).collect();
+            assert_eq!(per_stitch, flowed);
+        }
+
+        // Turning the rounds keeps the connections in step
+        anchor_at_angle(&mut pattern, "eye", 4, 90.0).unwrap();
+        assert_eq!(
+            pattern.connections,
+            row_connections(&pattern.rows, Construction::Round)
+        );
+
+        // A flat row starts in the last stitch of the row below
+        let swatch = generate_gauge_swatch(&AmigurumiConfig::default().yarn).unwrap();
+        let width = swatch.rows[0].total_stitches;
+        let second = &swatch.connections[1].worked_into;
+        assert_eq!(second[0], vec![width]);
+        assert_eq!(second[width - 1], vec![1]);
+        assert!(swatch.connections[0]
+            .worked_into
+            .iter()
+            .all(|w| w.is_empty()));
+    }
+
+    #[test]
+    fn test_invalid_anchor_requests() {
+        let mut pattern = create_head();
+
+        assert!(anchor_at_angle(&mut pattern, "eye", 999, 90.0).is_err());
+        assert!(anchor_at_angle(&mut pattern, "eye", 3, f64::NAN).is_err());
+        // A later round that claims no stitches but lists some
+        let mut zero = pattern.clone();
+        zero.rows[6].total_stitches = 0;
+        assert!(anchor_at_angle(&mut zero, "eye", 3, 90.0).is_err());
+        // ...or is written as plain-row shorthand
+        let mut shorthand = pattern.clone();
+        shorthand.rows[6].pattern.clear();
+        assert!(anchor_at_angle(&mut shorthand, "eye", 3, 90.0).is_err());
+        assert!(anchor_at_angle(&mut shorthand, "eye", 8, 90.0).is_ok());
+        assert!(trace_column(&pattern, 3, 0).is_err());
+        assert!(trace_column(&pattern, 3, 999).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use crochet_types::*;
+
+use crate::parser::{consumes, produces};
+
+/// Add stitch-marker hints to a pattern, replacing any it already has
+///
+/// A piece worked in the round gets a start-of-round marker on its first
+/// row. Every `every`th row (none when 0), and each row where the shaping
+/// changes between increasing, working even and decreasing, gets a check
+/// halfway along: how far into the row the crocheter should be once they
+/// have worked into half of the row below.
+pub fn add_marker_hints(pattern: &mut CrochetPattern, every: usize) {
+    for idx in 0..pattern.rows.len() {
+        pattern.rows[idx].markers =
+            row_marker_hints(&pattern.rows, idx, pattern.construction, every);
+    }
+}
+
+/// Marker hints `add_marker_hints` gives row `idx`, which depend only on
+/// that row and the two before it
+pub(crate) fn row_marker_hints(
+    rows: &[Row],
+    idx: usize,
+    construction: Construction,
+    every: usize,
+) -> Vec<MarkerHint> {
+    if idx == 0 {
+        return match construction {
+            Construction::Round => vec![MarkerHint::RoundStart],
+            Construction::Flat => vec![],
+        };
+    }
+    let shaping = |idx: usize| rows[idx].total_stitches.cmp(&rows[idx - 1].total_stitches);
+    let before = match (idx, construction) {
+        (1, Construction::Round) => std::cmp::Ordering::Greater,
+        (1, Construction::Flat) => std::cmp::Ordering::Equal,
+        _ => shaping(idx - 1),
+    };
+    let periodic = every > 0 && rows[idx].row_number.is_multiple_of(every);
+    if periodic || shaping(idx) != before {
+        halfway_checkpoint(&rows[idx]).into_iter().collect()
+    } else {
+        vec![]
+    }
+}
+
+/// Check at the first instruction boundary that reaches half the row below
+fn halfway_checkpoint(row: &Row) -> Option<MarkerHint> {
+    let below: usize = row.pattern.iter().map(|s| consumes(s.stitch_type)).sum();
+    if below < 2 {
+        return None;
+    }
+    checkpoint_at(row, below.div_ceil(2))
+}
+
+fn checkpoint_at(row: &Row, worked: usize) -> Option<MarkerHint> {
+    let mut consumed = 0;
+    let mut produced = 0;
+    for instruction in &row.pattern {
+        consumed += consumes(instruction.stitch_type);
+        produced += produces(instruction.stitch_type);
+        if consumed >= worked {
+            return Some(MarkerHint::Checkpoint {
+                worked: consumed,
+                stitch: produced,
+                total: row.total_stitches,
+            });
+        }
+    }
+    None
+}
+
+/// Work a row's checkpoints out again after its instructions were reordered
+pub(crate) fn recount_markers(row: &mut Row) {
+    let markers = std::mem::take(&mut row.markers);
+    row.markers = markers
+        .into_iter()
+        .filter_map(|marker| match marker {
+            MarkerHint::Checkpoint { worked, .. } => checkpoint_at(row, worked),
+            other => Some(other),
+        })
+        .collect();
+}
+
+/// Sentence telling the crocheter what to do with a marker
+pub fn marker_instruction(marker: &MarkerHint) -> String {
+    match marker {
+        MarkerHint::RoundStart => {
+            "Place a marker in the first stitch and move it up at the start of each round."
+                .to_string()
+        }
+        MarkerHint::Checkpoint {
+            worked,
+            stitch,
+            total,
+        } => format!(
+            "Marker check: after working into {} stitches, you should be on stitch {} of {}.",
+            worked, stitch, total
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_pattern;
+
+    fn create_pattern() -> CrochetPattern {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(4.0, 1.0),
+                control2: Point2D::new(4.0, 6.0),
+                end: Point2D::new(1.0, 7.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 1.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: 7.0,
+            ..Default::default()
+        };
+        generate_pattern(&curve, &config).unwrap()
+    }
+
+    #[test]
+    fn test_hints_at_start_and_shaping_changes() {
+        let mut pattern = create_pattern();
+        add_marker_hints(&mut pattern, 0);
+
+        assert_eq!(pattern.rows[0].markers, [MarkerHint::RoundStart]);
+        assert!(pattern.rows[1].markers.is_empty());
+        let first_decrease = pattern
+            .rows
+            .windows(2)
+            .find(|pair| pair[1].total_stitches < pair[0].total_stitches)
+            .map(|pair| &pair[1])
+            .unwrap();
+        let Some(MarkerHint::Checkpoint {
+            worked,
+            stitch,
+            total,
+        }) = first_decrease.markers.first().copied()
+        else {
+            panic!("no checkpoint on row {}", first_decrease.row_number);
+        };
+        assert_eq!(total, first_decrease.total_stitches);
+        assert!(stitch < total && stitch <= worked);
+
+        let hinted = pattern
+            .rows
+            .iter()
+            .filter(|r| !r.markers.is_empty())
+            .count();
+        add_marker_hints(&mut pattern, 4);
+        assert!(
+            pattern
+                .rows
+                .iter()
+                .filter(|r| !r.markers.is_empty())
+                .count()
+                > hinted
+        );
+        assert!(!pattern.rows[3].markers.is_empty());
+    }
+
+    #[test]
+    fn test_checkpoints_follow_reordered_rows() {
+        use StitchType::*;
+        let mut row = Row {
+            row_number: 3,
+            total_stitches: 8,
+            pattern: [INC, INC, SC, SC, SC, SC]
+                .iter()
+                .enumerate()
+                .map(|(i, &stitch_type)| StitchInstruction {
+                    stitch_type,
+                    angular_position: 0.0,
+                    stitch_index: i,
+                })
+                .collect(),
+            markers: Vec::new(),
+        };
+        row.markers = halfway_checkpoint(&row).into_iter().collect();
+        assert_eq!(
+            row.markers,
+            [MarkerHint::Checkpoint {
+                worked: 3,
+                stitch: 5,
+                total: 8
+            }]
+        );
+
+        row.pattern.rotate_left(2);
+        recount_markers(&mut row);
+        assert_eq!(
+            row.markers,
+            [MarkerHint::Checkpoint {
+                worked: 3,
+                stitch: 3,
+                total: 8
+            }]
+        );
+
+        assert_eq!(
+            marker_instruction(&row.markers[0]),
+            "Marker check: after working into 3 stitches, you should be on stitch 3 of 8."
+        );
+    }
+}
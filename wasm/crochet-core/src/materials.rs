@@ -0,0 +1,363 @@
+use crochet_types::*;
+use std::f64::consts::PI;
+
+use crate::yarn::{closest_weight, substitute_yarn, weight_info};
+
+/// Tuning for shopping list estimates
+#[derive(Debug, Clone)]
+pub struct ShoppingListOptions {
+    /// Yarn per skein in meters
+    pub skein_length_m: f64,
+    /// Extra yarn on top of the estimate (0.2 = 20%)
+    pub yarn_margin: f64,
+    /// Fiberfill needed per cm³ of stuffed volume
+    pub stuffing_grams_per_cm3: f64,
+}
+
+impl Default for ShoppingListOptions {
+    fn default() -> Self {
+        Self {
+            skein_length_m: 100.0,
+            yarn_margin: 0.2,
+            stuffing_grams_per_cm3: 0.025,
+        }
+    }
+}
+
+/// Build a consolidated shopping list across all pieces of a project
+pub fn build_shopping_list(pieces: &[ProjectPiece], options: &ShoppingListOptions) -> ShoppingList {
+    let mut yarn: Vec<YarnRequirement> = Vec::new();
+    let mut hook_sizes_mm: Vec<f64> = Vec::new();
+    let mut stuffing_grams = 0.0;
+    let mut safety_eyes = 0;
+    let mut stitch_markers = 0;
+
+    for piece in pieces {
+        let meters = piece.pattern.metadata.yarn_length_meters * piece.quantity as f64;
+        match yarn.iter_mut().find(|y| y.color == piece.color) {
+            Some(existing) => existing.meters += meters,
+            None => yarn.push(YarnRequirement {
+                color: piece.color.clone(),
+                meters,
+                skeins: 0,
+            }),
+        }
+
+        let hook = piece.yarn.recommended_hook_size_mm;
+        if !hook_sizes_mm.iter().any(|&h| (h - hook).abs() < 1e-9) {
+            hook_sizes_mm.push(hook);
+        }
+
+        if piece.stuffed {
+            stuffing_grams += stuffed_volume_cm3(&piece.pattern, &piece.yarn)
+                * options.stuffing_grams_per_cm3
+                * piece.quantity as f64;
+        }
+
+        safety_eyes += piece.safety_eyes * piece.quantity;
+
+        // One marker per piece worked in the round to mark the round start
+        if piece.pattern.construction == Construction::Round {
+            stitch_markers += 1;
+        }
+    }
+
+    for requirement in yarn.iter_mut() {
+        requirement.meters *= 1.0 + options.yarn_margin;
+        requirement.skeins = (requirement.meters / options.skein_length_m).ceil() as usize;
+    }
+    hook_sizes_mm.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    ShoppingList {
+        yarn,
+        hook_sizes_mm,
+        stuffing_grams,
+        safety_eyes,
+        stitch_markers: stitch_markers.max(1),
+    }
+}
+
+/// Check which project pieces can be made from the yarn on hand
+///
+/// Pieces are matched in order, each taking all its yarn from a single
+/// stash entry so the copies match. A piece first looks for its own color
+/// and weight, then another color of the same weight, then its own color in
+/// the nearest other weight. Yarn used by one piece is not available to the
+/// pieces after it.
+pub fn match_stash(
+    pieces: &[ProjectPiece],
+    stash: &[StashYarn],
+    options: &ShoppingListOptions,
+) -> StashReport {
+    let mut remaining: Vec<f64> = stash.iter().map(|y| y.meters).collect();
+    let mut matches = Vec::with_capacity(pieces.len());
+
+    for piece in pieces {
+        let weight = closest_weight(&piece.yarn).weight;
+        let meters = piece.pattern.metadata.yarn_length_meters
+            * piece.quantity as f64
+            * (1.0 + options.yarn_margin);
+        let same_color = |y: &StashYarn| y.color.eq_ignore_ascii_case(&piece.color);
+
+        let exact = (0..stash.len()).find(|&i| {
+            same_color(&stash[i]) && stash[i].weight == weight && remaining[i] >= meters
+        });
+        let recolor =
+            || (0..stash.len()).find(|&i| stash[i].weight == weight && remaining[i] >= meters);
+        let reweigh = || {
+            let mut candidates: Vec<(usize, f64)> = (0..stash.len())
+                .filter(|&i| same_color(&stash[i]) && stash[i].weight != weight)
+                .map(|i| (i, meters_in_weight(piece, meters, stash[i].weight)))
+                .filter(|&(i, needed)| remaining[i] >= needed)
+                .collect();
+            let category = weight_info(weight).category;
+            candidates
+                .sort_by_key(|&(i, _)| weight_info(stash[i].weight).category.abs_diff(category));
+            candidates.first().copied()
+        };
+
+        let mut found = StashMatch {
+            piece: piece.name.clone(),
+            color: piece.color.clone(),
+            weight,
+            meters,
+            feasible: true,
+            use_color: None,
+            use_weight: None,
+            shortfall_meters: 0.0,
+        };
+        if let Some(i) = exact {
+            remaining[i] -= meters;
+        } else if let Some(i) = recolor() {
+            remaining[i] -= meters;
+            found.use_color = Some(stash[i].color.clone());
+        } else if let Some((i, needed)) = reweigh() {
+            remaining[i] -= needed;
+            found.use_weight = Some(stash[i].weight);
+        } else {
+            let most = (0..stash.len())
+                .filter(|&i| same_color(&stash[i]) && stash[i].weight == weight)
+                .map(|i| remaining[i])
+                .fold(0.0, f64::max);
+            found.feasible = false;
+            found.shortfall_meters = meters - most;
+        }
+        matches.push(found);
+    }
+
+    StashReport {
+        feasible: matches.iter().all(|m| m.feasible),
+        matches,
+    }
+}
+
+/// Yarn a piece needs once regenerated for another weight
+///
+/// The stitch count changes with the square of the gauge while each stitch
+/// uses yarn in proportion to its size, so the length needed scales with
+/// the gauge itself.
+fn meters_in_weight(piece: &ProjectPiece, meters: f64, weight: YarnWeight) -> f64 {
+    let config = AmigurumiConfig {
+        yarn: piece.yarn.clone(),
+        ..Default::default()
+    };
+    let substitution = substitute_yarn(&config, weight);
+    meters * (substitution.stitch_scale * substitution.row_scale).sqrt()
+}
+
+/// Approximate interior volume of a piece worked in the round
+///
+/// Each row is treated as a short cylinder with the radius implied by its
+/// stitch count and the height of one row.
+fn stuffed_volume_cm3(pattern: &CrochetPattern, yarn: &YarnSpec) -> f64 {
+    if pattern.construction != Construction::Round {
+        return 0.0;
+    }
+
+    let row_height = 1.0 / yarn.gauge_rows_per_cm;
+    pattern
+        .rows
+        .iter()
+        .map(|row| {
+            let radius = row.total_stitches as f64 / yarn.gauge_stitches_per_cm / (2.0 * PI);
+            PI * radius * radius * row_height
+        })
+        .sum()
+}
+
+/// Render a shopping list as plain text
+pub fn shopping_list_to_text(list: &ShoppingList) -> String {
+    let mut text = String::from("SHOPPING LIST\n");
+    text.push_str(&"=".repeat(50));
+    text.push_str("\n\nYARN:\n");
+    for y in &list.yarn {
+        text.push_str(&format!(
+            "- {}: {:.1}m ({} {})\n",
+            y.color,
+            y.meters,
+            y.skeins,
+            if y.skeins == 1 { "skein" } else { "skeins" }
+        ));
+    }
+
+    text.push_str("\nNOTIONS:\n");
+    for line in notion_lines(list) {
+        text.push_str(&format!("- {}\n", line));
+    }
+
+    text
+}
+
+/// Render a shopping list as Markdown
+pub fn shopping_list_to_markdown(list: &ShoppingList) -> String {
+    let mut md = String::from("# Shopping List\n\n## Yarn\n\n");
+    md.push_str("| Color | Meters | Skeins |\n|---|---:|---:|\n");
+    for y in &list.yarn {
+        md.push_str(&format!("| {} | {:.1} | {} |\n", y.color, y.meters, y.skeins));
+    }
+
+    md.push_str("\n## Notions\n\n");
+    for line in notion_lines(list) {
+        md.push_str(&format!("- {}\n", line));
+    }
+
+    md
+}
+
+/// Non-yarn items shared by the text and Markdown renderers
+fn notion_lines(list: &ShoppingList) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    let hooks: Vec<String> = list.hook_sizes_mm.iter().map(|h| format!("{}mm", h)).collect();
+    if !hooks.is_empty() {
+        lines.push(format!("Hook: {}", hooks.join(", ")));
+    }
+    if list.stuffing_grams > 0.0 {
+        lines.push(format!("Polyester fiberfill: ~{:.0}g", list.stuffing_grams.ceil()));
+    }
+    if list.safety_eyes > 0 {
+        lines.push(format!("Safety eyes: {}", list.safety_eyes));
+    }
+    lines.push(format!("Stitch markers: {}", list.stitch_markers));
+    lines.push("Yarn needle".to_string());
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_piece(name: &str, color: &str, quantity: usize) -> ProjectPiece {
+        let rows: Vec<Row> = (1..=30)
+            .map(|n| Row {
+                row_number: n,
+                total_stitches: 36,
+                pattern: vec![],
+                markers: Vec::new(),
+            })
+            .collect();
+
+        ProjectPiece {
+            name: name.to_string(),
+            color: color.to_string(),
+            pattern: CrochetPattern {
+                rows,
+                metadata: PatternMetadata {
+                    total_rows: 30,
+                    total_stitches: 1080,
+                    estimated_time_minutes: 36.0,
+                    yarn_length_meters: 50.0,
+                    ..Default::default()
+                },
+                row_groups: vec![],
+                construction: Construction::Round,
+                foundation: Foundation::Chain,
+                notes: vec![],
+                anchors: vec![],
+                sections: vec![],
+                color_changes: vec![],
+                connections: vec![],
+            },
+            yarn: YarnSpec::default(),
+            quantity,
+            stuffed: true,
+            safety_eyes: 0,
+        }
+    }
+
+    #[test]
+    fn test_yarn_consolidated_per_color() {
+        let pieces = vec![
+            create_test_piece("Body", "Pink", 1),
+            create_test_piece("Arm", "Pink", 2),
+            create_test_piece("Ear", "White", 2),
+        ];
+
+        let list = build_shopping_list(&pieces, &ShoppingListOptions::default());
+
+        assert_eq!(list.yarn.len(), 2);
+        assert_eq!(list.yarn[0].color, "Pink");
+        // 3 copies × 50m + 20% margin
+        assert!((list.yarn[0].meters - 180.0).abs() < 1e-9);
+        assert_eq!(list.yarn[0].skeins, 2);
+        assert_eq!(list.hook_sizes_mm, vec![3.5]);
+        assert!(list.stuffing_grams > 0.0);
+    }
+
+    #[test]
+    fn test_stash_matching_and_substitutions() {
+        let weight = closest_weight(&YarnSpec::default()).weight;
+        let stash_yarn = |color: &str, weight: YarnWeight, meters: f64| StashYarn {
+            color: color.to_string(),
+            weight,
+            meters,
+        };
+        let pieces = vec![
+            create_test_piece("Body", "Pink", 1),
+            create_test_piece("Arm", "Pink", 2),
+            create_test_piece("Ear", "White", 2),
+        ];
+        let stash = vec![
+            stash_yarn("pink", weight, 100.0),
+            stash_yarn("Blue", weight, 150.0),
+        ];
+
+        let report = match_stash(&pieces, &stash, &ShoppingListOptions::default());
+
+        // Body takes 60m of the pink, leaving too little for both arms (120m)
+        assert!(report.matches[0].feasible);
+        assert_eq!(report.matches[0].use_color, None);
+        assert_eq!(report.matches[1].use_color.as_deref(), Some("Blue"));
+        assert!(!report.matches[2].feasible);
+        assert!((report.matches[2].shortfall_meters - 120.0).abs() < 1e-9);
+        assert!(!report.feasible);
+
+        // Thicker white yarn covers the ears once regenerated for it
+        let heavier = yarn_weights_after(weight);
+        let stash = vec![stash_yarn("White", heavier, 200.0)];
+        let report = match_stash(&pieces[2..], &stash, &ShoppingListOptions::default());
+        assert!(report.feasible);
+        assert_eq!(report.matches[0].use_weight, Some(heavier));
+    }
+
+    fn yarn_weights_after(weight: YarnWeight) -> YarnWeight {
+        let weights = crate::yarn::yarn_weights();
+        let idx = weights.iter().position(|w| w.weight == weight).unwrap();
+        weights[idx + 1].weight
+    }
+
+    #[test]
+    fn test_renderers_list_every_color() {
+        let mut head = create_test_piece("Head", "Cream", 1);
+        head.safety_eyes = 2;
+        let list = build_shopping_list(&[head], &ShoppingListOptions::default());
+
+        let text = shopping_list_to_text(&list);
+        assert!(text.contains("- Cream:"));
+        assert!(text.contains("Safety eyes: 2"));
+
+        let md = shopping_list_to_markdown(&list);
+        assert!(md.contains("| Cream |"));
+    }
+}
@@ -1,19 +1,158 @@
-use crochet_types::{Row, StitchInstruction, StitchType};
-use rand::Rng;
+use crochet_types::{OptimizationWeights, PlacementMethod, Row, StitchInstruction, StitchType};
+#[cfg(feature = "annealing")]
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "annealing")]
 use rand_chacha::ChaCha8Rng;
-use rand::SeedableRng;
 use std::f64::consts::PI;
 
-/// Optimize stitch placement using simulated annealing
+use crate::plugins::PlacementStrategy;
+
+/// Annealing iterations per row used by `optimize_stitch_placement`
+pub const DEFAULT_ITERATIONS: usize = 500;
+
+/// Largest per-row annealing budget a configuration may ask for
+pub const MAX_ITERATIONS: usize = 20_000;
+
+/// Seed used when the configuration does not give one
+pub const DEFAULT_SEED: u64 = 42;
+
+/// Temperature kept per iteration when the configuration gives no cooling
+/// rate; every budget cools to the same final temperature
+pub fn default_cooling_rate(iterations: usize) -> f64 {
+    0.95f64.powf(DEFAULT_ITERATIONS as f64 / iterations.max(1) as f64)
+}
+
+/// Optimize stitch placement
 /// 
 /// In crochet, stitches must be worked sequentially around the circle.
 /// This optimization adjusts WHERE special stitches (INC/DEC) are placed
-/// in the sequence while maintaining the circular order.
+/// in the sequence while maintaining the circular order. Rows of only
+/// increases or only decreases are solved exactly; rows mixing them use
+/// simulated annealing.
 pub fn optimize_stitch_placement(rows: &[Row]) -> Vec<Row> {
-    let mut optimized = Vec::with_capacity(rows.len());
-    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    optimize_stitch_placement_with_iterations(rows, DEFAULT_ITERATIONS)
+}
+
+/// Tuning for `optimize_stitch_placement_with`
+#[derive(Debug, Clone, Copy)]
+pub struct PlacementOptions {
+    /// Annealing iterations per row
+    pub iterations: usize,
+    /// Alternate the side decreases drift to, round by round
+    pub balance_decreases: bool,
+    /// Random seed; the same rows, options and seed always give the same layout
+    pub seed: u64,
+    /// Temperature kept per iteration; `None` cools to the same final
+    /// temperature whatever the iteration budget
+    pub cooling_rate: Option<f64>,
+    pub weights: OptimizationWeights,
+    pub method: PlacementMethod,
+}
+
+impl Default for PlacementOptions {
+    fn default() -> Self {
+        Self {
+            iterations: DEFAULT_ITERATIONS,
+            balance_decreases: false,
+            seed: DEFAULT_SEED,
+            cooling_rate: None,
+            weights: OptimizationWeights::default(),
+            method: PlacementMethod::default(),
+        }
+    }
+}
+
+/// Optimize stitch placement with a given annealing budget per row
+pub fn optimize_stitch_placement_with_iterations(rows: &[Row], iterations: usize) -> Vec<Row> {
+    optimize_stitch_placement_with(
+        rows,
+        &PlacementOptions {
+            iterations,
+            ..Default::default()
+        },
+    )
+}
+
+/// Optimize stitch placement with explicit options
+///
+/// With `balance_decreases`, each decreasing row is pushed to the opposite
+/// side of the previous decreasing row's decreases. Staggered decreases
+/// that always step the same way stack into a spiral that twists the
+/// fabric; alternating sides keeps the closing shape straight.
+pub fn optimize_stitch_placement_with(rows: &[Row], options: &PlacementOptions) -> Vec<Row> {
+    let mut placer = StitchPlacer::new(options);
+    for row in rows {
+        placer.place(row);
+    }
+    placer.into_rows()
+}
+
+/// Row-by-row stitch placement, for callers that need to pause between rows
+///
+/// Each row is staggered against the row placed before it, so rows must be
+/// given in order. Placing every row and taking `into_rows` gives the same
+/// result as `optimize_stitch_placement_with`.
+pub struct StitchPlacer {
+    strategy: Box<dyn PlacementStrategy>,
+    placed: Vec<Row>,
+}
+
+impl StitchPlacer {
+    pub fn new(options: &PlacementOptions) -> Self {
+        Self::with_strategy(Box::new(DefaultPlacement::new(options)))
+    }
 
-    for (row_idx, row) in rows.iter().enumerate() {
+    /// Place rows with a custom strategy instead of the built-in one
+    pub fn with_strategy(strategy: Box<dyn PlacementStrategy>) -> Self {
+        Self {
+            strategy,
+            placed: Vec::new(),
+        }
+    }
+
+    /// Number of rows placed so far
+    pub fn rows_placed(&self) -> usize {
+        self.placed.len()
+    }
+
+    /// Rows placed so far, in order
+    pub fn placed_rows(&self) -> &[Row] {
+        &self.placed
+    }
+
+    pub fn into_rows(self) -> Vec<Row> {
+        self.placed
+    }
+
+    /// Optimize one row against the rows already placed
+    pub fn place(&mut self, row: &Row) {
+        let placed = self.strategy.place(row, &self.placed);
+        self.placed.push(placed);
+    }
+}
+
+/// The built-in placement: spacing, staggering and (optionally) balancing
+/// the decreases of each row as `PlacementOptions` asks
+pub struct DefaultPlacement {
+    options: PlacementOptions,
+    annealer: Annealer,
+    // Side (+1 / -1) the last decreasing row drifted to
+    last_drift: f64,
+}
+
+impl DefaultPlacement {
+    pub fn new(options: &PlacementOptions) -> Self {
+        Self {
+            options: *options,
+            annealer: Annealer::new(options.seed),
+            last_drift: 0.0,
+        }
+    }
+}
+
+impl PlacementStrategy for DefaultPlacement {
+    fn place(&mut self, row: &Row, placed: &[Row]) -> Row {
+        let options = self.options;
         // Count special stitches
         let special_count = row
             .pattern
@@ -23,8 +162,7 @@ pub fn optimize_stitch_placement(rows: &[Row]) -> Vec<Row> {
 
         if special_count == 0 {
             // No optimization needed
-            optimized.push(row.clone());
-            continue;
+            return row.clone();
         }
 
         // Extract indices of special stitches in the sequence
@@ -36,36 +174,70 @@ pub fn optimize_stitch_placement(rows: &[Row]) -> Vec<Row> {
             .map(|(i, _)| i)
             .collect();
 
-        // Get previous row's special stitch positions for staggering
-        let prev_special_indices: Vec<usize> = if row_idx > 0 {
-            let prev_row = &optimized[row_idx - 1];
+        // Get previous row's special stitch positions for staggering,
+        // mapped by angle onto this row's instruction indices
+        let prev_special_indices: Vec<usize> = if let Some(prev_row) = placed.last() {
+            let prev_len = prev_row.pattern.len();
+            let len = row.pattern.len();
             prev_row
                 .pattern
                 .iter()
                 .enumerate()
                 .filter(|(_, s)| s.stitch_type != StitchType::SC)
-                .map(|(i, _)| i)
+                .map(|(i, _)| (i * len + prev_len / 2) / prev_len % len)
                 .collect()
         } else {
             vec![]
         };
 
-        // Run simulated annealing to find optimal placement
-        let optimized_indices = optimize_special_stitch_indices(
-            &special_indices,
-            &prev_special_indices,
-            row.pattern.len(),
-            &mut rng,
-        );
+        let decreasing = row
+            .pattern
+            .iter()
+            .any(|s| matches!(s.stitch_type, StitchType::DEC | StitchType::INVDEC));
+        let avoid_drift = if options.balance_decreases && decreasing {
+            self.last_drift
+        } else {
+            0.0
+        };
+
+        // Increases (or decreases) alone are interchangeable and can be
+        // placed exactly; rows mixing them are annealed
+        let first_type = row.pattern[special_indices[0]].stitch_type;
+        let uniform = special_indices
+            .iter()
+            .all(|&i| row.pattern[i].stitch_type == first_type);
+        let optimized_indices = if uniform && options.method == PlacementMethod::Even {
+            even_stitch_indices(
+                special_indices.len(),
+                &prev_special_indices,
+                row.pattern.len(),
+                &options.weights,
+                avoid_drift,
+            )
+        } else {
+            optimize_special_stitch_indices(
+                &special_indices,
+                &prev_special_indices,
+                row.pattern.len(),
+                &options,
+                avoid_drift,
+                &mut self.annealer,
+            )
+        };
+
+        if decreasing {
+            let drift = mean_drift(&optimized_indices, &prev_special_indices, row.pattern.len());
+            if drift != 0.0 {
+                self.last_drift = drift.signum();
+            }
+        }
 
         // Create new pattern with optimized positions
         let mut new_pattern = vec![StitchType::SC; row.pattern.len()];
         
         // Place special stitches at optimized positions
-        let mut special_idx = 0;
-        for &pos in &optimized_indices {
+        for (special_idx, &pos) in optimized_indices.iter().enumerate() {
             new_pattern[pos] = row.pattern[special_indices[special_idx]].stitch_type;
-            special_idx += 1;
         }
 
         // Convert to StitchInstruction vec
@@ -82,14 +254,103 @@ pub fn optimize_stitch_placement(rows: &[Row]) -> Vec<Row> {
             })
             .collect();
 
-        optimized.push(Row {
+        Row {
             row_number: row.row_number,
             total_stitches: row.total_stitches,
             pattern: pattern_vec,
-        });
+            markers: Vec::new(),
+        }
+    }
+}
+
+/// Evenly spaced positions for `count` interchangeable special stitches,
+/// turned to sit best against the previous row's
+///
+/// The most even split is what the spacing term favours, so only the turn
+/// is searched. Each turn up to one gap is scored with the stagger and
+/// drift terms the annealer uses, looking up every position's offset to
+/// the nearest previous-row stitch in a table built once per row. Runs in
+/// O(n) with no randomness; ties go to the smallest turn.
+fn even_stitch_indices(
+    count: usize,
+    prev_special_indices: &[usize],
+    pattern_length: usize,
+    weights: &OptimizationWeights,
+    avoid_drift: f64,
+) -> Vec<usize> {
+    let n = pattern_length;
+    let base: Vec<usize> = (0..count).map(|j| j * n / count).collect();
+    if prev_special_indices.is_empty() {
+        return base;
+    }
+
+    let nearest = nearest_offsets(prev_special_indices, n);
+    let gap = n.div_ceil(count);
+    let cost = |turn: usize| {
+        let mut stagger = 0.0;
+        let mut drift = 0;
+        for &pos in &base {
+            let offset = nearest[(pos + turn) % n];
+            stagger += stagger_penalty(offset.unsigned_abs() as usize, n, count, weights.stagger);
+            drift += offset;
+        }
+        let mean_drift = drift as f64 / count as f64;
+        stagger + weights.drift * (mean_drift * avoid_drift).max(0.0)
+    };
+    let mut best_turn = 0;
+    let mut best_cost = f64::INFINITY;
+    for turn in 0..gap {
+        let c = cost(turn);
+        if c < best_cost {
+            best_turn = turn;
+            best_cost = c;
+        }
+    }
+
+    let mut indices: Vec<usize> = base.iter().map(|&pos| (pos + best_turn) % n).collect();
+    indices.sort_unstable();
+    indices
+}
+
+/// Signed offset (in stitches) from every index to the nearest of
+/// `prev_indices`; positive when the index comes after it
+fn nearest_offsets(prev_indices: &[usize], pattern_length: usize) -> Vec<i64> {
+    let n = pattern_length;
+    let mut is_prev = vec![false; n];
+    for &p in prev_indices {
+        is_prev[p % n] = true;
+    }
+
+    // Two laps each way, so the wrap-around is covered
+    let mut after = vec![n; n];
+    let mut since = n;
+    for i in 0..2 * n {
+        since = if is_prev[i % n] { 0 } else { since + 1 };
+        after[i % n] = after[i % n].min(since);
+    }
+    let mut before = vec![n; n];
+    let mut until = n;
+    for i in (0..2 * n).rev() {
+        until = if is_prev[i % n] { 0 } else { until + 1 };
+        before[i % n] = before[i % n].min(until);
     }
 
-    optimized
+    after
+        .iter()
+        .zip(&before)
+        .map(|(&a, &b)| if a <= b { a as i64 } else { -(b as i64) })
+        .collect()
+}
+
+/// Penalty for a special stitch `min_dist` stitches from the nearest of
+/// the previous row's
+fn stagger_penalty(min_dist: usize, pattern_length: usize, count: usize, lambda: f64) -> f64 {
+    if min_dist < pattern_length / (count * 2) {
+        // Strong penalty if within "too close" range
+        lambda * 10.0 * (-(min_dist as f64)).exp()
+    } else {
+        lambda * (-(min_dist as f64 / 2.0)).exp()
+    }
 }
 
 /// Optimize the placement of special stitches within a sequential pattern
@@ -97,7 +358,9 @@ fn optimize_special_stitch_indices(
     special_indices: &[usize],
     prev_special_indices: &[usize],
     pattern_length: usize,
-    rng: &mut ChaCha8Rng,
+    options: &PlacementOptions,
+    avoid_drift: f64,
+    annealer: &mut Annealer,
 ) -> Vec<usize> {
     if special_indices.is_empty() {
         return vec![];
@@ -117,59 +380,127 @@ fn optimize_special_stitch_indices(
         current = current.iter().map(|&pos| (pos + offset) % pattern_length).collect();
     }
     
-    let mut best = current.clone();
-    let mut best_energy = index_energy(&best, prev_special_indices, pattern_length);
+    let weights = &options.weights;
+    let energy = |indices: &[usize]| {
+        index_energy(indices, prev_special_indices, pattern_length, weights)
+            + weights.drift
+                * drift_energy(indices, prev_special_indices, pattern_length, avoid_drift)
+    };
+
+    annealer.refine(
+        current,
+        pattern_length,
+        options.iterations,
+        options.cooling_rate,
+        energy,
+    )
+}
 
-    let mut temperature = 1.0;
-    let cooling_rate = 0.95;
-    let iterations = 500;
+/// Simulated annealing over special stitch positions
+#[cfg(feature = "annealing")]
+struct Annealer {
+    rng: ChaCha8Rng,
+}
 
-    for _ in 0..iterations {
-        // Perturb: swap two positions or shift one
-        let mut candidate = current.clone();
-        
-        if rng.gen_bool(0.5) && n > 1 {
-            // Swap two positions
-            let i = rng.gen_range(0..n);
-            let j = rng.gen_range(0..n);
-            candidate.swap(i, j);
-        } else {
-            // Shift one position
-            let i = rng.gen_range(0..n);
-            let delta = rng.gen_range(-3..=3);
-            candidate[i] = ((candidate[i] as i32 + delta).rem_euclid(pattern_length as i32)) as usize;
+#[cfg(feature = "annealing")]
+impl Annealer {
+    fn new(seed: u64) -> Self {
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(seed),
         }
+    }
 
-        // Ensure no duplicates
-        candidate.sort_unstable();
-        candidate.dedup();
-        if candidate.len() != n {
-            continue; // Skip if we lost positions due to collision
-        }
+    fn refine(
+        &mut self,
+        start: Vec<usize>,
+        pattern_length: usize,
+        iterations: usize,
+        cooling_rate: Option<f64>,
+        energy: impl Fn(&[usize]) -> f64,
+    ) -> Vec<usize> {
+        let rng = &mut self.rng;
+        let n = start.len();
+        let mut current = start;
+        let mut best = current.clone();
+        let mut best_energy = energy(&best);
+
+        let mut temperature = 1.0;
+        let cooling_rate = cooling_rate.unwrap_or_else(|| default_cooling_rate(iterations));
+
+        for _ in 0..iterations {
+            // Perturb: swap two positions or shift one
+            let mut candidate = current.clone();
+
+            if rng.gen_bool(0.5) && n > 1 {
+                // Swap two positions
+                let i = rng.gen_range(0..n);
+                let j = rng.gen_range(0..n);
+                candidate.swap(i, j);
+            } else {
+                // Shift one position
+                let i = rng.gen_range(0..n);
+                let delta = rng.gen_range(-3..=3);
+                candidate[i] = ((candidate[i] as i32 + delta).rem_euclid(pattern_length as i32)) as usize;
+            }
+
+            // Ensure no duplicates
+            candidate.sort_unstable();
+            candidate.dedup();
+            if candidate.len() != n {
+                continue; // Skip if we lost positions due to collision
+            }
 
-        let current_energy = index_energy(&current, prev_special_indices, pattern_length);
-        let candidate_energy = index_energy(&candidate, prev_special_indices, pattern_length);
+            let current_energy = energy(&current);
+            let candidate_energy = energy(&candidate);
 
-        // Accept or reject
-        let delta_e = candidate_energy - current_energy;
-        if delta_e < 0.0 || rng.gen::<f64>() < (-delta_e / temperature).exp() {
-            current = candidate;
+            // Accept or reject
+            let delta_e = candidate_energy - current_energy;
+            if delta_e < 0.0 || rng.gen::<f64>() < (-delta_e / temperature).exp() {
+                current = candidate;
 
-            if candidate_energy < best_energy {
-                best = current.clone();
-                best_energy = candidate_energy;
+                if candidate_energy < best_energy {
+                    best = current.clone();
+                    best_energy = candidate_energy;
+                }
             }
+
+            temperature *= cooling_rate;
         }
 
-        temperature *= cooling_rate;
+        best
+    }
+}
+
+/// Without the `annealing` feature the staggered starting positions are kept
+#[cfg(not(feature = "annealing"))]
+struct Annealer;
+
+#[cfg(not(feature = "annealing"))]
+impl Annealer {
+    fn new(_seed: u64) -> Self {
+        Self
     }
 
-    best
+    fn refine(
+        &mut self,
+        start: Vec<usize>,
+        _pattern_length: usize,
+        _iterations: usize,
+        _cooling_rate: Option<f64>,
+        _energy: impl Fn(&[usize]) -> f64,
+    ) -> Vec<usize> {
+        start
+    }
 }
 
 /// Energy function for index-based optimization
 /// Lower energy = better distribution
-fn index_energy(indices: &[usize], prev_indices: &[usize], pattern_length: usize) -> f64 {
+fn index_energy(
+    indices: &[usize],
+    prev_indices: &[usize],
+    pattern_length: usize,
+    weights: &OptimizationWeights,
+) -> f64 {
     let n = indices.len();
     if n <= 1 {
         return 0.0;
@@ -182,13 +513,13 @@ fn index_energy(indices: &[usize], prev_indices: &[usize], pattern_length: usize
         for j in (i + 1)..n {
             let dist = circular_distance(indices[i], indices[j], pattern_length);
             // Penalize clustering - stronger penalty for closer spacing
-            e -= (dist as f64 + 1.0).ln();
+            e -= weights.spacing * (dist as f64 + 1.0).ln();
         }
     }
 
     // Staggering term: offset from previous row (stronger weight)
     if !prev_indices.is_empty() {
-        let lambda = 1.0; // Increased from 0.5 for stronger staggering
+        let lambda = weights.stagger;
         for &idx in indices {
             let mut min_dist = pattern_length;
             for &prev_idx in prev_indices {
@@ -196,21 +527,51 @@ fn index_energy(indices: &[usize], prev_indices: &[usize], pattern_length: usize
                 min_dist = min_dist.min(dist);
             }
             // Penalty if too close to previous row's stitches
-            if min_dist < pattern_length / (indices.len() * 2) {
-                // Strong penalty if within "too close" range
-                e += lambda * 10.0 * (-(min_dist as f64)).exp();
-            } else {
-                e += lambda * (-(min_dist as f64 / 2.0)).exp();
-            }
+            e += stagger_penalty(min_dist, pattern_length, indices.len(), lambda);
         }
     }
 
     e
 }
 
+/// Average signed offset (in stitches) from each index to the nearest
+/// previous-row index; positive means the row stepped forward
+fn mean_drift(indices: &[usize], prev_indices: &[usize], pattern_length: usize) -> f64 {
+    if indices.is_empty() || prev_indices.is_empty() {
+        return 0.0;
+    }
+
+    let half = (pattern_length / 2) as i64;
+    let total: i64 = indices
+        .iter()
+        .map(|&idx| {
+            prev_indices
+                .iter()
+                .map(|&prev| (idx as i64 - prev as i64 + half).rem_euclid(pattern_length as i64) - half)
+                .min_by_key(|d| d.abs())
+                .unwrap_or(0)
+        })
+        .sum();
+    total as f64 / indices.len() as f64
+}
+
+/// Penalty for drifting to the same side as the last decreasing row
+fn drift_energy(
+    indices: &[usize],
+    prev_indices: &[usize],
+    pattern_length: usize,
+    avoid_drift: f64,
+) -> f64 {
+    if avoid_drift == 0.0 {
+        return 0.0;
+    }
+    let drift = mean_drift(indices, prev_indices, pattern_length);
+    (drift * avoid_drift).max(0.0)
+}
+
 /// Calculate circular distance between two indices
 fn circular_distance(a: usize, b: usize, length: usize) -> usize {
-    let diff = if a > b { a - b } else { b - a };
+    let diff = a.abs_diff(b);
     diff.min(length - diff)
 }
 
@@ -219,22 +580,10 @@ mod tests {
     use super::*;
 
     fn create_test_row(row_number: usize, total_stitches: usize, inc_count: usize) -> Row {
-        let mut pattern = Vec::new();
+        let mut pattern: Vec<StitchInstruction> = Vec::new();
 
-        if inc_count == 0 {
-            // All SC
-            for i in 0..total_stitches {
-                let angle = 2.0 * PI * i as f64 / total_stitches as f64;
-                pattern.push(StitchInstruction {
-                    stitch_type: StitchType::SC,
-                    angular_position: angle,
-                    stitch_index: i,
-                });
-            }
-        } else {
+        if let Some(inc_spacing) = total_stitches.checked_div(inc_count) {
             // Simple uniform distribution
-            let inc_spacing = total_stitches / inc_count;
-
             for i in 0..total_stitches {
                 let stitch_type = if i % inc_spacing == 0 && pattern.iter().filter(|s| s.stitch_type == StitchType::INC).count() < inc_count {
                     StitchType::INC
@@ -250,12 +599,23 @@ mod tests {
                     stitch_index: i,
                 });
             }
+        } else {
+            // All SC
+            for i in 0..total_stitches {
+                let angle = 2.0 * PI * i as f64 / total_stitches as f64;
+                pattern.push(StitchInstruction {
+                    stitch_type: StitchType::SC,
+                    angular_position: angle,
+                    stitch_index: i,
+                });
+            }
         }
 
         Row {
             row_number,
             total_stitches,
             pattern,
+            markers: Vec::new(),
         }
     }
 
@@ -284,15 +644,138 @@ mod tests {
         assert_eq!(inc_count, 6);
     }
 
+    #[test]
+    fn test_optimize_after_longer_row() {
+        // Previous row has more instructions than the current one
+        let rows = vec![create_test_row(1, 30, 10), create_test_row(2, 12, 3)];
+        let optimized = optimize_stitch_placement(&rows);
+
+        assert_eq!(optimized.len(), 2);
+        assert_eq!(optimized[1].pattern.len(), 12);
+    }
+
+    #[test]
+    fn test_iteration_budget_preserves_counts() {
+        let rows = vec![create_test_row(1, 18, 6), create_test_row(2, 24, 6)];
+
+        for iterations in [0, 50, 2000] {
+            let optimized = optimize_stitch_placement_with_iterations(&rows, iterations);
+            let inc_count = optimized[1]
+                .pattern
+                .iter()
+                .filter(|s| s.stitch_type == StitchType::INC)
+                .count();
+            assert_eq!(inc_count, 6);
+        }
+    }
+
+    fn create_decrease_row(row_number: usize, prev_stitches: usize, dec_count: usize) -> Row {
+        let len = prev_stitches - dec_count;
+        let spacing = len / dec_count;
+        let pattern = (0..len)
+            .map(|i| StitchInstruction {
+                stitch_type: if i % spacing == 0 && i / spacing < dec_count {
+                    StitchType::INVDEC
+                } else {
+                    StitchType::SC
+                },
+                angular_position: 2.0 * PI * i as f64 / len as f64,
+                stitch_index: i,
+            })
+            .collect();
+        Row {
+            row_number,
+            total_stitches: len,
+            pattern,
+            markers: Vec::new(),
+        }
+    }
+
+    fn special_indices(row: &Row) -> Vec<usize> {
+        row.pattern
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.stitch_type != StitchType::SC)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    #[test]
+    fn test_balanced_decreases_alternate_sides() {
+        let rows = vec![
+            create_test_row(1, 48, 0),
+            create_decrease_row(2, 48, 6),
+            create_decrease_row(3, 42, 6),
+            create_decrease_row(4, 36, 6),
+            create_decrease_row(5, 30, 6),
+        ];
+        let options = PlacementOptions {
+            balance_decreases: true,
+            ..Default::default()
+        };
+
+        let optimized = optimize_stitch_placement_with(&rows, &options);
+
+        let drifts: Vec<f64> = optimized
+            .windows(2)
+            .skip(1)
+            .map(|pair| {
+                let (prev, row) = (&pair[0], &pair[1]);
+                let len = row.pattern.len();
+                let prev_len = prev.pattern.len();
+                let mapped: Vec<usize> = special_indices(prev)
+                    .iter()
+                    .map(|&i| (i * len + prev_len / 2) / prev_len % len)
+                    .collect();
+                mean_drift(&special_indices(row), &mapped, len)
+            })
+            .collect();
+
+        for pair in drifts.windows(2) {
+            assert!(pair[0] * pair[1] <= 0.0, "drifts {:?} do not alternate", drifts);
+        }
+    }
+
     #[test]
     fn test_energy_function() {
         // Evenly spaced indices should have lower energy
         let even = vec![0, 5, 10, 15, 20, 25];
         let clustered = vec![0, 1, 2, 15, 16, 17];
 
-        let e_even = index_energy(&even, &[], 30);
-        let e_clustered = index_energy(&clustered, &[], 30);
+        let e_even = index_energy(&even, &[], 30, &OptimizationWeights::default());
+        let e_clustered = index_energy(&clustered, &[], 30, &OptimizationWeights::default());
 
         assert!(e_even < e_clustered);
     }
+
+    #[test]
+    fn test_nearest_offsets_wrap_around() {
+        assert_eq!(nearest_offsets(&[1, 6], 8), vec![-1, 0, 1, 2, -2, -1, 0, 1]);
+    }
+
+    #[test]
+    fn test_even_solver_staggers_and_matches_annealing() {
+        let weights = OptimizationWeights::default();
+        let prev = vec![0, 6, 12, 18, 24, 30];
+
+        let indices = even_stitch_indices(6, &prev, 36, &weights, 0.0);
+
+        assert_eq!(indices, vec![3, 9, 15, 21, 27, 33]);
+        assert_eq!(even_stitch_indices(6, &prev, 36, &weights, 0.0), indices);
+
+        let rows = vec![create_test_row(1, 42, 6), create_test_row(2, 48, 6)];
+        let energy = |method| {
+            let options = PlacementOptions {
+                method,
+                ..Default::default()
+            };
+            let placed = optimize_stitch_placement_with(&rows, &options);
+            let prev: Vec<usize> = special_indices(&placed[0])
+                .iter()
+                .map(|&i| (i * 48 + 21) / 42 % 48)
+                .collect();
+            index_energy(&special_indices(&placed[1]), &prev, 48, &weights)
+        };
+        assert!(energy(PlacementMethod::Even) <= energy(PlacementMethod::Annealing) + 1e-9);
+    }
 }
@@ -0,0 +1,222 @@
+use crochet_types::*;
+
+/// A palette color as stored in the preset table
+struct PresetColor {
+    name: &'static str,
+    hex: &'static str,
+    part: &'static str,
+    /// (yarn line, shade) pairs
+    yarns: &'static [(&'static str, &'static str)],
+}
+
+struct PresetPalette {
+    name: &'static str,
+    description: &'static str,
+    colors: &'static [PresetColor],
+}
+
+const CATONA: &str = "Scheepjes Catona";
+const PAINTBOX: &str = "Paintbox Yarns Cotton DK";
+
+/// Curated palettes, each color matched to widely sold cotton amigurumi
+/// yarns. Shades are the nearest in each line; check a shade card before
+/// buying when the exact tone matters.
+const PALETTES: [PresetPalette; 3] = [
+    PresetPalette {
+        name: "skin-tones",
+        description: "Skin tones from fair to deep",
+        colors: &[
+            PresetColor {
+                name: "Porcelain",
+                hex: "#f6e3d4",
+                part: "skin",
+                yarns: &[(CATONA, "Old Lace"), (PAINTBOX, "Vanilla Cream")],
+            },
+            PresetColor {
+                name: "Peach",
+                hex: "#f1c9a5",
+                part: "skin",
+                yarns: &[(CATONA, "Nude"), (PAINTBOX, "Blush Pink")],
+            },
+            PresetColor {
+                name: "Tan",
+                hex: "#d7a274",
+                part: "skin",
+                yarns: &[(CATONA, "Light Caramel"), (PAINTBOX, "Caramel")],
+            },
+            PresetColor {
+                name: "Caramel",
+                hex: "#a86b43",
+                part: "skin",
+                yarns: &[(CATONA, "Root Beer"), (PAINTBOX, "Coffee Bean")],
+            },
+            PresetColor {
+                name: "Espresso",
+                hex: "#5a3825",
+                part: "skin",
+                yarns: &[(CATONA, "Chocolate"), (PAINTBOX, "Coffee Bean")],
+            },
+        ],
+    },
+    PresetPalette {
+        name: "teddy-bear",
+        description: "Fur, muzzle and features for a classic bear",
+        colors: &[
+            PresetColor {
+                name: "Honey",
+                hex: "#c8893d",
+                part: "body",
+                yarns: &[(CATONA, "Topaz"), (PAINTBOX, "Caramel")],
+            },
+            PresetColor {
+                name: "Cream",
+                hex: "#f3e9d2",
+                part: "muzzle",
+                yarns: &[(CATONA, "Old Lace"), (PAINTBOX, "Champagne White")],
+            },
+            PresetColor {
+                name: "Cocoa",
+                hex: "#6b4226",
+                part: "ears",
+                yarns: &[(CATONA, "Chocolate"), (PAINTBOX, "Coffee Bean")],
+            },
+            PresetColor {
+                name: "Black",
+                hex: "#1b1b1b",
+                part: "nose",
+                yarns: &[(CATONA, "Jet Black"), (PAINTBOX, "Pure Black")],
+            },
+        ],
+    },
+    PresetPalette {
+        name: "features",
+        description: "Accents for faces: cheeks, mouths, eyes and hair",
+        colors: &[
+            PresetColor {
+                name: "Blush",
+                hex: "#f0a7b4",
+                part: "cheeks",
+                yarns: &[(CATONA, "Powder Pink"), (PAINTBOX, "Candyfloss Pink")],
+            },
+            PresetColor {
+                name: "Rose",
+                hex: "#c4455a",
+                part: "mouth",
+                yarns: &[(CATONA, "Candy Apple"), (PAINTBOX, "Raspberry Pink")],
+            },
+            PresetColor {
+                name: "Black",
+                hex: "#1b1b1b",
+                part: "eyes",
+                yarns: &[(CATONA, "Jet Black"), (PAINTBOX, "Pure Black")],
+            },
+            PresetColor {
+                name: "White",
+                hex: "#fbfbf8",
+                part: "eye highlights",
+                yarns: &[(CATONA, "Bridal White"), (PAINTBOX, "Paper White")],
+            },
+            PresetColor {
+                name: "Chestnut",
+                hex: "#7b3f1d",
+                part: "hair",
+                yarns: &[(CATONA, "Root Beer"), (PAINTBOX, "Rusty Orange")],
+            },
+        ],
+    },
+];
+
+impl PresetColor {
+    fn to_palette_color(&self) -> PaletteColor {
+        PaletteColor {
+            name: self.name.to_string(),
+            hex: self.hex.to_string(),
+            part: self.part.to_string(),
+            yarns: self
+                .yarns
+                .iter()
+                .map(|&(line, shade)| YarnShade {
+                    line: line.to_string(),
+                    shade: shade.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl PresetPalette {
+    fn to_palette(&self) -> ColorPalette {
+        ColorPalette {
+            name: self.name.to_string(),
+            description: self.description.to_string(),
+            colors: self
+                .colors
+                .iter()
+                .map(PresetColor::to_palette_color)
+                .collect(),
+        }
+    }
+}
+
+/// Every bundled palette
+pub fn palettes() -> Vec<ColorPalette> {
+    PALETTES.iter().map(PresetPalette::to_palette).collect()
+}
+
+/// Look up a bundled palette by name ("skin-tones")
+pub fn palette(name: &str) -> Option<ColorPalette> {
+    PALETTES
+        .iter()
+        .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+        .map(PresetPalette::to_palette)
+}
+
+/// Look up a color of a palette by name ("Peach") or hex value
+pub fn palette_color(palette_name: &str, color: &str) -> Result<PaletteColor> {
+    let palette = palette(palette_name).ok_or_else(|| {
+        PatternError::InvalidConfiguration(format!("Unknown palette: {}", palette_name))
+    })?;
+    let color = color.trim();
+    palette
+        .colors
+        .into_iter()
+        .find(|c| c.name.eq_ignore_ascii_case(color) || c.hex.eq_ignore_ascii_case(color))
+        .ok_or_else(|| {
+            PatternError::InvalidConfiguration(format!(
+                "Palette {} has no color {}",
+                palette_name, color
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_entries_are_complete() {
+        let all = palettes();
+        assert_eq!(all.len(), PALETTES.len());
+        for palette in &all {
+            assert!(!palette.colors.is_empty());
+            for color in &palette.colors {
+                assert_eq!(color.hex.len(), 7, "{}", color.name);
+                assert!(color.hex.starts_with('#'));
+                assert!(color.hex[1..].chars().all(|c| c.is_ascii_hexdigit()));
+                assert!(!color.yarns.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_palette_color_lookup() {
+        let peach = palette_color("Skin-Tones", "peach").unwrap();
+        assert_eq!(peach.hex, "#f1c9a5");
+        assert_eq!(peach.part, "skin");
+        assert_eq!(palette_color("skin-tones", "#F1C9A5").unwrap(), peach);
+
+        assert!(palette_color("skin-tones", "Lime").is_err());
+        assert!(palette_color("neon", "Peach").is_err());
+        assert!(palette("teddy-bear").is_some());
+    }
+}
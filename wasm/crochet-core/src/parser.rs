@@ -0,0 +1,637 @@
+use crochet_types::*;
+use std::f64::consts::PI;
+
+use crate::difficulty::rate_difficulty;
+use crate::generator::calculate_metadata;
+use crate::grouping::group_rows;
+use crate::lineage::row_connections;
+
+/// Words that describe where a stitch goes but don't change what is worked
+const FILLER_WORDS: [&str; 12] = [
+    "in", "into", "the", "magic", "ring", "mr", "each", "st", "sts", "next", "x", "times",
+];
+
+/// One parsed piece of a row instruction
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Stitch(StitchType, usize),
+    /// "sc around": single crochet into every remaining stitch
+    Around,
+    Group(Vec<Item>, usize),
+}
+
+/// A row line after its label has been read
+struct ParsedLine {
+    first_row: usize,
+    last_row: usize,
+    items: Vec<Item>,
+    stated_count: Option<usize>,
+    turn: bool,
+    /// Worked as foundation single crochet
+    foundation: bool,
+}
+
+/// Parse written crochet instructions into a pattern
+///
+/// Lines starting with a row label ("Rnd 3:", "Round 3:", "Row 3:", "R3:",
+/// "Rnds 5-12:") are read; everything else (titles, materials, notes) is
+/// skipped. Instructions may use run-lengths ("2 sc", "sc 2", "sc in next 2
+/// sts"), repeats ("(sc, inc) x 6", "[sc, inc] 6 times") and "sc around".
+/// A trailing count ("— 18 sts", "(18)", "= 18") is checked against the
+/// stitches the row produces, and each row must work into exactly the
+/// stitches of the row below. Stitch names are read in the given
+/// terminology, so UK "dc" is single crochet. Rows past `config.limits`
+/// (too many rows, or too many stitches in one) are rejected before they are
+/// expanded; otherwise gauge in `config` is only used for the metadata
+/// estimates.
+pub fn parse_written_pattern(
+    text: &str,
+    config: &AmigurumiConfig,
+    terminology: Terminology,
+) -> Result<CrochetPattern> {
+    let mut rows: Vec<Row> = Vec::new();
+    let mut flat = false;
+    let mut foundation = Foundation::Chain;
+
+    for (line_idx, line) in text.lines().enumerate() {
+        let parsed = match parse_line(line, terminology) {
+            Ok(Some(parsed)) => parsed,
+            Ok(None) => continue,
+            Err(msg) => return Err(parse_error(line_idx, &msg)),
+        };
+        flat |= parsed.turn;
+        if parsed.foundation && parsed.first_row == 1 {
+            foundation = Foundation::SingleCrochet;
+        }
+
+        if parsed.first_row != rows.len() + 1 {
+            return Err(parse_error(
+                line_idx,
+                &format!(
+                    "expected row {} but found row {}",
+                    rows.len() + 1,
+                    parsed.first_row
+                ),
+            ));
+        }
+
+        if parsed.last_row > config.limits.max_rows {
+            return Err(parse_error(
+                line_idx,
+                &format!(
+                    "row {} is past the limit of {} rows",
+                    parsed.last_row, config.limits.max_rows
+                ),
+            ));
+        }
+
+        for row_number in parsed.first_row..=parsed.last_row {
+            let prev_stitches = rows.last().map(|r| r.total_stitches);
+            let stitches = expand(
+                &parsed.items,
+                prev_stitches,
+                config.limits.max_stitches_per_row,
+            )
+            .map_err(|msg| parse_error(line_idx, &msg))?;
+            let row = build_row(row_number, &stitches, prev_stitches)
+                .map_err(|msg| parse_error(line_idx, &msg))?;
+
+            if let Some(count) = parsed.stated_count {
+                if count != row.total_stitches {
+                    return Err(parse_error(
+                        line_idx,
+                        &format!(
+                            "row {} produces {} stitches but says {}",
+                            row_number, row.total_stitches, count
+                        ),
+                    ));
+                }
+            }
+            rows.push(row);
+        }
+    }
+
+    if rows.is_empty() {
+        return Err(PatternError::ParseError(
+            "No row instructions found".to_string(),
+        ));
+    }
+
+    let construction = if flat {
+        Construction::Flat
+    } else {
+        Construction::Round
+    };
+    let metadata = calculate_metadata(&rows, config, construction);
+
+    let mut pattern = CrochetPattern {
+        row_groups: group_rows(&rows),
+        connections: row_connections(&rows, construction),
+        rows,
+        metadata,
+        construction,
+        foundation,
+        notes: vec![],
+        anchors: vec![],
+        sections: vec![],
+        color_changes: vec![],
+    };
+    pattern.metadata.difficulty = Some(rate_difficulty(&pattern, 1));
+    Ok(pattern)
+}
+
+fn parse_error(line_idx: usize, msg: &str) -> PatternError {
+    PatternError::ParseError(format!("line {}: {}", line_idx + 1, msg))
+}
+
+/// Read one line; `Ok(None)` means it is not a row instruction
+fn parse_line(
+    line: &str,
+    terminology: Terminology,
+) -> std::result::Result<Option<ParsedLine>, String> {
+    let lower = line.trim().to_lowercase();
+    let Some((label, body)) = lower.split_once(':') else {
+        return Ok(None);
+    };
+    let Some((first_row, last_row)) = parse_label(label) else {
+        return Ok(None);
+    };
+
+    // Only the first sentence is the instruction ("sc around. Stuff the head.")
+    let body = body.split(". ").next().unwrap_or("").trim().trim_end_matches('.');
+    let (body, stated_count) = split_stated_count(body);
+
+    let mut turn = false;
+    let mut foundation = false;
+    let mut items = Vec::new();
+    for part in split_top_level(&body, ',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        if is_turning(part) {
+            turn = true;
+            continue;
+        }
+        // Foundation single crochet makes plain stitches of its own
+        let sc = StitchType::SC.abbreviation(terminology).to_lowercase();
+        let fsc = format!("f{}", sc);
+        if part.split_whitespace().any(|w| w == fsc) {
+            foundation = true;
+            turn = true;
+        }
+        let part: Vec<&str> = part
+            .split_whitespace()
+            .map(|w| if w == fsc { sc.as_str() } else { w })
+            .collect();
+        items.push(parse_item(&part.join(" "), terminology)?);
+    }
+
+    if items.is_empty() {
+        return Err("row has no stitches".to_string());
+    }
+
+    Ok(Some(ParsedLine {
+        first_row,
+        last_row,
+        items,
+        stated_count,
+        turn,
+        foundation,
+    }))
+}
+
+/// "rnd 3", "rounds 5-12", "row 4", "r3" -> row range
+fn parse_label(label: &str) -> Option<(usize, usize)> {
+    let label = label.trim();
+    let rest = ["rounds", "round", "rnds", "rnd", "rows", "row", "r"]
+        .iter()
+        .find_map(|prefix| label.strip_prefix(prefix))?
+        .trim();
+
+    let numbers: Vec<&str> = rest
+        .split(['-', '–', '—'])
+        .flat_map(|s| s.split(" to "))
+        .map(str::trim)
+        .collect();
+    let first: usize = numbers.first()?.parse().ok()?;
+    let last: usize = match numbers.len() {
+        1 => first,
+        2 => numbers[1].parse().ok()?,
+        _ => return None,
+    };
+
+    (first >= 1 && last >= first).then_some((first, last))
+}
+
+/// Strip a trailing stitch count: "(18)", "[18 sts]", "— 18 sts", "= 18"
+fn split_stated_count(body: &str) -> (String, Option<usize>) {
+    let trimmed = body.trim();
+
+    if let Some(close) = trimmed.chars().last().filter(|c| *c == ')' || *c == ']') {
+        let open = if close == ')' { '(' } else { '[' };
+        if let Some(start) = trimmed.rfind(open) {
+            if let Some(count) = parse_count(&trimmed[start + 1..trimmed.len() - 1]) {
+                return (trimmed[..start].trim().to_string(), Some(count));
+            }
+        }
+    }
+
+    for separator in ["—", "–", " - ", "="] {
+        if let Some(start) = trimmed.rfind(separator) {
+            if let Some(count) = parse_count(&trimmed[start + separator.len()..]) {
+                return (trimmed[..start].trim().to_string(), Some(count));
+            }
+        }
+    }
+
+    (trimmed.to_string(), None)
+}
+
+/// "18", "18 sts", "18 stitches"
+fn parse_count(text: &str) -> Option<usize> {
+    let mut words = text.split_whitespace();
+    let count = words.next()?.parse().ok()?;
+    match words.next() {
+        None | Some("st") | Some("sts") | Some("stitches") => {
+            words.next().is_none().then_some(count)
+        }
+        _ => None,
+    }
+}
+
+/// Split on `separator` outside of brackets
+fn split_top_level(text: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut current = String::new();
+
+    for ch in text.chars() {
+        match ch {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            _ => {}
+        }
+        if ch == separator && depth == 0 {
+            parts.push(std::mem::take(&mut current));
+        } else {
+            current.push(ch);
+        }
+    }
+    parts.push(current);
+
+    parts
+}
+
+fn is_turning(part: &str) -> bool {
+    part.split_whitespace().any(|w| w == "turn")
+        || matches!(part, "ch" | "ch 1" | "ch1" | "chain 1")
+}
+
+fn parse_item(part: &str, terminology: Terminology) -> std::result::Result<Item, String> {
+    // Repeated group: "(sc, inc) x 6", "[sc, inc] 6 times"
+    if let Some(open) = part.find(['(', '[']) {
+        let close_char = if part[open..].starts_with('(') { ')' } else { ']' };
+        let close = part
+            .rfind(close_char)
+            .ok_or_else(|| format!("unclosed bracket in \"{}\"", part))?;
+        let inner = &part[open + 1..close];
+        let times = parse_repeat(&part[close + 1..])
+            .ok_or_else(|| format!("missing repeat count after \"{}\"", &part[..=close]))?;
+
+        let items = split_top_level(inner, ',')
+            .iter()
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty())
+            .map(|p| parse_item(p, terminology))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        return Ok(Item::Group(items, times));
+    }
+
+    let words: Vec<&str> = part
+        .split_whitespace()
+        .filter(|w| !FILLER_WORDS.contains(w))
+        .collect();
+
+    let mut stitch = None;
+    let mut count = None;
+    let mut around = false;
+    for word in words {
+        if word == "around" || word == "across" {
+            around = true;
+        } else if let Ok(n) = word.parse::<usize>() {
+            count = Some(n);
+        } else if let Some(found) = stitch_from_word(word, terminology) {
+            stitch = Some(found);
+        } else if let Some((n, rest)) = split_number_prefix(word) {
+            // Compact forms like "2sc" or "x6"
+            match stitch_from_word(rest, terminology) {
+                Some(found) => {
+                    stitch = Some(found);
+                    count = Some(n);
+                }
+                None => return Err(format!("unrecognised instruction \"{}\"", part)),
+            }
+        } else if let Some(n) = word.strip_prefix('x').and_then(|n| n.parse().ok()) {
+            count = Some(n);
+        } else {
+            return Err(format!("unrecognised instruction \"{}\"", part));
+        }
+    }
+
+    match (stitch, around) {
+        (Some(StitchType::SC), true) if count.is_none() => Ok(Item::Around),
+        (Some(_), true) => Err(format!(
+            "only single crochet can be worked around in \"{}\"",
+            part
+        )),
+        (Some(stitch), false) => Ok(Item::Stitch(stitch, count.unwrap_or(1))),
+        (None, _) => Err(format!("no stitch in \"{}\"", part)),
+    }
+}
+
+/// "x 6", "*6", "6 times", "x6"
+fn parse_repeat(text: &str) -> Option<usize> {
+    let cleaned: String = text
+        .replace("times", " ")
+        .replace(['x', '*', '×'], " ");
+    let mut words = cleaned.split_whitespace();
+    let times = words.next()?.parse().ok()?;
+    words.next().is_none().then_some(times)
+}
+
+fn split_number_prefix(word: &str) -> Option<(usize, &str)> {
+    let digits = word.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 || digits == word.len() {
+        return None;
+    }
+    Some((word[..digits].parse().ok()?, &word[digits..]))
+}
+
+fn stitch_from_word(word: &str, terminology: Terminology) -> Option<StitchType> {
+    for stitch in [StitchType::SC, StitchType::INC, StitchType::DEC, StitchType::INVDEC] {
+        if word == stitch.abbreviation(terminology).to_lowercase() {
+            return Some(stitch);
+        }
+    }
+    let sc = StitchType::SC.abbreviation(terminology).to_lowercase();
+    match word {
+        "increase" => Some(StitchType::INC),
+        "decrease" => Some(StitchType::DEC),
+        w if w == format!("{}2tog", sc) => Some(StitchType::DEC),
+        "inv-dec" | "invdec" => Some(StitchType::INVDEC),
+        _ => None,
+    }
+}
+
+/// Stitches of the previous row worked into by one stitch
+pub(crate) fn consumes(stitch: StitchType) -> usize {
+    match stitch {
+        StitchType::SC | StitchType::INC => 1,
+        StitchType::DEC | StitchType::INVDEC => 2,
+    }
+}
+
+/// Stitches added to the current row by one stitch
+pub(crate) fn produces(stitch: StitchType) -> usize {
+    match stitch {
+        StitchType::SC | StitchType::DEC | StitchType::INVDEC => 1,
+        StitchType::INC => 2,
+    }
+}
+
+/// Stitches the items spell out, counting "around" as one; saturates
+/// rather than overflowing on absurd repeat counts
+fn stitch_count(items: &[Item]) -> usize {
+    items.iter().fold(0, |n: usize, item| {
+        n.saturating_add(match item {
+            Item::Stitch(_, count) => *count,
+            Item::Around => 1,
+            Item::Group(inner, times) => stitch_count(inner).saturating_mul(*times),
+        })
+    })
+}
+
+/// Flatten items into stitches, filling "around" up to the previous row
+///
+/// Rows spelling out more than `max_stitches` stitches are refused before
+/// anything is expanded.
+fn expand(
+    items: &[Item],
+    prev_stitches: Option<usize>,
+    max_stitches: usize,
+) -> std::result::Result<Vec<StitchType>, String> {
+    fn flatten(items: &[Item], out: &mut Vec<Option<StitchType>>) {
+        for item in items {
+            match item {
+                Item::Stitch(stitch, count) => {
+                    out.extend(std::iter::repeat_n(Some(*stitch), *count))
+                }
+                Item::Around => out.push(None),
+                // An empty group adds nothing however often it repeats
+                Item::Group(inner, _) if stitch_count(inner) == 0 => {}
+                Item::Group(inner, times) => {
+                    for _ in 0..*times {
+                        flatten(inner, out);
+                    }
+                }
+            }
+        }
+    }
+
+    if stitch_count(items) > max_stitches {
+        return Err(format!(
+            "row has more than the limit of {} stitches",
+            max_stitches
+        ));
+    }
+    let mut flat = Vec::new();
+    flatten(items, &mut flat);
+
+    let arounds = flat.iter().filter(|s| s.is_none()).count();
+    if arounds == 0 {
+        return Ok(flat.into_iter().flatten().collect());
+    }
+    if arounds > 1 {
+        return Err("\"around\" can only be used once per row".to_string());
+    }
+    let prev = prev_stitches.ok_or("\"around\" needs a previous row to work into")?;
+    let used: usize = flat.iter().flatten().map(|&s| consumes(s)).sum();
+    let remaining = prev
+        .checked_sub(used)
+        .ok_or_else(|| format!("works into {} stitches but previous row has {}", used, prev))?;
+
+    Ok(flat
+        .into_iter()
+        .flat_map(|s| match s {
+            Some(stitch) => vec![stitch],
+            None => vec![StitchType::SC; remaining],
+        })
+        .collect())
+}
+
+fn build_row(
+    row_number: usize,
+    stitches: &[StitchType],
+    prev_stitches: Option<usize>,
+) -> std::result::Result<Row, String> {
+    if stitches.is_empty() {
+        return Err(format!("row {} has no stitches", row_number));
+    }
+
+    if let Some(prev) = prev_stitches {
+        let used: usize = stitches.iter().map(|&s| consumes(s)).sum();
+        if used != prev {
+            return Err(format!(
+                "row {} works into {} stitches but row {} has {}",
+                row_number,
+                used,
+                row_number - 1,
+                prev
+            ));
+        }
+    } else if stitches.iter().any(|&s| s != StitchType::SC) {
+        return Err("the first row can only contain single crochet".to_string());
+    }
+
+    let len = stitches.len();
+    Ok(Row {
+        row_number,
+        total_stitches: stitches.iter().map(|&s| produces(s)).sum(),
+        pattern: stitches
+            .iter()
+            .enumerate()
+            .map(|(i, &stitch_type)| StitchInstruction {
+                stitch_type,
+                angular_position: 2.0 * PI * i as f64 / len as f64,
+                stitch_index: i,
+            })
+            .collect(),
+        markers: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatter::PatternFormatter;
+    use crate::generator::generate_pattern;
+
+    fn parse(text: &str) -> Result<CrochetPattern> {
+        parse_written_pattern(text, &AmigurumiConfig::default(), Terminology::US)
+    }
+
+    #[test]
+    fn test_parse_common_notation() {
+        let text = "Little Ball\n\
+                    Rnd 1: 6 sc in magic ring (6)\n\
+                    Rnd 2: inc x 6 — 12 sts\n\
+                    Rnd 3: (sc, inc) x 6 — 18 sts\n\
+                    Rnds 4-5: sc around = 18\n\
+                    Rnd 6: [sc, dec] 6 times (12 sts). Stuff firmly.\n\
+                    Rnd 7: sc2tog x6 [6]\n";
+
+        let pattern = parse(text).unwrap();
+
+        let counts: Vec<usize> = pattern.rows.iter().map(|r| r.total_stitches).collect();
+        assert_eq!(counts, vec![6, 12, 18, 18, 18, 12, 6]);
+        assert_eq!(pattern.construction, Construction::Round);
+        assert_eq!(pattern.metadata.total_stitches, 90);
+    }
+
+    #[test]
+    fn test_inconsistent_counts_are_rejected() {
+        let wrong_total = "Rnd 1: 6 sc in mr\nRnd 2: (sc, inc) x 3 (12)";
+        assert!(parse(wrong_total).unwrap_err().to_string().contains("says 12"));
+
+        let wrong_consumption = "Rnd 1: 6 sc in mr\nRnd 2: (sc, inc) x 4";
+        assert!(parse(wrong_consumption).unwrap_err().to_string().contains("works into 8"));
+
+        let skipped_row = "Rnd 1: 6 sc in mr\nRnd 3: sc around";
+        assert!(parse(skipped_row).is_err());
+
+        assert!(parse("Rnd 1: 6 hdc in mr").is_err());
+    }
+
+    #[test]
+    fn test_row_ranges_are_limited() {
+        let huge = "Rnd 1: 6 sc in mr\nRnds 2-4000000000: sc around";
+        let err = parse(huge).unwrap_err().to_string();
+        assert!(
+            err.contains("line 2") && err.contains("2000 rows"),
+            "{}",
+            err
+        );
+
+        let mut config = AmigurumiConfig::default();
+        config.limits.max_rows = 4;
+        let text = "Rnd 1: 6 sc in mr\nRnds 2-5: sc around";
+        assert!(parse_written_pattern(text, &config, Terminology::US).is_err());
+        config.limits.max_rows = 5;
+        assert!(parse_written_pattern(text, &config, Terminology::US).is_ok());
+    }
+
+    #[test]
+    fn test_repeat_counts_are_limited() {
+        let huge = "Row 1: (sc) x 18446744073709551615";
+        let err = parse(huge).unwrap_err().to_string();
+        assert!(err.contains("5000 stitches"), "{}", err);
+
+        let nested = "Row 1: ((sc) x 100000) x 100000";
+        assert!(parse(nested).is_err());
+        assert!(parse("Row 1: sc 5001").is_err());
+        assert_eq!(
+            parse("Row 1: sc 5000").unwrap().rows[0].total_stitches,
+            5000
+        );
+    }
+
+    #[test]
+    fn test_round_trip_with_text_export() {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, 1.0),
+                control2: Point2D::new(3.0, 5.0),
+                end: Point2D::new(1.0, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 1.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: 6.0,
+            ..Default::default()
+        };
+        let pattern = generate_pattern(&curve, &config).unwrap();
+
+        for terminology in [Terminology::US, Terminology::UK] {
+            let mut formatter = PatternFormatter::new(terminology);
+            formatter.worked_into = terminology == Terminology::UK;
+            let text = formatter.to_text(&pattern, &config);
+            let parsed = parse_written_pattern(&text, &config, terminology).unwrap();
+
+            assert_eq!(parsed.rows.len(), pattern.rows.len());
+            for (a, b) in parsed.rows.iter().zip(&pattern.rows) {
+                assert_eq!(a.total_stitches, b.total_stitches);
+            }
+        }
+    }
+
+    #[test]
+    fn test_foundation_single_crochet() {
+        let pattern = parse("Row 1: 10 fsc (10)\nRows 2-3: ch 1, turn, sc across (10)").unwrap();
+
+        assert_eq!(pattern.construction, Construction::Flat);
+        assert_eq!(pattern.foundation, Foundation::SingleCrochet);
+        assert_eq!(pattern.rows[0].total_stitches, 10);
+    }
+
+    #[test]
+    fn test_turned_rows_are_flat() {
+        let text = "Row 1: 10 sc (10)\nRows 2-4: ch 1, turn, sc across (10)";
+        let pattern = parse(text).unwrap();
+
+        assert_eq!(pattern.construction, Construction::Flat);
+        assert_eq!(pattern.rows.len(), 4);
+    }
+}
@@ -0,0 +1,798 @@
+use crochet_types::*;
+
+use crate::diagram::chart_description;
+use crate::formatter::{
+    gauge_label, render_template, yarn_materials, PatternFormatter, ESTIMATED_GAUGE_NOTE,
+    LEGEND_STITCHES,
+};
+
+/// A4 page size in points
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 50.0;
+
+const BODY_SIZE: f64 = 10.0;
+const HEADING_SIZE: f64 = 12.0;
+const TITLE_SIZE: f64 = 16.0;
+const LEADING: f64 = 14.0;
+
+/// Rough Helvetica character budget per line at body size
+const MAX_LINE_CHARS: usize = 90;
+
+/// Render a pattern as a printable multi-page PDF
+///
+/// Written instructions come first, followed by a stitch chart page. Streams
+/// are left uncompressed and text is WinAnsi-escaped, so the document is
+/// plain ASCII and can travel through string-only interfaces. The PDF is
+/// tagged for assistive technology: headings, paragraphs and lists are in
+/// a structure tree in reading order, the chart is a figure with alt text,
+/// chart markers are artifacts, and the language follows the terminology.
+pub fn export_pdf(
+    pattern: &CrochetPattern,
+    config: &AmigurumiConfig,
+    formatter: &PatternFormatter,
+) -> String {
+    let mut layout = PageLayout::new();
+    let vars = formatter.template_variables(pattern, config);
+
+    if let Some(header) = &formatter.branding.header {
+        layout.paragraph(&render_template(header, &vars));
+        layout.gap();
+    }
+    layout.block("H1");
+    layout.text_line("CROCHET AMIGURUMI PATTERN", Font::Bold, TITLE_SIZE);
+    layout.gap();
+
+    layout.heading("Materials");
+    for (item, amount) in yarn_materials(pattern) {
+        layout.bullet(&format!("{}: {}", item, amount));
+    }
+    layout.bullet(&format!("Hook: {}mm", config.yarn.recommended_hook_size_mm));
+    layout.bullet("Stitch marker");
+    layout.bullet("Yarn needle");
+    layout.bullet("Polyester fiberfill stuffing");
+    layout.gap();
+
+    layout.heading("Gauge");
+    layout.bullet(&format!(
+        "{} stitches per cm",
+        config.yarn.gauge_stitches_per_cm
+    ));
+    layout.bullet(&format!("{} rows per cm", config.yarn.gauge_rows_per_cm));
+    if pattern.metadata.gauge_estimated {
+        layout.bullet(ESTIMATED_GAUGE_NOTE);
+    }
+    layout.gap();
+
+    let size = &pattern.metadata.finished_size;
+    layout.heading("Finished Size");
+    layout.bullet(&format!("Height: {:.1} cm", size.height_cm));
+    layout.bullet(&format!("Width: {:.1} cm", size.width_cm));
+    layout.gap();
+
+    if !pattern.metadata.gauge_table.is_empty() {
+        layout.heading("If Your Gauge Is Off");
+        for variation in &pattern.metadata.gauge_table {
+            layout.bullet(&format!(
+                "{} ({:.2} sts/cm): {:.1} x {:.1} cm",
+                gauge_label(variation),
+                variation.stitches_per_cm,
+                variation.finished_size.height_cm,
+                variation.finished_size.width_cm
+            ));
+        }
+        layout.gap();
+    }
+
+    layout.heading("Abbreviations");
+    for (abbr, description) in formatter.abbreviation_legend() {
+        layout.bullet(&format!("{}: {}", abbr, description));
+    }
+    layout.gap();
+
+    if !pattern.notes.is_empty() {
+        layout.heading("Notes");
+        for note in &pattern.notes {
+            layout.bullet(note);
+        }
+        layout.gap();
+    }
+
+    for section in &formatter.branding.sections {
+        layout.heading(&section.title);
+        layout.paragraph(&render_template(&section.body, &vars));
+        layout.gap();
+    }
+
+    layout.heading("Pattern");
+    for (section, lines) in formatter.instruction_sections(pattern) {
+        if let Some(name) = section {
+            layout.subheading(&name);
+        }
+        for (label, instruction) in lines {
+            layout.paragraph(&format!("{}: {}", label, instruction));
+        }
+        layout.gap();
+    }
+
+    layout.paragraph(&format!(
+        "Total Rows: {}    Total Stitches: {}    Estimated Time: {} minutes",
+        pattern.metadata.total_rows,
+        pattern.metadata.total_stitches,
+        pattern.metadata.estimated_time_minutes.round()
+    ));
+
+    if let Some(footer) = &formatter.branding.footer {
+        layout.gap();
+        layout.paragraph(&render_template(footer, &vars));
+    }
+
+    layout.new_page();
+    draw_chart(&mut layout, pattern, formatter);
+
+    let (pages, elements) = layout.finish();
+    write_document(&pages, &elements, formatter.language_tag())
+}
+
+#[derive(Clone, Copy)]
+enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        }
+    }
+}
+
+/// Node of the structure tree that tells assistive technology what each
+/// run of page content is
+struct StructElem {
+    /// Standard structure type (Document, H1, P, L, LI, LBody, Figure, ...)
+    tag: &'static str,
+    /// `None` for the Document element at the root
+    parent: Option<usize>,
+    alt: Option<String>,
+    kids: Vec<StructKid>,
+}
+
+enum StructKid {
+    /// Marked content `mcid` of page `page`
+    Content {
+        page: usize,
+        mcid: usize,
+    },
+    Element(usize),
+}
+
+/// Top-down text flow that breaks onto new pages as it fills
+struct PageLayout {
+    pages: Vec<String>,
+    current: String,
+    y: f64,
+    /// Structure tree in reading order; the Document element comes first
+    elements: Vec<StructElem>,
+    /// Element that marked content goes into
+    open: usize,
+    /// List that further bullets join
+    list: Option<usize>,
+    next_mcid: usize,
+}
+
+impl PageLayout {
+    fn new() -> Self {
+        Self {
+            pages: Vec::new(),
+            current: String::new(),
+            y: PAGE_HEIGHT - MARGIN,
+            elements: vec![StructElem {
+                tag: "Document",
+                parent: None,
+                alt: None,
+                kids: Vec::new(),
+            }],
+            open: 0,
+            list: None,
+            next_mcid: 0,
+        }
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(std::mem::take(&mut self.current));
+        self.y = PAGE_HEIGHT - MARGIN;
+        self.next_mcid = 0;
+    }
+
+    /// Start a structure element under `parent`; content marked from now
+    /// on belongs to it
+    fn element(&mut self, tag: &'static str, parent: usize) -> usize {
+        let index = self.elements.len();
+        self.elements.push(StructElem {
+            tag,
+            parent: Some(parent),
+            alt: None,
+            kids: Vec::new(),
+        });
+        self.elements[parent].kids.push(StructKid::Element(index));
+        self.open = index;
+        index
+    }
+
+    /// Start a top-level element, ending any list
+    fn block(&mut self, tag: &'static str) -> usize {
+        self.list = None;
+        self.element(tag, 0)
+    }
+
+    /// Add `ops` as marked content of the open element. `text` is what the
+    /// content reads as, spelled out as /ActualText when the standard
+    /// fonts cannot draw all of it.
+    fn marked(&mut self, ops: &str, text: Option<&str>) {
+        let mcid = self.next_mcid;
+        self.next_mcid += 1;
+        let page = self.pages.len();
+        let element = &mut self.elements[self.open];
+        element.kids.push(StructKid::Content { page, mcid });
+        let actual_text = match text {
+            Some(text) if text.chars().any(|c| c != '\t' && winansi(c).is_none()) => {
+                format!(" /ActualText {}", text_string(text))
+            }
+            _ => String::new(),
+        };
+        self.current.push_str(&format!(
+            "/{} <</MCID {}{}>> BDC\n{}EMC\n",
+            element.tag, mcid, actual_text, ops
+        ));
+    }
+
+    /// Add decoration that assistive technology should skip
+    fn artifact(&mut self, ops: &str) {
+        self.current
+            .push_str(&format!("/Artifact BMC\n{}EMC\n", ops));
+    }
+
+    fn ensure_space(&mut self, height: f64) {
+        if self.y - height < MARGIN {
+            self.new_page();
+        }
+    }
+
+    fn text_line(&mut self, text: &str, font: Font, size: f64) {
+        self.ensure_space(LEADING.max(size));
+        self.y -= LEADING.max(size);
+        let op = text_op(MARGIN, self.y, text, font, size);
+        self.marked(&op, Some(text));
+    }
+
+    fn heading(&mut self, title: &str) {
+        // Keep a heading on the same page as its first line
+        self.ensure_space(LEADING * 2.0 + HEADING_SIZE);
+        self.block("H2");
+        self.text_line(&title.to_uppercase(), Font::Bold, HEADING_SIZE);
+    }
+
+    fn subheading(&mut self, title: &str) {
+        self.ensure_space(LEADING * 2.0);
+        self.block("H3");
+        self.text_line(title, Font::Bold, BODY_SIZE);
+    }
+
+    fn paragraph(&mut self, text: &str) {
+        self.block("P");
+        for line in text.lines() {
+            for wrapped in wrap(line, MAX_LINE_CHARS) {
+                self.text_line(&wrapped, Font::Regular, BODY_SIZE);
+            }
+        }
+    }
+
+    /// One item of a list; consecutive bullets make up one list
+    fn bullet(&mut self, text: &str) {
+        let list = match self.list {
+            Some(list) => list,
+            None => {
+                let list = self.element("L", 0);
+                self.list = Some(list);
+                list
+            }
+        };
+        self.list_item(list);
+        for (i, wrapped) in wrap(text, MAX_LINE_CHARS - 2).iter().enumerate() {
+            let prefix = if i == 0 { "- " } else { "  " };
+            self.text_line(&format!("{}{}", prefix, wrapped), Font::Regular, BODY_SIZE);
+        }
+    }
+
+    fn list_item(&mut self, list: usize) {
+        let item = self.element("LI", list);
+        self.element("LBody", item);
+    }
+
+    fn gap(&mut self) {
+        self.y -= LEADING / 2.0;
+    }
+
+    fn finish(mut self) -> (Vec<String>, Vec<StructElem>) {
+        if !self.current.is_empty() || self.pages.is_empty() {
+            self.pages.push(self.current);
+        }
+        (self.pages, self.elements)
+    }
+}
+
+fn text_op(x: f64, y: f64, text: &str, font: Font, size: f64) -> String {
+    format!(
+        "BT /{} {} Tf {:.2} {:.2} Td ({}) Tj ET\n",
+        font.resource(),
+        size,
+        x,
+        y,
+        escape_text(text)
+    )
+}
+
+/// Stitch chart: concentric rounds for work in the round, stacked rows for flat work
+fn draw_chart(layout: &mut PageLayout, pattern: &CrochetPattern, formatter: &PatternFormatter) {
+    let palette = formatter.palette;
+    layout.heading("Stitch chart");
+    layout.gap();
+
+    // Legend, its markers left as decoration since the text names each stitch
+    let list = layout.block("L");
+    let legend = LEGEND_STITCHES.iter().zip(formatter.abbreviation_legend());
+    for (stitch_type, (abbr, description)) in legend {
+        layout.y -= LEADING;
+        let mut marker = String::new();
+        push_marker(
+            &mut marker,
+            MARGIN + 3.5,
+            layout.y + 3.5,
+            7.0,
+            *stitch_type,
+            palette,
+        );
+        marker.push_str("0 g\n");
+        layout.artifact(&marker);
+        layout.list_item(list);
+        let text = format!("{}: {}", abbr, description);
+        let op = text_op(MARGIN + 12.0, layout.y, &text, Font::Regular, BODY_SIZE);
+        layout.marked(&op, Some(&text));
+    }
+    layout.gap();
+
+    let rows = &pattern.rows;
+    if rows.is_empty() {
+        return;
+    }
+
+    let area_top = layout.y - LEADING;
+    let area_width = PAGE_WIDTH - 2.0 * MARGIN;
+    let area_height = area_top - MARGIN;
+    let mut ops = String::new();
+
+    match pattern.construction {
+        Construction::Round => {
+            let cx = PAGE_WIDTH / 2.0;
+            let cy = MARGIN + area_height / 2.0;
+            let spacing = area_width.min(area_height) / 2.0 / rows.len() as f64;
+            let marker = (spacing * 0.6).clamp(0.5, 4.0);
+
+            for (ring, row) in rows.iter().enumerate() {
+                let radius = spacing * (ring as f64 + 0.5);
+                for stitch in &row.pattern {
+                    let x = cx + radius * stitch.angular_position.sin();
+                    let y = cy + radius * stitch.angular_position.cos();
+                    push_marker(&mut ops, x, y, marker, stitch.stitch_type, palette);
+                }
+            }
+        }
+        Construction::Flat => {
+            let widest = rows
+                .iter()
+                .map(|r| r.pattern.len())
+                .max()
+                .unwrap_or(1)
+                .max(1);
+            let dx = area_width / widest as f64;
+            let dy = (area_height / rows.len() as f64).min(dx * 2.0);
+            let marker = (dx.min(dy) * 0.6).clamp(0.5, 4.0);
+
+            for (i, row) in rows.iter().enumerate() {
+                let y = MARGIN + dy * (i as f64 + 0.5);
+                for (j, stitch) in row.pattern.iter().enumerate() {
+                    let x = MARGIN + dx * (j as f64 + 0.5);
+                    push_marker(&mut ops, x, y, marker, stitch.stitch_type, palette);
+                }
+            }
+        }
+    }
+
+    ops.push_str("0 g\n");
+    let figure = layout.block("Figure");
+    layout.elements[figure].alt = Some(chart_description(pattern));
+    layout.marked(&ops, None);
+    layout.y = MARGIN;
+}
+
+/// Filled marker whose shape also identifies the stitch, so the chart
+/// still reads in grayscale: square (sc), up triangle (inc), down
+/// triangle (dec) and diamond (invdec)
+fn push_marker(
+    ops: &mut String,
+    x: f64,
+    y: f64,
+    size: f64,
+    stitch_type: StitchType,
+    palette: DiagramPalette,
+) {
+    let (r, g, b) = palette.stitch_color(stitch_type);
+    let h = size / 2.0;
+    let shape = match stitch_type {
+        StitchType::SC => format!("{:.2} {:.2} {:.2} {:.2} re", x - h, y - h, size, size),
+        StitchType::INC => format!(
+            "{:.2} {:.2} m {:.2} {:.2} l {:.2} {:.2} l h",
+            x - h,
+            y - h,
+            x + h,
+            y - h,
+            x,
+            y + h
+        ),
+        StitchType::DEC => format!(
+            "{:.2} {:.2} m {:.2} {:.2} l {:.2} {:.2} l h",
+            x - h,
+            y + h,
+            x + h,
+            y + h,
+            x,
+            y - h
+        ),
+        StitchType::INVDEC => format!(
+            "{:.2} {:.2} m {:.2} {:.2} l {:.2} {:.2} l {:.2} {:.2} l h",
+            x,
+            y + h,
+            x + h,
+            y,
+            x,
+            y - h,
+            x - h,
+            y
+        ),
+    };
+    ops.push_str(&format!("{:.2} {:.2} {:.2} rg {} f\n", r, g, b, shape));
+}
+
+/// Greedy word wrap on character count
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Escape text for a PDF string literal in WinAnsi encoding
+///
+/// Characters outside ASCII become octal escapes of their WinAnsi code;
+/// anything WinAnsi lacks is drawn as `?` since the standard fonts cannot
+/// draw it (marked content then carries the real text as /ActualText).
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(ch);
+            }
+            ' '..='~' => out.push(ch),
+            '\t' => out.push(' '),
+            _ => match winansi(ch) {
+                Some(code) => out.push_str(&format!("\\{:03o}", code)),
+                None => out.push('?'),
+            },
+        }
+    }
+    out
+}
+
+/// WinAnsi code of a printable character: Latin-1, plus the typographic
+/// marks Windows-1252 keeps in 0x80-0x9F
+fn winansi(ch: char) -> Option<u8> {
+    let code = match ch {
+        ' '..='~' | '\u{a0}'..='\u{ff}' => ch as u8,
+        '\u{20ac}' => 0x80, // euro
+        '\u{201a}' => 0x82,
+        '\u{0192}' => 0x83,
+        '\u{201e}' => 0x84,
+        '\u{2026}' => 0x85, // ellipsis
+        '\u{2020}' => 0x86,
+        '\u{2021}' => 0x87,
+        '\u{02c6}' => 0x88,
+        '\u{2030}' => 0x89,
+        '\u{0160}' => 0x8a,
+        '\u{2039}' => 0x8b,
+        '\u{0152}' => 0x8c,
+        '\u{017d}' => 0x8e,
+        '\u{2018}' => 0x91, // curly quotes
+        '\u{2019}' => 0x92,
+        '\u{201c}' => 0x93,
+        '\u{201d}' => 0x94,
+        '\u{2022}' => 0x95, // bullet
+        '\u{2013}' => 0x96, // en and em dash
+        '\u{2014}' => 0x97,
+        '\u{02dc}' => 0x98,
+        '\u{2122}' => 0x99,
+        '\u{0161}' => 0x9a,
+        '\u{203a}' => 0x9b,
+        '\u{0153}' => 0x9c,
+        '\u{017e}' => 0x9e,
+        '\u{0178}' => 0x9f,
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// PDF text string in UTF-16BE hex, for text outside WinAnsi
+fn text_string(text: &str) -> String {
+    let hex: String = text.encode_utf16().map(|u| format!("{:04X}", u)).collect();
+    format!("<FEFF{}>", hex)
+}
+
+/// Serialize page content streams and their structure tree into a
+/// complete tagged PDF file
+fn write_document(pages: &[String], elements: &[StructElem], lang: &str) -> String {
+    // Objects: 1 catalog, 2 page tree, 3-4 fonts, then (page, content)
+    // pairs, the structure tree root, its parent tree, one object per
+    // structure element and the document information
+    let first_page_obj = 5;
+    let page_obj = |i: usize| first_page_obj + 2 * i;
+    let struct_root_obj = page_obj(pages.len());
+    let parent_tree_obj = struct_root_obj + 1;
+    let element_obj = |i: usize| parent_tree_obj + 1 + i;
+    let info_obj = element_obj(elements.len());
+    let mut objects: Vec<String> = Vec::new();
+
+    objects.push(format!(
+        "<< /Type /Catalog /Pages 2 0 R /Lang ({}) /MarkInfo << /Marked true >> \
+         /StructTreeRoot {} 0 R /ViewerPreferences << /DisplayDocTitle true >> >>",
+        escape_text(lang),
+        struct_root_obj
+    ));
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", page_obj(i)))
+        .collect();
+    objects.push(format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        pages.len()
+    ));
+    objects.push(
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    );
+    objects.push(
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    );
+
+    for (i, content) in pages.iter().enumerate() {
+        let content_obj = page_obj(i) + 1;
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R \
+             /StructParents {} /Tabs /S >>",
+            PAGE_WIDTH, PAGE_HEIGHT, content_obj, i
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+
+    // The element each marked-content id of a page belongs to
+    let mut parents: Vec<Vec<usize>> = vec![Vec::new(); pages.len()];
+    for (i, element) in elements.iter().enumerate() {
+        for kid in &element.kids {
+            if let StructKid::Content { page, mcid } = *kid {
+                if parents[page].len() <= mcid {
+                    parents[page].resize(mcid + 1, 0);
+                }
+                parents[page][mcid] = i;
+            }
+        }
+    }
+    objects.push(format!(
+        "<< /Type /StructTreeRoot /K {} 0 R /ParentTree {} 0 R /ParentTreeNextKey {} >>",
+        element_obj(0),
+        parent_tree_obj,
+        pages.len()
+    ));
+    let nums: Vec<String> = parents
+        .iter()
+        .enumerate()
+        .map(|(page, ids)| {
+            let refs: Vec<String> = ids
+                .iter()
+                .map(|&i| format!("{} 0 R", element_obj(i)))
+                .collect();
+            format!("{} [{}]", page, refs.join(" "))
+        })
+        .collect();
+    objects.push(format!("<< /Nums [{}] >>", nums.join(" ")));
+    for element in elements {
+        let kids: Vec<String> = element
+            .kids
+            .iter()
+            .map(|kid| match *kid {
+                StructKid::Content { page, mcid } => {
+                    format!("<< /Type /MCR /Pg {} 0 R /MCID {} >>", page_obj(page), mcid)
+                }
+                StructKid::Element(i) => format!("{} 0 R", element_obj(i)),
+            })
+            .collect();
+        let alt = element
+            .alt
+            .as_ref()
+            .map_or(String::new(), |alt| format!(" /Alt {}", text_string(alt)));
+        objects.push(format!(
+            "<< /Type /StructElem /S /{} /P {} 0 R{} /K [{}] >>",
+            element.tag,
+            element.parent.map_or(struct_root_obj, element_obj),
+            alt,
+            kids.join(" ")
+        ));
+    }
+    objects.push("<< /Title (Crochet Amigurumi Pattern) >>".to_string());
+    debug_assert_eq!(objects.len(), info_obj);
+
+    let mut out = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+
+    let xref_offset = out.len();
+    out.push_str(&format!("xref\n0 {}\n", objects.len() + 1));
+    out.push_str("0000000000 65535 f \n");
+    for offset in offsets {
+        out.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    out.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        info_obj,
+        xref_offset
+    ));
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_pattern;
+
+    fn create_test_pattern(height: f64) -> (CrochetPattern, AmigurumiConfig) {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, height / 6.0),
+                control2: Point2D::new(3.0, height * 5.0 / 6.0),
+                end: Point2D::new(2.0, height),
+            }],
+            start_radius: 0.0,
+            end_radius: 2.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: height,
+            ..Default::default()
+        };
+        (generate_pattern(&curve, &config).unwrap(), config)
+    }
+
+    #[test]
+    fn test_pdf_structure() {
+        let (pattern, config) = create_test_pattern(6.0);
+        let pdf = export_pdf(&pattern, &config, &PatternFormatter::default());
+
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        assert!(pdf.is_ascii());
+        assert!(pdf.contains("(Row 1: 6 SC in magic ring \\(6\\)) Tj"));
+
+        // Every xref entry must point at its object header
+        let xref = pdf.rfind("xref\n").unwrap();
+        let entries = pdf[xref..].lines().skip(3);
+        for (i, entry) in entries.take_while(|l| l.ends_with(" n ")).enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+
+    #[test]
+    fn test_grayscale_chart_has_no_color() {
+        let (pattern, config) = create_test_pattern(6.0);
+        let formatter = PatternFormatter {
+            palette: DiagramPalette::Grayscale,
+            ..Default::default()
+        };
+        let pdf = export_pdf(&pattern, &config, &formatter);
+
+        let fills: Vec<Vec<&str>> = pdf
+            .lines()
+            .filter(|l| l.contains(" rg "))
+            .map(|l| l.split_whitespace().take(3).collect())
+            .collect();
+        assert!(!fills.is_empty());
+        assert!(fills.iter().all(|rgb| rgb[0] == rgb[1] && rgb[1] == rgb[2]));
+        // Increases are drawn as triangles rather than squares
+        assert!(pdf.contains(" l h f\n"));
+    }
+
+    #[test]
+    fn test_long_pattern_spans_pages() {
+        let (pattern, config) = create_test_pattern(40.0);
+        let pdf = export_pdf(&pattern, &config, &PatternFormatter::default());
+
+        let count: usize = pdf
+            .split("/Count ")
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .and_then(|n| n.parse().ok())
+            .unwrap();
+        assert!(count >= 3);
+    }
+
+    #[test]
+    fn test_escape_text() {
+        assert_eq!(escape_text("a (b) \\"), "a \\(b\\) \\\\");
+        assert_eq!(escape_text("\u{a9} Ada"), "\\251 Ada");
+        assert_eq!(escape_text("\u{2764}"), "?");
+        // Typographic marks Windows-1252 adds above Latin-1
+        assert_eq!(escape_text("6 sc \u{2014} 18"), "6 sc \\227 18");
+        assert_eq!(text_string("A\u{2764}"), "<FEFF00412764>");
+    }
+
+    #[test]
+    fn test_pdf_is_tagged() {
+        let (mut pattern, config) = create_test_pattern(6.0);
+        pattern.notes.push("Stuff firmly \u{2764}".to_string());
+        let pdf = export_pdf(&pattern, &config, &PatternFormatter::new(Terminology::UK));
+
+        assert!(pdf.contains("/Lang (en-GB) /MarkInfo << /Marked true >> /StructTreeRoot "));
+        for tag in ["Document", "H1", "H2", "P", "L", "LI", "LBody", "Figure"] {
+            assert!(pdf.contains(&format!("/S /{} /P ", tag)), "{}", tag);
+        }
+        let alt = format!("/Alt {}", text_string(&chart_description(&pattern)));
+        assert!(pdf.contains(&alt));
+        // Every marked run of content is claimed by one structure element
+        assert_eq!(
+            pdf.matches(" BDC\n").count(),
+            pdf.matches("/Type /MCR").count()
+        );
+        assert!(pdf.contains("/Artifact BMC\n"));
+        // Text the fonts cannot draw is still read out
+        assert!(pdf.contains(&format!(
+            "/ActualText {}",
+            text_string("- Stuff firmly \u{2764}")
+        )));
+    }
+}
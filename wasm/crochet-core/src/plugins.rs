@@ -0,0 +1,191 @@
+use crochet_types::*;
+
+use crate::stitch_count::calculate_stitch_counts_with_warnings;
+
+/// Decides how many stitches each row has
+///
+/// Given the radius every row should reach (row 1 is the magic ring),
+/// returns one stitch count per row, plus any warnings to report in the
+/// pattern's metadata. Each row may at most double or halve the one
+/// before it.
+pub trait RowShaper {
+    fn stitch_counts(
+        &self,
+        row_radii: &[f64],
+        config: &AmigurumiConfig,
+    ) -> (Vec<usize>, Vec<String>);
+}
+
+/// The built-in shaper: counts from the gauge, capped by the
+/// configuration's stitch delta limits
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GaugeShaper;
+
+impl RowShaper for GaugeShaper {
+    fn stitch_counts(
+        &self,
+        row_radii: &[f64],
+        config: &AmigurumiConfig,
+    ) -> (Vec<usize>, Vec<String>) {
+        calculate_stitch_counts_with_warnings(row_radii, config)
+    }
+}
+
+/// Decides where each row's increases and decreases go
+///
+/// Rows arrive in order with the stitches the shaper asked for, evenly
+/// spread; `placed` holds the rows already returned. The result must keep
+/// the row's number, stitch count and stitches, only reordered, or the
+/// generation fails. The ridge pass may still turn the rows afterwards
+/// unless `ridge_window` is 0.
+pub trait PlacementStrategy {
+    fn place(&mut self, row: &Row, placed: &[Row]) -> Row;
+}
+
+/// Replacement stages for the generation pipeline, for trying other
+/// optimizers without forking it; stages left `None` are the built-in ones
+#[derive(Default)]
+pub struct GenerationPlugins {
+    pub shaper: Option<Box<dyn RowShaper>>,
+    pub placement: Option<Box<dyn PlacementStrategy>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{generate_pattern, generate_pattern_with_plugins};
+
+    fn create_test_curve() -> ProfileCurve {
+        ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, 1.0),
+                control2: Point2D::new(3.0, 5.0),
+                end: Point2D::new(1.0, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 1.0,
+        }
+    }
+
+    fn create_test_config() -> AmigurumiConfig {
+        AmigurumiConfig {
+            total_height_cm: 6.0,
+            ..Default::default()
+        }
+    }
+
+    /// A ball that grows by 6 a round to 24 stitches, then closes again
+    struct Ball;
+
+    impl RowShaper for Ball {
+        fn stitch_counts(
+            &self,
+            row_radii: &[f64],
+            _: &AmigurumiConfig,
+        ) -> (Vec<usize>, Vec<String>) {
+            let n = row_radii.len();
+            let counts = (0..n)
+                .map(|i| (6 * (i + 1)).min(6 * (n - i)).min(24))
+                .collect();
+            (counts, vec!["shaped as a ball".to_string()])
+        }
+    }
+
+    /// Every increase or decrease at the start of the round
+    struct FrontLoaded;
+
+    impl PlacementStrategy for FrontLoaded {
+        fn place(&mut self, row: &Row, _: &[Row]) -> Row {
+            let mut row = row.clone();
+            row.pattern.sort_by_key(|s| s.stitch_type == StitchType::SC);
+            for (i, stitch) in row.pattern.iter_mut().enumerate() {
+                stitch.stitch_index = i;
+            }
+            row
+        }
+    }
+
+    /// Loses a stitch from every row
+    struct Lossy;
+
+    impl PlacementStrategy for Lossy {
+        fn place(&mut self, row: &Row, _: &[Row]) -> Row {
+            let mut row = row.clone();
+            if row.pattern.len() > 1 {
+                row.pattern.pop();
+            }
+            row
+        }
+    }
+
+    /// Grows faster than increases allow
+    struct Jumpy;
+
+    impl RowShaper for Jumpy {
+        fn stitch_counts(
+            &self,
+            row_radii: &[f64],
+            _: &AmigurumiConfig,
+        ) -> (Vec<usize>, Vec<String>) {
+            ((0..row_radii.len()).map(|i| 6 + 20 * i).collect(), vec![])
+        }
+    }
+
+    #[test]
+    fn test_custom_stages_replace_the_built_in_ones() {
+        let curve = create_test_curve();
+        let config = AmigurumiConfig {
+            optimization: OptimizationConfig {
+                ridge_window: Some(0),
+                ..Default::default()
+            },
+            ..create_test_config()
+        };
+
+        let pattern = generate_pattern_with_plugins(
+            &curve,
+            &config,
+            GenerationPlugins {
+                shaper: Some(Box::new(Ball)),
+                placement: Some(Box::new(FrontLoaded)),
+            },
+        )
+        .unwrap();
+
+        let counts: Vec<usize> = pattern.rows.iter().map(|r| r.total_stitches).collect();
+        assert_eq!(&counts[..4], &[6, 12, 18, 24]);
+        assert_eq!(counts.last(), Some(&6));
+        assert!(pattern
+            .metadata
+            .warnings
+            .contains(&"shaped as a ball".to_string()));
+        assert_eq!(pattern.rows[2].pattern_string(), "6 INC, 6 SC");
+
+        // No plugins is the plain pipeline
+        let plain =
+            generate_pattern_with_plugins(&curve, &config, GenerationPlugins::default()).unwrap();
+        let expected = generate_pattern(&curve, &config).unwrap();
+        for (a, b) in plain.rows.iter().zip(&expected.rows) {
+            assert_eq!(a.pattern_string(), b.pattern_string());
+        }
+    }
+
+    #[test]
+    fn test_broken_plugins_fail_the_generation() {
+        let curve = create_test_curve();
+        let config = create_test_config();
+
+        let lossy = GenerationPlugins {
+            placement: Some(Box::new(Lossy)),
+            ..Default::default()
+        };
+        assert!(generate_pattern_with_plugins(&curve, &config, lossy).is_err());
+
+        let jumpy = GenerationPlugins {
+            shaper: Some(Box::new(Jumpy)),
+            ..Default::default()
+        };
+        assert!(generate_pattern_with_plugins(&curve, &config, jumpy).is_err());
+    }
+}
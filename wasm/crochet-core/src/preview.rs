@@ -0,0 +1,257 @@
+use crochet_types::*;
+use std::f64::consts::PI;
+
+use crate::scene::stitch_scene;
+use crate::yarn::{closest_weight, weight_info};
+
+/// Sides of the tube swept along the yarn
+const TUBE_SIDES: usize = 6;
+/// Points sampled along each loop
+const LOOP_SAMPLES: usize = 8;
+
+/// A loop of yarn as a cubic Bézier in a stitch's own frame: x along the
+/// row and y up the stitch, both as fractions of the stitch's size, with
+/// `lift` yarn diameters of standoff from the fabric at the loop's top
+struct Loop {
+    x: [f64; 4],
+    lift: f64,
+}
+
+/// Simplified loops of each stitch: a single crochet is one arch, an
+/// increase two arches from the same base and a decrease one arch drawn
+/// in from two stitches below
+fn stitch_loops(stitch: StitchType) -> &'static [Loop] {
+    match stitch {
+        StitchType::SC => &[Loop {
+            x: [-0.5, -0.5, 0.5, 0.5],
+            lift: 1.0,
+        }],
+        StitchType::INC => &[
+            Loop {
+                x: [-0.05, -0.5, 0.0, 0.0],
+                lift: 1.0,
+            },
+            Loop {
+                x: [0.0, 0.0, 0.5, 0.05],
+                lift: 1.0,
+            },
+        ],
+        StitchType::DEC => &[Loop {
+            x: [-1.0, -0.5, 0.5, 1.0],
+            lift: 1.0,
+        }],
+        // Worked through front loops only, so it lies flatter
+        StitchType::INVDEC => &[Loop {
+            x: [-1.0, -0.5, 0.5, 1.0],
+            lift: 0.5,
+        }],
+    }
+}
+
+/// Mesh of the yarn in a pattern, for a plush-looking preview
+///
+/// Each stitch of the `stitch_scene` gets the loops of its type, and a
+/// tube as thick as the yarn is swept along every loop. The thickness is
+/// that of the configuration's `yarn_estimate` weight, or of the weight
+/// closest to its gauge. Tubes are left open at their ends, which sit in
+/// the fabric.
+pub fn yarn_preview(pattern: &CrochetPattern, config: &AmigurumiConfig) -> YarnMesh {
+    let weight = config
+        .yarn_estimate
+        .weight
+        .unwrap_or_else(|| closest_weight(&config.yarn).weight);
+    let radius = weight_info(weight).yarn_diameter_mm / 20.0;
+
+    let mut mesh = YarnMesh {
+        vertices: Vec::new(),
+        normals: Vec::new(),
+        indices: Vec::new(),
+    };
+    for glyph in &stitch_scene(pattern, config).glyphs {
+        let up = cross(glyph.normal, glyph.direction);
+        for stitch_loop in stitch_loops(glyph.stitch_type) {
+            let centre: Vec<Point3D> = (0..=LOOP_SAMPLES)
+                .map(|i| {
+                    let t = i as f64 / LOOP_SAMPLES as f64;
+                    let x = bezier(stitch_loop.x, t) * glyph.width_cm;
+                    let y = bezier([-0.5, 0.5, 0.5, -0.5], t) * glyph.height_cm;
+                    let z = stitch_loop.lift * 2.0 * radius * (PI * t).sin();
+                    add(
+                        glyph.position,
+                        add(
+                            scale(glyph.direction, x),
+                            add(scale(up, y), scale(glyph.normal, z)),
+                        ),
+                    )
+                })
+                .collect();
+            sweep_tube(&mut mesh, &centre, glyph.normal, radius);
+        }
+    }
+    mesh
+}
+
+/// Wavefront OBJ text of a mesh, with vertex normals
+pub fn mesh_to_obj(mesh: &YarnMesh) -> String {
+    let mut obj = String::from("# Crochet yarn preview, units in cm\n");
+    for v in &mesh.vertices {
+        obj.push_str(&format!("v {:.4} {:.4} {:.4}\n", v.x, v.y, v.z));
+    }
+    for n in &mesh.normals {
+        obj.push_str(&format!("vn {:.4} {:.4} {:.4}\n", n.x, n.y, n.z));
+    }
+    for triangle in mesh.indices.chunks(3) {
+        // OBJ counts from 1
+        let [a, b, c] = [triangle[0] + 1, triangle[1] + 1, triangle[2] + 1];
+        obj.push_str(&format!("f {a}//{a} {b}//{b} {c}//{c}\n"));
+    }
+    obj
+}
+
+/// Add a ring of `TUBE_SIDES` vertices around every point of `centre`
+/// and join neighbouring rings
+fn sweep_tube(mesh: &mut YarnMesh, centre: &[Point3D], reference: Point3D, radius: f64) {
+    let first = mesh.vertices.len() as u32;
+    for (i, &point) in centre.iter().enumerate() {
+        let ahead = centre[(i + 1).min(centre.len() - 1)];
+        let behind = centre[i.saturating_sub(1)];
+        let tangent = normalize(sub(ahead, behind));
+        // Rings are kept square to the tangent, seamed on the fabric side
+        let side = normalize(cross(tangent, reference));
+        let out = cross(side, tangent);
+        for k in 0..TUBE_SIDES {
+            let (sin, cos) = (2.0 * PI * k as f64 / TUBE_SIDES as f64).sin_cos();
+            let normal = add(scale(out, cos), scale(side, sin));
+            mesh.vertices.push(add(point, scale(normal, radius)));
+            mesh.normals.push(normal);
+        }
+    }
+
+    let sides = TUBE_SIDES as u32;
+    for ring in 0..centre.len() as u32 - 1 {
+        let (this, next) = (first + ring * sides, first + (ring + 1) * sides);
+        for k in 0..sides {
+            let k1 = (k + 1) % sides;
+            mesh.indices.extend([
+                this + k,
+                this + k1,
+                next + k,
+                this + k1,
+                next + k1,
+                next + k,
+            ]);
+        }
+    }
+}
+
+fn bezier(p: [f64; 4], t: f64) -> f64 {
+    let u = 1.0 - t;
+    u * u * u * p[0] + 3.0 * u * u * t * p[1] + 3.0 * u * t * t * p[2] + t * t * t * p[3]
+}
+
+fn add(a: Point3D, b: Point3D) -> Point3D {
+    Point3D::new(a.x + b.x, a.y + b.y, a.z + b.z)
+}
+
+fn sub(a: Point3D, b: Point3D) -> Point3D {
+    Point3D::new(a.x - b.x, a.y - b.y, a.z - b.z)
+}
+
+fn scale(a: Point3D, k: f64) -> Point3D {
+    Point3D::new(a.x * k, a.y * k, a.z * k)
+}
+
+fn cross(a: Point3D, b: Point3D) -> Point3D {
+    Point3D::new(
+        a.y * b.z - a.z * b.y,
+        a.z * b.x - a.x * b.z,
+        a.x * b.y - a.y * b.x,
+    )
+}
+
+fn normalize(a: Point3D) -> Point3D {
+    let length = (a.x * a.x + a.y * a.y + a.z * a.z).sqrt();
+    if length < 1e-12 {
+        return a;
+    }
+    scale(a, 1.0 / length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swatch::generate_gauge_swatch;
+
+    fn create_swatch() -> (CrochetPattern, AmigurumiConfig) {
+        let config = AmigurumiConfig::default();
+        (generate_gauge_swatch(&config.yarn).unwrap(), config)
+    }
+
+    fn dot(a: Point3D, b: Point3D) -> f64 {
+        a.x * b.x + a.y * b.y + a.z * b.z
+    }
+
+    /// Distance of every vertex from the centre of its ring
+    fn tube_radii(mesh: &YarnMesh) -> Vec<f64> {
+        mesh.vertices
+            .chunks(TUBE_SIDES)
+            .flat_map(|ring| {
+                let centre = scale(
+                    ring.iter()
+                        .fold(Point3D::new(0.0, 0.0, 0.0), |a, &b| add(a, b)),
+                    1.0 / TUBE_SIDES as f64,
+                );
+                ring.iter()
+                    .map(move |&v| dot(sub(v, centre), sub(v, centre)).sqrt())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_loops_are_swept_outward() {
+        let (swatch, config) = create_swatch();
+        let mesh = yarn_preview(&swatch, &config);
+
+        let loops: usize = swatch
+            .rows
+            .iter()
+            .flat_map(|r| &r.pattern)
+            .map(|s| stitch_loops(s.stitch_type).len())
+            .sum();
+        assert_eq!(mesh.vertices.len(), loops * (LOOP_SAMPLES + 1) * TUBE_SIDES);
+        assert_eq!(mesh.normals.len(), mesh.vertices.len());
+        assert_eq!(mesh.indices.len(), loops * LOOP_SAMPLES * TUBE_SIDES * 6);
+        for triangle in mesh.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize]);
+            let facing = cross(sub(b, a), sub(c, a));
+            assert!(dot(facing, mesh.normals[triangle[0] as usize]) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_tubes_are_as_thick_as_the_yarn() {
+        let (swatch, mut config) = create_swatch();
+
+        // Fine yarn suits the default 3.5 mm hook and is 1.4 mm across
+        let fine = yarn_preview(&swatch, &config);
+        assert!(tube_radii(&fine).iter().all(|r| (r - 0.07).abs() < 1e-9));
+
+        config.yarn_estimate.weight = Some(YarnWeight::Bulky);
+        let bulky = yarn_preview(&swatch, &config);
+        assert!(tube_radii(&bulky).iter().all(|r| (r - 0.14).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_obj_export() {
+        let (swatch, config) = create_swatch();
+        let mesh = yarn_preview(&swatch, &config);
+
+        let obj = mesh_to_obj(&mesh);
+
+        let count = |prefix: &str| obj.lines().filter(|l| l.starts_with(prefix)).count();
+        assert_eq!(count("v "), mesh.vertices.len());
+        assert_eq!(count("vn "), mesh.normals.len());
+        assert_eq!(count("f "), mesh.indices.len() / 3);
+        assert!(obj.contains("\nf 1//1 "));
+    }
+}
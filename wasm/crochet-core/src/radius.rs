@@ -52,13 +52,8 @@ pub fn calculate_radius_profile(samples: &[Point2D]) -> Vec<f64> {
     // Extract radius (x-coordinate) from each point
     let radii: Vec<f64> = samples.iter().map(|p| p.x.max(0.0)).collect();
 
-    // Apply Gaussian smoothing
-    let spacing = if samples.len() > 1 {
-        (samples.last().unwrap().y - samples[0].y) / (samples.len() - 1) as f64
-    } else {
-        1.0
-    };
-    let sigma = 0.5 * spacing;
+    // Apply Gaussian smoothing (sigma is measured in samples, not cm)
+    let sigma = 1.0;
 
     gaussian_smooth(&radii, sigma)
 }
@@ -0,0 +1,219 @@
+use crochet_types::{Row, StitchType};
+
+/// Rows either side of each row compared by `smooth_ridges`
+pub const DEFAULT_RIDGE_WINDOW: usize = 3;
+
+/// Largest window a configuration may ask for
+pub const MAX_RIDGE_WINDOW: usize = 20;
+
+/// Passes over the rows before `smooth_ridges` gives up improving them
+const MAX_SWEEPS: usize = 4;
+
+/// How strongly increases and decreases line up across rows
+///
+/// Every special stitch is scored against the nearest special stitch in
+/// each of the `window` rows below it: 1 when directly above it, falling
+/// off by a factor of e per stitch apart and shared out by how many rows
+/// apart they are. The total is divided by the number of special stitches,
+/// so 0 means nothing lines up and values near 1 mean a visible ridge.
+pub fn ridge_energy(rows: &[Row], window: usize) -> f64 {
+    let positions: Vec<Vec<f64>> = rows.iter().map(special_positions).collect();
+    let specials: usize = positions.iter().map(Vec::len).sum();
+    if specials == 0 {
+        return 0.0;
+    }
+
+    let total: f64 = (0..rows.len())
+        .map(|r| {
+            (1..=window)
+                .filter(|d| r + d < rows.len())
+                .map(|d| {
+                    pair_energy(
+                        &positions[r],
+                        &positions[r + d],
+                        rows[r + d].pattern.len(),
+                        d,
+                    )
+                })
+                .sum::<f64>()
+        })
+        .sum();
+    total / specials as f64
+}
+
+/// Turn whole rows so increases and decreases don't line up over several rows
+///
+/// Placement staggers each row against the one before it only, which
+/// still lets every other row line up and build a ridge or spiral. This
+/// pass looks `window` rows either way: each row is in turn rotated by up
+/// to one gap between its special stitches, keeping their spacing, to
+/// whatever rotation lowers `ridge_energy` most, until a sweep changes
+/// nothing. With `keep_decreasing`, decreasing rows stay where they are
+/// (their side-to-side balance was already chosen).
+pub fn smooth_ridges(rows: &mut [Row], window: usize, keep_decreasing: bool) {
+    if window == 0 {
+        return;
+    }
+    let mut positions: Vec<Vec<f64>> = rows.iter().map(special_positions).collect();
+
+    for _ in 0..MAX_SWEEPS {
+        let mut changed = false;
+        for r in 0..rows.len() {
+            let len = rows[r].pattern.len();
+            let count = positions[r].len();
+            if count == 0 || count == len || (keep_decreasing && is_decreasing(&rows[r])) {
+                continue;
+            }
+
+            let local = |candidate: &[f64]| {
+                (1..=window)
+                    .map(|d| {
+                        let below = r
+                            .checked_sub(d)
+                            .map_or(0.0, |b| pair_energy(&positions[b], candidate, len, d));
+                        let above = rows.get(r + d).map_or(0.0, |row| {
+                            pair_energy(candidate, &positions[r + d], row.pattern.len(), d)
+                        });
+                        below + above
+                    })
+                    .sum::<f64>()
+            };
+            let mut best_turn = 0;
+            let mut best_energy = local(&positions[r]);
+            for turn in 1..len.div_ceil(count) {
+                let energy = local(&turned_positions(&positions[r], turn, len));
+                if energy < best_energy - 1e-12 {
+                    best_turn = turn;
+                    best_energy = energy;
+                }
+            }
+
+            if best_turn > 0 {
+                turn_row(&mut rows[r], best_turn);
+                positions[r] = special_positions(&rows[r]);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+}
+
+/// Special stitch positions as a fraction of the way around, ascending
+fn special_positions(row: &Row) -> Vec<f64> {
+    let len = row.pattern.len() as f64;
+    row.pattern
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.stitch_type != StitchType::SC)
+        .map(|(i, _)| i as f64 / len)
+        .collect()
+}
+
+fn turned_positions(positions: &[f64], turn: usize, len: usize) -> Vec<f64> {
+    let shift = turn as f64 / len as f64;
+    let mut turned: Vec<f64> = positions.iter().map(|p| (p + shift).fract()).collect();
+    turned.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    turned
+}
+
+/// Move every instruction `turn` places later in the round
+fn turn_row(row: &mut Row, turn: usize) {
+    let n = row.pattern.len();
+    let types: Vec<StitchType> = row.pattern.iter().map(|s| s.stitch_type).collect();
+    for (i, instruction) in row.pattern.iter_mut().enumerate() {
+        instruction.stitch_type = types[(i + n - turn % n) % n];
+    }
+}
+
+fn is_decreasing(row: &Row) -> bool {
+    row.pattern
+        .iter()
+        .any(|s| matches!(s.stitch_type, StitchType::DEC | StitchType::INVDEC))
+}
+
+/// Alignment of the special stitches of `upper` (a row of `len`
+/// instructions) with those of `lower`, `rows_apart` rows below
+fn pair_energy(lower: &[f64], upper: &[f64], len: usize, rows_apart: usize) -> f64 {
+    if lower.is_empty() {
+        return 0.0;
+    }
+    upper
+        .iter()
+        .map(|&p| {
+            // First position at or after p, and the one before it, wrapping
+            let next = lower.partition_point(|&q| q < p);
+            let after = lower[next % lower.len()];
+            let before = lower[(next + lower.len() - 1) % lower.len()];
+            let gap = |q: f64| {
+                let d = (p - q).abs();
+                d.min(1.0 - d)
+            };
+            let stitches = gap(after).min(gap(before)) * len as f64;
+            (-stitches).exp() / rows_apart as f64
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crochet_types::StitchInstruction;
+    use std::f64::consts::PI;
+
+    fn create_row(row_number: usize, len: usize, specials: &[usize]) -> Row {
+        Row {
+            row_number,
+            total_stitches: len + specials.len(),
+            pattern: (0..len)
+                .map(|i| StitchInstruction {
+                    stitch_type: if specials.contains(&i) {
+                        StitchType::INC
+                    } else {
+                        StitchType::SC
+                    },
+                    angular_position: 2.0 * PI * i as f64 / len as f64,
+                    stitch_index: i,
+                })
+                .collect(),
+            markers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_alternating_rows_are_spread_out() {
+        // Each row is staggered against the one below, but rows 1 and 3
+        // (and 2 and 4) line up exactly
+        let mut rows = vec![
+            create_row(1, 24, &[0, 6, 12, 18]),
+            create_row(2, 24, &[3, 9, 15, 21]),
+            create_row(3, 24, &[0, 6, 12, 18]),
+            create_row(4, 24, &[3, 9, 15, 21]),
+        ];
+        let before = ridge_energy(&rows, DEFAULT_RIDGE_WINDOW);
+
+        smooth_ridges(&mut rows, DEFAULT_RIDGE_WINDOW, false);
+
+        let after = ridge_energy(&rows, DEFAULT_RIDGE_WINDOW);
+        assert!(after < before, "{} -> {}", before, after);
+        for row in &rows {
+            assert_eq!(special_positions(row).len(), 4);
+        }
+    }
+
+    #[test]
+    fn test_window_zero_and_dense_rows_untouched() {
+        let rows = vec![
+            create_row(1, 6, &[0, 1, 2, 3, 4, 5]),
+            create_row(2, 12, &[0, 2, 4, 6, 8, 10]),
+        ];
+        let mut smoothed = rows.clone();
+        smooth_ridges(&mut smoothed, 0, false);
+        assert_eq!(special_positions(&smoothed[1]), special_positions(&rows[1]));
+
+        smooth_ridges(&mut smoothed, DEFAULT_RIDGE_WINDOW, false);
+        assert_eq!(special_positions(&smoothed[0]), special_positions(&rows[0]));
+        assert_eq!(ridge_energy(&[], DEFAULT_RIDGE_WINDOW), 0.0);
+    }
+}
@@ -61,7 +61,8 @@ fn find_nearest_sample_by_height(samples: &[Point2D], target_height: f64) -> usi
     let dist_left = (samples[left - 1].y - target_height).abs();
     let dist_right = (samples[left].y - target_height).abs();
 
-    if dist_left < dist_right {
+    // Ties resolve to the lower sample
+    if dist_left <= dist_right {
         left - 1
     } else {
         left
@@ -81,13 +82,12 @@ mod tests {
 
         let config = AmigurumiConfig {
             total_height_cm: 10.0,
-            start_diameter_cm: 10.0,
-            end_diameter_cm: 10.0,
             yarn: YarnSpec {
                 gauge_stitches_per_cm: 3.0,
                 gauge_rows_per_cm: 3.0,
                 recommended_hook_size_mm: 3.5,
             },
+            ..Default::default()
         };
 
         let mapping = map_samples_to_rows(&samples, &config);
@@ -120,17 +120,16 @@ mod tests {
 
         let config = AmigurumiConfig {
             total_height_cm: 10.0,
-            start_diameter_cm: 10.0,
-            end_diameter_cm: 10.0,
             yarn: YarnSpec {
                 gauge_stitches_per_cm: 3.0,
                 gauge_rows_per_cm: 3.0,
                 recommended_hook_size_mm: 3.5,
             },
+            ..Default::default()
         };
 
         let mapping = map_samples_to_rows(&samples, &config);
-        assert!(mapping.len() > 0);
+        assert!(!mapping.is_empty());
         
         // All should map to index 0
         for &idx in &mapping {
@@ -0,0 +1,162 @@
+use crochet_types::{Row, StitchInstruction};
+use std::f64::consts::PI;
+
+use crate::markers::recount_markers;
+
+/// Shift every angular position in a round by `offset` radians
+///
+/// The instructions keep their order; the whole round turns on the piece,
+/// which moves where the round starts.
+pub fn offset_row(row: &Row, offset: f64) -> Row {
+    let pattern = row
+        .pattern
+        .iter()
+        .map(|s| StitchInstruction {
+            angular_position: (s.angular_position + offset).rem_euclid(2.0 * PI),
+            ..s.clone()
+        })
+        .collect();
+
+    Row {
+        row_number: row.row_number,
+        total_stitches: row.total_stitches,
+        pattern,
+        markers: row.markers.clone(),
+    }
+}
+
+/// Mirror a round for the opposite working direction
+///
+/// The first stitch stays put and the rest are worked in reverse order, so
+/// each instruction lands at the reflection of its original angle.
+pub fn mirror_row(row: &Row) -> Row {
+    let n = row.pattern.len();
+    let pattern = (0..n)
+        .map(|j| {
+            let source = &row.pattern[(n - j) % n];
+            StitchInstruction {
+                stitch_type: source.stitch_type,
+                angular_position: (2.0 * PI - source.angular_position) % (2.0 * PI),
+                stitch_index: j,
+            }
+        })
+        .collect();
+
+    let mut mirrored = Row {
+        row_number: row.row_number,
+        total_stitches: row.total_stitches,
+        pattern,
+        markers: row.markers.clone(),
+    };
+    recount_markers(&mut mirrored);
+    mirrored
+}
+
+/// Work the same stitches in the opposite order
+///
+/// Unlike `mirror_row` the shape is unchanged: every instruction stays on
+/// the stitch it was worked into, so angles are kept and only the indices
+/// are renumbered. This is how a flat row reads after turning the work.
+pub fn reverse_row(row: &Row) -> Row {
+    let pattern = row
+        .pattern
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(j, s)| StitchInstruction {
+            stitch_index: j,
+            ..s.clone()
+        })
+        .collect();
+
+    let mut reversed = Row {
+        row_number: row.row_number,
+        total_stitches: row.total_stitches,
+        pattern,
+        markers: row.markers.clone(),
+    };
+    recount_markers(&mut reversed);
+    reversed
+}
+
+/// Start a round `stitches` instructions later (earlier if negative)
+///
+/// Instructions stay on the stitches they were worked into, so angles are
+/// kept and the indices are renumbered from the new first instruction.
+pub fn rotate_row(row: &Row, stitches: isize) -> Row {
+    let n = row.pattern.len();
+    if n == 0 {
+        return row.clone();
+    }
+    let shift = stitches.rem_euclid(n as isize) as usize;
+    let pattern = (0..n)
+        .map(|j| StitchInstruction {
+            stitch_index: j,
+            ..row.pattern[(j + shift) % n].clone()
+        })
+        .collect();
+
+    let mut rotated = Row {
+        row_number: row.row_number,
+        total_stitches: row.total_stitches,
+        pattern,
+        markers: row.markers.clone(),
+    };
+    recount_markers(&mut rotated);
+    rotated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crochet_types::StitchType;
+
+    fn create_test_row() -> Row {
+        use StitchType::*;
+        let types = [INC, SC, SC, DEC, SC];
+        Row {
+            row_number: 3,
+            total_stitches: 5,
+            pattern: types
+                .iter()
+                .enumerate()
+                .map(|(i, &stitch_type)| StitchInstruction {
+                    stitch_type,
+                    angular_position: 2.0 * PI * i as f64 / types.len() as f64,
+                    stitch_index: i,
+                })
+                .collect(),
+            markers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_reverse_keeps_angles() {
+        let row = create_test_row();
+        let reversed = reverse_row(&row);
+
+        for (j, s) in reversed.pattern.iter().enumerate() {
+            let source = &row.pattern[4 - j];
+            assert_eq!(s.stitch_index, j);
+            assert_eq!(s.stitch_type, source.stitch_type);
+            assert_eq!(s.angular_position, source.angular_position);
+        }
+        assert_eq!(reverse_row(&reversed).pattern[3].stitch_type, StitchType::DEC);
+    }
+
+    #[test]
+    fn test_rotate_moves_start() {
+        let row = create_test_row();
+        let rotated = rotate_row(&row, 3);
+
+        assert_eq!(rotated.pattern[0].stitch_type, StitchType::DEC);
+        assert_eq!(rotated.pattern[0].stitch_index, 0);
+        assert_eq!(rotated.pattern[0].angular_position, row.pattern[3].angular_position);
+        assert_eq!(rotated.pattern[2].stitch_type, StitchType::INC);
+
+        let back = rotate_row(&rotated, -8);
+        let types: Vec<_> = back.pattern.iter().map(|s| s.stitch_type).collect();
+        let original: Vec<_> = row.pattern.iter().map(|s| s.stitch_type).collect();
+        assert_eq!(types, original);
+    }
+}
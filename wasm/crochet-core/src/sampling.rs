@@ -2,6 +2,11 @@ use crochet_types::{Point2D, ProfileCurve, SplineSegment};
 
 /// Calculate arc length of a spline segment using adaptive Simpson integration
 fn segment_arc_length(segment: &SplineSegment, tolerance: f64) -> f64 {
+    partial_arc_length(segment, 1.0, tolerance)
+}
+
+/// Calculate arc length of a spline segment from t = 0 up to `t_end`
+fn partial_arc_length(segment: &SplineSegment, t_end: f64, tolerance: f64) -> f64 {
     fn integrand(segment: &SplineSegment, t: f64) -> f64 {
         let deriv = segment.derivative(t);
         (deriv.x * deriv.x + deriv.y * deriv.y).sqrt()
@@ -33,27 +38,25 @@ fn segment_arc_length(segment: &SplineSegment, tolerance: f64) -> f64 {
         }
     }
 
-    let whole = (integrand(segment, 0.0) + 4.0 * integrand(segment, 0.5)
-        + integrand(segment, 1.0))
-        * 1.0
+    if t_end <= 0.0 {
+        return 0.0;
+    }
+
+    let whole = (integrand(segment, 0.0) + 4.0 * integrand(segment, t_end / 2.0)
+        + integrand(segment, t_end))
+        * t_end
         / 6.0;
-    simpson_adaptive(segment, 0.0, 1.0, tolerance, whole)
+    simpson_adaptive(segment, 0.0, t_end, tolerance, whole)
 }
 
 /// Find parameter t on segment for a given arc length from start using Newton-Raphson
 fn find_t_for_arc_length(segment: &SplineSegment, target_length: f64, tolerance: f64) -> f64 {
     let mut t = target_length / segment_arc_length(segment, tolerance * 0.1); // Initial guess
-    t = t.max(0.0).min(1.0);
+    t = t.clamp(0.0, 1.0);
 
     for _ in 0..20 {
         // Calculate current arc length from 0 to t
-        let current_segment = SplineSegment {
-            start: segment.start,
-            control1: segment.control1,
-            control2: segment.control2,
-            end: segment.evaluate(t),
-        };
-        let current_length = segment_arc_length(&current_segment, tolerance * 0.1);
+        let current_length = partial_arc_length(segment, t, tolerance * 0.1);
 
         let error = current_length - target_length;
         if error.abs() < tolerance {
@@ -69,7 +72,7 @@ fn find_t_for_arc_length(segment: &SplineSegment, target_length: f64, tolerance:
         }
 
         t -= error / speed;
-        t = t.max(0.0).min(1.0);
+        t = t.clamp(0.0, 1.0);
     }
 
     t
@@ -173,8 +176,8 @@ mod tests {
         assert_eq!(samples.len(), 11);
         
         // Check uniform spacing
-        for i in 0..samples.len() {
-            assert_relative_eq!(samples[i].y, i as f64, epsilon = 1e-3);
+        for (i, sample) in samples.iter().enumerate() {
+            assert_relative_eq!(sample.y, i as f64, epsilon = 1e-3);
         }
     }
 
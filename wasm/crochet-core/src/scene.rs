@@ -0,0 +1,222 @@
+use crochet_types::*;
+use std::f64::consts::PI;
+
+use crate::fidelity::achievable_radius;
+use crate::parser::produces;
+
+/// Every stitch placed on the finished piece, for a 3D preview
+///
+/// Work in the round is rebuilt as the surface of revolution the generator
+/// assumes: each round's radius follows from its stitch count at the
+/// configured gauge, and each round lies one row height further along the
+/// surface than the one before, starting from the centre of the ring. The
+/// normal tilts with the surface between neighbouring rounds. Flat work is
+/// laid out in the x-y plane, turned rows running back the other way.
+pub fn stitch_scene(pattern: &CrochetPattern, config: &AmigurumiConfig) -> StitchScene {
+    let row_height = 1.0 / config.yarn.gauge_rows_per_cm;
+    let stitch_width = 1.0 / config.yarn.gauge_stitches_per_cm;
+    let glyph = |row: &Row, stitch: &StitchInstruction, position, direction, normal| StitchGlyph {
+        row_number: row.row_number,
+        stitch_index: stitch.stitch_index,
+        stitch_type: stitch.stitch_type,
+        position,
+        direction,
+        normal,
+        width_cm: produces(stitch.stitch_type) as f64 * stitch_width,
+        height_cm: row_height,
+    };
+
+    let mut glyphs = Vec::new();
+    match pattern.construction {
+        Construction::Round => {
+            let profile = surface_profile(&pattern.rows, config);
+            for (idx, row) in pattern.rows.iter().enumerate() {
+                let (radius, height) = profile[idx + 1];
+                let (normal_r, normal_y) =
+                    profile_normal(profile[idx], profile[(idx + 2).min(profile.len() - 1)]);
+                let turn = working_turn(row);
+                for stitch in &row.pattern {
+                    let (sin, cos) = stitch.angular_position.sin_cos();
+                    glyphs.push(glyph(
+                        row,
+                        stitch,
+                        Point3D::new(radius * sin, height, radius * cos),
+                        Point3D::new(turn * cos, 0.0, -turn * sin),
+                        Point3D::new(normal_r * sin, normal_y, normal_r * cos),
+                    ));
+                }
+            }
+        }
+        Construction::Flat => {
+            for (idx, row) in pattern.rows.iter().enumerate() {
+                let y = (idx as f64 + 0.5) * row_height;
+                let row_width = row.total_stitches as f64 * stitch_width;
+                let turned = idx % 2 == 1;
+                let facing = if turned { -1.0 } else { 1.0 };
+                let mut made = 0;
+                for stitch in &row.pattern {
+                    let along =
+                        (made as f64 + produces(stitch.stitch_type) as f64 / 2.0) * stitch_width;
+                    made += produces(stitch.stitch_type);
+                    let x = if turned { row_width - along } else { along };
+                    glyphs.push(glyph(
+                        row,
+                        stitch,
+                        Point3D::new(x, y, 0.0),
+                        Point3D::new(facing, 0.0, 0.0),
+                        Point3D::new(0.0, 0.0, facing),
+                    ));
+                }
+            }
+        }
+    }
+
+    StitchScene {
+        construction: pattern.construction,
+        glyphs,
+    }
+}
+
+/// (radius, height) of the centre of the ring and then of every round
+fn surface_profile(rows: &[Row], config: &AmigurumiConfig) -> Vec<(f64, f64)> {
+    let row_height = 1.0 / config.yarn.gauge_rows_per_cm;
+    let mut profile = vec![(0.0, 0.0)];
+    for row in rows {
+        let (r0, y0) = profile[profile.len() - 1];
+        let r = achievable_radius(row.total_stitches, config);
+        // A round as wide as the last rises a full row; one that grows or
+        // shrinks by a row height or more lies flat
+        let rise = (row_height * row_height - (r - r0) * (r - r0))
+            .max(0.0)
+            .sqrt();
+        profile.push((r, y0 + rise));
+    }
+    profile
+}
+
+/// Outward unit normal, in (radius, height), of the surface running from
+/// `before` to `after`
+fn profile_normal(before: (f64, f64), after: (f64, f64)) -> (f64, f64) {
+    let (dr, dy) = (after.0 - before.0, after.1 - before.1);
+    let length = (dr * dr + dy * dy).sqrt();
+    if length < 1e-12 {
+        return (1.0, 0.0);
+    }
+    (dy / length, -dr / length)
+}
+
+/// 1.0 when a round's angles rise in working order, -1.0 when they fall
+fn working_turn(row: &Row) -> f64 {
+    match row.pattern.get(..2) {
+        Some([first, second]) => {
+            let delta = (second.angular_position - first.angular_position).rem_euclid(2.0 * PI);
+            if delta > PI {
+                -1.0
+            } else {
+                1.0
+            }
+        }
+        _ => 1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_pattern;
+    use crate::swatch::generate_gauge_swatch;
+
+    fn length(p: Point3D) -> f64 {
+        (p.x * p.x + p.y * p.y + p.z * p.z).sqrt()
+    }
+
+    fn dot(a: Point3D, b: Point3D) -> f64 {
+        a.x * b.x + a.y * b.y + a.z * b.z
+    }
+
+    fn create_ball() -> (CrochetPattern, AmigurumiConfig) {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(4.0, 0.0),
+                control2: Point2D::new(4.0, 6.0),
+                end: Point2D::new(0.0, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 0.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: 6.0,
+            ..Default::default()
+        };
+        (generate_pattern(&curve, &config).unwrap(), config)
+    }
+
+    #[test]
+    fn test_round_glyphs_sit_on_the_surface() {
+        let (pattern, config) = create_ball();
+        let scene = stitch_scene(&pattern, &config);
+
+        let stitches: usize = pattern.rows.iter().map(|r| r.pattern.len()).sum();
+        assert_eq!(scene.glyphs.len(), stitches);
+        for glyph in &scene.glyphs {
+            assert!((length(glyph.direction) - 1.0).abs() < 1e-9);
+            assert!((length(glyph.normal) - 1.0).abs() < 1e-9);
+            assert!(dot(glyph.direction, glyph.normal).abs() < 1e-9);
+            let row = &pattern.rows[glyph.row_number - 1];
+            let radius = glyph.position.x.hypot(glyph.position.z);
+            assert!((radius - achievable_radius(row.total_stitches, &config)).abs() < 1e-9);
+        }
+
+        // The bottom faces down, the widest round out and the top up
+        let first = &scene.glyphs[0];
+        assert!(first.normal.y < -0.5);
+        let widest = pattern
+            .rows
+            .iter()
+            .max_by_key(|r| r.total_stitches)
+            .unwrap()
+            .row_number;
+        let side = scene
+            .glyphs
+            .iter()
+            .find(|g| g.row_number == widest)
+            .unwrap();
+        assert!(side.normal.y.abs() < 0.5);
+        assert!(scene.glyphs.last().unwrap().normal.y > 0.5);
+        // Height only ever rises
+        for pair in scene.glyphs.windows(2) {
+            assert!(pair[1].position.y >= pair[0].position.y - 1e-9);
+        }
+
+        // Each stitch points on to the next one of its round
+        let g = &scene.glyphs[20];
+        let next = &scene.glyphs[21];
+        assert_eq!(g.row_number, next.row_number);
+        let step = Point3D::new(
+            next.position.x - g.position.x,
+            0.0,
+            next.position.z - g.position.z,
+        );
+        assert!(dot(g.direction, step) > 0.0);
+    }
+
+    #[test]
+    fn test_flat_rows_turn() {
+        let config = AmigurumiConfig::default();
+        let swatch = generate_gauge_swatch(&config.yarn).unwrap();
+
+        let scene = stitch_scene(&swatch, &config);
+
+        let row = |n: usize| -> Vec<&StitchGlyph> {
+            scene.glyphs.iter().filter(|g| g.row_number == n).collect()
+        };
+        let (one, two) = (row(1), row(2));
+        assert_eq!(one[0].direction, Point3D::new(1.0, 0.0, 0.0));
+        assert_eq!(two[0].direction, Point3D::new(-1.0, 0.0, 0.0));
+        // Row 2 starts above the end of row 1
+        assert!((two[0].position.x - one.last().unwrap().position.x).abs() < 1e-9);
+        assert!(two[0].position.y > one[0].position.y);
+        assert_eq!(two[0].normal.z, -1.0);
+    }
+}
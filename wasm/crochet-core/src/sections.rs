@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use crochet_types::*;
+
+use crate::grouping::group_rows;
+use crate::lineage::row_connections;
+
+/// Number rows 1, 2, 3, ... in their current order
+///
+/// Use after rows have been removed, reordered or merged in from another
+/// pattern. Anchors, sections and color changes follow their rows; anchors
+/// and color changes on rows that are gone are dropped, as are sections
+/// left with no rows. Row groups and
+/// the row and stitch totals are recomputed.
+pub fn renumber_rows(pattern: &mut CrochetPattern) {
+    let renumbered: HashMap<usize, usize> = pattern
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(idx, row)| (row.row_number, idx + 1))
+        .collect();
+
+    pattern.sections = pattern
+        .sections
+        .iter()
+        .filter_map(|section| {
+            let rows: Vec<usize> = pattern
+                .rows
+                .iter()
+                .filter(|r| section.contains(r.row_number))
+                .map(|r| renumbered[&r.row_number])
+                .collect();
+            Some(Section {
+                name: section.name.clone(),
+                first_row: *rows.iter().min()?,
+                last_row: *rows.iter().max()?,
+            })
+        })
+        .collect();
+    pattern.sections.sort_by_key(|s| s.first_row);
+
+    pattern.anchors = pattern
+        .anchors
+        .iter()
+        .filter_map(|anchor| {
+            Some(Anchor {
+                row_number: *renumbered.get(&anchor.row_number)?,
+                ..anchor.clone()
+            })
+        })
+        .collect();
+
+    pattern.color_changes = pattern
+        .color_changes
+        .iter()
+        .filter_map(|change| {
+            Some(ColorChange {
+                row_number: *renumbered.get(&change.row_number)?,
+                ..change.clone()
+            })
+        })
+        .collect();
+
+    for (idx, row) in pattern.rows.iter_mut().enumerate() {
+        row.row_number = idx + 1;
+    }
+    pattern.row_groups = group_rows(&pattern.rows);
+    pattern.connections = row_connections(&pattern.rows, pattern.construction);
+    pattern.metadata.total_rows = pattern.rows.len();
+    pattern.metadata.total_stitches = pattern.rows.iter().map(|r| r.total_stitches).sum();
+}
+
+/// Name rows `first_row..=last_row` as a section
+///
+/// Sections may not overlap; an existing section with the same name is
+/// replaced.
+pub fn add_section(
+    pattern: &mut CrochetPattern,
+    name: &str,
+    first_row: usize,
+    last_row: usize,
+) -> Result<()> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(PatternError::InvalidConfiguration(
+            "Section name must not be empty".to_string(),
+        ));
+    }
+    let has_row = |n| pattern.rows.iter().any(|r| r.row_number == n);
+    if first_row > last_row || !has_row(first_row) || !has_row(last_row) {
+        return Err(PatternError::InvalidConfiguration(format!(
+            "Pattern has no rows {}-{}",
+            first_row, last_row
+        )));
+    }
+
+    let section = Section {
+        name: name.to_string(),
+        first_row,
+        last_row,
+    };
+    if let Some(other) = pattern
+        .sections
+        .iter()
+        .find(|s| s.name != section.name && s.first_row <= last_row && first_row <= s.last_row)
+    {
+        return Err(PatternError::InvalidConfiguration(format!(
+            "Rows {}-{} overlap section \"{}\" (rows {}-{})",
+            first_row, last_row, other.name, other.first_row, other.last_row
+        )));
+    }
+
+    pattern.sections.retain(|s| s.name != section.name);
+    pattern.sections.push(section);
+    pattern.sections.sort_by_key(|s| s.first_row);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swatch::generate_gauge_swatch;
+
+    #[test]
+    fn test_renumber_after_removing_rows() {
+        let mut pattern = generate_gauge_swatch(&YarnSpec::default()).unwrap();
+        let total = pattern.rows.len();
+        add_section(&mut pattern, "Body", 3, total).unwrap();
+        pattern.anchors.push(Anchor {
+            name: "tail".to_string(),
+            row_number: 5,
+            stitch: 1,
+        });
+        pattern.rows.drain(1..3);
+
+        renumber_rows(&mut pattern);
+
+        let numbers: Vec<usize> = pattern.rows.iter().map(|r| r.row_number).collect();
+        assert_eq!(numbers, (1..=total - 2).collect::<Vec<_>>());
+        assert_eq!(pattern.sections[0].first_row, 2);
+        assert_eq!(pattern.sections[0].last_row, total - 2);
+        assert_eq!(pattern.anchors[0].row_number, 3);
+        assert_eq!(pattern.metadata.total_rows, total - 2);
+        assert_eq!(
+            pattern.row_groups.last().unwrap().last_row,
+            pattern.rows.len()
+        );
+    }
+
+    #[test]
+    fn test_sections_must_not_overlap() {
+        let mut pattern = generate_gauge_swatch(&YarnSpec::default()).unwrap();
+        add_section(&mut pattern, "Base", 1, 4).unwrap();
+
+        assert!(add_section(&mut pattern, "Top", 4, 6).is_err());
+        assert!(add_section(&mut pattern, "Top", 5, 500).is_err());
+        assert!(add_section(&mut pattern, " ", 5, 6).is_err());
+
+        add_section(&mut pattern, "Top", 5, 6).unwrap();
+        add_section(&mut pattern, "Base", 1, 2).unwrap();
+        let names: Vec<&str> = pattern.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Base", "Top"]);
+    }
+}
@@ -0,0 +1,234 @@
+use crochet_types::*;
+
+use crate::formatter::{escape_html, PatternFormatter};
+use crate::markers::marker_instruction;
+
+/// Rows between spoken checkpoints
+const CHECKPOINT_EVERY: usize = 5;
+const ROW_PAUSE: &str = "2s";
+const CHECKPOINT_PAUSE: &str = "5s";
+
+/// Render a pattern as an SSML script for hands-free crocheting
+///
+/// Every row is read out on its own, even where the written pattern
+/// merges identical rows into a range, with abbreviations spoken in full
+/// and the stitch count to check at the end. Section starts are announced,
+/// and every few rows (and at the end of each section) a checkpoint asks
+/// the crocheter to count and leaves a longer pause.
+pub fn export_ssml(pattern: &CrochetPattern, formatter: &PatternFormatter) -> String {
+    let terminology = formatter.terminology;
+    let lang = match terminology {
+        Terminology::US => "en-US",
+        Terminology::UK => "en-GB",
+    };
+    let row_word = match pattern.construction {
+        Construction::Round => "Round",
+        Construction::Flat => "Row",
+    };
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<speak version=\"1.1\" xmlns=\"http://www.w3.org/2001/10/synthesis\" xml:lang=\"{}\">\n",
+        lang
+    ));
+    out.push_str(&format!(
+        "<p><s>Crochet amigurumi pattern.</s><s>{} {}s, {} stitches in total.</s></p>\n",
+        pattern.rows.len(),
+        row_word.to_lowercase(),
+        pattern.metadata.total_stitches
+    ));
+    if !pattern.notes.is_empty() {
+        out.push_str("<p><s>Before you start.</s>");
+        for note in &pattern.notes {
+            out.push_str(&format!("<s>{}</s>", escape_html(note)));
+        }
+        out.push_str(&format!("<break time=\"{}\"/></p>\n", ROW_PAUSE));
+    }
+
+    for row in &pattern.rows {
+        let section = pattern.sections.iter().find(|s| s.contains(row.row_number));
+        if let Some(section) = section.filter(|s| s.first_row == row.row_number) {
+            out.push_str(&format!(
+                "<p><s>Section: {}.</s></p>\n",
+                escape_html(&section.name)
+            ));
+        }
+
+        out.push_str(&format!(
+            "<p><s>{} {}.</s><s>{}</s><s>You should have {} stitches.</s>",
+            row_word,
+            row.row_number,
+            spoken_row(pattern, row, terminology),
+            row.total_stitches
+        ));
+        for marker in &row.markers {
+            out.push_str(&format!("<s>{}</s>", marker_instruction(marker)));
+        }
+
+        let section_ends = section.is_some_and(|s| s.last_row == row.row_number);
+        let last = pattern
+            .rows
+            .last()
+            .is_some_and(|r| r.row_number == row.row_number);
+        if !last && (section_ends || row.row_number % CHECKPOINT_EVERY == 0) {
+            out.push_str(&format!(
+                "<s>Checkpoint. Count your stitches before going on.</s><break time=\"{}\"/>",
+                CHECKPOINT_PAUSE
+            ));
+        } else {
+            out.push_str(&format!("<break time=\"{}\"/>", ROW_PAUSE));
+        }
+        out.push_str("</p>\n");
+    }
+
+    out.push_str("<p><s>That is the last row. Fasten off.</s></p>\n</speak>\n");
+    out
+}
+
+/// One row's instruction as a spoken sentence
+fn spoken_row(pattern: &CrochetPattern, row: &Row, terminology: Terminology) -> String {
+    let sc = spoken_stitch(StitchType::SC.abbreviation(terminology), terminology);
+    if row.row_number == 1 && pattern.foundation == Foundation::ChainRing {
+        return format!(
+            "Chain {n} and join them into a ring with a slip stitch, then work {n} {} into the chains.",
+            sc,
+            n = row.total_stitches
+        );
+    }
+    if row.row_number == 1 && pattern.construction == Construction::Round {
+        return format!(
+            "Make a magic ring and work {} {} into it.",
+            row.total_stitches, sc
+        );
+    }
+    if row.row_number == 1 && pattern.foundation == Foundation::SingleCrochet {
+        return format!("Work {} foundation {}.", row.total_stitches, sc);
+    }
+    if row.pattern.iter().all(|s| s.stitch_type == StitchType::SC) {
+        let direction = match pattern.construction {
+            Construction::Round => "around",
+            Construction::Flat => "across",
+        };
+        return format!("{} in each stitch {}.", capitalize(&sc), direction);
+    }
+    capitalize(&spoken_stitches(
+        &row.pattern_string_with(terminology),
+        terminology,
+    ))
+}
+
+/// Speak a written stitch sequence such as "SC, (2 SC, INC) x 5, SC"
+fn spoken_stitches(written: &str, terminology: Terminology) -> String {
+    let (Some(open), Some(close)) = (written.find('('), written.find(") x ")) else {
+        return format!("{}.", spoken_runs(written, terminology));
+    };
+
+    let prefix = written[..open].trim_end_matches([',', ' ']);
+    let unit = &written[open + 1..close];
+    let after = &written[close + 4..];
+    let (times, suffix) = after.split_once(", ").unwrap_or((after, ""));
+
+    let mut sentence = String::new();
+    if !prefix.is_empty() {
+        sentence.push_str(&format!("{}, then ", spoken_runs(prefix, terminology)));
+    }
+    sentence.push_str(&format!(
+        "{}; do that {} times",
+        spoken_runs(unit, terminology),
+        times
+    ));
+    if !suffix.is_empty() {
+        sentence.push_str(&format!(", then {}", spoken_runs(suffix, terminology)));
+    }
+    sentence.push('.');
+    sentence
+}
+
+/// "3 SC, INC" as "3 single crochet, increase"
+fn spoken_runs(runs: &str, terminology: Terminology) -> String {
+    runs.split(", ")
+        .map(|run| match run.split_once(' ') {
+            Some((count, abbr)) => format!("{} {}", count, spoken_stitch(abbr, terminology)),
+            None => spoken_stitch(run, terminology),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn spoken_stitch(abbreviation: &str, terminology: Terminology) -> String {
+    match (abbreviation, terminology) {
+        ("SC", _) => "single crochet",
+        ("DC", Terminology::UK) => "double crochet",
+        ("INC", _) => "increase",
+        ("DEC", _) => "decrease",
+        ("INVDEC", _) => "invisible decrease",
+        _ => abbreviation,
+    }
+    .to_string()
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_pattern;
+    use crate::sections::add_section;
+
+    fn create_test_pattern() -> CrochetPattern {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, 1.0),
+                control2: Point2D::new(3.0, 5.0),
+                end: Point2D::new(1.0, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 1.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: 6.0,
+            ..Default::default()
+        };
+        generate_pattern(&curve, &config).unwrap()
+    }
+
+    #[test]
+    fn test_every_row_is_narrated() {
+        let mut pattern = create_test_pattern();
+        add_section(&mut pattern, "Crown", 3, 4).unwrap();
+
+        let ssml = export_ssml(&pattern, &PatternFormatter::default());
+
+        assert!(ssml.contains("xml:lang=\"en-US\""));
+        assert!(ssml.contains(
+            "<s>Round 1.</s><s>Make a magic ring and work 6 single crochet into it.</s>"
+        ));
+        for row in &pattern.rows {
+            assert!(ssml.contains(&format!("<s>Round {}.</s>", row.row_number)));
+            assert!(ssml.contains(&format!("You should have {} stitches.", row.total_stitches)));
+        }
+        assert!(ssml.contains("<s>Section: Crown.</s>"));
+        assert!(ssml.contains("Checkpoint"));
+        assert!(!ssml.contains(" SC"));
+        assert!(!ssml.contains("INC"));
+    }
+
+    #[test]
+    fn test_repeats_are_spoken() {
+        assert_eq!(
+            spoken_stitches("SC, (2 SC, INC) x 5, SC", Terminology::US),
+            "single crochet, then 2 single crochet, increase; do that 5 times, then single crochet."
+        );
+        assert_eq!(
+            spoken_stitches("3 DC, INVDEC", Terminology::UK),
+            "3 double crochet, invisible decrease."
+        );
+    }
+}
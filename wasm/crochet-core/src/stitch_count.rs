@@ -3,49 +3,90 @@ use std::f64::consts::PI;
 
 /// Calculate stitch count for each row based on radii
 pub fn calculate_stitch_counts(radii: &[f64], config: &AmigurumiConfig) -> Vec<usize> {
+    calculate_stitch_counts_with_warnings(radii, config).0
+}
+
+/// Calculate stitch counts and report rows where the delta cap overrides the curve
+pub fn calculate_stitch_counts_with_warnings(
+    radii: &[f64],
+    config: &AmigurumiConfig,
+) -> (Vec<usize>, Vec<String>) {
     if radii.is_empty() {
-        return vec![];
+        return (vec![], vec![]);
     }
 
+    let min_round = config.min_round_stitches;
+
     // Convert each radius to ideal stitch count
     let ideal_counts: Vec<usize> = radii.iter().enumerate().map(|(i, &radius)| {
         if i == 0 {
-            // Magic ring: standard 6 SC (not calculated from circumference!)
-            return 6;
+            // Magic ring: configured count, 6 SC by default (not calculated from circumference!)
+            return min_round;
         }
         
         let r = radius.max(0.1);
         let circumference = 2.0 * PI * r;
         let stitches = (circumference * config.yarn.gauge_stitches_per_cm).round() as usize;
-        stitches.max(6)
+        stitches.max(min_round)
     }).collect();
     
     // Apply physical constraints: can't increase/decrease too fast
     let mut actual_counts = Vec::with_capacity(ideal_counts.len());
-    actual_counts.push(ideal_counts[0]); // Magic ring: 6 SC
+    let mut warnings = Vec::new();
+    if min_round < 6 {
+        warnings.push(format!(
+            "Rounds as small as {} stitches are tight to work and leave little room to stuff; use a smaller hook if they pucker",
+            min_round
+        ));
+    }
+    actual_counts.push(ideal_counts[0]); // Magic ring
     
     for i in 1..ideal_counts.len() {
         let prev = actual_counts[i - 1];
         let ideal = ideal_counts[i];
         
         // Physical limit: INC can double at most, INVDEC can halve at most
-        let max_increase = prev; // Can double (all INC)
-        let max_decrease = prev / 2; // Can halve (all INVDEC)
+        let max_increase = max_delta(prev, prev, config); // Can double (all INC)
+        let max_decrease = max_delta(prev, prev / 2, config); // Can halve (all INVDEC)
         
         let actual = if ideal > prev {
-            // Increasing: cap at doubling
+            // Increasing: cap at configured rate
             ideal.min(prev + max_increase)
         } else if ideal < prev {
-            // Decreasing: cap at halving
+            // Decreasing: cap at configured rate
             ideal.max(prev.saturating_sub(max_decrease))
         } else {
             ideal
         };
+        let actual = actual.max(min_round);
+
+        if actual != ideal {
+            warnings.push(format!(
+                "Row {}: curve needs {} stitches but the increase/decrease cap allows {}",
+                i + 1,
+                ideal,
+                actual
+            ));
+        }
         
-        actual_counts.push(actual.max(6));
+        actual_counts.push(actual);
     }
     
-    actual_counts
+    (actual_counts, warnings)
+}
+
+/// Largest allowed stitch change from a row of `prev` stitches
+///
+/// `physical_limit` is what the stitches themselves allow (doubling or halving);
+/// the configured fraction and absolute cap can only tighten it.
+pub(crate) fn max_delta(prev: usize, physical_limit: usize, config: &AmigurumiConfig) -> usize {
+    let by_fraction = (prev as f64 * config.max_stitch_delta_fraction).floor() as usize;
+    let mut limit = physical_limit.min(by_fraction);
+    if let Some(cap) = config.max_stitch_delta {
+        limit = limit.min(cap);
+    }
+    // Always allow some change so the shape can eventually follow the curve
+    limit.max(1)
 }
 
 #[cfg(test)]
@@ -63,15 +104,17 @@ mod tests {
                 gauge_rows_per_cm: 3.0,
                 recommended_hook_size_mm: 3.5,
             },
+            ..Default::default()
         };
 
         let counts = calculate_stitch_counts(&radii, &config);
         assert_eq!(counts.len(), 10);
 
-        // Should have approximately the same count for all rows
-        let first = counts[0];
-        for &count in &counts {
-            assert!((count as i32 - first as i32).abs() <= 1);
+        // Magic ring starts at 6 and can at most double per row; once the
+        // target circumference is reached the count should hold steady
+        let last = counts[counts.len() - 1];
+        for &count in &counts[4..] {
+            assert!((count as i32 - last as i32).abs() <= 1);
         }
     }
 
@@ -85,6 +128,7 @@ mod tests {
                 gauge_rows_per_cm: 3.0,
                 recommended_hook_size_mm: 3.5,
             },
+            ..Default::default()
         };
 
         let counts = calculate_stitch_counts(&radii, &config);
@@ -106,6 +150,7 @@ mod tests {
                 gauge_rows_per_cm: 3.0,
                 recommended_hook_size_mm: 3.5,
             },
+            ..Default::default()
         };
 
         let counts = calculate_stitch_counts(&radii, &config);
@@ -119,7 +164,8 @@ mod tests {
     #[test]
     fn test_follows_curve_exactly() {
         // Pattern should follow curve exactly
-        let radii = vec![2.0, 10.0, 2.0]; // Expansion then contraction
+        // Expansion then contraction (extra rows let the magic ring grow)
+        let radii = vec![2.0, 10.0, 10.0, 10.0, 10.0, 10.0, 2.0];
         let config = AmigurumiConfig {
             total_height_cm: 3.0,
             yarn: YarnSpec {
@@ -127,12 +173,45 @@ mod tests {
                 gauge_rows_per_cm: 3.0,
                 recommended_hook_size_mm: 3.5,
             },
+            ..Default::default()
         };
 
         let counts = calculate_stitch_counts(&radii, &config);
         
         // Should follow the radii pattern
         assert!(counts[0] < counts[1]); // Increases
-        assert!(counts[2] < counts[1]); // Decreases
+        assert!(counts[6] < counts[5]); // Decreases
+    }
+
+    #[test]
+    fn test_delta_fraction_limits_growth() {
+        let radii = vec![5.0; 6];
+        let config = AmigurumiConfig {
+            max_stitch_delta_fraction: 0.5,
+            ..Default::default()
+        };
+
+        let counts = calculate_stitch_counts(&radii, &config);
+
+        // 6 -> 9 -> 13 -> 19 -> 28 -> 42 (each row grows by at most half)
+        for i in 1..counts.len() {
+            assert!(counts[i] <= counts[i - 1] + counts[i - 1] / 2);
+        }
+    }
+
+    #[test]
+    fn test_absolute_cap_and_warnings() {
+        let radii = vec![2.0, 2.0, 2.0];
+        let config = AmigurumiConfig {
+            max_stitch_delta: Some(4),
+            ..Default::default()
+        };
+
+        let (counts, warnings) = calculate_stitch_counts_with_warnings(&radii, &config);
+
+        assert_eq!(counts, vec![6, 10, 14]);
+        // Both capped rows fall short of the 38 stitches the curve asks for
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("Row 2"));
     }
 }
@@ -0,0 +1,323 @@
+use crochet_types::*;
+
+use crate::grouping::group_rows;
+use crate::lineage::row_connections;
+use crate::markers::recount_markers;
+use crate::parser::{consumes, produces};
+
+/// Color the rows in repeating stripes, replacing any earlier color changes
+///
+/// With `jogless` set on a piece worked in the round, every change after
+/// the first row moves the start of the round one stitch on, and each
+/// later round starts where the one below it now does. The rounds'
+/// instructions and stitch numbers are turned to begin at the new start
+/// (the stitches themselves stay where they were), and anchors on them
+/// are renumbered to match, so each must name a stitch its row has. The
+/// yarn and time estimates in the metadata are left as they were;
+/// `estimate_yarn_usage` splits the yarn by color and
+/// `estimate_row_minutes` adds the time the changes take.
+pub fn add_stripes(pattern: &mut CrochetPattern, stripes: &StripeConfig) -> Result<()> {
+    if stripes.colors.is_empty() || stripes.colors.iter().any(|c| c.trim().is_empty()) {
+        return Err(PatternError::InvalidConfiguration(
+            "Stripes need at least one named color".to_string(),
+        ));
+    }
+    if stripes.rows_per_stripe == 0 {
+        return Err(PatternError::InvalidConfiguration(
+            "Stripes must be at least one row tall".to_string(),
+        ));
+    }
+    if stripes.jogless.is_some() && pattern.construction != Construction::Round {
+        return Err(PatternError::InvalidConfiguration(
+            "Only work in the round has a jog to hide".to_string(),
+        ));
+    }
+    if stripes.jogless.is_some() {
+        // Anchors are renumbered from the new round starts, so each must
+        // name a stitch its row has
+        for anchor in &pattern.anchors {
+            let Some(row) = pattern
+                .rows
+                .iter()
+                .find(|r| r.row_number == anchor.row_number)
+            else {
+                continue;
+            };
+            if anchor.stitch == 0 || anchor.stitch > row.total_stitches {
+                return Err(PatternError::InvalidConfiguration(format!(
+                    "Anchor {} is on stitch {} of row {}, which has {} stitches",
+                    anchor.name, anchor.stitch, anchor.row_number, row.total_stitches
+                )));
+            }
+        }
+    }
+
+    let mut changes: Vec<ColorChange> = Vec::new();
+    for (idx, row) in pattern.rows.iter().enumerate() {
+        let color = &stripes.colors[idx / stripes.rows_per_stripe % stripes.colors.len()];
+        if changes.last().is_some_and(|c| &c.color == color) {
+            continue;
+        }
+        changes.push(ColorChange {
+            row_number: row.row_number,
+            color: color.trim().to_string(),
+            jog: if idx == 0 { None } else { stripes.jogless },
+            start_shift: 0,
+        });
+    }
+
+    if stripes.jogless.is_some() {
+        // Stitch of the row below, in its original numbering, that the
+        // round now starts from
+        let mut offset = 0;
+        for idx in 1..pattern.rows.len() {
+            let below = pattern.rows[idx - 1].total_stitches.max(1);
+            let row_number = pattern.rows[idx].row_number;
+            let change = changes
+                .iter_mut()
+                .find(|c| c.row_number == row_number && c.jog.is_some());
+            let target = offset + usize::from(change.is_some());
+
+            let (turn, consumed, produced) = start_at(&pattern.rows[idx], target % below);
+            if let Some(change) = change {
+                change.start_shift = (consumed + below - offset % below) % below;
+            }
+            turn_start(pattern, idx, turn, produced);
+            offset = produced;
+        }
+        pattern.row_groups = group_rows(&pattern.rows);
+        pattern.connections = row_connections(&pattern.rows, pattern.construction);
+    }
+
+    pattern.color_changes = changes;
+    Ok(())
+}
+
+/// Fewest instructions to skip so a round starts at or after stitch
+/// `stitch` of the row below, with the stitches they consume and produce
+fn start_at(row: &Row, stitch: usize) -> (usize, usize, usize) {
+    let mut consumed = 0;
+    let mut produced = 0;
+    for (turn, instruction) in row.pattern.iter().enumerate() {
+        if consumed >= stitch {
+            return (turn, consumed, produced);
+        }
+        consumed += consumes(instruction.stitch_type);
+        produced += produces(instruction.stitch_type);
+    }
+    (0, 0, 0)
+}
+
+/// Start row `idx` `turn` instructions (`produced` stitches) later
+fn turn_start(pattern: &mut CrochetPattern, idx: usize, turn: usize, produced: usize) {
+    let row = &mut pattern.rows[idx];
+    if turn == 0 {
+        return;
+    }
+    row.pattern.rotate_left(turn);
+    for (i, instruction) in row.pattern.iter_mut().enumerate() {
+        instruction.stitch_index = i;
+    }
+    recount_markers(row);
+    let (row_number, total) = (row.row_number, row.total_stitches.max(1));
+    for anchor in pattern
+        .anchors
+        .iter_mut()
+        .filter(|a| a.row_number == row_number)
+    {
+        anchor.stitch = (anchor.stitch - 1 + total - produced % total) % total + 1;
+    }
+}
+
+/// Instruction to work before a row's stitches when it starts a new color
+pub fn color_change_instruction(
+    change: &ColorChange,
+    terminology: Terminology,
+    first_row: bool,
+) -> String {
+    if first_row {
+        return format!("With {},", change.color);
+    }
+    let sc = StitchType::SC.abbreviation(terminology);
+    match change.jog {
+        None => format!(
+            "Change to {} in the last st of the round before,",
+            change.color
+        ),
+        Some(JogMethod::SlipStitch) => format!(
+            "Change to {} in the last st of the round before, sl st in the next {} (not counted), move the marker to the st after it,",
+            change.color,
+            stitches(change.start_shift)
+        ),
+        Some(JogMethod::StandingStitch) => format!(
+            "Fasten off, skip {} and join {} with a standing {} in the next st, place the marker in it,",
+            stitches(change.start_shift),
+            change.color,
+            sc
+        ),
+    }
+}
+
+fn stitches(count: usize) -> String {
+    if count == 1 {
+        "st".to_string()
+    } else {
+        format!("{} sts", count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatter::PatternFormatter;
+    use crate::generator::generate_pattern;
+
+    fn create_pattern() -> CrochetPattern {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(4.0, 1.0),
+                control2: Point2D::new(4.0, 6.0),
+                end: Point2D::new(1.0, 7.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 1.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: 7.0,
+            ..Default::default()
+        };
+        generate_pattern(&curve, &config).unwrap()
+    }
+
+    fn stripes(jogless: Option<JogMethod>) -> StripeConfig {
+        StripeConfig {
+            colors: vec!["Cream".to_string(), "Honey".to_string()],
+            rows_per_stripe: 3,
+            jogless,
+        }
+    }
+
+    #[test]
+    fn test_plain_stripes_change_every_few_rows() {
+        let mut pattern = create_pattern();
+        let before = pattern.clone();
+
+        add_stripes(&mut pattern, &stripes(None)).unwrap();
+
+        let rows: Vec<usize> = pattern.color_changes.iter().map(|c| c.row_number).collect();
+        assert_eq!(&rows[..3], &[1, 4, 7]);
+        assert_eq!(pattern.color_changes[1].color, "Honey");
+        assert!(pattern.color_changes.iter().all(|c| c.start_shift == 0));
+        for (a, b) in pattern.rows.iter().zip(&before.rows) {
+            assert_eq!(a.pattern_string(), b.pattern_string());
+        }
+
+        let lines = PatternFormatter::new(Terminology::US).instruction_lines(&pattern);
+        assert!(lines[0].1.starts_with("With Cream, 6 SC in magic ring"));
+        let row_4 = lines
+            .iter()
+            .find(|(label, _)| label.starts_with("Row 4"))
+            .unwrap();
+        assert!(row_4
+            .1
+            .starts_with("Change to Honey in the last st of the round before, "));
+    }
+
+    #[test]
+    fn test_jogless_stripes_move_the_round_start() {
+        let mut pattern = create_pattern();
+        pattern.anchors.push(Anchor {
+            name: "eye".to_string(),
+            row_number: 8,
+            stitch: 5,
+        });
+        let before = pattern.clone();
+
+        add_stripes(&mut pattern, &stripes(Some(JogMethod::SlipStitch))).unwrap();
+
+        assert_eq!(pattern.color_changes[0].jog, None);
+        let change = &pattern.color_changes[1];
+        assert_eq!(change.jog, Some(JogMethod::SlipStitch));
+        assert!(change.start_shift >= 1);
+        // The first round of the new color starts later, with the same stitches
+        let row = &pattern.rows[3];
+        let old = &before.rows[3];
+        assert_ne!(
+            row.pattern[0].angular_position,
+            old.pattern[0].angular_position
+        );
+        assert!(row
+            .pattern
+            .iter()
+            .enumerate()
+            .all(|(i, s)| s.stitch_index == i));
+        for (a, b) in pattern.rows.iter().zip(&before.rows) {
+            assert_eq!(a.total_stitches, b.total_stitches);
+            let stitches = |row: &Row| {
+                let mut stitches: Vec<_> = row
+                    .pattern
+                    .iter()
+                    .map(|s| (s.angular_position.to_bits(), s.stitch_type.to_string()))
+                    .collect();
+                stitches.sort();
+                stitches
+            };
+            assert_eq!(stitches(a), stitches(b));
+        }
+        // The anchor names the same stitch counted from the new start
+        let anchor = &pattern.anchors[0];
+        let position = |row: &Row, stitch: usize| {
+            let mut produced = 0;
+            row.pattern
+                .iter()
+                .find(|s| {
+                    produced += produces(s.stitch_type);
+                    produced >= stitch
+                })
+                .map(|s| s.angular_position)
+        };
+        assert_ne!(anchor.stitch, 5);
+        assert_eq!(
+            position(&pattern.rows[7], anchor.stitch),
+            position(&before.rows[7], 5)
+        );
+
+        let text = color_change_instruction(change, Terminology::US, false);
+        assert!(text
+            .starts_with("Change to Honey in the last st of the round before, sl st in the next "));
+    }
+
+    #[test]
+    fn test_invalid_stripes() {
+        let mut pattern = create_pattern();
+        let mut config = stripes(None);
+        config.rows_per_stripe = 0;
+        assert!(add_stripes(&mut pattern, &config).is_err());
+        assert!(add_stripes(
+            &mut pattern,
+            &StripeConfig {
+                colors: vec![],
+                ..stripes(None)
+            }
+        )
+        .is_err());
+
+        // Anchors outside their row can't be renumbered
+        for stitch in [0, pattern.rows[7].total_stitches + 1] {
+            let mut anchored = pattern.clone();
+            anchored.anchors.push(Anchor {
+                name: "eye".to_string(),
+                row_number: 8,
+                stitch,
+            });
+            assert!(add_stripes(&mut anchored, &stripes(Some(JogMethod::SlipStitch))).is_err());
+            assert!(anchored.color_changes.is_empty());
+            assert!(add_stripes(&mut anchored, &stripes(None)).is_ok());
+        }
+
+        pattern.construction = Construction::Flat;
+        assert!(add_stripes(&mut pattern, &stripes(Some(JogMethod::StandingStitch))).is_err());
+        assert!(add_stripes(&mut pattern, &stripes(None)).is_ok());
+    }
+}
@@ -0,0 +1,184 @@
+use crochet_types::*;
+use std::f64::consts::PI;
+
+use crate::generator::calculate_metadata;
+use crate::grouping::group_rows;
+use crate::lineage::row_connections;
+
+/// Side length of the area measured on the finished swatch
+const MEASURE_CM: f64 = 10.0;
+
+/// Side length of the swatch itself; the extra margin keeps edge
+/// stitches (which are always a little uneven) out of the measurement
+const SWATCH_CM: f64 = 12.0;
+
+/// Generate a flat single-crochet gauge swatch for the given yarn
+///
+/// The swatch is worked slightly larger than the 10 × 10 cm measuring
+/// square. Setup and measuring instructions are returned in `notes`.
+pub fn generate_gauge_swatch(yarn: &YarnSpec) -> Result<CrochetPattern> {
+    generate_gauge_swatch_with_foundation(yarn, Foundation::Chain)
+}
+
+/// Gauge swatch started with a chain or with foundation single crochet
+///
+/// Foundation single crochet makes row 1 in one pass, so there is no
+/// separate chain to count and the bottom edge stretches like the rows
+/// above it. Gauges must be finite and positive, and the swatch must fit
+/// the default `InputLimits`.
+pub fn generate_gauge_swatch_with_foundation(
+    yarn: &YarnSpec,
+    foundation: Foundation,
+) -> Result<CrochetPattern> {
+    let gauges = [yarn.gauge_stitches_per_cm, yarn.gauge_rows_per_cm];
+    if !gauges.iter().all(|g| g.is_finite() && *g > 0.0) {
+        return Err(PatternError::InvalidConfiguration(
+            "Gauge must be positive".to_string(),
+        ));
+    }
+    // A swatch is flat, so a chain ring is just a chain
+    let foundation = match foundation {
+        Foundation::ChainRing => Foundation::Chain,
+        other => other,
+    };
+
+    let stitches = (SWATCH_CM * yarn.gauge_stitches_per_cm).ceil() as usize;
+    let num_rows = (SWATCH_CM * yarn.gauge_rows_per_cm).ceil() as usize;
+    let stitches = stitches.max(1);
+    let num_rows = num_rows.max(1);
+    let limits = InputLimits::default();
+    if num_rows > limits.max_rows || stitches > limits.max_stitches_per_row {
+        return Err(PatternError::InvalidConfiguration(format!(
+            "Swatch would need {} rows of {} stitches, limits are {} and {}",
+            num_rows, stitches, limits.max_rows, limits.max_stitches_per_row
+        )));
+    }
+
+    // Every row is worked into the one below (row 1 into the foundation
+    // chain, or itself the foundation when started with fsc)
+    let rows: Vec<Row> = (0..num_rows)
+        .map(|row_idx| Row {
+            row_number: row_idx + 1,
+            total_stitches: stitches,
+            pattern: (0..stitches)
+                .map(|i| StitchInstruction {
+                    stitch_type: StitchType::SC,
+                    angular_position: 2.0 * PI * i as f64 / stitches as f64,
+                    stitch_index: i,
+                })
+                .collect(),
+            markers: Vec::new(),
+        })
+        .collect();
+
+    let expected_stitches = MEASURE_CM * yarn.gauge_stitches_per_cm;
+    let expected_rows = MEASURE_CM * yarn.gauge_rows_per_cm;
+
+    let start = match foundation {
+        Foundation::Chain | Foundation::ChainRing => vec![
+            format!(
+                "Using a {:.1} mm hook, chain {} (turning chain included).",
+                yarn.recommended_hook_size_mm,
+                stitches + 1
+            ),
+            format!(
+                "Row 1: sc in 2nd ch from hook and in each ch across ({} sts).",
+                stitches
+            ),
+        ],
+        Foundation::SingleCrochet => vec![
+            format!(
+                "Using a {:.1} mm hook, chain 2 and work the first fsc into the 2nd ch from hook.",
+                yarn.recommended_hook_size_mm
+            ),
+            format!("Row 1: {} fsc ({} sts).", stitches, stitches),
+        ],
+    };
+
+    let mut notes = start;
+    notes.extend([
+        format!(
+            "Rows 2-{}: ch 1, turn, sc in each st across ({} sts).",
+            num_rows, stitches
+        ),
+        "Fasten off and lay the swatch flat without stretching it.".to_string(),
+        format!(
+            "Measure a {:.0} cm square in the middle: you should count {:.1} stitches across and {:.1} rows up.",
+            MEASURE_CM, expected_stitches, expected_rows
+        ),
+        "More stitches than expected: try a larger hook. Fewer: try a smaller hook.".to_string(),
+    ]);
+
+    let config = AmigurumiConfig {
+        total_height_cm: SWATCH_CM,
+        yarn: yarn.clone(),
+        ..Default::default()
+    };
+    let mut metadata = calculate_metadata(&rows, &config, Construction::Flat);
+    metadata.design_size = Dimensions {
+        height_cm: SWATCH_CM,
+        width_cm: SWATCH_CM,
+    };
+
+    Ok(CrochetPattern {
+        row_groups: group_rows(&rows),
+        connections: row_connections(&rows, Construction::Flat),
+        rows,
+        metadata,
+        construction: Construction::Flat,
+        foundation,
+        notes,
+        anchors: vec![],
+        sections: vec![],
+        color_changes: vec![],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swatch_dimensions_follow_gauge() {
+        let yarn = YarnSpec::default();
+        let pattern = generate_gauge_swatch(&yarn).unwrap();
+
+        // 12 cm at 3 st/cm and 3 rows/cm
+        assert_eq!(pattern.rows.len(), 36);
+        assert_eq!(pattern.construction, Construction::Flat);
+        for row in &pattern.rows {
+            assert_eq!(row.total_stitches, 36);
+            assert_eq!(row.pattern_string(), "36 SC");
+        }
+        assert!(!pattern.notes.is_empty());
+    }
+
+    #[test]
+    fn test_fsc_swatch_has_no_chain() {
+        let yarn = YarnSpec::default();
+        let chain = generate_gauge_swatch(&yarn).unwrap();
+        let fsc = generate_gauge_swatch_with_foundation(&yarn, Foundation::SingleCrochet).unwrap();
+
+        assert_eq!(fsc.foundation, Foundation::SingleCrochet);
+        assert_eq!(fsc.rows.len(), chain.rows.len());
+        assert!(fsc.notes.iter().any(|n| n == "Row 1: 36 fsc (36 sts)."));
+        assert!(!fsc.notes.iter().any(|n| n.contains("chain 37")));
+    }
+
+    #[test]
+    fn test_swatch_rejects_invalid_gauge() {
+        let yarn = YarnSpec {
+            gauge_stitches_per_cm: 0.0,
+            ..Default::default()
+        };
+        assert!(generate_gauge_swatch(&yarn).is_err());
+
+        for gauge in [f64::NAN, f64::INFINITY, 1e308, 1000.0] {
+            let yarn = YarnSpec {
+                gauge_rows_per_cm: gauge,
+                ..Default::default()
+            };
+            assert!(generate_gauge_swatch(&yarn).is_err(), "{}", gauge);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use crochet_types::*;
+
+/// Floats in a tapestry chart longer than `max_float` stitches
+///
+/// A yarn is carried from each stitch worked in it to the next, so the
+/// float is the run of other colors in between. A flat row's yarns are
+/// only carried between their first and last stitch in the row; in the
+/// round they travel on up the spiral, so a float can start near the end
+/// of one round and finish in the next. Floats up to twice the limit can
+/// be caught once halfway; longer ones want a bobbin of their own.
+pub fn find_long_floats(chart: &TapestryChart, max_float: usize) -> Result<Vec<LongFloat>> {
+    if max_float == 0 {
+        return Err(PatternError::InvalidConfiguration(
+            "Longest allowed float must be at least one stitch".to_string(),
+        ));
+    }
+    for row in &chart.rows {
+        if row
+            .runs
+            .iter()
+            .any(|run| run.stitches == 0 || run.color.trim().is_empty())
+        {
+            return Err(PatternError::InvalidConfiguration(format!(
+                "Row {}: every color run needs a color and at least one stitch",
+                row.row_number
+            )));
+        }
+    }
+
+    let streams: Vec<Vec<&TapestryRow>> = match chart.construction {
+        Construction::Flat => chart.rows.iter().map(|row| vec![row]).collect(),
+        Construction::Round => vec![chart.rows.iter().collect()],
+    };
+
+    let mut floats = Vec::new();
+    for rows in streams {
+        // (position in working order, row number, stitch, run) of the
+        // first stitch of every run; runs are walked whole since a run can
+        // be any number of stitches
+        let mut runs: Vec<(usize, usize, usize, &ColorRun)> = Vec::new();
+        let mut position = 0usize;
+        for row in &rows {
+            let mut stitch = 1usize;
+            for run in &row.runs {
+                runs.push((position, row.row_number, stitch, run));
+                position = position.saturating_add(run.stitches);
+                stitch = stitch.saturating_add(run.stitches);
+            }
+        }
+        // Row number and stitch of a position in working order
+        let locate = |position: usize| {
+            let idx = runs.partition_point(|&(start, ..)| start <= position) - 1;
+            let (start, row_number, stitch, _) = runs[idx];
+            (row_number, stitch + (position - start))
+        };
+
+        let mut last_worked: HashMap<&str, usize> = HashMap::new();
+        for &(start, _, _, run) in &runs {
+            let color = run.color.trim();
+            let end = start.saturating_add(run.stitches) - 1;
+            if let Some(last) = last_worked.insert(color, end) {
+                let length = start.saturating_sub(last + 1);
+                if length <= max_float {
+                    continue;
+                }
+                let (row_number, start_stitch) = locate(last + 1);
+                let fix = if length > 2 * max_float {
+                    FloatFix::Bobbin
+                } else {
+                    let (row_number, stitch) = locate(last + 1 + length / 2);
+                    FloatFix::Catch { row_number, stitch }
+                };
+                floats.push(LongFloat {
+                    row_number,
+                    color: color.to_string(),
+                    start_stitch,
+                    length,
+                    fix,
+                });
+            }
+        }
+    }
+
+    floats.sort_by_key(|f| (f.row_number, f.start_stitch));
+    Ok(floats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(row_number: usize, runs: &[(&str, usize)]) -> TapestryRow {
+        TapestryRow {
+            row_number,
+            runs: runs
+                .iter()
+                .map(|&(color, stitches)| ColorRun {
+                    color: color.to_string(),
+                    stitches,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_flat_rows_flag_long_floats() {
+        let chart = TapestryChart {
+            construction: Construction::Flat,
+            rows: vec![
+                row(1, &[("Cream", 3), ("Navy", 2), ("Cream", 3)]),
+                row(2, &[("Cream", 2), ("Navy", 8), ("Cream", 2)]),
+                row(3, &[("Cream", 2), ("Navy", 14), ("Cream", 2)]),
+                // Navy is not carried to the edges of the row
+                row(4, &[("Navy", 1), ("Cream", 16), ("Navy", 1)]),
+            ],
+        };
+
+        let floats = find_long_floats(&chart, 5).unwrap();
+
+        let rows: Vec<usize> = floats.iter().map(|f| f.row_number).collect();
+        assert_eq!(rows, [2, 3, 4]);
+        assert_eq!(floats[0].color, "Cream");
+        assert_eq!(floats[0].start_stitch, 3);
+        assert_eq!(floats[0].length, 8);
+        assert_eq!(
+            floats[0].fix,
+            FloatFix::Catch {
+                row_number: 2,
+                stitch: 7
+            }
+        );
+        assert_eq!(floats[1].length, 14);
+        assert_eq!(floats[1].fix, FloatFix::Bobbin);
+        assert_eq!(floats[2].color, "Navy");
+        assert_eq!(floats[2].start_stitch, 2);
+
+        assert!(find_long_floats(&chart, 16).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_round_floats_continue_into_the_next_round() {
+        let chart = TapestryChart {
+            construction: Construction::Round,
+            rows: vec![
+                row(1, &[("Red", 2), ("White", 4)]),
+                row(2, &[("White", 4), ("Red", 2)]),
+            ],
+        };
+
+        let floats = find_long_floats(&chart, 5).unwrap();
+
+        // Red waits out the rest of round 1 and the start of round 2
+        assert_eq!(floats.len(), 1);
+        assert_eq!(floats[0].row_number, 1);
+        assert_eq!(floats[0].color, "Red");
+        assert_eq!(floats[0].start_stitch, 3);
+        assert_eq!(floats[0].length, 8);
+        assert_eq!(
+            floats[0].fix,
+            FloatFix::Catch {
+                row_number: 2,
+                stitch: 1
+            }
+        );
+
+        // Worked flat, each row carries Red only within itself
+        let flat = TapestryChart {
+            construction: Construction::Flat,
+            ..chart
+        };
+        assert!(find_long_floats(&flat, 5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_float_check() {
+        let chart = TapestryChart {
+            construction: Construction::Flat,
+            rows: vec![row(1, &[("Cream", 3), ("Navy", 0)])],
+        };
+        assert!(find_long_floats(&chart, 5).is_err());
+        let chart = TapestryChart {
+            construction: Construction::Flat,
+            rows: vec![row(1, &[("Cream", 3)])],
+        };
+        assert!(find_long_floats(&chart, 0).is_err());
+        assert!(find_long_floats(&chart, 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_huge_runs_are_measured_not_expanded() {
+        let chart = TapestryChart {
+            construction: Construction::Round,
+            rows: vec![
+                row(1, &[("Cream", 1), ("Navy", 400_000_000)]),
+                row(2, &[("Cream", 1)]),
+            ],
+        };
+
+        let floats = find_long_floats(&chart, 300_000_000).unwrap();
+
+        assert_eq!(floats.len(), 1);
+        assert_eq!(floats[0].start_stitch, 2);
+        assert_eq!(floats[0].length, 400_000_000);
+        assert_eq!(
+            floats[0].fix,
+            FloatFix::Catch {
+                row_number: 1,
+                stitch: 200_000_002
+            }
+        );
+    }
+}
@@ -0,0 +1,373 @@
+use crochet_types::*;
+
+use crate::palettes::palette_color;
+use crate::yarn::{closest_weight, stitch_yarn_cm};
+
+/// Width of the texture relative to plain single crochet; the long
+/// stitches pull the rows they cross in a little
+const TEXTURE_WIDTH_SCALE: f64 = 0.95;
+
+/// Height of each row relative to plain single crochet; long stitches
+/// pinch every pair of rows together
+const TEXTURE_ROW_SCALE: f64 = 0.9;
+
+/// Yarn a long stitch uses relative to a short one
+const LONG_YARN_SCALE: f64 = 1.6;
+
+/// Gauge of the two-color texture for a yarn's plain single-crochet gauge
+pub fn texture_gauge(yarn: &YarnSpec) -> TextureGauge {
+    TextureGauge {
+        stitches_per_cm: yarn.gauge_stitches_per_cm / TEXTURE_WIDTH_SCALE,
+        rows_per_cm: yarn.gauge_rows_per_cm / TEXTURE_ROW_SCALE,
+        long_yarn_scale: LONG_YARN_SCALE,
+    }
+}
+
+/// Reversible two-color texture of alternating short and long rows
+///
+/// Odd rows are short stitches and even rows long (spike) stitches worked
+/// over them into the row below, so each color shows as a raised rib on
+/// both faces. A tube changes color every round. A flat piece works each
+/// color for two rows, out and back, so the color needed next is always
+/// waiting at the edge where the row starts: A, B, B, A, A, B, ...
+pub fn generate_texture_pattern(config: &TextureConfig) -> Result<TexturePattern> {
+    let values = [
+        config.width_cm,
+        config.height_cm,
+        config.yarn.gauge_stitches_per_cm,
+        config.yarn.gauge_rows_per_cm,
+        config.yarn.recommended_hook_size_mm,
+    ];
+    if values.iter().any(|v| !(v.is_finite() && *v > 0.0)) {
+        return Err(PatternError::InvalidConfiguration(
+            "Size, gauge and hook size must be positive".to_string(),
+        ));
+    }
+    let palette_colors = match &config.palette {
+        Some(name) => [
+            Some(palette_color(name, &config.color_a)?),
+            Some(palette_color(name, &config.color_b)?),
+        ],
+        None => [None, None],
+    };
+    let [color_a, color_b] = [0, 1].map(|i| match &palette_colors[i] {
+        Some(color) => color.hex.clone(),
+        None => [&config.color_a, &config.color_b][i].clone(),
+    });
+    for color in [&color_a, &color_b] {
+        if !is_hex_color(color) {
+            return Err(PatternError::InvalidConfiguration(format!(
+                "Color must be written #rrggbb, not {}",
+                color
+            )));
+        }
+    }
+
+    let gauge = texture_gauge(&config.yarn);
+    let stitches = ((config.width_cm * gauge.stitches_per_cm).round() as usize).max(2);
+    let num_rows = ((config.height_cm * gauge.rows_per_cm).round() as usize).max(2);
+    let limits = InputLimits::default();
+    if num_rows > limits.max_rows || stitches > limits.max_stitches_per_row {
+        return Err(PatternError::InvalidConfiguration(format!(
+            "Piece would need {} rows of {} stitches, limits are {} and {}",
+            num_rows, stitches, limits.max_rows, limits.max_stitches_per_row
+        )));
+    }
+
+    let rows: Vec<TextureRow> = (1..=num_rows)
+        .map(|row_number| TextureRow {
+            row_number,
+            color: row_color(config.construction, row_number),
+            stitch: if row_number % 2 == 1 {
+                TextureStitch::Short
+            } else {
+                TextureStitch::Long
+            },
+            stitches,
+        })
+        .collect();
+
+    // Short stitches are single crochets, measured as the pattern yarn
+    // estimate measures them; long ones are pulled up through two rows
+    let short_cm = stitch_yarn_cm(
+        StitchType::SC,
+        config.yarn.recommended_hook_size_mm,
+        closest_weight(&config.yarn).weight,
+    );
+    let mut yarn_meters = [0.0; 2];
+    for row in &rows {
+        let per_stitch = match row.stitch {
+            TextureStitch::Short => short_cm,
+            TextureStitch::Long => short_cm * LONG_YARN_SCALE,
+        };
+        yarn_meters[row.color as usize] += row.stitches as f64 * per_stitch / 100.0;
+    }
+
+    let notes = match config.construction {
+        Construction::Round => vec![
+            "Join each round with a sl st in the first stitch; change color in that sl st.".to_string(),
+            "Carry the unused color up the inside of the tube rather than cutting it.".to_string(),
+        ],
+        Construction::Flat => vec![
+            "Each color works two rows, out and back; drop it at the edge and pick up the other color waiting there.".to_string(),
+            "Carry the unused color loosely up the side edge.".to_string(),
+        ],
+    };
+
+    Ok(TexturePattern {
+        construction: config.construction,
+        color_a,
+        color_b,
+        rows,
+        gauge,
+        yarn_meters,
+        finished_size: Dimensions {
+            height_cm: num_rows as f64 / gauge.rows_per_cm,
+            width_cm: stitches as f64 / gauge.stitches_per_cm,
+        },
+        notes,
+        palette_colors,
+    })
+}
+
+fn row_color(construction: Construction, row_number: usize) -> TextureColor {
+    let second = match construction {
+        Construction::Round => row_number.is_multiple_of(2),
+        Construction::Flat => (row_number / 2) % 2 == 1,
+    };
+    if second {
+        TextureColor::B
+    } else {
+        TextureColor::A
+    }
+}
+
+fn is_hex_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Written instruction for one row, without the "Row n" label
+pub fn texture_row_instruction(
+    pattern: &TexturePattern,
+    row: &TextureRow,
+    terminology: Terminology,
+) -> String {
+    let stitch = row.stitch.abbreviation(terminology);
+    let (rows, around) = match pattern.construction {
+        Construction::Round => ("rounds", "around"),
+        Construction::Flat => ("rows", "across"),
+    };
+    let mut text = match (row.row_number, pattern.construction) {
+        (1, Construction::Round) => format!(
+            "With A, ch {}, join with sl st to form a ring, {} in each ch around",
+            row.stitches, stitch
+        ),
+        (1, Construction::Flat) => format!(
+            "With A, ch {}, {} in 2nd ch from hook and each ch across",
+            row.stitches + 1,
+            stitch
+        ),
+        (2, _) => format!(
+            "Join B, {} in each st {}, working into the foundation ch",
+            stitch, around
+        ),
+        _ => {
+            let color = match row.color {
+                TextureColor::A => "A",
+                TextureColor::B => "B",
+            };
+            let below = match row.stitch {
+                TextureStitch::Short => String::new(),
+                TextureStitch::Long => format!(", working into the st 2 {} below", rows),
+            };
+            format!("With {}, {} in each st {}{}", color, stitch, around, below)
+        }
+    };
+    if pattern.construction == Construction::Flat {
+        text.push_str(", turn");
+    }
+    format!("{} ({})", text, row.stitches)
+}
+
+/// Plain-text texture pattern with materials, gauge, legend and rows
+pub fn texture_to_text(pattern: &TexturePattern, terminology: Terminology) -> String {
+    let rule = "=".repeat(50);
+    let mut text = String::new();
+    text.push_str("TWO-COLOR TEXTURE PATTERN\n");
+    text.push_str(&rule);
+    text.push_str("\n\n");
+
+    text.push_str("MATERIALS:\n");
+    let hexes = [&pattern.color_a, &pattern.color_b];
+    for (i, label) in ["A", "B"].iter().enumerate() {
+        let name = match &pattern.palette_colors[i] {
+            Some(color) => format!("{}, {}", color.name, hexes[i]),
+            None => hexes[i].to_string(),
+        };
+        text.push_str(&format!(
+            "- Color {} ({}): {:.1}m (plus 20% extra)\n",
+            label, name, pattern.yarn_meters[i]
+        ));
+        for yarn in pattern.palette_colors[i].iter().flat_map(|c| &c.yarns) {
+            text.push_str(&format!("  - {}: {}\n", yarn.line, yarn.shade));
+        }
+    }
+    text.push('\n');
+
+    text.push_str("GAUGE (in pattern):\n");
+    text.push_str(&format!(
+        "- {:.2} stitches per cm\n- {:.2} rows per cm\n\n",
+        pattern.gauge.stitches_per_cm, pattern.gauge.rows_per_cm
+    ));
+
+    text.push_str("FINISHED SIZE:\n");
+    text.push_str(&format!(
+        "- Height: {:.1} cm\n- Width: {:.1} cm\n\n",
+        pattern.finished_size.height_cm, pattern.finished_size.width_cm
+    ));
+
+    text.push_str("ABBREVIATIONS:\n");
+    for stitch in [TextureStitch::Short, TextureStitch::Long] {
+        text.push_str(&format!(
+            "- {}: {}\n",
+            stitch.abbreviation(terminology),
+            stitch.description(terminology)
+        ));
+    }
+    text.push('\n');
+
+    text.push_str("NOTES:\n");
+    for note in &pattern.notes {
+        text.push_str(&format!("- {}\n", note));
+    }
+    text.push('\n');
+
+    text.push_str("PATTERN:\n");
+    text.push_str(&"-".repeat(50));
+    text.push_str("\n\n");
+    let label = match pattern.construction {
+        Construction::Round => "Round",
+        Construction::Flat => "Row",
+    };
+    for row in &pattern.rows {
+        text.push_str(&format!(
+            "{} {}: {}\n",
+            label,
+            row.row_number,
+            texture_row_instruction(pattern, row, terminology)
+        ));
+    }
+    text.push_str("Fasten off both colors and weave in the ends.\n");
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_config(construction: Construction) -> TextureConfig {
+        TextureConfig {
+            construction,
+            width_cm: 10.0,
+            height_cm: 6.0,
+            yarn: YarnSpec::default(),
+            color_a: "#1f4e79".to_string(),
+            color_b: "#f2c14e".to_string(),
+            palette: None,
+        }
+    }
+
+    #[test]
+    fn test_flat_texture_pairs_colors() {
+        let pattern = generate_texture_pattern(&create_config(Construction::Flat)).unwrap();
+
+        let colors: Vec<TextureColor> = pattern.rows.iter().take(6).map(|r| r.color).collect();
+        use TextureColor::*;
+        assert_eq!(colors, [A, B, B, A, A, B]);
+        assert_eq!(pattern.rows[0].stitch, TextureStitch::Short);
+        assert_eq!(pattern.rows[1].stitch, TextureStitch::Long);
+        assert!(pattern
+            .rows
+            .iter()
+            .all(|r| r.stitches == pattern.rows[0].stitches));
+        assert!((pattern.finished_size.width_cm - 10.0).abs() < 0.5);
+        assert!(pattern.yarn_meters.iter().all(|&m| m > 0.0));
+
+        let text = texture_to_text(&pattern, Terminology::UK);
+        assert!(text.contains("Row 1: With A, ch "));
+        assert!(text.contains("- Spike DC: Spike Double Crochet (US single crochet), worked into"));
+        assert!(text.contains(
+            "Row 4: With A, Spike DC in each st across, working into the st 2 rows below, turn ("
+        ));
+    }
+
+    #[test]
+    fn test_tube_alternates_every_round() {
+        let pattern = generate_texture_pattern(&create_config(Construction::Round)).unwrap();
+
+        assert!(pattern
+            .rows
+            .iter()
+            .all(|r| (r.color == TextureColor::B) == (r.row_number % 2 == 0)));
+        let first = texture_row_instruction(&pattern, &pattern.rows[0], Terminology::US);
+        assert!(first.starts_with("With A, ch "));
+        assert!(first.contains("join with sl st to form a ring"));
+        assert!(!first.contains("turn"));
+    }
+
+    #[test]
+    fn test_invalid_texture_config() {
+        let mut config = create_config(Construction::Flat);
+        config.color_b = "gold".to_string();
+        assert!(generate_texture_pattern(&config).is_err());
+
+        let mut config = create_config(Construction::Flat);
+        config.width_cm = 0.0;
+        assert!(generate_texture_pattern(&config).is_err());
+    }
+
+    #[test]
+    fn test_yarn_follows_stitch_length() {
+        let config = create_config(Construction::Round);
+        let pattern = generate_texture_pattern(&config).unwrap();
+
+        // A tube's first color works only short rows, the second only long
+        let stitches = pattern.rows[0].stitches as f64;
+        let rounds = pattern.rows.len() as f64 / 2.0;
+        let sc_cm = stitch_yarn_cm(
+            StitchType::SC,
+            config.yarn.recommended_hook_size_mm,
+            closest_weight(&config.yarn).weight,
+        );
+        let short_meters = rounds * stitches * sc_cm / 100.0;
+        assert!((pattern.yarn_meters[0] - short_meters).abs() < 1e-9);
+        assert!((pattern.yarn_meters[1] - short_meters * LONG_YARN_SCALE).abs() < 1e-9);
+
+        // At the same gauge, a bigger hook pulls up longer loops
+        let mut chunky = config.clone();
+        chunky.yarn.recommended_hook_size_mm = 8.0;
+        let chunky = generate_texture_pattern(&chunky).unwrap();
+        assert_eq!(chunky.rows, pattern.rows);
+        assert!(chunky.yarn_meters[0] > pattern.yarn_meters[0]);
+    }
+
+    #[test]
+    fn test_palette_colors_name_yarns() {
+        let mut config = create_config(Construction::Round);
+        config.palette = Some("teddy-bear".to_string());
+        config.color_a = "Honey".to_string();
+        config.color_b = "cream".to_string();
+
+        let pattern = generate_texture_pattern(&config).unwrap();
+
+        assert_eq!(pattern.color_a, "#c8893d");
+        assert_eq!(pattern.palette_colors[1].as_ref().unwrap().name, "Cream");
+        let text = texture_to_text(&pattern, Terminology::US);
+        assert!(text.contains("- Color A (Honey, #c8893d): "));
+        assert!(text.contains("  - Scheepjes Catona: Old Lace\n"));
+
+        // Colors outside the palette are refused rather than passed through
+        config.color_b = "#ffffff".to_string();
+        assert!(generate_texture_pattern(&config).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+use crochet_types::*;
+
+/// Minutes each row of a pattern takes at the crocheter's speed
+///
+/// Every row pays the profile's start-of-row pause, each stitch its time
+/// at `sc_per_minute` plus any increase or decrease penalty, and a row
+/// that starts a new color after the first row the color change. Sum
+/// them for the whole piece.
+pub fn estimate_row_minutes(pattern: &CrochetPattern, profile: &CrocheterProfile) -> Vec<f64> {
+    row_minutes(&pattern.rows, &pattern.color_changes, profile)
+}
+
+pub(crate) fn row_minutes(
+    rows: &[Row],
+    changes: &[ColorChange],
+    profile: &CrocheterProfile,
+) -> Vec<f64> {
+    let sc = 60.0 / profile.sc_per_minute;
+    let stitch_seconds = |stitch: StitchType| match stitch {
+        StitchType::SC => sc,
+        StitchType::INC => 2.0 * sc + profile.inc_penalty,
+        StitchType::DEC | StitchType::INVDEC => sc + profile.dec_penalty,
+    };
+    let first_row = rows.first().map(|r| r.row_number);
+
+    rows.iter()
+        .map(|row| {
+            // An empty pattern is shorthand for a plain row
+            let mut seconds = if row.pattern.is_empty() {
+                row.total_stitches as f64 * sc
+            } else {
+                row.pattern
+                    .iter()
+                    .map(|s| stitch_seconds(s.stitch_type))
+                    .sum()
+            };
+            seconds += profile.row_start_seconds;
+            let new_color = changes.iter().any(|c| c.row_number == row.row_number);
+            if new_color && Some(row.row_number) != first_row {
+                seconds += profile.color_change_seconds;
+            }
+            seconds / 60.0
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_pattern;
+    use crate::stripes::add_stripes;
+
+    fn create_test_config() -> AmigurumiConfig {
+        AmigurumiConfig {
+            total_height_cm: 6.0,
+            ..Default::default()
+        }
+    }
+
+    fn create_test_pattern(config: &AmigurumiConfig) -> CrochetPattern {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, 1.0),
+                control2: Point2D::new(3.0, 5.0),
+                end: Point2D::new(1.0, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 1.0,
+        };
+        generate_pattern(&curve, config).unwrap()
+    }
+
+    #[test]
+    fn test_rows_take_time_by_stitch() {
+        let config = create_test_config();
+        let pattern = create_test_pattern(&config);
+        let minutes = &pattern.metadata.row_time_minutes;
+
+        assert_eq!(minutes.len(), pattern.rows.len());
+        let total: f64 = minutes.iter().sum();
+        assert!((pattern.metadata.estimated_time_minutes - total).abs() < 1e-9);
+
+        // Row 1 is 6 SC at 2 seconds each, plus the 5 second start
+        assert!((minutes[0] - 17.0 / 60.0).abs() < 1e-9);
+        // Row 2 is 6 INC: 6 x (4 + 1) seconds, plus the start
+        assert!((minutes[1] - 35.0 / 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_profile_and_color_changes() {
+        let mut config = create_test_config();
+        let mut pattern = create_test_pattern(&config);
+        let usual: f64 = pattern.metadata.row_time_minutes.iter().sum();
+
+        config.crocheter.sc_per_minute = 15.0;
+        let slower = create_test_pattern(&config);
+        assert!(slower.metadata.estimated_time_minutes > 1.5 * usual);
+
+        let stripes = StripeConfig {
+            colors: vec!["Honey".to_string(), "Sage".to_string()],
+            rows_per_stripe: 4,
+            jogless: None,
+        };
+        add_stripes(&mut pattern, &stripes).unwrap();
+        let profile = CrocheterProfile::default();
+        let striped = estimate_row_minutes(&pattern, &profile);
+
+        // Starting in Honey is free; every later stripe costs a change
+        let changes = pattern.color_changes.len() - 1;
+        let total: f64 = striped.iter().sum();
+        let expected = usual + changes as f64 * profile.color_change_seconds / 60.0;
+        assert!((total - expected).abs() < 1e-9);
+        assert!(striped[4] > pattern.metadata.row_time_minutes[4]);
+        assert!((striped[3] - pattern.metadata.row_time_minutes[3]).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,229 @@
+use crochet_types::*;
+
+use crate::parser::{consumes, produces};
+use crate::stitch_count::max_delta;
+
+/// Check a hand-edited pattern against the rules a generated one keeps
+///
+/// Every problem is listed rather than stopping at the first: numbering,
+/// each row using up the row below and making the count it states, the
+/// smallest round, the configured increase/decrease caps and input limits,
+/// and the allowed stitches. An empty list means the pattern is sound.
+pub fn verify_pattern(pattern: &CrochetPattern, config: &AmigurumiConfig) -> Vec<PatternProblem> {
+    let mut problems = limit_problems(pattern, &config.limits);
+    let mut problem = |row_number: Option<usize>, kind, message: String| {
+        problems.push(PatternProblem {
+            row_number,
+            kind,
+            message,
+        })
+    };
+
+    let allowed = config.stitches.allowed(pattern.construction);
+    let min_stitches = match pattern.construction {
+        Construction::Round => config.min_round_stitches.max(1),
+        Construction::Flat => 1,
+    };
+    for (idx, row) in pattern.rows.iter().enumerate() {
+        let n = Some(row.row_number);
+        if row.row_number != idx + 1 {
+            problem(
+                n,
+                ProblemKind::Numbering,
+                format!("Row {} should be numbered {}", row.row_number, idx + 1),
+            );
+        }
+
+        // An empty pattern is shorthand for a plain row
+        let (consumed, produced) = if row.pattern.is_empty() {
+            (row.total_stitches, row.total_stitches)
+        } else {
+            row.pattern.iter().fold((0, 0), |(c, p), s| {
+                (c + consumes(s.stitch_type), p + produces(s.stitch_type))
+            })
+        };
+        if produced != row.total_stitches {
+            problem(
+                n,
+                ProblemKind::StitchCount,
+                format!(
+                    "Row {} makes {} stitches but says {}",
+                    row.row_number, produced, row.total_stitches
+                ),
+            );
+        }
+        if let Some(below) = idx.checked_sub(1).map(|i| &pattern.rows[i]) {
+            if consumed != below.total_stitches {
+                problem(
+                    n,
+                    ProblemKind::StitchCount,
+                    format!(
+                        "Row {} works into {} stitches but row {} has {}",
+                        row.row_number, consumed, below.row_number, below.total_stitches
+                    ),
+                );
+            }
+            let (prev, total) = (below.total_stitches, row.total_stitches);
+            // Increases can at most double a row and decreases halve it
+            let physical = if total > prev { prev } else { prev / 2 };
+            let cap = max_delta(prev, physical, config);
+            if prev.abs_diff(total) > cap {
+                problem(
+                    n,
+                    ProblemKind::DeltaCap,
+                    format!(
+                        "Row {} goes from {} to {} stitches; the caps allow a change of {}",
+                        row.row_number, prev, total, cap
+                    ),
+                );
+            }
+        }
+
+        if row.total_stitches < min_stitches {
+            problem(
+                n,
+                ProblemKind::TooFewStitches,
+                format!(
+                    "Row {} has {} stitches, fewer than the {} allowed",
+                    row.row_number, row.total_stitches, min_stitches
+                ),
+            );
+        }
+        if let Some(s) = row
+            .pattern
+            .iter()
+            .find(|s| !allowed.contains(&s.stitch_type))
+        {
+            problem(
+                n,
+                ProblemKind::DisallowedStitch,
+                format!(
+                    "Row {} uses {}, which is not an allowed stitch",
+                    row.row_number,
+                    s.stitch_type.to_string()
+                ),
+            );
+        }
+    }
+
+    problems
+}
+
+/// A pattern's rows past the input limits: too many of them, or rows
+/// stating or listing too many stitches
+pub fn limit_problems(pattern: &CrochetPattern, limits: &InputLimits) -> Vec<PatternProblem> {
+    row_limit_problems(
+        pattern
+            .rows
+            .iter()
+            .map(|r| (r.row_number, r.total_stitches.max(r.pattern.len()))),
+        limits,
+    )
+}
+
+/// `limit_problems` for rows given as (row number, stitches)
+///
+/// Only counts are compared, so anything read from outside can be checked
+/// before it is looped over stitch by stitch.
+pub fn row_limit_problems(
+    rows: impl ExactSizeIterator<Item = (usize, usize)>,
+    limits: &InputLimits,
+) -> Vec<PatternProblem> {
+    let mut problems = Vec::new();
+    if rows.len() > limits.max_rows {
+        problems.push(PatternProblem {
+            row_number: None,
+            kind: ProblemKind::Limits,
+            message: format!(
+                "Pattern has {} rows, limit is {}",
+                rows.len(),
+                limits.max_rows
+            ),
+        });
+    }
+    for (row_number, stitches) in rows {
+        if stitches > limits.max_stitches_per_row {
+            problems.push(PatternProblem {
+                row_number: Some(row_number),
+                kind: ProblemKind::Limits,
+                message: format!(
+                    "Row {} has {} stitches, limit is {}",
+                    row_number, stitches, limits.max_stitches_per_row
+                ),
+            });
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_pattern;
+
+    fn create_pattern() -> (CrochetPattern, AmigurumiConfig) {
+        let curve = ProfileCurve {
+            segments: vec![SplineSegment {
+                start: Point2D::new(0.0, 0.0),
+                control1: Point2D::new(3.0, 1.0),
+                control2: Point2D::new(3.0, 5.0),
+                end: Point2D::new(1.0, 6.0),
+            }],
+            start_radius: 0.0,
+            end_radius: 1.0,
+        };
+        let config = AmigurumiConfig {
+            total_height_cm: 6.0,
+            max_stitch_delta: Some(6),
+            ..Default::default()
+        };
+        (generate_pattern(&curve, &config).unwrap(), config)
+    }
+
+    fn kinds(problems: &[PatternProblem]) -> Vec<(Option<usize>, ProblemKind)> {
+        problems.iter().map(|p| (p.row_number, p.kind)).collect()
+    }
+
+    #[test]
+    fn test_generated_pattern_is_sound() {
+        let (pattern, config) = create_pattern();
+        assert_eq!(verify_pattern(&pattern, &config), vec![]);
+    }
+
+    #[test]
+    fn test_edits_are_reported() {
+        let (mut pattern, config) = create_pattern();
+        // Drop a stitch from row 3 without fixing its count
+        pattern.rows[2].pattern.pop();
+        // Number row 5 out of order
+        pattern.rows[4].row_number = 7;
+
+        let problems = verify_pattern(&pattern, &config);
+
+        let found = kinds(&problems);
+        assert!(found.contains(&(Some(3), ProblemKind::StitchCount)));
+        assert!(found.contains(&(Some(7), ProblemKind::Numbering)));
+        assert!(problems[0].message.starts_with("Row 3 "));
+    }
+
+    #[test]
+    fn test_config_rules_are_checked() {
+        let (pattern, mut config) = create_pattern();
+        config.max_stitch_delta = Some(3);
+        config.min_round_stitches = 8;
+        config.stitches.round.retain(|&s| s != StitchType::INC);
+
+        let found = kinds(&verify_pattern(&pattern, &config));
+
+        assert!(found.contains(&(Some(1), ProblemKind::TooFewStitches)));
+        assert!(found.contains(&(Some(2), ProblemKind::DeltaCap)));
+        assert!(found.contains(&(Some(2), ProblemKind::DisallowedStitch)));
+        assert!(!found
+            .iter()
+            .any(|&(_, kind)| kind == ProblemKind::StitchCount));
+
+        config.limits.max_rows = 3;
+        let found = kinds(&verify_pattern(&pattern, &config));
+        assert_eq!(found[0], (None, ProblemKind::Limits));
+    }
+}
@@ -0,0 +1,436 @@
+use crochet_types::{
+    AmigurumiConfig, ColorChange, ColorYarnUsage, CrochetPattern, Fiber, Row, StitchType, YarnSpec,
+    YarnSubstitution, YarnWeight,
+};
+
+/// Typical single-crochet gauge and hook for a yarn weight
+#[derive(Debug, Clone, Copy)]
+pub struct YarnWeightInfo {
+    pub weight: YarnWeight,
+    /// Craft Yarn Council category number
+    pub category: u8,
+    pub name: &'static str,
+    pub gauge_stitches_per_cm: f64,
+    pub gauge_rows_per_cm: f64,
+    pub hook_size_mm: f64,
+    /// Thickness of the strand, which adds to every loop around the hook
+    pub yarn_diameter_mm: f64,
+    /// Typical length of a 100 g ball
+    pub meters_per_100g: f64,
+}
+
+/// Midpoints of the Craft Yarn Council single-crochet ranges (per 10 cm).
+/// Single crochet fabric is close to square, so rows track stitches.
+/// Diameters and ball lengths are typical of acrylic and cotton yarns.
+const YARN_WEIGHTS: [YarnWeightInfo; 8] = [
+    YarnWeightInfo {
+        weight: YarnWeight::Lace,
+        category: 0,
+        name: "Lace",
+        gauge_stitches_per_cm: 3.7,
+        gauge_rows_per_cm: 3.7,
+        hook_size_mm: 1.5,
+        yarn_diameter_mm: 0.7,
+        meters_per_100g: 800.0,
+    },
+    YarnWeightInfo {
+        weight: YarnWeight::SuperFine,
+        category: 1,
+        name: "Super Fine",
+        gauge_stitches_per_cm: 2.65,
+        gauge_rows_per_cm: 2.65,
+        hook_size_mm: 2.75,
+        yarn_diameter_mm: 1.1,
+        meters_per_100g: 400.0,
+    },
+    YarnWeightInfo {
+        weight: YarnWeight::Fine,
+        category: 2,
+        name: "Fine",
+        gauge_stitches_per_cm: 1.8,
+        gauge_rows_per_cm: 1.8,
+        hook_size_mm: 4.0,
+        yarn_diameter_mm: 1.4,
+        meters_per_100g: 300.0,
+    },
+    YarnWeightInfo {
+        weight: YarnWeight::Light,
+        category: 3,
+        name: "Light",
+        gauge_stitches_per_cm: 1.45,
+        gauge_rows_per_cm: 1.45,
+        hook_size_mm: 5.0,
+        yarn_diameter_mm: 1.7,
+        meters_per_100g: 250.0,
+    },
+    YarnWeightInfo {
+        weight: YarnWeight::Medium,
+        category: 4,
+        name: "Medium",
+        gauge_stitches_per_cm: 1.25,
+        gauge_rows_per_cm: 1.25,
+        hook_size_mm: 6.0,
+        yarn_diameter_mm: 2.1,
+        meters_per_100g: 180.0,
+    },
+    YarnWeightInfo {
+        weight: YarnWeight::Bulky,
+        category: 5,
+        name: "Bulky",
+        gauge_stitches_per_cm: 0.95,
+        gauge_rows_per_cm: 0.95,
+        hook_size_mm: 8.0,
+        yarn_diameter_mm: 2.8,
+        meters_per_100g: 120.0,
+    },
+    YarnWeightInfo {
+        weight: YarnWeight::SuperBulky,
+        category: 6,
+        name: "Super Bulky",
+        gauge_stitches_per_cm: 0.8,
+        gauge_rows_per_cm: 0.8,
+        hook_size_mm: 12.0,
+        yarn_diameter_mm: 3.8,
+        meters_per_100g: 80.0,
+    },
+    YarnWeightInfo {
+        weight: YarnWeight::Jumbo,
+        category: 7,
+        name: "Jumbo",
+        gauge_stitches_per_cm: 0.6,
+        gauge_rows_per_cm: 0.6,
+        hook_size_mm: 15.0,
+        yarn_diameter_mm: 5.5,
+        meters_per_100g: 40.0,
+    },
+];
+
+/// All standard yarn weights, lightest first
+pub fn yarn_weights() -> &'static [YarnWeightInfo] {
+    &YARN_WEIGHTS
+}
+
+/// Typical gauge and hook for a yarn weight
+pub fn weight_info(weight: YarnWeight) -> YarnWeightInfo {
+    *YARN_WEIGHTS.iter().find(|w| w.weight == weight).unwrap()
+}
+
+/// Look up a yarn weight by name ("bulky", "Super Fine", "super_fine", ...)
+pub fn weight_from_name(name: &str) -> Option<YarnWeight> {
+    let normalized: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    YARN_WEIGHTS
+        .iter()
+        .find(|w| w.name.replace(' ', "").to_ascii_lowercase() == normalized)
+        .map(|w| w.weight)
+}
+
+/// Rescale a configuration to a different yarn weight
+///
+/// Amigurumi is worked tighter than the yarn label suggests, so the hook is
+/// scaled by the same ratio as the current hook to the current weight's
+/// typical hook rather than taken straight from the table.
+pub fn substitute_yarn(config: &AmigurumiConfig, weight: YarnWeight) -> YarnSubstitution {
+    let info = weight_info(weight);
+    let current = closest_weight(&config.yarn);
+    let tension = config.yarn.recommended_hook_size_mm / current.hook_size_mm;
+
+    let yarn = YarnSpec {
+        gauge_stitches_per_cm: config.yarn.gauge_stitches_per_cm
+            * info.gauge_stitches_per_cm
+            / current.gauge_stitches_per_cm,
+        gauge_rows_per_cm: config.yarn.gauge_rows_per_cm * info.gauge_rows_per_cm
+            / current.gauge_rows_per_cm,
+        recommended_hook_size_mm: round_to_quarter(info.hook_size_mm * tension),
+    };
+
+    let stitch_scale = yarn.gauge_stitches_per_cm / config.yarn.gauge_stitches_per_cm;
+    let row_scale = yarn.gauge_rows_per_cm / config.yarn.gauge_rows_per_cm;
+    let total_rows = ((config.total_height_cm * yarn.gauge_rows_per_cm).round() as usize).max(1);
+
+    YarnSubstitution {
+        weight,
+        config: AmigurumiConfig {
+            yarn,
+            ..config.clone()
+        },
+        stitch_scale,
+        row_scale,
+        total_rows,
+    }
+}
+
+/// Share of a stitch's width taken up by the yarn itself rather than the
+/// loop formed around the hook
+const YARN_WIDTH_SHARE: f64 = 0.4;
+
+/// Estimate single-crochet gauge from hook size, yarn weight and fiber
+///
+/// The weight table supplies the gauge at that weight's typical hook. Only
+/// the loop part of a stitch grows with the hook, so stitch size is scaled
+/// partly by the hook ratio, then by how much the fiber lofts or packs.
+/// Use this when there is no swatch, and set `gauge_estimated` on the
+/// config so the pattern is marked as an estimate.
+pub fn estimate_gauge(hook_size_mm: f64, weight: YarnWeight, fiber: Fiber) -> YarnSpec {
+    let info = weight_info(weight);
+    let hook_ratio = hook_size_mm / info.hook_size_mm;
+    let size_factor =
+        (YARN_WIDTH_SHARE + (1.0 - YARN_WIDTH_SHARE) * hook_ratio) * fiber_factor(fiber);
+
+    YarnSpec {
+        gauge_stitches_per_cm: info.gauge_stitches_per_cm / size_factor,
+        gauge_rows_per_cm: info.gauge_rows_per_cm / size_factor,
+        recommended_hook_size_mm: hook_size_mm,
+    }
+}
+
+/// Relative stitch size for a fiber compared with acrylic
+fn fiber_factor(fiber: Fiber) -> f64 {
+    match fiber {
+        Fiber::Acrylic => 1.0,
+        Fiber::Wool => 1.0,
+        // Little stretch or loft, so stitches sit tighter
+        Fiber::Cotton => 0.95,
+        Fiber::Alpaca => 1.03,
+        Fiber::Chenille => 1.1,
+    }
+}
+
+/// Look up a fiber by name ("cotton", "Wool", ...)
+pub fn fiber_from_name(name: &str) -> Option<Fiber> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "acrylic" => Some(Fiber::Acrylic),
+        "cotton" => Some(Fiber::Cotton),
+        "wool" => Some(Fiber::Wool),
+        "alpaca" => Some(Fiber::Alpaca),
+        "chenille" => Some(Fiber::Chenille),
+        _ => None,
+    }
+}
+
+/// Yarn weight whose typical hook is closest to the one in use
+pub fn closest_weight(yarn: &YarnSpec) -> YarnWeightInfo {
+    *YARN_WEIGHTS
+        .iter()
+        .min_by(|a, b| {
+            let da = (a.hook_size_mm - yarn.recommended_hook_size_mm).abs();
+            let db = (b.hook_size_mm - yarn.recommended_hook_size_mm).abs();
+            da.partial_cmp(&db).unwrap()
+        })
+        .unwrap()
+}
+
+/// Name the yarn estimate gives rows worked before any color change
+pub const MAIN_COLOR: &str = "Main color";
+
+/// Tail left at each end of a run of one color, to weave in
+const TAIL_CM: f64 = 15.0;
+
+/// Yarn one stitch uses, in cm
+///
+/// A stitch is made of loops pulled up around the hook, each as long as
+/// the hook's circumference widened by the yarn's own thickness; a single
+/// crochet takes about three and a half of them.
+pub fn stitch_yarn_cm(stitch: StitchType, hook_size_mm: f64, weight: YarnWeight) -> f64 {
+    let loops = match stitch {
+        StitchType::SC => 3.5,
+        // Two single crochets into one stitch
+        StitchType::INC => 7.0,
+        // Two loops pulled up, then drawn through together
+        StitchType::DEC => 5.5,
+        // Front loops only, so barely more than a single crochet
+        StitchType::INVDEC => 4.0,
+    };
+    let loop_mm = std::f64::consts::PI * (hook_size_mm + weight_info(weight).yarn_diameter_mm);
+    loops * loop_mm / 10.0
+}
+
+/// Yarn needed in each color of a pattern
+///
+/// Rows are worked in the color of the last change at or before them,
+/// or in `MAIN_COLOR` before the first change, and each run of a color
+/// leaves a tail at both ends. Weight, skein size and the crocheter's
+/// calibration come from the configuration's `yarn_estimate`.
+pub fn estimate_yarn_usage(
+    pattern: &CrochetPattern,
+    config: &AmigurumiConfig,
+) -> Vec<ColorYarnUsage> {
+    yarn_by_color(&pattern.rows, &pattern.color_changes, config)
+}
+
+pub(crate) fn yarn_by_color(
+    rows: &[Row],
+    changes: &[ColorChange],
+    config: &AmigurumiConfig,
+) -> Vec<ColorYarnUsage> {
+    let estimate = &config.yarn_estimate;
+    let weight = estimate
+        .weight
+        .unwrap_or_else(|| closest_weight(&config.yarn).weight);
+    let hook = config.yarn.recommended_hook_size_mm;
+    let stitch_cm =
+        |stitch: StitchType| stitch_yarn_cm(stitch, hook, weight) * estimate.calibration;
+
+    let mut usage: Vec<ColorYarnUsage> = Vec::new();
+    let mut current: Option<&str> = None;
+    for row in rows {
+        let color = changes
+            .iter()
+            .rev()
+            .find(|c| c.row_number <= row.row_number)
+            .map_or(MAIN_COLOR, |c| c.color.as_str());
+        // An empty pattern is shorthand for a plain row
+        let mut cm = if row.pattern.is_empty() {
+            row.total_stitches as f64 * stitch_cm(StitchType::SC)
+        } else {
+            row.pattern.iter().map(|s| stitch_cm(s.stitch_type)).sum()
+        };
+        if current != Some(color) {
+            cm += 2.0 * TAIL_CM;
+            current = Some(color);
+        }
+        match usage.iter_mut().find(|u| u.color == color) {
+            Some(existing) => existing.meters += cm / 100.0,
+            None => usage.push(ColorYarnUsage {
+                color: color.to_string(),
+                meters: cm / 100.0,
+                grams: 0.0,
+                skeins: 0,
+            }),
+        }
+    }
+
+    let grams_per_meter = 100.0 / weight_info(weight).meters_per_100g;
+    for color in usage.iter_mut() {
+        color.grams = color.meters * grams_per_meter;
+        color.skeins = (color.grams / estimate.skein_grams).ceil() as usize;
+    }
+    usage
+}
+
+/// Hooks are sold in (roughly) quarter-millimetre steps
+fn round_to_quarter(mm: f64) -> f64 {
+    (mm * 4.0).round() / 4.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weights_ordered_by_thickness() {
+        let weights = yarn_weights();
+        for pair in weights.windows(2) {
+            assert!(pair[1].gauge_stitches_per_cm < pair[0].gauge_stitches_per_cm);
+            assert!(pair[1].hook_size_mm > pair[0].hook_size_mm);
+        }
+    }
+
+    #[test]
+    fn test_weight_from_name() {
+        assert_eq!(weight_from_name("bulky"), Some(YarnWeight::Bulky));
+        assert_eq!(weight_from_name("Super Fine"), Some(YarnWeight::SuperFine));
+        assert_eq!(weight_from_name("super_bulky"), Some(YarnWeight::SuperBulky));
+        assert_eq!(weight_from_name("chunky-ish"), None);
+    }
+
+    #[test]
+    fn test_thicker_yarn_needs_fewer_stitches() {
+        let config = AmigurumiConfig::default();
+        let sub = substitute_yarn(&config, YarnWeight::Bulky);
+
+        assert!(sub.stitch_scale < 1.0);
+        assert!(sub.row_scale < 1.0);
+        assert!(sub.total_rows < 30);
+        assert!(sub.config.yarn.recommended_hook_size_mm > config.yarn.recommended_hook_size_mm);
+        assert_eq!(sub.config.total_height_cm, config.total_height_cm);
+    }
+
+    #[test]
+    fn test_estimate_gauge() {
+        let info = weight_info(YarnWeight::Medium);
+        let typical = estimate_gauge(info.hook_size_mm, YarnWeight::Medium, Fiber::Acrylic);
+        assert!((typical.gauge_stitches_per_cm - info.gauge_stitches_per_cm).abs() < 1e-9);
+
+        // Smaller hook means tighter, denser fabric
+        let tight = estimate_gauge(4.0, YarnWeight::Medium, Fiber::Acrylic);
+        assert!(tight.gauge_stitches_per_cm > typical.gauge_stitches_per_cm);
+        assert_eq!(tight.recommended_hook_size_mm, 4.0);
+
+        let cotton = estimate_gauge(4.0, YarnWeight::Medium, Fiber::Cotton);
+        assert!(cotton.gauge_stitches_per_cm > tight.gauge_stitches_per_cm);
+    }
+
+    #[test]
+    fn test_same_weight_is_identity() {
+        let config = AmigurumiConfig::default();
+        let current = closest_weight(&config.yarn);
+        let sub = substitute_yarn(&config, current.weight);
+
+        assert!((sub.stitch_scale - 1.0).abs() < 1e-9);
+        assert!((sub.row_scale - 1.0).abs() < 1e-9);
+    }
+
+    fn plain_rows(counts: &[usize]) -> Vec<Row> {
+        counts
+            .iter()
+            .enumerate()
+            .map(|(i, &total_stitches)| Row {
+                row_number: i + 1,
+                total_stitches,
+                pattern: vec![],
+                markers: vec![],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stitch_yarn_grows_with_hook_and_weight() {
+        let sc = stitch_yarn_cm(StitchType::SC, 3.5, YarnWeight::Fine);
+        assert!(sc > 4.0 && sc < 7.0);
+        assert!(stitch_yarn_cm(StitchType::SC, 5.0, YarnWeight::Fine) > sc);
+        assert!(stitch_yarn_cm(StitchType::SC, 3.5, YarnWeight::Bulky) > sc);
+        assert!(stitch_yarn_cm(StitchType::INC, 3.5, YarnWeight::Fine) > 1.9 * sc);
+        let dec = stitch_yarn_cm(StitchType::DEC, 3.5, YarnWeight::Fine);
+        assert!(stitch_yarn_cm(StitchType::INVDEC, 3.5, YarnWeight::Fine) < dec);
+    }
+
+    #[test]
+    fn test_yarn_usage_per_color() {
+        let mut config = AmigurumiConfig::default();
+        config.yarn_estimate.weight = Some(YarnWeight::Medium);
+        config.yarn_estimate.skein_grams = 10.0;
+        let rows = plain_rows(&[20; 12]);
+        let change = |row_number: usize, color: &str| ColorChange {
+            row_number,
+            color: color.to_string(),
+            jog: None,
+            start_shift: 0,
+        };
+
+        let single = yarn_by_color(&rows, &[], &config);
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].color, MAIN_COLOR);
+        let sc_m = stitch_yarn_cm(StitchType::SC, 3.5, YarnWeight::Medium) / 100.0;
+        let tails_m = 2.0 * TAIL_CM / 100.0;
+        assert!((single[0].meters - (240.0 * sc_m + tails_m)).abs() < 1e-9);
+        assert!((single[0].grams - single[0].meters / 1.8).abs() < 1e-9);
+        assert_eq!(single[0].skeins, (single[0].grams / 10.0).ceil() as usize);
+
+        // Stripes of four rows: Honey, Sage, Honey
+        let changes = [change(1, "Honey"), change(5, "Sage"), change(9, "Honey")];
+        let striped = yarn_by_color(&rows, &changes, &config);
+        let colors: Vec<&str> = striped.iter().map(|u| u.color.as_str()).collect();
+        assert_eq!(colors, ["Honey", "Sage"]);
+        assert!((striped[0].meters - 2.0 * striped[1].meters).abs() < 1e-9);
+        let total: f64 = striped.iter().map(|u| u.meters).sum();
+        assert!((total - single[0].meters - 2.0 * tails_m).abs() < 1e-9);
+
+        config.yarn_estimate.calibration = 1.1;
+        let loose = yarn_by_color(&rows, &[], &config);
+        assert!(loose[0].meters > single[0].meters);
+    }
+}
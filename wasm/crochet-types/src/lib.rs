@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// 2D point in drawing space
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -19,6 +20,20 @@ impl Point2D {
     }
 }
 
+/// 3D point or direction, in cm with y up
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Point3D {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Point3D {
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+}
+
 /// Cubic Bézier spline segment
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SplineSegment {
@@ -82,11 +97,400 @@ pub struct YarnSpec {
     pub recommended_hook_size_mm: f64,
 }
 
+impl Default for YarnSpec {
+    fn default() -> Self {
+        Self {
+            gauge_stitches_per_cm: 3.0,
+            gauge_rows_per_cm: 3.0,
+            recommended_hook_size_mm: 3.5,
+        }
+    }
+}
+
+/// Standard yarn weight categories (Craft Yarn Council 0-7)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum YarnWeight {
+    Lace,
+    SuperFine,
+    Fine,
+    Light,
+    Medium,
+    Bulky,
+    SuperBulky,
+    Jumbo,
+}
+
+/// Yarn fiber, which affects how large a stitch comes out at a given hook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Fiber {
+    #[default]
+    Acrylic,
+    Cotton,
+    Wool,
+    Alpaca,
+    Chenille,
+}
+
+/// Which hand holds the hook; sets the working direction around each round
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Handedness {
+    /// Rounds worked counter-clockwise (viewed from the outside)
+    #[default]
+    Right,
+    /// Rounds worked clockwise; instructions are mirrored
+    Left,
+}
+
+/// Where on the piece each round begins (and so where the spiral jog lands)
+///
+/// Angles are measured counter-clockwise from the front, viewed from above.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum RoundStart {
+    #[default]
+    Front,
+    Left,
+    Back,
+    Right,
+    /// Explicit angle in radians
+    Angle(f64),
+}
+
+impl RoundStart {
+    /// Angular offset in radians (0 to 2π)
+    pub fn angle(&self) -> f64 {
+        use std::f64::consts::PI;
+        match self {
+            RoundStart::Front => 0.0,
+            RoundStart::Left => PI / 2.0,
+            RoundStart::Back => PI,
+            RoundStart::Right => 3.0 * PI / 2.0,
+            RoundStart::Angle(a) => a.rem_euclid(2.0 * PI),
+        }
+    }
+}
+
+/// Speed/precision trade-off applied across the generation pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Quality {
+    Fast,
+    #[default]
+    Balanced,
+    Best,
+}
+
+impl Quality {
+    /// Simulated annealing iterations per row of stitch placement
+    pub fn annealing_iterations(&self) -> usize {
+        match self {
+            Quality::Fast => 100,
+            Quality::Balanced => 500,
+            Quality::Best => 2000,
+        }
+    }
+
+    /// Height tolerance (cm) when locating each row on the profile curve
+    pub fn curve_tolerance(&self) -> f64 {
+        match self {
+            Quality::Fast => 1e-3,
+            Quality::Balanced => 1e-6,
+            Quality::Best => 1e-9,
+        }
+    }
+}
+
+/// Tuning for the stitch placement optimizer
+///
+/// Every field is optional; left empty, placement follows `quality` and
+/// the fixed default seed, exactly as without this section.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OptimizationConfig {
+    /// Random seed; pass a pattern's `metadata.seed` back in to reproduce
+    /// its layout. Older configs gave it as a top-level `seed`, which is
+    /// still read into here
+    pub seed: Option<u64>,
+    /// Annealing iterations per row, in place of the quality's
+    pub iterations: Option<usize>,
+    /// Temperature kept after each iteration, between 0 and 1; by default
+    /// it is chosen so every run cools to the same final temperature
+    pub cooling_rate: Option<f64>,
+    pub weights: OptimizationWeights,
+    pub method: PlacementMethod,
+    /// Rows either side the ridge pass compares each row with; 0 turns
+    /// the pass off, `None` uses the default of 3
+    pub ridge_window: Option<usize>,
+}
+
+/// How increases and decreases are placed within a row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PlacementMethod {
+    /// Solve rows of only increases or only decreases exactly, annealing
+    /// only rows that mix them
+    #[default]
+    Even,
+    /// Anneal every row (the seed, iterations and cooling rate apply to all)
+    Annealing,
+}
+
+/// Relative importance of the placement goals
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OptimizationWeights {
+    /// Spreading a row's increases or decreases evenly around it
+    pub spacing: f64,
+    /// Keeping them off the previous row's, so they don't stack into a seam
+    pub stagger: f64,
+    /// Alternating the side decreases drift to (with `balance_decreases`)
+    pub drift: f64,
+}
+
+impl Default for OptimizationWeights {
+    fn default() -> Self {
+        Self {
+            spacing: 1.0,
+            stagger: 1.0,
+            drift: 2.0,
+        }
+    }
+}
+
+/// Result of swapping a configuration onto a different yarn weight
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YarnSubstitution {
+    pub weight: YarnWeight,
+    /// Original configuration with the yarn replaced
+    pub config: AmigurumiConfig,
+    /// Multiplier applied to every row's stitch count
+    pub stitch_scale: f64,
+    /// Multiplier applied to the number of rows
+    pub row_scale: f64,
+    pub total_rows: usize,
+}
+
+/// Hard limits that keep hostile or broken input from hanging or
+/// exhausting memory in the generator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputLimits {
+    pub max_segments: usize,
+    pub max_rows: usize,
+    pub max_stitches_per_row: usize,
+    /// Largest accepted JSON document, in bytes
+    pub max_input_bytes: usize,
+}
+
+impl Default for InputLimits {
+    fn default() -> Self {
+        Self {
+            max_segments: 1_000,
+            max_rows: 2_000,
+            max_stitches_per_row: 5_000,
+            max_input_bytes: 1 << 20,
+        }
+    }
+}
+
+/// Settings for the yarn usage estimate
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct YarnEstimateConfig {
+    /// Weight of the yarn in use; `None` takes the weight whose typical
+    /// hook is closest to the configured one
+    pub weight: Option<YarnWeight>,
+    /// Grams in one skein of the yarn
+    pub skein_grams: f64,
+    /// Multiplier for the crocheter's own tension (1.1 if they use 10%
+    /// more yarn than estimated)
+    pub calibration: f64,
+}
+
+impl Default for YarnEstimateConfig {
+    fn default() -> Self {
+        Self {
+            weight: None,
+            skein_grams: 100.0,
+            calibration: 1.0,
+        }
+    }
+}
+
+/// How fast the crocheter works, for time estimates
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrocheterProfile {
+    pub sc_per_minute: f64,
+    /// Seconds an increase takes beyond two single crochets
+    pub inc_penalty: f64,
+    /// Seconds a decrease (plain or invisible) takes beyond one single crochet
+    pub dec_penalty: f64,
+    /// Seconds to cut, join and secure a new color
+    pub color_change_seconds: f64,
+    /// Seconds at the start of each row to turn or move the marker up
+    pub row_start_seconds: f64,
+}
+
+impl Default for CrocheterProfile {
+    fn default() -> Self {
+        Self {
+            sc_per_minute: 30.0,
+            inc_penalty: 1.0,
+            dec_penalty: 2.0,
+            color_change_seconds: 60.0,
+            row_start_seconds: 5.0,
+        }
+    }
+}
+
 /// Dimensions in real-world units
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct AmigurumiConfig {
     pub total_height_cm: f64,
     pub yarn: YarnSpec,
+    /// Largest stitch change per row as a fraction of the previous row
+    /// (1.0 allows doubling; decreases are always limited to halving)
+    #[serde(default = "default_max_stitch_delta_fraction")]
+    pub max_stitch_delta_fraction: f64,
+    /// Optional absolute cap on stitches added or removed in a single row
+    #[serde(default)]
+    pub max_stitch_delta: Option<usize>,
+    #[serde(default)]
+    pub handedness: Handedness,
+    #[serde(default)]
+    pub round_start: RoundStart,
+    #[serde(default)]
+    pub limits: InputLimits,
+    #[serde(default)]
+    pub quality: Quality,
+    /// Gauge came from `estimate_gauge` rather than a measured swatch
+    #[serde(default)]
+    pub gauge_estimated: bool,
+    /// Alternate decreases left and right of the previous round's to stop
+    /// closing shapes from twisting
+    #[serde(default)]
+    pub balance_decreases: bool,
+    /// Stitches in the magic ring and the smallest any round may shrink to;
+    /// 6 is standard, tiny limbs may want 4 or 5
+    #[serde(default = "default_min_round_stitches")]
+    pub min_round_stitches: usize,
+    #[serde(default)]
+    pub optimization: OptimizationConfig,
+    /// Stitches the crocheter knows; the generator uses no others
+    #[serde(default)]
+    pub stitches: StitchRepertoire,
+    /// Add stitch-marker hints every this many rows and at each change
+    /// of shaping (0 for shaping changes only); `None` adds none
+    #[serde(default)]
+    pub marker_every: Option<usize>,
+    #[serde(default)]
+    pub yarn_estimate: YarnEstimateConfig,
+    #[serde(default)]
+    pub crocheter: CrocheterProfile,
+}
+
+impl Serialize for AmigurumiConfig {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        AmigurumiConfig::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AmigurumiConfig {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        /// Configs from before the seed moved into `optimization`
+        #[derive(Deserialize)]
+        struct WithTopLevelSeed {
+            #[serde(flatten, with = "AmigurumiConfig")]
+            config: AmigurumiConfig,
+            #[serde(default)]
+            seed: Option<u64>,
+        }
+
+        let WithTopLevelSeed { mut config, seed } = WithTopLevelSeed::deserialize(deserializer)?;
+        config.optimization.seed = config.optimization.seed.or(seed);
+        Ok(config)
+    }
+}
+
+fn default_max_stitch_delta_fraction() -> f64 {
+    1.0
+}
+
+fn default_min_round_stitches() -> usize {
+    6
+}
+
+impl Default for AmigurumiConfig {
+    fn default() -> Self {
+        Self {
+            total_height_cm: 10.0,
+            yarn: YarnSpec::default(),
+            max_stitch_delta_fraction: default_max_stitch_delta_fraction(),
+            max_stitch_delta: None,
+            handedness: Handedness::Right,
+            round_start: RoundStart::Front,
+            limits: InputLimits::default(),
+            quality: Quality::Balanced,
+            gauge_estimated: false,
+            balance_decreases: false,
+            min_round_stitches: default_min_round_stitches(),
+            optimization: OptimizationConfig::default(),
+            stitches: StitchRepertoire::default(),
+            marker_every: None,
+            yarn_estimate: YarnEstimateConfig::default(),
+            crocheter: CrocheterProfile::default(),
+        }
+    }
+}
+
+/// Stitch techniques allowed in each kind of work
+///
+/// Where both decreases are allowed, the invisible decrease is used; it
+/// leaves no gap, but some crocheters only know (or prefer) the standard
+/// decrease, especially on flat parts where the back of the work shows.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StitchRepertoire {
+    /// Stitches allowed in rounds
+    pub round: Vec<StitchType>,
+    /// Stitches allowed in rows worked flat
+    pub flat: Vec<StitchType>,
+}
+
+impl Default for StitchRepertoire {
+    fn default() -> Self {
+        let all = vec![
+            StitchType::SC,
+            StitchType::INC,
+            StitchType::DEC,
+            StitchType::INVDEC,
+        ];
+        Self {
+            round: all.clone(),
+            flat: all,
+        }
+    }
+}
+
+impl StitchRepertoire {
+    /// Stitches allowed for the given construction
+    pub fn allowed(&self, construction: Construction) -> &[StitchType] {
+        match construction {
+            Construction::Round => &self.round,
+            Construction::Flat => &self.flat,
+        }
+    }
+
+    /// Decrease to use, if any is allowed
+    pub fn decrease(&self, construction: Construction) -> Option<StitchType> {
+        let allowed = self.allowed(construction);
+        [StitchType::INVDEC, StitchType::DEC]
+            .into_iter()
+            .find(|s| allowed.contains(s))
+    }
 }
 
 /// Stitch type enumeration
@@ -100,15 +504,41 @@ pub enum StitchType {
 
 impl StitchType {
     pub fn to_string(&self) -> &'static str {
-        match self {
-            StitchType::SC => "SC",
-            StitchType::INC => "INC",
-            StitchType::DEC => "DEC",
-            StitchType::INVDEC => "INVDEC",
+        self.abbreviation(Terminology::US)
+    }
+
+    /// Abbreviation in the given terminology (UK "DC" is US "SC")
+    pub fn abbreviation(&self, terminology: Terminology) -> &'static str {
+        match (self, terminology) {
+            (StitchType::SC, Terminology::US) => "SC",
+            (StitchType::SC, Terminology::UK) => "DC",
+            (StitchType::INC, _) => "INC",
+            (StitchType::DEC, _) => "DEC",
+            (StitchType::INVDEC, _) => "INVDEC",
+        }
+    }
+
+    /// Legend entry explaining the abbreviation
+    pub fn description(&self, terminology: Terminology) -> &'static str {
+        match (self, terminology) {
+            (StitchType::SC, Terminology::US) => "Single Crochet",
+            (StitchType::SC, Terminology::UK) => "Double Crochet (US single crochet)",
+            (StitchType::INC, Terminology::US) => "Increase (2 SC in same stitch)",
+            (StitchType::INC, Terminology::UK) => "Increase (2 DC in same stitch)",
+            (StitchType::DEC, _) => "Decrease (2 stitches together)",
+            (StitchType::INVDEC, _) => "Invisible Decrease (front loops only, 2 together)",
         }
     }
 }
 
+/// Regional crochet terminology used when rendering instructions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Terminology {
+    #[default]
+    US,
+    UK,
+}
+
 /// Stitch instruction with position
 /// 
 /// Represents an instruction to work into a stitch from the previous row.
@@ -136,51 +566,473 @@ pub struct Row {
     pub total_stitches: usize,
     /// Instructions to execute (length = previous row's stitch count for rows > 1)
     pub pattern: Vec<StitchInstruction>,
+    /// Stitch-marker hints to check the row against while working it
+    #[serde(default)]
+    pub markers: Vec<MarkerHint>,
+}
+
+/// Stitch-marker hint for staying on track through a row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarkerHint {
+    /// Mark the first stitch and move the marker up every round
+    RoundStart,
+    /// Having worked into `worked` stitches of the row below, the row
+    /// should be at stitch `stitch` of its `total`
+    Checkpoint {
+        worked: usize,
+        stitch: usize,
+        total: usize,
+    },
 }
 
 impl Row {
     /// Convert pattern to human-readable string
     pub fn pattern_string(&self) -> String {
+        self.pattern_string_with(Terminology::US)
+    }
+
+    /// Convert pattern to human-readable string using the given terminology
+    ///
+    /// Uses written-pattern repeat notation such as "SC, (2 SC, INC) x 5, SC"
+    /// when a repeating unit covers most of the row, and falls back to plain
+    /// run-length groups ("3 SC, INC, 3 SC, ...") otherwise.
+    pub fn pattern_string_with(&self, terminology: Terminology) -> String {
         if self.pattern.is_empty() {
-            return format!("{} SC", self.total_stitches);
+            return format!("{} {}", self.total_stitches, StitchType::SC.abbreviation(terminology));
         }
 
-        let mut result = String::new();
-        let mut current_type = self.pattern[0].stitch_type;
-        let mut count = 1;
+        let types: Vec<StitchType> = self.pattern.iter().map(|s| s.stitch_type).collect();
 
-        for i in 1..self.pattern.len() {
-            if self.pattern[i].stitch_type == current_type {
-                count += 1;
-            } else {
-                if count > 1 {
-                    result.push_str(&format!("{} {}, ", count, current_type.to_string()));
-                } else {
-                    result.push_str(&format!("{}, ", current_type.to_string()));
+        match find_repeat(&types) {
+            Some((start, unit_len, repeats)) => {
+                let end = start + unit_len * repeats;
+                let mut parts = Vec::new();
+                if start > 0 {
+                    parts.push(run_length_string(&types[..start], terminology));
+                }
+                parts.push(format!(
+                    "({}) x {}",
+                    run_length_string(&types[start..start + unit_len], terminology),
+                    repeats
+                ));
+                if end < types.len() {
+                    parts.push(run_length_string(&types[end..], terminology));
                 }
-                current_type = self.pattern[i].stitch_type;
-                count = 1;
+                parts.join(", ")
             }
+            None => run_length_string(&types, terminology),
         }
+    }
+}
+
+/// Most runs of identical stitches a repeating unit may span: written
+/// repeats such as "(19 SC, INC)" are short even when their stitch count
+/// is not, and the cap keeps the search linear in the length of the row
+const MAX_REPEAT_RUNS: usize = 16;
+
+/// Find the repeating unit that covers the most of a row
+///
+/// Returns (start, unit length, repeat count). Only units that mix stitch
+/// types, span at most `MAX_REPEAT_RUNS` runs, start within their first
+/// repeat, repeat at least twice and cover at least half the row qualify;
+/// ties prefer shorter units, then an earlier start.
+///
+/// Works on the run-length encoding: inside a repeated region every run
+/// equals the run as many runs later as the unit spans, and only the runs
+/// at its two ends may be cut short, so each region is found by comparing
+/// runs rather than stitches.
+fn find_repeat(types: &[StitchType]) -> Option<(usize, usize, usize)> {
+    let n = types.len();
+    let runs = stitch_runs(types);
+    if runs.len() < 2 {
+        return None;
+    }
+    // Stitch index each run begins at, plus the end of the row
+    let mut starts = Vec::with_capacity(runs.len() + 1);
+    let mut position = 0;
+    for &(_, count) in &runs {
+        starts.push(position);
+        position += count;
+    }
+    starts.push(position);
 
-        // Add final group
-        if count > 1 {
-            result.push_str(&format!("{} {}", count, current_type.to_string()));
+    // Stitches two runs that line up at their ends (or starts) share
+    let overlap = |a: usize, b: usize| {
+        if runs[a].0 == runs[b].0 {
+            runs[a].1.min(runs[b].1)
         } else {
-            result.push_str(current_type.to_string());
+            0
         }
+    };
 
-        result
+    let mut best: Option<(usize, usize, usize)> = None;
+    for span in 2..=MAX_REPEAT_RUNS.min(runs.len()) {
+        let mut run = 0;
+        while run + span <= runs.len() {
+            let first_run = run;
+            while run + span < runs.len() && runs[run] == runs[run + span] {
+                run += 1;
+            }
+
+            // Runs first_run..run equal the runs `span` later (possibly
+            // none); the repeated region reaches into the runs on either
+            // side as far as they agree
+            let unit_len = starts[first_run + span] - starts[first_run];
+            let back = match first_run {
+                0 => 0,
+                _ => overlap(first_run - 1, first_run - 1 + span),
+            };
+            let forward = if run + span < runs.len() {
+                overlap(run, run + span)
+            } else {
+                0
+            };
+            let start = starts[first_run] - back;
+            let end = starts[run] + forward + unit_len;
+            let repeats = (end - start) / unit_len;
+            run += 1;
+
+            let coverage = repeats * unit_len;
+            if start >= unit_len || repeats < 2 || coverage * 2 < n {
+                continue;
+            }
+            let better = match best {
+                None => true,
+                Some((best_start, best_len, best_repeats)) => {
+                    let best_coverage = best_len * best_repeats;
+                    coverage > best_coverage
+                        || (coverage == best_coverage && (unit_len, start) < (best_len, best_start))
+                }
+            };
+            if better {
+                best = Some((start, unit_len, repeats));
+            }
+        }
+    }
+
+    best
+}
+
+/// Runs of identical consecutive stitches, as (stitch, count)
+fn stitch_runs(types: &[StitchType]) -> Vec<(StitchType, usize)> {
+    let mut runs: Vec<(StitchType, usize)> = Vec::new();
+    for &stitch_type in types {
+        match runs.last_mut() {
+            Some((last, count)) if *last == stitch_type => *count += 1,
+            _ => runs.push((stitch_type, 1)),
+        }
     }
+    runs
+}
+
+/// Group consecutive identical stitches: "3 SC, INC, 2 SC"
+fn run_length_string(types: &[StitchType], terminology: Terminology) -> String {
+    stitch_runs(types)
+        .into_iter()
+        .map(|(stitch_type, count)| {
+            if count > 1 {
+                format!("{} {}", count, stitch_type.abbreviation(terminology))
+            } else {
+                stitch_type.abbreviation(terminology).to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// How closely the achievable shape follows the drawn profile
+///
+/// Stitch counts are whole numbers and limited in how fast they can change,
+/// so the crocheted radius of each row can differ from the requested one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShapeFidelity {
+    /// Per-row radius error in cm (achievable minus requested)
+    pub row_errors_cm: Vec<f64>,
+    /// Largest absolute radius error in cm
+    pub max_deviation_cm: f64,
+    /// Root-mean-square radius error in cm
+    pub rms_deviation_cm: f64,
+}
+
+/// Physical size of a piece in cm
+///
+/// For pieces worked in the round `width_cm` is the widest diameter;
+/// for flat pieces it is the row width.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Dimensions {
+    pub height_cm: f64,
+    pub width_cm: f64,
 }
 
 /// Pattern metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PatternMetadata {
     pub total_rows: usize,
     pub total_stitches: usize,
     pub estimated_time_minutes: f64,
+    /// Minutes each row takes, in row order; they add up to
+    /// `estimated_time_minutes`
+    #[serde(default)]
+    pub row_time_minutes: Vec<f64>,
     pub yarn_length_meters: f64,
+    #[serde(default)]
+    pub shape_fidelity: ShapeFidelity,
+    /// Non-fatal issues found while generating (e.g. rows limited by the stitch delta cap)
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// Size requested by the drawing and configuration
+    #[serde(default)]
+    pub design_size: Dimensions,
+    /// Size implied by the stitch/row counts at the configured gauge
+    #[serde(default)]
+    pub finished_size: Dimensions,
+    /// Gauge (and so size and yarn) is an estimate, not from a swatch
+    #[serde(default)]
+    pub gauge_estimated: bool,
+    /// Seed the placement optimizer ran with, if the rows came from it
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Annealing iterations per row it ran with
+    #[serde(default)]
+    pub iterations: Option<usize>,
+    /// Cooling rate it ran with, including a default chosen from the
+    /// iteration budget
+    #[serde(default)]
+    pub cooling_rate: Option<f64>,
+    /// Placement method it ran with
+    #[serde(default)]
+    pub method: Option<PlacementMethod>,
+    /// Finished size if the crocheter's gauge is off by a few percent
+    #[serde(default)]
+    pub gauge_table: Vec<GaugeVariation>,
+    /// How much increases and decreases line up into ridges across rows
+    /// (see `ridge_energy`), measured over the configured `ridge_window`,
+    /// if the rows came from the placement optimizer
+    #[serde(default)]
+    pub ridge_energy: Option<f64>,
+    /// Yarn needed in each color, in the order the colors are first used
+    #[serde(default)]
+    pub yarn_by_color: Vec<ColorYarnUsage>,
+    /// Skill level and techniques the pattern asks for (see `rate_difficulty`)
+    #[serde(default)]
+    pub difficulty: Option<DifficultyRating>,
+}
+
+/// Skill level label for publishing a pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Difficulty {
+    Beginner,
+    Intermediate,
+    Advanced,
+}
+
+/// How hard a pattern is to work, and what the crocheter needs to know
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DifficultyRating {
+    /// 0 (easiest) to 100
+    pub score: u32,
+    pub level: Difficulty,
+    /// Techniques used, in the order a crocheter meets them ("Magic ring",
+    /// "Invisible decrease", ...)
+    pub skills: Vec<String>,
+}
+
+/// Estimated yarn for one color of a piece, tails included
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColorYarnUsage {
+    pub color: String,
+    pub meters: f64,
+    pub grams: f64,
+    /// Whole skeins of `YarnEstimateConfig::skein_grams` to buy
+    pub skeins: usize,
+}
+
+/// Finished size at a gauge some percent off the configured one
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct GaugeVariation {
+    /// Positive means more stitches and rows per cm (tighter work)
+    pub deviation_percent: f64,
+    pub stitches_per_cm: f64,
+    pub rows_per_cm: f64,
+    pub finished_size: Dimensions,
+}
+
+/// How the piece is worked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Construction {
+    /// Worked in continuous rounds from a magic ring
+    #[default]
+    Round,
+    /// Worked back and forth in turned rows
+    Flat,
+}
+
+/// How the first row is started
+///
+/// Work in the round starts from a magic ring unless the foundation is
+/// `ChainRing`; `Chain` and `SingleCrochet` apply to flat work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Foundation {
+    /// Foundation chain, then row 1 worked into it
+    #[default]
+    Chain,
+    /// Row 1 is worked as foundation single crochet, with no separate chain
+    SingleCrochet,
+    /// Chain joined into a ring, with round 1 worked into the chains
+    ChainRing,
+}
+
+/// Stitches of the two-color texture mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextureStitch {
+    /// Single crochet into the stitch below
+    Short,
+    /// Spike single crochet into the stitch two rows below, over the
+    /// other color's row
+    Long,
+}
+
+impl TextureStitch {
+    /// Abbreviation in the given terminology
+    pub fn abbreviation(&self, terminology: Terminology) -> String {
+        let sc = StitchType::SC.abbreviation(terminology);
+        match self {
+            TextureStitch::Short => sc.to_string(),
+            TextureStitch::Long => format!("Spike {}", sc),
+        }
+    }
+
+    /// Legend entry explaining the stitch
+    pub fn description(&self, terminology: Terminology) -> String {
+        match self {
+            TextureStitch::Short => StitchType::SC.description(terminology).to_string(),
+            TextureStitch::Long => format!(
+                "Spike {}, worked into the stitch two rows below",
+                StitchType::SC.description(terminology)
+            ),
+        }
+    }
+}
+
+/// Which of the two texture colors a row is worked in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextureColor {
+    A,
+    B,
+}
+
+/// A reversible two-color textured piece, such as a scarf or hat band
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextureConfig {
+    /// `Round` makes a tube; `Flat` a rectangle
+    pub construction: Construction,
+    /// Width of a flat piece, or circumference of a tube
+    pub width_cm: f64,
+    pub height_cm: f64,
+    pub yarn: YarnSpec,
+    /// Colors as "#rrggbb", or color names when `palette` is set
+    pub color_a: String,
+    pub color_b: String,
+    /// Palette preset to take `color_a` and `color_b` from
+    #[serde(default)]
+    pub palette: Option<String>,
+}
+
+/// Gauge of the texture, from the plain single-crochet gauge
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TextureGauge {
+    pub stitches_per_cm: f64,
+    pub rows_per_cm: f64,
+    /// Yarn a long stitch uses relative to a short one
+    pub long_yarn_scale: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextureRow {
+    pub row_number: usize,
+    pub color: TextureColor,
+    pub stitch: TextureStitch,
+    pub stitches: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TexturePattern {
+    pub construction: Construction,
+    pub color_a: String,
+    pub color_b: String,
+    pub rows: Vec<TextureRow>,
+    pub gauge: TextureGauge,
+    /// Yarn for color A and color B, in meters
+    pub yarn_meters: [f64; 2],
+    pub finished_size: Dimensions,
+    pub notes: Vec<String>,
+    /// Palette colors chosen for A and B, with yarns to buy
+    #[serde(default)]
+    pub palette_colors: [Option<PaletteColor>; 2],
+}
+
+/// A yarn that comes close to a palette color
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct YarnShade {
+    /// Brand and yarn line ("Scheepjes Catona")
+    pub line: String,
+    /// Shade name as the line sells it
+    pub shade: String,
+}
+
+/// One named color of a palette
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaletteColor {
+    pub name: String,
+    /// "#rrggbb"
+    pub hex: String,
+    /// Part of a toy the color is usually used for ("skin", "cheeks")
+    pub part: String,
+    pub yarns: Vec<YarnShade>,
+}
+
+/// Curated set of colors that can all be bought as yarn
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColorPalette {
+    pub name: String,
+    pub description: String,
+    pub colors: Vec<PaletteColor>,
+}
+
+/// Counts for checking a foundation chain before it is joined into a ring
+///
+/// A stitch marker goes in every `marker_every`th chain so the chain can be
+/// recounted at a glance; with all markers on the same side the chain is
+/// known not to be twisted before the joining slip stitch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RingJoinCheck {
+    pub chain_count: usize,
+    pub marker_every: usize,
+    pub marker_count: usize,
+}
+
+/// A piece split into a flat base worked separately and the body it is
+/// sewn into, so the finished toy stands upright
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StandingPieces {
+    pub base: CrochetPattern,
+    pub body: CrochetPattern,
+}
+
+/// Run of consecutive rows with identical instructions ("Rows 5-12: SC around")
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowGroup {
+    pub first_row: usize,
+    pub last_row: usize,
+    pub total_stitches: usize,
+}
+
+impl RowGroup {
+    /// Number of rows covered by the group (1 for a malformed group that
+    /// ends before it starts)
+    pub fn row_count(&self) -> usize {
+        self.last_row.saturating_sub(self.first_row) + 1
+    }
 }
 
 /// Complete generated pattern
@@ -188,6 +1040,498 @@ pub struct PatternMetadata {
 pub struct CrochetPattern {
     pub rows: Vec<Row>,
     pub metadata: PatternMetadata,
+    /// Rows consolidated into ranges; exporters regroup from `rows` rather
+    /// than trust these
+    #[serde(default)]
+    pub row_groups: Vec<RowGroup>,
+    #[serde(default)]
+    pub construction: Construction,
+    /// How row 1 is started (see `Foundation`)
+    #[serde(default)]
+    pub foundation: Foundation,
+    /// Free-form instructions that accompany the rows (setup, measuring, finishing)
+    #[serde(default)]
+    pub notes: Vec<String>,
+    /// Named stitch positions for attachments (safety eyes, limbs, ...)
+    #[serde(default)]
+    pub anchors: Vec<Anchor>,
+    /// Named runs of rows ("Head", "Decrease crown"), in row order
+    #[serde(default)]
+    pub sections: Vec<Section>,
+    /// Rows that start a new yarn color, in row order
+    #[serde(default)]
+    pub color_changes: Vec<ColorChange>,
+    /// Stitches of the row below that each instruction works into, per row
+    #[serde(default)]
+    pub connections: Vec<RowConnections>,
+}
+
+/// Which stitches of the row below the instructions of a row work into
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowConnections {
+    pub row_number: usize,
+    /// One entry per instruction, in order: the 1-based stitches of the
+    /// row below it uses up, numbered as that row was worked (two for a
+    /// decrease); empty on the first row
+    pub worked_into: Vec<Vec<usize>>,
+}
+
+/// One meaningful difference between two versions of a pattern
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternChange {
+    /// What changed ("rows", "row 12", "notes", "metadata.total_stitches", ...)
+    pub field: String,
+    /// Value in the first pattern; empty when the item was added
+    pub before: String,
+    /// Value in the second pattern; empty when the item was removed
+    pub after: String,
+}
+
+/// How one row differs between two generations of a pattern
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowDiff {
+    pub row_number: usize,
+    /// Stitch count in the first pattern; `None` when the row was added
+    pub before_stitches: Option<usize>,
+    /// Stitch count in the second pattern; `None` when the row was removed
+    pub after_stitches: Option<usize>,
+    /// Instructions whose stitch differs, in order
+    pub stitch_changes: Vec<StitchChange>,
+}
+
+/// A different stitch at one instruction of a row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StitchChange {
+    pub stitch_index: usize,
+    /// `None` where the row had fewer instructions
+    pub before: Option<StitchType>,
+    pub after: Option<StitchType>,
+}
+
+/// A named stitch in a row, used to place attachments
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Anchor {
+    pub name: String,
+    pub row_number: usize,
+    /// 1-based stitch of the row, counted from the start of the round
+    pub stitch: usize,
+}
+
+/// One stitch of a 3D preview, placed and oriented on the piece's surface
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StitchGlyph {
+    pub row_number: usize,
+    pub stitch_index: usize,
+    pub stitch_type: StitchType,
+    /// Centre of the stitch
+    pub position: Point3D,
+    /// Unit vector along the row, in the working direction
+    pub direction: Point3D,
+    /// Unit vector out of the side of the fabric facing the crocheter as
+    /// the stitch is worked (the outside, in the round)
+    pub normal: Point3D,
+    /// Along the row, in cm (an increase is two stitches wide)
+    pub width_cm: f64,
+    pub height_cm: f64,
+}
+
+/// Every stitch of a pattern as an oriented glyph, for a 3D preview
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StitchScene {
+    pub construction: Construction,
+    pub glyphs: Vec<StitchGlyph>,
+}
+
+/// Triangle mesh of the yarn, in cm with y up
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct YarnMesh {
+    pub vertices: Vec<Point3D>,
+    /// Unit normal of each vertex
+    pub normals: Vec<Point3D>,
+    /// Three vertex indices per triangle, counter-clockwise seen from outside
+    pub indices: Vec<u32>,
+}
+
+/// Element ids of one row in a symbol chart, for highlighting it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChartRowIndex {
+    pub row_number: usize,
+    /// Id of the `<g>` holding the row's symbols and label
+    pub group_id: String,
+    /// Id of each stitch symbol, in working order
+    pub stitch_ids: Vec<String>,
+}
+
+/// How the stitch columns of a piece run from round to round
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnFlow {
+    pub rows: Vec<ColumnFlowRow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnFlowRow {
+    pub row_number: usize,
+    /// One entry per stitch the row produces, from the start of the round
+    pub stitches: Vec<FlowStitch>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlowStitch {
+    /// Instruction that made the stitch (both stitches of an increase are INC)
+    pub stitch_type: StitchType,
+    /// 1-based stitches of the row below it was worked into: one, or two
+    /// for a decrease; empty on the first round
+    pub worked_into: Vec<usize>,
+}
+
+/// Where a pair of safety eyes goes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EyePlacement {
+    /// Eyes go between this row and the next
+    pub row_number: usize,
+    /// 1-based stitches of the row, on the left and right of centre front
+    pub left_stitch: usize,
+    pub right_stitch: usize,
+    /// Stitches between the eyes across the front
+    pub stitches_apart: usize,
+    /// Eye distance achieved at this gauge, along the surface
+    pub distance_cm: f64,
+}
+
+/// Generator output measured against a canonical pattern from the corpus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorpusComparison {
+    pub name: String,
+    /// Stitch count of each row of the canonical pattern
+    pub expected_counts: Vec<usize>,
+    /// Stitch count of each row the generator produced for the same shape
+    pub generated_counts: Vec<usize>,
+    /// Largest difference between matching rows, in stitches
+    pub max_row_difference: usize,
+    /// Generated total minus canonical total, in stitches
+    pub total_stitch_difference: i64,
+}
+
+/// Output format for written patterns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Text,
+    Pdf,
+    Markdown,
+    Html,
+    /// Line-based machine-readable stream, one operation per stitch
+    Crochetout,
+    /// SSML narration script for hands-free crocheting
+    Ssml,
+}
+
+/// Named run of rows, rendered as a heading above its instructions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Section {
+    pub name: String,
+    pub first_row: usize,
+    pub last_row: usize,
+}
+
+impl Section {
+    pub fn contains(&self, row_number: usize) -> bool {
+        (self.first_row..=self.last_row).contains(&row_number)
+    }
+}
+
+/// How a color change is hidden when rounds are worked in a spiral
+///
+/// Both methods move the start of the round one stitch on at every
+/// change, so the step between the old and new color is spread out
+/// instead of stacking into one visible jog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum JogMethod {
+    /// Slip stitch into the first stitch with the new color, then start
+    /// the round in the stitch after it
+    #[default]
+    SlipStitch,
+    /// Fasten off and join the new color with a standing stitch one
+    /// stitch along
+    StandingStitch,
+}
+
+/// Stripes of color repeated up the piece
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StripeConfig {
+    /// Yarn colors in the order they are worked, repeating
+    pub colors: Vec<String>,
+    pub rows_per_stripe: usize,
+    /// Hide the jog at each change when working in the round; `None`
+    /// changes color plainly at the end of the round
+    #[serde(default)]
+    pub jogless: Option<JogMethod>,
+}
+
+/// A row that starts a new yarn color
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColorChange {
+    pub row_number: usize,
+    pub color: String,
+    #[serde(default)]
+    pub jog: Option<JogMethod>,
+    /// Stitches the start of the round moved on at this change
+    #[serde(default)]
+    pub start_shift: usize,
+}
+
+/// Stitches worked in one color before the next change within a row
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColorRun {
+    pub color: String,
+    pub stitches: usize,
+}
+
+/// One row of a tapestry chart, its color runs in working order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TapestryRow {
+    pub row_number: usize,
+    pub runs: Vec<ColorRun>,
+}
+
+/// Colorwork with every yarn in use carried along inside the stitches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TapestryChart {
+    pub construction: Construction,
+    pub rows: Vec<TapestryRow>,
+}
+
+/// How to shorten a float that is too long
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FloatFix {
+    /// Work the color's separate areas from their own bobbins instead of
+    /// carrying one strand between them
+    Bobbin,
+    /// Catch the carried yarn under this stitch, splitting the float in two
+    Catch { row_number: usize, stitch: usize },
+}
+
+/// A stretch of stitches a carried yarn passes behind without being worked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LongFloat {
+    pub row_number: usize,
+    pub color: String,
+    /// First stitch of the float, counted from 1 along the row
+    pub start_stitch: usize,
+    pub length: usize,
+    pub fix: FloatFix,
+}
+
+/// Kind of problem found when checking an edited pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProblemKind {
+    /// Rows are not numbered 1, 2, 3, ... in order
+    Numbering,
+    /// A row's instructions don't use up the row below, or don't make
+    /// the stitch count it states
+    StitchCount,
+    /// A round smaller than `min_round_stitches`, or an empty row
+    TooFewStitches,
+    /// A row grows or shrinks faster than the configured caps allow
+    DeltaCap,
+    /// More rows or stitches than the configured limits
+    Limits,
+    /// A stitch outside the configured repertoire
+    DisallowedStitch,
+}
+
+/// One problem in a pattern, with the row it is on where there is one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatternProblem {
+    pub row_number: Option<usize>,
+    pub kind: ProblemKind,
+    pub message: String,
+}
+
+/// Titled block of free text added to exported patterns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteSection {
+    pub title: String,
+    pub body: String,
+}
+
+/// Organisation branding for exported patterns
+///
+/// Header, footer and section bodies may contain `{{name}}` placeholders,
+/// filled from `variables` (e.g. maker, yarn_brand, date) and from the
+/// built-in pattern values (total_rows, total_stitches, hook_mm, height_cm).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Branding {
+    #[serde(default)]
+    pub header: Option<String>,
+    #[serde(default)]
+    pub footer: Option<String>,
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    #[serde(default)]
+    pub sections: Vec<NoteSection>,
+}
+
+/// Colors for stitch charts
+///
+/// Stitch types always differ in symbol shape as well, so no palette relies
+/// on color alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DiagramPalette {
+    /// Black symbols in SVG charts, green/red markers in PDF charts
+    #[default]
+    Standard,
+    /// Okabe-Ito colors, distinguishable with the common color vision deficiencies
+    ColorblindSafe,
+    /// Shades of grey for black-and-white printing
+    Grayscale,
+    /// Saturated dark colors and heavier lines for low vision
+    HighContrast,
+}
+
+impl DiagramPalette {
+    /// RGB fill/stroke color (0-1 per channel) for a stitch type
+    pub fn stitch_color(&self, stitch_type: StitchType) -> (f64, f64, f64) {
+        use StitchType::*;
+        match (self, stitch_type) {
+            (DiagramPalette::Standard, SC) => (0.55, 0.55, 0.55),
+            (DiagramPalette::Standard, INC) => (0.13, 0.59, 0.33),
+            (DiagramPalette::Standard, DEC) => (0.80, 0.20, 0.20),
+            (DiagramPalette::Standard, INVDEC) => (0.85, 0.45, 0.10),
+            (DiagramPalette::ColorblindSafe, SC) => (0.60, 0.60, 0.60),
+            (DiagramPalette::ColorblindSafe, INC) => (0.00, 0.45, 0.70),
+            (DiagramPalette::ColorblindSafe, DEC) => (0.90, 0.62, 0.00),
+            (DiagramPalette::ColorblindSafe, INVDEC) => (0.80, 0.47, 0.65),
+            (DiagramPalette::Grayscale, SC) => (0.75, 0.75, 0.75),
+            (DiagramPalette::Grayscale, INC) => (0.00, 0.00, 0.00),
+            (DiagramPalette::Grayscale, DEC) => (0.35, 0.35, 0.35),
+            (DiagramPalette::Grayscale, INVDEC) => (0.55, 0.55, 0.55),
+            (DiagramPalette::HighContrast, SC) => (0.00, 0.00, 0.00),
+            (DiagramPalette::HighContrast, INC) => (0.00, 0.25, 0.90),
+            (DiagramPalette::HighContrast, DEC) => (0.80, 0.00, 0.00),
+            (DiagramPalette::HighContrast, INVDEC) => (0.55, 0.00, 0.55),
+        }
+    }
+}
+
+/// Book metadata for EPUB export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpubMetadata {
+    #[serde(default = "default_epub_title")]
+    pub title: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// BCP 47 language tag
+    #[serde(default = "default_epub_language")]
+    pub language: String,
+    /// Last-modified time, "YYYY-MM-DDThh:mm:ssZ"; readers tell editions
+    /// apart by it, so it has no default
+    pub modified: String,
+}
+
+fn default_epub_title() -> String {
+    "Crochet Amigurumi Pattern".to_string()
+}
+
+fn default_epub_language() -> String {
+    "en".to_string()
+}
+
+/// Options for rendering a pattern as written instructions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportOptions {
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub terminology: Terminology,
+    #[serde(default)]
+    pub branding: Branding,
+    /// Say what each row is worked into, for beginners
+    #[serde(default)]
+    pub worked_into: bool,
+    /// Colors for stitch charts
+    #[serde(default)]
+    pub palette: DiagramPalette,
+    /// Draw a small how-to sketch beside each chart legend entry
+    #[serde(default)]
+    pub legend_thumbnails: bool,
+}
+
+/// One piece of a multi-piece project (head, body, arms, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectPiece {
+    pub name: String,
+    pub color: String,
+    pub pattern: CrochetPattern,
+    pub yarn: YarnSpec,
+    /// Number of copies to make (e.g. 2 for arms)
+    #[serde(default = "default_quantity")]
+    pub quantity: usize,
+    #[serde(default)]
+    pub stuffed: bool,
+    /// Safety eyes needed per copy
+    #[serde(default)]
+    pub safety_eyes: usize,
+}
+
+fn default_quantity() -> usize {
+    1
+}
+
+/// Yarn needed in a single color
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YarnRequirement {
+    pub color: String,
+    pub meters: f64,
+    pub skeins: usize,
+}
+
+/// Consolidated materials for every piece of a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShoppingList {
+    pub yarn: Vec<YarnRequirement>,
+    pub hook_sizes_mm: Vec<f64>,
+    pub stuffing_grams: f64,
+    pub safety_eyes: usize,
+    pub stitch_markers: usize,
+}
+
+/// Yarn the user already has in one color and weight
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashYarn {
+    pub color: String,
+    pub weight: YarnWeight,
+    pub meters: f64,
+}
+
+/// How one project piece can be made from the stash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashMatch {
+    pub piece: String,
+    pub color: String,
+    /// Weight the piece's pattern was generated for
+    pub weight: YarnWeight,
+    /// Yarn needed for every copy of the piece, margin included
+    pub meters: f64,
+    pub feasible: bool,
+    /// Stash color to use in place of the one the piece calls for
+    #[serde(default)]
+    pub use_color: Option<String>,
+    /// Stash weight to use instead; the piece must be regenerated for it
+    #[serde(default)]
+    pub use_weight: Option<YarnWeight>,
+    /// Meters still missing when no stash yarn is long enough
+    #[serde(default)]
+    pub shortfall_meters: f64,
+}
+
+/// Which pieces of a project the stash covers, and with what substitutions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashReport {
+    pub matches: Vec<StashMatch>,
+    /// Every piece can be made from the stash
+    pub feasible: bool,
 }
 
 /// Error types for pattern generation
@@ -197,6 +1541,10 @@ pub enum PatternError {
     InvalidConfiguration(String),
     OptimizationFailure(String),
     InternalError(String),
+    /// Written instructions that could not be read
+    ParseError(String),
+    /// Generation was stopped through its cancellation token
+    Cancelled,
 }
 
 impl std::fmt::Display for PatternError {
@@ -208,10 +1556,256 @@ impl std::fmt::Display for PatternError {
             }
             PatternError::OptimizationFailure(msg) => write!(f, "Optimization failed: {}", msg),
             PatternError::InternalError(msg) => write!(f, "Internal error: {}", msg),
+            PatternError::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            PatternError::Cancelled => write!(f, "Generation cancelled"),
         }
     }
 }
 
 impl std::error::Error for PatternError {}
 
+/// Machine-readable kind of a `CrochetError`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorCode {
+    /// Input was not valid JSON for the expected type
+    InvalidJson,
+    /// Binary input was not valid CBOR for the expected type
+    InvalidBinary,
+    /// Input was larger than `InputLimits` allows
+    InputTooLarge,
+    /// A named option (format, yarn weight, ...) was not recognised
+    UnknownOption,
+    InvalidProfileCurve,
+    InvalidConfiguration,
+    OptimizationFailure,
+    /// Written instructions that could not be read
+    UnreadableInstructions,
+    Cancelled,
+    /// A result could not be written out
+    SerializationFailure,
+    InternalError,
+}
+
+/// Where in the pipeline an error happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorStage {
+    /// Reading and decoding the request
+    Input,
+    /// Checking the curve and configuration
+    Validation,
+    /// Working out stitch counts and placement
+    Generation,
+    /// Rendering or serializing the result
+    Export,
+}
+
+/// Error passed across the wasm boundary, as JSON, so callers can branch
+/// on `code` rather than on the wording of `message`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrochetError {
+    pub code: ErrorCode,
+    pub stage: ErrorStage,
+    /// Human-readable description
+    pub message: String,
+    /// Extra context such as the underlying parser error
+    #[serde(default)]
+    pub details: Option<String>,
+}
+
+impl CrochetError {
+    pub fn new(code: ErrorCode, stage: ErrorStage, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            stage,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+}
+
+impl From<PatternError> for CrochetError {
+    fn from(error: PatternError) -> Self {
+        let (code, stage) = match &error {
+            PatternError::InvalidProfileCurve(_) => {
+                (ErrorCode::InvalidProfileCurve, ErrorStage::Validation)
+            }
+            PatternError::InvalidConfiguration(_) => {
+                (ErrorCode::InvalidConfiguration, ErrorStage::Validation)
+            }
+            PatternError::OptimizationFailure(_) => {
+                (ErrorCode::OptimizationFailure, ErrorStage::Generation)
+            }
+            PatternError::InternalError(_) => (ErrorCode::InternalError, ErrorStage::Generation),
+            PatternError::ParseError(_) => (ErrorCode::UnreadableInstructions, ErrorStage::Input),
+            PatternError::Cancelled => (ErrorCode::Cancelled, ErrorStage::Generation),
+        };
+        Self::new(code, stage, error.to_string())
+    }
+}
+
+impl std::fmt::Display for CrochetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(details) = &self.details {
+            write!(f, " ({})", details)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CrochetError {}
+
 pub type Result<T> = std::result::Result<T, PatternError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row_from_types(types: &[StitchType]) -> Row {
+        Row {
+            row_number: 2,
+            total_stitches: 0,
+            pattern: types
+                .iter()
+                .enumerate()
+                .map(|(i, &stitch_type)| StitchInstruction {
+                    stitch_type,
+                    angular_position: 0.0,
+                    stitch_index: i,
+                })
+                .collect(),
+            markers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_exact_repeat() {
+        use StitchType::*;
+        let row = row_from_types(&[SC, SC, SC, INC, SC, SC, SC, INC, SC, SC, SC, INC]);
+        assert_eq!(row.pattern_string(), "(3 SC, INC) x 3");
+    }
+
+    #[test]
+    fn test_repeat_with_prefix_and_suffix() {
+        use StitchType::*;
+        let row = row_from_types(&[SC, SC, INC, SC, INC, SC, INC, SC, SC]);
+        assert_eq!(row.pattern_string(), "SC, (SC, INC) x 3, 2 SC");
+    }
+
+    /// The stitch-by-stitch search `find_repeat` replaced
+    fn find_repeat_exhaustive(types: &[StitchType]) -> Option<(usize, usize, usize)> {
+        let n = types.len();
+        let mut best: Option<(usize, usize, usize)> = None;
+        let mut best_coverage = 0;
+
+        for unit_len in 2..=n / 2 {
+            for start in 0..unit_len.min(n - 2 * unit_len + 1) {
+                let unit = &types[start..start + unit_len];
+                if unit.iter().all(|&t| t == unit[0]) {
+                    continue;
+                }
+
+                let mut repeats = 1;
+                while start + (repeats + 1) * unit_len <= n
+                    && types[start + repeats * unit_len..start + (repeats + 1) * unit_len] == *unit
+                {
+                    repeats += 1;
+                }
+
+                let coverage = repeats * unit_len;
+                if repeats >= 2 && coverage * 2 >= n && coverage > best_coverage {
+                    best = Some((start, unit_len, repeats));
+                    best_coverage = coverage;
+                }
+            }
+        }
+
+        best
+    }
+
+    #[test]
+    fn test_repeat_search_matches_exhaustive_search() {
+        use StitchType::*;
+        let alphabet = [SC, INC, DEC];
+        // Every row of up to 9 stitches over three stitch types
+        for len in 0..=9u32 {
+            for code in 0..3usize.pow(len) {
+                let types: Vec<StitchType> = (0..len)
+                    .map(|i| alphabet[code / 3usize.pow(i) % 3])
+                    .collect();
+                assert_eq!(
+                    find_repeat(&types),
+                    find_repeat_exhaustive(&types),
+                    "{:?}",
+                    types
+                );
+            }
+        }
+        // Longer rows, mostly SC, from a fixed pseudo-random sequence
+        let mut state = 12345u64;
+        for _ in 0..2000 {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let len = 10 + (state >> 33) as usize % 40;
+            let unit: Vec<StitchType> = (0..1 + (state >> 40) as usize % 6)
+                .map(|i| {
+                    if (state >> (20 + i)) & 3 == 0 {
+                        INC
+                    } else {
+                        SC
+                    }
+                })
+                .collect();
+            let types: Vec<StitchType> = (0..len)
+                .map(|i| {
+                    if i == len / 3 {
+                        DEC
+                    } else {
+                        unit[i % unit.len()]
+                    }
+                })
+                .collect();
+            assert_eq!(
+                find_repeat(&types),
+                find_repeat_exhaustive(&types),
+                "{:?}",
+                types
+            );
+        }
+    }
+
+    #[test]
+    fn test_long_rows_keep_repeats() {
+        use StitchType::*;
+        let mut types = Vec::new();
+        for _ in 0..6 {
+            types.extend([SC; 19]);
+            types.push(INC);
+        }
+        assert_eq!(row_from_types(&types).pattern_string(), "(19 SC, INC) x 6");
+
+        let mut types = vec![SC; 4999];
+        types.push(INC);
+        assert_eq!(row_from_types(&types).pattern_string(), "4999 SC, INC");
+        let types: Vec<StitchType> = (0..5000)
+            .map(|i| if i % 2 == 0 { SC } else { INC })
+            .collect();
+        assert_eq!(row_from_types(&types).pattern_string(), "(SC, INC) x 2500");
+    }
+
+    #[test]
+    fn test_falls_back_to_run_length() {
+        use StitchType::*;
+        let row = row_from_types(&[SC, SC, SC, SC, SC, INC, SC, INC]);
+        assert_eq!(row.pattern_string(), "5 SC, INC, SC, INC");
+
+        let all_sc = row_from_types(&[SC; 6]);
+        assert_eq!(all_sc.pattern_string(), "6 SC");
+    }
+}
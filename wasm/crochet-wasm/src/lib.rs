@@ -1,5 +1,37 @@
 use wasm_bindgen::prelude::*;
-use crochet_core::generator::generate_pattern;
+use wasm_bindgen::JsCast;
+use crochet_core::generator::{apply_repertoire, generate_pattern, PatternGeneration};
+use crochet_core::swatch::generate_gauge_swatch_with_foundation;
+use crochet_core::timing::estimate_row_minutes;
+use crochet_core::scene::stitch_scene;
+use crochet_core::preview::{mesh_to_obj, yarn_preview};
+use crochet_core::yarn::{
+    estimate_gauge, estimate_yarn_usage, fiber_from_name, substitute_yarn, weight_from_name,
+};
+use crochet_core::formatter::PatternFormatter;
+use crochet_core::pdf::export_pdf;
+use crochet_core::epub::export_epub;
+use crochet_core::speech::export_ssml;
+use crochet_core::diagram::DiagramGenerator;
+use crochet_core::crochetout::to_crochetout;
+use crochet_core::parser::parse_written_pattern;
+use crochet_core::corpus::{compare_with_corpus, corpus_shape, load_corpus_pattern, CORPUS};
+use crochet_core::eyes::place_safety_eyes;
+use crochet_core::base::{chain_ring_check, separate_flat_base};
+use crochet_core::sections::{add_section, renumber_rows};
+use crochet_core::stripes::add_stripes;
+use crochet_core::markers::add_marker_hints;
+use crochet_core::tapestry::find_long_floats;
+use crochet_core::verify::{limit_problems, row_limit_problems};
+use crochet_core::compare::{compare_patterns, diff_patterns};
+use crochet_core::difficulty::rate_difficulty;
+use crochet_core::lineage::{anchor_at_angle, column_flow, row_connections};
+use crochet_core::palettes::palettes;
+use crochet_core::texture::{generate_texture_pattern, texture_to_text};
+use crochet_core::materials::{
+    build_shopping_list, match_stash, shopping_list_to_markdown, shopping_list_to_text,
+    ShoppingListOptions,
+};
 use crochet_types::*;
 
 #[wasm_bindgen]
@@ -13,30 +45,1032 @@ pub fn generate_pattern_from_json(
     profile_json: &str,
     config_json: &str,
 ) -> std::result::Result<String, String> {
-    // Parse inputs
-    let profile: ProfileCurve = serde_json::from_str(profile_json)
-        .map_err(|e| format!("Failed to parse profile: {}", e))?;
+    // Parse inputs (config first, it carries the size limits)
+    let config = parse_config(config_json)?;
 
-    let config: AmigurumiConfig = serde_json::from_str(config_json)
-        .map_err(|e| format!("Failed to parse config: {}", e))?;
+    let profile: ProfileCurve = parse_json_within("profile", profile_json, &config.limits)?;
 
     // Generate pattern
     let pattern = generate_pattern(&profile, &config)
-        .map_err(|e| e.to_string())?;
+        .map_err(pattern_error)?;
 
     // Serialize result
-    serde_json::to_string(&pattern)
-        .map_err(|e| format!("Failed to serialize pattern: {}", e))
+    to_json("pattern", &pattern)
+}
+
+/// Generate a crochet pattern as CBOR instead of JSON
+///
+/// Takes the same JSON input as `generate_pattern_from_json`. CBOR is much
+/// smaller and faster to decode for patterns with many thousands of
+/// stitches; decode it with `pattern_from_binary` or any CBOR library.
+#[wasm_bindgen]
+pub fn generate_pattern_binary(
+    profile_json: &str,
+    config_json: &str,
+) -> std::result::Result<Vec<u8>, String> {
+    let config = parse_config(config_json)?;
+
+    let profile: ProfileCurve = parse_json_within("profile", profile_json, &config.limits)?;
+
+    let pattern = generate_pattern(&profile, &config).map_err(pattern_error)?;
+
+    to_cbor("pattern", &pattern)
+}
+
+/// Convert a CBOR-encoded pattern to JSON
+#[wasm_bindgen]
+pub fn pattern_from_binary(pattern_cbor: &[u8]) -> std::result::Result<String, String> {
+    let pattern: CrochetPattern = parse_cbor("pattern", pattern_cbor, &InputLimits::default())?;
+    to_json("pattern", &pattern)
+}
+
+/// Convert a JSON pattern to CBOR
+#[wasm_bindgen]
+pub fn pattern_to_binary(pattern_json: &str) -> std::result::Result<Vec<u8>, String> {
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+    to_cbor("pattern", &pattern)
+}
+
+/// Milliseconds of work between yields to the event loop
+const YIELD_EVERY_MS: f64 = 16.0;
+
+/// Generate a crochet pattern without blocking the page
+///
+/// Same input and output as `generate_pattern_from_json`, but resolves as a
+/// Promise and hands control back to the event loop about once a frame
+/// while rows are being optimized, so the page stays responsive when this
+/// runs on the main thread rather than in a worker.
+#[wasm_bindgen]
+pub async fn generate_pattern_async(
+    profile_json: String,
+    config_json: String,
+) -> std::result::Result<String, String> {
+    generate_in_steps(&profile_json, &config_json, |_, _| false, |_| false).await
+}
+
+/// Generate a crochet pattern, reporting progress as it goes
+///
+/// `on_progress(stage, percent)` is called with the stage name ("rows",
+/// "placement" or "assembly") and the overall percent done. Returning
+/// `false` from it cancels the generation, which then rejects with
+/// "Generation cancelled". Like `generate_pattern_async`, this yields to
+/// the event loop while it works, so messages (such as a cancel request
+/// posted to a worker) are handled in the meantime.
+#[wasm_bindgen]
+pub async fn generate_pattern_with_progress(
+    profile_json: String,
+    config_json: String,
+    on_progress: js_sys::Function,
+) -> std::result::Result<String, String> {
+    generate_in_steps(
+        &profile_json,
+        &config_json,
+        |stage, percent| {
+            // A callback that throws cancels as well
+            on_progress
+                .call2(&JsValue::NULL, &stage.into(), &percent.into())
+                .map_or(true, |ret| ret.as_bool() == Some(false))
+        },
+        |_| false,
+    )
+    .await
+}
+
+/// Generate a crochet pattern, handing over rows as soon as they are placed
+///
+/// `on_rows(rows_json)` is called with a JSON array of the rows that became
+/// final since the previous call, about once a frame and once more before
+/// the pattern is assembled. Rows arrive in order and exactly as they
+/// appear in the resolved pattern, markers included. The ridge pass can
+/// turn any row until every row is placed, so with it on (the default)
+/// all rows arrive together at the end; set the config's
+/// `optimization.ridge_window` to 0 to get each row as soon as it is
+/// placed, so a large pattern can be drawn from the top while the rest is
+/// still being worked out. Returning `false` (or throwing) cancels, as
+/// with `generate_pattern_with_progress`.
+#[wasm_bindgen]
+pub async fn generate_pattern_streaming(
+    profile_json: String,
+    config_json: String,
+    on_rows: js_sys::Function,
+) -> std::result::Result<String, String> {
+    generate_in_steps(
+        &profile_json,
+        &config_json,
+        |_, _| false,
+        |rows| {
+            let Ok(rows_json) = serde_json::to_string(rows) else {
+                return true;
+            };
+            on_rows
+                .call1(&JsValue::NULL, &rows_json.into())
+                .map_or(true, |ret| ret.as_bool() == Some(false))
+        },
+    )
+    .await
+}
+
+/// Shared loop of the async bindings; `on_progress` and `on_rows` (called
+/// with each new batch of placed rows) return true to cancel
+async fn generate_in_steps(
+    profile_json: &str,
+    config_json: &str,
+    mut on_progress: impl FnMut(&str, f64) -> bool,
+    mut on_rows: impl FnMut(&[Row]) -> bool,
+) -> std::result::Result<String, String> {
+    let config = parse_config(config_json)?;
+
+    let profile: ProfileCurve = parse_json_within("profile", profile_json, &config.limits)?;
+    let cancelled = || pattern_error(PatternError::Cancelled);
+    let mut generation = PatternGeneration::start(&profile, &config)
+        .map_err(pattern_error)?;
+    if on_progress("rows", 0.0) {
+        return Err(cancelled());
+    }
+    let mut last_yield = js_sys::Date::now();
+    let mut rows_sent = 0;
+    let mut send_rows = |generation: &PatternGeneration| {
+        let rows = generation.placed_rows(rows_sent);
+        rows_sent += rows.len();
+        !rows.is_empty() && on_rows(&rows)
+    };
+    while !generation.is_done() {
+        generation.step();
+        if on_progress("placement", 100.0 * generation.progress()) {
+            return Err(cancelled());
+        }
+        if js_sys::Date::now() - last_yield >= YIELD_EVERY_MS {
+            if send_rows(&generation) {
+                return Err(cancelled());
+            }
+            yield_to_event_loop().await;
+            last_yield = js_sys::Date::now();
+        }
+    }
+    if send_rows(&generation) {
+        return Err(cancelled());
+    }
+    let pattern = generation.finish().map_err(pattern_error)?;
+    if on_progress("assembly", 100.0) {
+        return Err(cancelled());
+    }
+
+    to_json("pattern", &pattern)
+}
+
+/// Resolve on the next macrotask, so rendering and input get a turn
+async fn yield_to_event_loop() {
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let set_timeout = js_sys::Reflect::get(&js_sys::global(), &"setTimeout".into())
+            .ok()
+            .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+        let _ = match set_timeout {
+            Some(set_timeout) => set_timeout.call2(&JsValue::NULL, &resolve, &0.into()),
+            None => resolve.call0(&JsValue::NULL),
+        };
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// Reject oversized input before handing it to the parser
+fn check_input_size(
+    name: &str,
+    input: &[u8],
+    limits: &InputLimits,
+) -> std::result::Result<(), String> {
+    if input.len() > limits.max_input_bytes {
+        return Err(js_error(
+            CrochetError::new(
+                ErrorCode::InputTooLarge,
+                ErrorStage::Input,
+                format!("{} input is too large", name),
+            )
+            .with_details(format!(
+                "{} bytes, limit is {}",
+                input.len(),
+                limits.max_input_bytes
+            )),
+        ));
+    }
+    Ok(())
+}
+
+/// Inputs worked through row by row and stitch by stitch
+///
+/// A few bytes of JSON can claim millions of stitches, so these are
+/// checked against the limits before anything loops over them.
+trait RowLimits {
+    fn limit_problems(&self, limits: &InputLimits) -> Vec<PatternProblem>;
+}
+
+impl RowLimits for CrochetPattern {
+    fn limit_problems(&self, limits: &InputLimits) -> Vec<PatternProblem> {
+        limit_problems(self, limits)
+    }
+}
+
+impl RowLimits for Vec<ProjectPiece> {
+    fn limit_problems(&self, limits: &InputLimits) -> Vec<PatternProblem> {
+        self.iter()
+            .flat_map(|piece| limit_problems(&piece.pattern, limits))
+            .collect()
+    }
+}
+
+impl RowLimits for TexturePattern {
+    fn limit_problems(&self, limits: &InputLimits) -> Vec<PatternProblem> {
+        row_limit_problems(self.rows.iter().map(|r| (r.row_number, r.stitches)), limits)
+    }
+}
+
+impl RowLimits for TapestryChart {
+    fn limit_problems(&self, limits: &InputLimits) -> Vec<PatternProblem> {
+        let width = |row: &TapestryRow| {
+            row.runs
+                .iter()
+                .fold(0usize, |total, run| total.saturating_add(run.stitches))
+        };
+        row_limit_problems(self.rows.iter().map(|r| (r.row_number, width(r))), limits)
+    }
+}
+
+fn check_rows(
+    name: &str,
+    input: &impl RowLimits,
+    limits: &InputLimits,
+) -> std::result::Result<(), String> {
+    match input.limit_problems(limits).into_iter().next() {
+        Some(problem) => Err(js_error(
+            CrochetError::new(
+                ErrorCode::InputTooLarge,
+                ErrorStage::Input,
+                format!("{} input is too large", name),
+            )
+            .with_details(problem.message),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Errors cross the wasm boundary as `CrochetError` JSON
+fn js_error(error: CrochetError) -> String {
+    serde_json::to_string(&error).unwrap_or(error.message)
+}
+
+fn pattern_error(error: PatternError) -> String {
+    js_error(error.into())
+}
+
+fn unknown_option(message: String) -> String {
+    js_error(CrochetError::new(
+        ErrorCode::UnknownOption,
+        ErrorStage::Input,
+        message,
+    ))
+}
+
+/// Parse a configuration and check it as the generator does, so no
+/// binding works from settings `generate_pattern` would reject
+fn parse_config(config_json: &str) -> std::result::Result<AmigurumiConfig, String> {
+    let config: AmigurumiConfig = parse_json("config", config_json)?;
+    crochet_core::generator::validate_config(&config).map_err(pattern_error)?;
+    Ok(config)
+}
+
+/// Parse JSON no larger than the default limits allow
+fn parse_json<T: serde::de::DeserializeOwned>(what: &str, json: &str) -> std::result::Result<T, String> {
+    parse_json_within(what, json, &InputLimits::default())
+}
+
+/// Parse JSON no larger than a configuration's limits allow
+fn parse_json_within<T: serde::de::DeserializeOwned>(
+    what: &str,
+    json: &str,
+    limits: &InputLimits,
+) -> std::result::Result<T, String> {
+    check_input_size(what, json.as_bytes(), limits)?;
+    serde_json::from_str(json).map_err(|e| {
+        js_error(
+            CrochetError::new(
+                ErrorCode::InvalidJson,
+                ErrorStage::Input,
+                format!("Failed to parse {}", what),
+            )
+            .with_details(e.to_string()),
+        )
+    })
+}
+
+/// Parse JSON of something worked row by row, within `limits` in both
+/// bytes and rows and stitches
+fn parse_rows<T: serde::de::DeserializeOwned + RowLimits>(
+    what: &str,
+    json: &str,
+    limits: &InputLimits,
+) -> std::result::Result<T, String> {
+    let value: T = parse_json_within(what, json, limits)?;
+    check_rows(what, &value, limits)?;
+    Ok(value)
+}
+
+/// `parse_rows` for CBOR
+fn parse_cbor<T: serde::de::DeserializeOwned + RowLimits>(
+    what: &str,
+    bytes: &[u8],
+    limits: &InputLimits,
+) -> std::result::Result<T, String> {
+    check_input_size(what, bytes, limits)?;
+    let value: T = ciborium::from_reader(bytes).map_err(|e| {
+        js_error(
+            CrochetError::new(
+                ErrorCode::InvalidBinary,
+                ErrorStage::Input,
+                format!("Failed to decode {}", what),
+            )
+            .with_details(e.to_string()),
+        )
+    })?;
+    check_rows(what, &value, limits)?;
+    Ok(value)
+}
+
+fn to_cbor<T: serde::Serialize>(what: &str, value: &T) -> std::result::Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes).map_err(|e| {
+        js_error(
+            CrochetError::new(
+                ErrorCode::SerializationFailure,
+                ErrorStage::Export,
+                format!("Failed to encode {}", what),
+            )
+            .with_details(e.to_string()),
+        )
+    })?;
+    Ok(bytes)
+}
+
+fn to_json<T: serde::Serialize>(what: &str, value: &T) -> std::result::Result<String, String> {
+    serde_json::to_string(value).map_err(|e| {
+        js_error(
+            CrochetError::new(
+                ErrorCode::SerializationFailure,
+                ErrorStage::Export,
+                format!("Failed to serialize {}", what),
+            )
+            .with_details(e.to_string()),
+        )
+    })
+}
+
+/// Render a generated pattern as written instructions
+///
+/// `options_json` is an `ExportOptions` object (format, terminology,
+/// branding); missing fields use their defaults. The "pdf" format returns
+/// the PDF document itself, which is plain ASCII.
+#[wasm_bindgen]
+pub fn export_pattern(
+    pattern_json: &str,
+    config_json: &str,
+    options_json: &str,
+) -> std::result::Result<String, String> {
+    let config = parse_config(config_json)?;
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &config.limits)?;
+    render_export(&pattern, &config, options_json)
+}
+
+/// `export_pattern` for a CBOR-encoded pattern, returning the document's bytes
+#[wasm_bindgen]
+pub fn export_pattern_binary(
+    pattern_cbor: &[u8],
+    config_json: &str,
+    options_json: &str,
+) -> std::result::Result<Vec<u8>, String> {
+    let config = parse_config(config_json)?;
+    let pattern: CrochetPattern = parse_cbor("pattern", pattern_cbor, &config.limits)?;
+    render_export(&pattern, &config, options_json).map(String::into_bytes)
+}
+
+fn render_export(
+    pattern: &CrochetPattern,
+    config: &AmigurumiConfig,
+    options_json: &str,
+) -> std::result::Result<String, String> {
+    let options: ExportOptions = parse_json("export options", options_json)?;
+    let formatter = PatternFormatter::from_options(&options);
+
+    match options.format {
+        ExportFormat::Text => Ok(formatter.to_text(pattern, config)),
+        ExportFormat::Pdf => Ok(export_pdf(pattern, config, &formatter)),
+        ExportFormat::Markdown => Ok(formatter.to_markdown(pattern, config)),
+        ExportFormat::Html => Ok(formatter.to_html(pattern, config)),
+        ExportFormat::Crochetout => Ok(to_crochetout(pattern)),
+        ExportFormat::Ssml => Ok(export_ssml(pattern, &formatter)),
+    }
+}
+
+/// Read written instructions ("Rnd 3: (sc, inc) x 6 — 18 sts") into a pattern
+///
+/// Stitch names are read in the terminology from `options_json`; the
+/// config's gauge feeds the size and yarn estimates. The result can be
+/// passed straight to `export_pattern`.
+#[wasm_bindgen]
+pub fn import_written_pattern(
+    text: &str,
+    config_json: &str,
+    options_json: &str,
+) -> std::result::Result<String, String> {
+    let config = parse_config(config_json)?;
+    let options: ExportOptions = parse_json("export options", options_json)?;
+
+    check_input_size("pattern text", text.as_bytes(), &config.limits)?;
+    let pattern = parse_written_pattern(text, &config, options.terminology)
+        .map_err(pattern_error)?;
+
+    to_json("pattern", &pattern)
+}
+
+/// Names of the canonical shapes bundled with the generator, as a JSON array
+#[wasm_bindgen]
+pub fn list_corpus_shapes() -> String {
+    let names: Vec<&str> = CORPUS.iter().map(|shape| shape.name).collect();
+    serde_json::to_string(&names).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Load a canonical corpus pattern (e.g. "sphere") as pattern JSON
+#[wasm_bindgen]
+pub fn load_corpus_pattern_json(name: &str, config_json: &str) -> std::result::Result<String, String> {
+    let config = parse_config(config_json)?;
+    let shape = corpus_shape(name).ok_or_else(|| unknown_option(format!("Unknown corpus shape: {}", name)))?;
+    let pattern = load_corpus_pattern(&shape, &config).map_err(pattern_error)?;
+
+    to_json("pattern", &pattern)
+}
+
+/// Regenerate every corpus shape with `config_json` and compare against the originals
+#[wasm_bindgen]
+pub fn compare_generator_with_corpus(config_json: &str) -> std::result::Result<String, String> {
+    let config = parse_config(config_json)?;
+    let comparisons = CORPUS
+        .iter()
+        .map(|shape| compare_with_corpus(shape, &config))
+        .collect::<Result<Vec<_>>>()
+        .map_err(pattern_error)?;
+
+    to_json("comparisons", &comparisons)
+}
+
+/// Place safety eyes `eye_distance_cm` apart on a round of a generated pattern
+///
+/// Returns the pattern with `left_eye`/`right_eye` anchors and a placement note.
+#[wasm_bindgen]
+pub fn place_safety_eyes_on_pattern(
+    pattern_json: &str,
+    config_json: &str,
+    row_number: usize,
+    eye_distance_cm: f64,
+) -> std::result::Result<String, String> {
+    let config = parse_config(config_json)?;
+    let mut pattern: CrochetPattern = parse_rows("pattern", pattern_json, &config.limits)?;
+
+    place_safety_eyes(&mut pattern, &config, row_number, eye_distance_cm)
+        .map_err(pattern_error)?;
+
+    to_json("pattern", &pattern)
+}
+
+/// Anchor a feature at `angle_degrees` on a round and hold its column there
+///
+/// Returns the pattern with the anchor added and later rounds turned where
+/// needed so the column above it keeps that angle.
+#[wasm_bindgen]
+pub fn anchor_at_angle_on_pattern(
+    pattern_json: &str,
+    name: &str,
+    row_number: usize,
+    angle_degrees: f64,
+) -> std::result::Result<String, String> {
+    let mut pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+
+    anchor_at_angle(&mut pattern, name, row_number, angle_degrees).map_err(pattern_error)?;
+
+    to_json("pattern", &pattern)
+}
+
+/// Split a piece worked in the round into a flat base and the body it is sewn into
+///
+/// `base_rows` is the number of opening rounds to move into the base; pass
+/// 0 to detect the flat bottom automatically. Returns `StandingPieces` JSON.
+#[wasm_bindgen]
+pub fn separate_flat_base_from_pattern(
+    pattern_json: &str,
+    config_json: &str,
+    base_rows: usize,
+) -> std::result::Result<String, String> {
+    let config = parse_config(config_json)?;
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &config.limits)?;
+    let base_rows = (base_rows > 0).then_some(base_rows);
+    let pieces = separate_flat_base(&pattern, &config, base_rows).map_err(pattern_error)?;
+
+    to_json("pieces", &pieces)
+}
+
+/// Marker and count check for a pattern started on a chain ring
+///
+/// Returns `RingJoinCheck` JSON, or `null` when the pattern has no chain ring.
+#[wasm_bindgen]
+pub fn get_ring_join_check(pattern_json: &str) -> std::result::Result<String, String> {
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+
+    to_json("ring check", &chain_ring_check(&pattern))
+}
+
+/// Meaningful differences between two saved patterns
+///
+/// Returns a JSON list of `PatternChange`; an empty list means both
+/// describe the same piece.
+#[wasm_bindgen]
+pub fn compare_pattern_json(
+    before_json: &str,
+    after_json: &str,
+) -> std::result::Result<String, String> {
+    let before: CrochetPattern = parse_rows("first pattern", before_json, &InputLimits::default())?;
+    let after: CrochetPattern = parse_rows("second pattern", after_json, &InputLimits::default())?;
+
+    to_json("changes", &compare_patterns(&before, &after))
+}
+
+/// Row-by-row diff of two patterns
+///
+/// Returns a JSON list of `RowDiff`: for each row that differs, its stitch
+/// count on both sides and the instructions whose stitch changed.
+#[wasm_bindgen]
+pub fn diff_pattern_json(a_json: &str, b_json: &str) -> std::result::Result<String, String> {
+    let a: CrochetPattern = parse_rows("first pattern", a_json, &InputLimits::default())?;
+    let b: CrochetPattern = parse_rows("second pattern", b_json, &InputLimits::default())?;
+
+    to_json("row diff", &diff_patterns(&a, &b))
+}
+
+/// Rate how hard a pattern is to work, for labelling it
+///
+/// Returns a `DifficultyRating` with a 0-100 score, a Beginner /
+/// Intermediate / Advanced level and the skills needed. `pieces` is how
+/// many pieces the finished project is sewn from.
+#[wasm_bindgen]
+pub fn rate_pattern_difficulty(
+    pattern_json: &str,
+    pieces: usize,
+) -> std::result::Result<String, String> {
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+
+    to_json("difficulty", &rate_difficulty(&pattern, pieces.max(1)))
+}
+
+/// Check a hand-edited pattern and list every problem found
+///
+/// Stitch caps, limits and allowed stitches come from `config_json` when
+/// given, and from the default configuration otherwise. An empty list
+/// means the pattern is sound.
+#[wasm_bindgen]
+pub fn verify_pattern(
+    pattern_json: &str,
+    config_json: Option<String>,
+) -> std::result::Result<String, String> {
+    let config: AmigurumiConfig = match config_json {
+        Some(json) => parse_config(&json)?,
+        None => AmigurumiConfig::default(),
+    };
+    // Rows past the limits are listed among the problems rather than
+    // refused; verifying only walks the instructions the JSON spells out
+    let pattern: CrochetPattern = parse_json_within("pattern", pattern_json, &config.limits)?;
+
+    to_json(
+        "problems",
+        &crochet_core::verify::verify_pattern(&pattern, &config),
+    )
+}
+
+/// Name rows `first_row..=last_row` of a pattern as a section ("Head", "Body")
+#[wasm_bindgen]
+pub fn add_pattern_section(
+    pattern_json: &str,
+    name: &str,
+    first_row: usize,
+    last_row: usize,
+) -> std::result::Result<String, String> {
+    let mut pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+
+    add_section(&mut pattern, name, first_row, last_row).map_err(pattern_error)?;
+
+    to_json("pattern", &pattern)
+}
+
+/// Color a pattern's rows in repeating stripes from a `StripeConfig`
+///
+/// With `jogless` set, the rounds after each change start one stitch
+/// later; the returned pattern's rows and anchors are renumbered to match.
+#[wasm_bindgen]
+pub fn add_pattern_stripes(
+    pattern_json: &str,
+    stripes_json: &str,
+) -> std::result::Result<String, String> {
+    let mut pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+    let stripes: StripeConfig = parse_json("stripes", stripes_json)?;
+
+    add_stripes(&mut pattern, &stripes).map_err(pattern_error)?;
+
+    to_json("pattern", &pattern)
+}
+
+/// List the floats in a `TapestryChart` longer than `max_float` stitches,
+/// each with a suggested fix
+#[wasm_bindgen]
+pub fn find_tapestry_floats(
+    chart_json: &str,
+    max_float: usize,
+) -> std::result::Result<String, String> {
+    let chart: TapestryChart = parse_rows("tapestry chart", chart_json, &InputLimits::default())?;
+
+    let floats = find_long_floats(&chart, max_float).map_err(pattern_error)?;
+
+    to_json("floats", &floats)
+}
+
+/// Swap a pattern's decreases for the ones a `StitchRepertoire` allows
+///
+/// Fails when a row needs a stitch the repertoire leaves out.
+#[wasm_bindgen]
+pub fn apply_stitch_repertoire(
+    pattern_json: &str,
+    repertoire_json: &str,
+) -> std::result::Result<String, String> {
+    let mut pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+    let repertoire: StitchRepertoire = parse_json("stitch repertoire", repertoire_json)?;
+
+    apply_repertoire(&mut pattern, &repertoire).map_err(pattern_error)?;
+
+    to_json("pattern", &pattern)
+}
+
+/// Add stitch-marker hints to a pattern: a start-of-round marker, and a
+/// halfway check every `every` rows (0 for none) and at shaping changes
+#[wasm_bindgen]
+pub fn add_pattern_markers(
+    pattern_json: &str,
+    every: usize,
+) -> std::result::Result<String, String> {
+    let mut pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+
+    add_marker_hints(&mut pattern, every);
+
+    to_json("pattern", &pattern)
+}
+
+/// Number an edited pattern's rows 1, 2, 3, ... in their current order
+#[wasm_bindgen]
+pub fn renumber_pattern_rows(pattern_json: &str) -> std::result::Result<String, String> {
+    let mut pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+
+    renumber_rows(&mut pattern);
+
+    to_json("pattern", &pattern)
+}
+
+/// Render a generated pattern as an SVG crochet symbol chart
+///
+/// Only the `terminology` (for the legend), `palette` and
+/// `legend_thumbnails` fields of `options_json` are used.
+#[wasm_bindgen]
+pub fn generate_symbol_chart(
+    pattern_json: &str,
+    options_json: &str,
+) -> std::result::Result<String, String> {
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+    let options: ExportOptions = parse_json("export options", options_json)?;
+    let generator = DiagramGenerator {
+        palette: options.palette,
+        legend_thumbnails: options.legend_thumbnails,
+        ..DiagramGenerator::new(options.terminology)
+    };
+    Ok(generator.generate_symbol_chart(&pattern))
+}
+
+/// One small SVG chart per row, to show inline with its instruction
+///
+/// Returns a JSON array of SVG strings in row order. Only the `palette`
+/// field of `options_json` is used.
+#[wasm_bindgen]
+pub fn generate_row_diagrams(
+    pattern_json: &str,
+    options_json: &str,
+) -> std::result::Result<String, String> {
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+    let options: ExportOptions = parse_json("export options", options_json)?;
+    let generator = DiagramGenerator {
+        palette: options.palette,
+        ..Default::default()
+    };
+    to_json("row diagrams", &generator.generate_row_diagrams(&pattern))
+}
+
+/// Element ids of each row and stitch in the symbol chart
+///
+/// Returns a JSON list of `ChartRowIndex`, for highlighting the row being
+/// worked in the SVG from `generate_symbol_chart`.
+#[wasm_bindgen]
+pub fn symbol_chart_index(pattern_json: &str) -> std::result::Result<String, String> {
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+
+    to_json(
+        "chart index",
+        &DiagramGenerator::default().symbol_chart_index(&pattern),
+    )
+}
+
+/// Render a compact SVG strip of per-row stitch counts and inc/dec badges
+///
+/// Only the `palette` field of `options_json` is used.
+#[wasm_bindgen]
+pub fn generate_stitch_ticker(
+    pattern_json: &str,
+    options_json: &str,
+) -> std::result::Result<String, String> {
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+    let options: ExportOptions = parse_json("export options", options_json)?;
+    let generator = DiagramGenerator {
+        palette: options.palette,
+        ..Default::default()
+    };
+    Ok(generator.generate_stitch_ticker(&pattern))
+}
+
+/// Which stitches of the round below every stitch was worked into
+///
+/// Returns `ColumnFlow` JSON.
+#[wasm_bindgen]
+pub fn get_column_flow(pattern_json: &str) -> std::result::Result<String, String> {
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+    let flow = column_flow(&pattern).map_err(pattern_error)?;
+    to_json("column flow", &flow)
+}
+
+/// Which stitches of the row below each instruction works into
+///
+/// Returns a JSON list of `RowConnections`, worked out afresh from the
+/// rows so patterns saved before `connections` was added work too.
+#[wasm_bindgen]
+pub fn get_row_connections(pattern_json: &str) -> std::result::Result<String, String> {
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+    to_json(
+        "row connections",
+        &row_connections(&pattern.rows, pattern.construction),
+    )
+}
+
+/// SVG of the stitch columns running from round to round
+///
+/// Only the `palette` field of `options_json` is used.
+#[wasm_bindgen]
+pub fn generate_column_flow_svg(
+    pattern_json: &str,
+    options_json: &str,
+) -> std::result::Result<String, String> {
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &InputLimits::default())?;
+    let options: ExportOptions = parse_json("export options", options_json)?;
+    let flow = column_flow(&pattern).map_err(pattern_error)?;
+    let generator = DiagramGenerator {
+        palette: options.palette,
+        ..Default::default()
+    };
+    Ok(generator.generate_column_flow(&flow))
+}
+
+/// Bundled color palettes, with the yarn shades matching each color
+///
+/// Returns a JSON array of `ColorPalette`.
+#[wasm_bindgen]
+pub fn list_palettes() -> String {
+    serde_json::to_string(&palettes()).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Generate a two-color textured piece from a `TextureConfig`
+///
+/// With `palette` set, the colors are names from `list_palettes`. Returns `TexturePattern` JSON.
+#[wasm_bindgen]
+pub fn generate_texture_pattern_from_json(config_json: &str) -> std::result::Result<String, String> {
+    let config: TextureConfig = parse_json("texture config", config_json)?;
+    let pattern = generate_texture_pattern(&config).map_err(pattern_error)?;
+    to_json("texture pattern", &pattern)
+}
+
+/// Written instructions for a texture pattern
+///
+/// Only the `terminology` field of `options_json` is used.
+#[wasm_bindgen]
+pub fn texture_pattern_to_text(
+    pattern_json: &str,
+    options_json: &str,
+) -> std::result::Result<String, String> {
+    let pattern: TexturePattern =
+        parse_rows("texture pattern", pattern_json, &InputLimits::default())?;
+    let options: ExportOptions = parse_json("export options", options_json)?;
+    Ok(texture_to_text(&pattern, options.terminology))
+}
+
+/// Render a texture pattern as an SVG chart in its two yarn colors
+///
+/// Only the `terminology` field of `options_json` is used.
+#[wasm_bindgen]
+pub fn generate_texture_chart(
+    pattern_json: &str,
+    options_json: &str,
+) -> std::result::Result<String, String> {
+    let pattern: TexturePattern =
+        parse_rows("texture pattern", pattern_json, &InputLimits::default())?;
+    let options: ExportOptions = parse_json("export options", options_json)?;
+    Ok(DiagramGenerator::new(options.terminology).generate_texture_chart(&pattern))
+}
+
+/// Generate a gauge swatch pattern from a JSON yarn spec
+///
+/// `foundation` is "chain" or "fsc" (foundation single crochet).
+#[wasm_bindgen]
+pub fn generate_gauge_swatch_from_json(
+    yarn_json: &str,
+    foundation: &str,
+) -> std::result::Result<String, String> {
+    let yarn: YarnSpec = parse_json("yarn", yarn_json)?;
+    let foundation = match foundation {
+        "chain" => Foundation::Chain,
+        "fsc" => Foundation::SingleCrochet,
+        other => return Err(unknown_option(format!("Unknown foundation: {}", other))),
+    };
+
+    let pattern = generate_gauge_swatch_with_foundation(&yarn, foundation)
+        .map_err(pattern_error)?;
+
+    to_json("pattern", &pattern)
+}
+
+/// Build a shopping list for a set of project pieces
+///
+/// `format` is one of "text", "markdown" or "json".
+#[wasm_bindgen]
+pub fn generate_shopping_list(pieces_json: &str, format: &str) -> std::result::Result<String, String> {
+    let pieces: Vec<ProjectPiece> = parse_rows("pieces", pieces_json, &InputLimits::default())?;
+    let list = build_shopping_list(&pieces, &ShoppingListOptions::default());
+
+    match format {
+        "text" => Ok(shopping_list_to_text(&list)),
+        "markdown" => Ok(shopping_list_to_markdown(&list)),
+        "json" => to_json("shopping list", &list),
+        other => Err(unknown_option(format!("Unknown shopping list format: {}", other))),
+    }
+}
+
+/// Render project pieces as an EPUB book for e-readers, returning its bytes
+///
+/// `metadata_json` is an `EpubMetadata` object, whose `modified` time
+/// (usually the export time) is required; `options_json` is used as for
+/// `export_pattern`, except that `format` is ignored.
+#[wasm_bindgen]
+pub fn export_project_epub(
+    pieces_json: &str,
+    metadata_json: &str,
+    options_json: &str,
+) -> std::result::Result<Vec<u8>, String> {
+    let pieces: Vec<ProjectPiece> = parse_rows("pieces", pieces_json, &InputLimits::default())?;
+    let metadata: EpubMetadata = parse_json("epub metadata", metadata_json)?;
+    let options: ExportOptions = parse_json("export options", options_json)?;
+
+    export_epub(
+        &pieces,
+        &metadata,
+        &PatternFormatter::from_options(&options),
+    )
+    .map_err(pattern_error)
+}
+
+/// Check which project pieces can be made from a yarn stash
+///
+/// `stash_json` is a list of `StashYarn`. Returns `StashReport` JSON.
+#[wasm_bindgen]
+pub fn match_yarn_stash(pieces_json: &str, stash_json: &str) -> std::result::Result<String, String> {
+    let pieces: Vec<ProjectPiece> = parse_rows("pieces", pieces_json, &InputLimits::default())?;
+    let stash: Vec<StashYarn> = parse_json("stash", stash_json)?;
+
+    to_json("stash report", &match_stash(&pieces, &stash, &ShoppingListOptions::default()))
+}
+
+/// Rescale a configuration to a different yarn weight (e.g. "bulky")
+#[wasm_bindgen]
+pub fn substitute_yarn_weight(config_json: &str, weight: &str) -> std::result::Result<String, String> {
+    let config = parse_config(config_json)?;
+    let weight = weight_from_name(weight)
+        .ok_or_else(|| unknown_option(format!("Unknown yarn weight: {}", weight)))?;
+
+    to_json("substitution", &substitute_yarn(&config, weight))
+}
+
+/// Estimate gauge for a hook size, yarn weight and fiber when there is no swatch
+///
+/// Returns a `YarnSpec`; set `gauge_estimated` on the config that uses it.
+#[wasm_bindgen]
+pub fn estimate_gauge_from_yarn(
+    hook_size_mm: f64,
+    weight: &str,
+    fiber: &str,
+) -> std::result::Result<String, String> {
+    if !(hook_size_mm > 0.0 && hook_size_mm.is_finite()) {
+        return Err(invalid_configuration("Hook size must be positive".to_string()));
+    }
+
+    let weight = weight_from_name(weight)
+        .ok_or_else(|| unknown_option(format!("Unknown yarn weight: {}", weight)))?;
+    let fiber = fiber_from_name(fiber)
+        .ok_or_else(|| unknown_option(format!("Unknown fiber: {}", fiber)))?;
+
+    to_json("yarn", &estimate_gauge(hook_size_mm, weight, fiber))
+}
+
+/// Estimate the yarn a pattern needs in each of its colors
+///
+/// Returns a JSON list of `ColorYarnUsage`, with meters, grams and skeins
+/// for the weight and skein size in the config's `yarn_estimate`. Use it
+/// to refresh `metadata.yarn_by_color` after adding stripes.
+#[wasm_bindgen]
+pub fn estimate_pattern_yarn(
+    pattern_json: &str,
+    config_json: &str,
+) -> std::result::Result<String, String> {
+    let config = parse_config(config_json)?;
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &config.limits)?;
+
+    to_json("yarn usage", &estimate_yarn_usage(&pattern, &config))
+}
+
+/// Minutes each row of a pattern takes at the config's `crocheter` speed
+///
+/// Returns a JSON list in row order, with each color change counted on
+/// the row that starts it; sum it for the whole piece.
+#[wasm_bindgen]
+pub fn estimate_pattern_time(
+    pattern_json: &str,
+    config_json: &str,
+) -> std::result::Result<String, String> {
+    let config = parse_config(config_json)?;
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &config.limits)?;
+
+    to_json(
+        "row times",
+        &estimate_row_minutes(&pattern, &config.crocheter),
+    )
+}
+
+/// Place every stitch of a pattern in 3D for a preview
+///
+/// Each glyph carries its position in cm, the direction it was worked and
+/// the outward normal of the fabric, sized from the config's gauge.
+#[wasm_bindgen]
+pub fn export_stitch_scene(
+    pattern_json: &str,
+    config_json: &str,
+) -> std::result::Result<String, String> {
+    let config = parse_config(config_json)?;
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &config.limits)?;
+
+    to_json("stitch scene", &stitch_scene(&pattern, &config))
+}
+
+/// Mesh the yarn of a pattern for a plush-looking 3D preview
+///
+/// `format` is "json" for a `YarnMesh` ready for WebGL buffers, or "obj"
+/// for Wavefront OBJ text. The yarn is as thick as the config's
+/// `yarn_estimate` weight, or the weight closest to its hook.
+#[wasm_bindgen]
+pub fn generate_yarn_preview(
+    pattern_json: &str,
+    config_json: &str,
+    format: &str,
+) -> std::result::Result<String, String> {
+    let config = parse_config(config_json)?;
+    let pattern: CrochetPattern = parse_rows("pattern", pattern_json, &config.limits)?;
+    let mesh = yarn_preview(&pattern, &config);
+
+    match format {
+        "json" => to_json("yarn mesh", &mesh),
+        "obj" => Ok(mesh_to_obj(&mesh)),
+        other => Err(unknown_option(format!("Unknown mesh format: {}", other))),
+    }
 }
 
 /// Validate a profile curve
 #[wasm_bindgen]
 pub fn validate_profile(profile_json: &str) -> std::result::Result<String, String> {
-    let profile: ProfileCurve = serde_json::from_str(profile_json)
-        .map_err(|e| format!("Failed to parse profile: {}", e))?;
+    let profile: ProfileCurve = parse_json("profile", profile_json)?;
 
     if profile.segments.is_empty() {
-        return Err("Profile has no segments".to_string());
+        return Err(invalid_profile("Profile has no segments".to_string()));
     }
 
     // Check continuity
@@ -46,10 +1080,10 @@ pub fn validate_profile(profile_json: &str) -> std::result::Result<String, Strin
         let dist = prev_end.distance_to(&curr_start);
         
         if dist > 1e-6 {
-            return Err(format!(
+            return Err(invalid_profile(format!(
                 "Discontinuity between segments {} and {}: distance = {}",
                 i - 1, i, dist
-            ));
+            )));
         }
     }
 
@@ -59,22 +1093,17 @@ pub fn validate_profile(profile_json: &str) -> std::result::Result<String, Strin
 /// Validate a configuration
 #[wasm_bindgen]
 pub fn validate_config(config_json: &str) -> std::result::Result<String, String> {
-    let config: AmigurumiConfig = serde_json::from_str(config_json)
-        .map_err(|e| format!("Failed to parse config: {}", e))?;
-
-    if config.total_height_cm <= 0.0 {
-        return Err("Height must be positive".to_string());
-    }
+    parse_config(config_json)?;
 
-    if config.yarn.gauge_stitches_per_cm <= 0.0 {
-        return Err("Gauge stitches per cm must be positive".to_string());
-    }
+    Ok("Configuration is valid".to_string())
+}
 
-    if config.yarn.gauge_rows_per_cm <= 0.0 {
-        return Err("Gauge rows per cm must be positive".to_string());
-    }
+fn invalid_profile(message: String) -> String {
+    pattern_error(PatternError::InvalidProfileCurve(message))
+}
 
-    Ok("Configuration is valid".to_string())
+fn invalid_configuration(message: String) -> String {
+    pattern_error(PatternError::InvalidConfiguration(message))
 }
 
 #[cfg(test)]
@@ -107,6 +1136,173 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_oversized_profile_rejected() {
+        let config_json = r#"{
+            "total_height_cm": 10.0,
+            "yarn": {
+                "gauge_stitches_per_cm": 3.0,
+                "gauge_rows_per_cm": 3.0,
+                "recommended_hook_size_mm": 3.5
+            },
+            "limits": {
+                "max_segments": 10,
+                "max_rows": 100,
+                "max_stitches_per_row": 100,
+                "max_input_bytes": 16
+            }
+        }"#;
+
+        let result = generate_pattern_from_json(&" ".repeat(64), config_json);
+        let error: CrochetError = serde_json::from_str(&result.unwrap_err()).unwrap();
+        assert_eq!(error.code, ErrorCode::InputTooLarge);
+        assert_eq!(error.stage, ErrorStage::Input);
+        assert!(error.details.unwrap().contains("limit"));
+    }
+
+    #[test]
+    fn test_oversized_inputs_rejected() {
+        let profile_json = r#"{
+            "segments": [{
+                "start": {"x": 2.0, "y": 0.0},
+                "control1": {"x": 2.0, "y": 3.33},
+                "control2": {"x": 2.0, "y": 6.67},
+                "end": {"x": 2.0, "y": 10.0}
+            }],
+            "start_radius": 2.0,
+            "end_radius": 2.0
+        }"#;
+        let config_json = r#"{
+            "total_height_cm": 10.0,
+            "yarn": {
+                "gauge_stitches_per_cm": 3.0,
+                "gauge_rows_per_cm": 3.0,
+                "recommended_hook_size_mm": 3.5
+            }
+        }"#;
+        let too_large = |result: std::result::Result<String, String>| {
+            let error: CrochetError = serde_json::from_str(&result.unwrap_err()).unwrap();
+            assert_eq!(error.code, ErrorCode::InputTooLarge);
+        };
+
+        // A short row of JSON claiming a trillion stitches
+        let sound_json = generate_pattern_from_json(profile_json, config_json).unwrap();
+        let mut pattern: CrochetPattern = serde_json::from_str(&sound_json).unwrap();
+        pattern.rows[2].total_stitches = 1 << 40;
+        pattern.rows[2].pattern.clear();
+        let pattern_json = serde_json::to_string(&pattern).unwrap();
+        too_large(get_row_connections(&pattern_json));
+        too_large(get_column_flow(&pattern_json));
+        too_large(export_pattern(&pattern_json, config_json, "{}"));
+        too_large(generate_symbol_chart(&pattern_json, "{}"));
+        // Verifying reports it instead
+        let problems: Vec<PatternProblem> =
+            serde_json::from_str(&verify_pattern(&pattern_json, None).unwrap()).unwrap();
+        assert!(problems.iter().any(|p| p.kind == ProblemKind::Limits));
+
+        let chart_json = r#"{
+            "construction": "Round",
+            "rows": [{"row_number": 1, "runs": [
+                {"color": "A", "stitches": 18446744073709551615},
+                {"color": "B", "stitches": 1}
+            ]}]
+        }"#;
+        too_large(find_tapestry_floats(chart_json, 5));
+
+        let mut texture: TexturePattern = serde_json::from_str(
+            &generate_texture_pattern_from_json(
+                r##"{
+                    "construction": "Flat",
+                    "width_cm": 10.0,
+                    "height_cm": 5.0,
+                    "yarn": {
+                        "gauge_stitches_per_cm": 3.0,
+                        "gauge_rows_per_cm": 3.0,
+                        "recommended_hook_size_mm": 3.5
+                    },
+                    "color_a": "#1f4e79",
+                    "color_b": "#f2c14e"
+                }"##,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        texture.rows[0].stitches = 1 << 40;
+        too_large(generate_texture_chart(
+            &serde_json::to_string(&texture).unwrap(),
+            "{}",
+        ));
+
+        // Every JSON input is held to the byte limit, not just the large ones
+        let padded = format!("{{{}}}", " ".repeat(InputLimits::default().max_input_bytes));
+        assert!(generate_symbol_chart(&sound_json, "{}").is_ok());
+        too_large(generate_symbol_chart(&sound_json, &padded));
+        too_large(validate_config(&padded));
+    }
+
+    fn parse_total_stitches(pattern_json: &str) -> usize {
+        let pattern: CrochetPattern = serde_json::from_str(pattern_json).unwrap();
+        pattern.metadata.total_stitches
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let profile_json = r#"{
+            "segments": [{
+                "start": {"x": 0.0, "y": 0.0},
+                "control1": {"x": 3.0, "y": 1.0},
+                "control2": {"x": 3.0, "y": 5.0},
+                "end": {"x": 2.0, "y": 6.0}
+            }],
+            "start_radius": 0.0,
+            "end_radius": 2.0
+        }"#;
+        let config_json = r#"{
+            "total_height_cm": 6.0,
+            "yarn": {
+                "gauge_stitches_per_cm": 3.0,
+                "gauge_rows_per_cm": 3.0,
+                "recommended_hook_size_mm": 3.5
+            }
+        }"#;
+
+        let json = generate_pattern_from_json(profile_json, config_json).unwrap();
+        let cbor = generate_pattern_binary(profile_json, config_json).unwrap();
+        assert!(cbor.len() < json.len());
+        assert_eq!(pattern_from_binary(&cbor).unwrap(), json);
+        let reencoded = pattern_from_binary(&pattern_to_binary(&json).unwrap()).unwrap();
+        let reencoded: CrochetPattern = serde_json::from_str(&reencoded).unwrap();
+        assert_eq!(reencoded.metadata.total_stitches, parse_total_stitches(&json));
+
+        let text = export_pattern(&json, config_json, r#"{"format": "text"}"#).unwrap();
+        let bytes = export_pattern_binary(&cbor, config_json, r#"{"format": "text"}"#).unwrap();
+        assert_eq!(bytes, text.into_bytes());
+
+        let error: CrochetError =
+            serde_json::from_str(&pattern_from_binary(&[0xff, 0x00]).unwrap_err()).unwrap();
+        assert_eq!(error.code, ErrorCode::InvalidBinary);
+    }
+
+    #[test]
+    fn test_errors_are_structured() {
+        let error = |result: std::result::Result<String, String>| -> CrochetError {
+            serde_json::from_str(&result.unwrap_err()).unwrap()
+        };
+
+        let bad_json = error(substitute_yarn_weight("{", "bulky"));
+        assert_eq!(bad_json.code, ErrorCode::InvalidJson);
+        assert_eq!(bad_json.message, "Failed to parse config");
+        assert!(bad_json.details.is_some());
+
+        let unknown = error(estimate_gauge_from_yarn(4.0, "chunky-ish", "wool"));
+        assert_eq!(unknown.code, ErrorCode::UnknownOption);
+
+        let no_segments = r#"{"segments": [], "start_radius": 2.0, "end_radius": 2.0}"#;
+        let invalid = error(validate_profile(no_segments));
+        assert_eq!(invalid.code, ErrorCode::InvalidProfileCurve);
+        assert_eq!(invalid.stage, ErrorStage::Validation);
+    }
+
     #[test]
     fn test_validate_profile() {
         let valid_json = r#"{
@@ -132,4 +1328,91 @@ mod tests {
         let result = validate_profile(invalid_json);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_validate_config_matches_generator() {
+        let config_json = |extra: &str| {
+            format!(
+                r#"{{
+                    "total_height_cm": 10.0,
+                    "yarn": {{
+                        "gauge_stitches_per_cm": 3.0,
+                        "gauge_rows_per_cm": 3.0,
+                        "recommended_hook_size_mm": 3.5
+                    }}{}
+                }}"#,
+                extra
+            )
+        };
+        assert!(validate_config(&config_json("")).is_ok());
+
+        // Settings only the generator used to reject
+        for extra in [
+            r#", "min_round_stitches": 2"#,
+            r#", "optimization": {"cooling_rate": 1.5}"#,
+        ] {
+            let error: CrochetError =
+                serde_json::from_str(&validate_config(&config_json(extra)).unwrap_err()).unwrap();
+            assert_eq!(error.code, ErrorCode::InvalidConfiguration);
+        }
+
+        // Every binding that reads a config checks it the same way
+        let profile_json = r#"{
+            "segments": [{
+                "start": {"x": 2.0, "y": 0.0},
+                "control1": {"x": 2.0, "y": 3.33},
+                "control2": {"x": 2.0, "y": 6.67},
+                "end": {"x": 2.0, "y": 10.0}
+            }],
+            "start_radius": 2.0,
+            "end_radius": 2.0
+        }"#;
+        let pattern_json = generate_pattern_from_json(profile_json, &config_json("")).unwrap();
+        let bad = config_json(r#", "crocheter": {"inc_penalty": -5.0}"#);
+        let results = [
+            estimate_pattern_time(&pattern_json, &bad),
+            estimate_pattern_yarn(&pattern_json, &bad),
+            export_stitch_scene(&pattern_json, &bad),
+            generate_yarn_preview(&pattern_json, &bad, "json"),
+            substitute_yarn_weight(&bad, "bulky"),
+            export_pattern(&pattern_json, &bad, r#"{"format": "text"}"#),
+        ];
+        for result in results {
+            let error: CrochetError = serde_json::from_str(&result.unwrap_err()).unwrap();
+            assert_eq!(error.code, ErrorCode::InvalidConfiguration);
+        }
+        assert!(estimate_pattern_time(&pattern_json, &config_json("")).is_ok());
+    }
+
+    #[test]
+    fn test_top_level_seed_read_into_optimization() {
+        let config_json = |extra: &str| {
+            format!(
+                r#"{{
+                    "total_height_cm": 10.0,
+                    "yarn": {{
+                        "gauge_stitches_per_cm": 3.0,
+                        "gauge_rows_per_cm": 3.0,
+                        "recommended_hook_size_mm": 3.5
+                    }}{}
+                }}"#,
+                extra
+            )
+        };
+        let config = |extra: &str| parse_config(&config_json(extra)).unwrap();
+
+        assert_eq!(config(r#", "seed": 7"#).optimization.seed, Some(7));
+        assert_eq!(
+            config(r#", "seed": 7, "optimization": {"seed": 11}"#)
+                .optimization
+                .seed,
+            Some(11)
+        );
+        assert_eq!(config("").optimization.seed, None);
+
+        // Written back out, the seed only appears in one place
+        let written = serde_json::to_value(config(r#", "seed": 7"#)).unwrap();
+        assert!(written.get("seed").is_none());
+        assert_eq!(written["optimization"]["seed"], 7);
+    }
 }